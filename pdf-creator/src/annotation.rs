@@ -0,0 +1,1067 @@
+use crate::color::Color;
+use crate::destination::Destination;
+use crate::embedded::EmbeddedFile;
+use crate::font::{Font, StandardFont};
+use crate::geometry::{Point, Rect, number};
+use crate::graphics::{Paint, PathBuilder, StrokeStyle};
+use crate::metadata::PdfDate;
+use crate::object::{Dictionary, Object, ObjectRef};
+use crate::resources::ResourceRefs;
+use crate::text::{Align, TextStyle};
+use crate::xobject::FormXObject;
+use anyhow::{Result, bail, ensure};
+
+/// Something placed over the content of a page rather than drawn in it,
+/// such as a review comment, kept apart so viewers can show, edit and
+/// remove it. Added with [`Page::add_annotation`](crate::Page::add_annotation).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    rect: Rect,
+    kind: Kind,
+    contents: Option<String>,
+    author: Option<String>,
+    color: Option<Color>,
+    modified: Option<PdfDate>,
+    /// The fill of shapes, left empty if not set.
+    interior: Option<Color>,
+    /// The width and dashes of the outline of shapes and of ink.
+    border: Option<StrokeStyle>,
+    /// How the annotation looks, drawn in `rect` instead of what the
+    /// reader would make up.
+    appearance: Option<FormXObject>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    /// A clickable area.
+    Link(LinkTarget),
+    /// A sticky note, shown as an icon that opens a pop-up with the text.
+    Text { icon: NoteIcon, open: bool },
+    /// Marks over regions of text, each written as the four corners of a
+    /// quadrilateral.
+    Markup {
+        style: MarkupStyle,
+        regions: Vec<Rect>,
+    },
+    /// Text shown on the page in a box, which readers let the user edit.
+    /// With a callout, a line runs from the box to a point it comments on,
+    /// and `rect` takes them both in.
+    FreeText {
+        text_box: Rect,
+        style: TextStyle,
+        callout: Vec<Point>,
+        /// Whether the appearance is made from the text, and to be made
+        /// again when the annotation changes.
+        generated: bool,
+    },
+    /// A shape drawn over the page, such as one circling a defect on a
+    /// drawing.
+    Shape(Shape),
+    /// Freehand strokes, each a line through its points.
+    Ink(Vec<Vec<Point>>),
+    /// A rubber stamp, the name telling what it says.
+    Stamp(String),
+    /// An icon standing for a file carried in the document.
+    FileAttachment {
+        file: EmbeddedFile,
+        icon: AttachmentIcon,
+    },
+}
+
+/// The outline of a shape annotation.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// The rectangle of the annotation.
+    Square,
+    /// The ellipse fitting in the rectangle of the annotation.
+    Circle,
+    /// A closed outline through the vertices.
+    Polygon(Vec<Point>),
+    /// An open line through the vertices.
+    PolyLine(Vec<Point>),
+}
+
+impl Shape {
+    fn name(&self) -> &'static str {
+        match self {
+            Shape::Square => "Square",
+            Shape::Circle => "Circle",
+            Shape::Polygon(_) => "Polygon",
+            Shape::PolyLine(_) => "PolyLine",
+        }
+    }
+}
+
+/// Where clicking a link takes the reader.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LinkTarget {
+    /// A web address or other URI.
+    Uri(String),
+    /// A place in the document.
+    Destination(Destination),
+}
+
+/// How text markup marks the text it covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupStyle {
+    Highlight,
+    Underline,
+    StrikeOut,
+    /// A wavy underline.
+    Squiggly,
+}
+
+impl MarkupStyle {
+    fn name(self) -> &'static str {
+        match self {
+            MarkupStyle::Highlight => "Highlight",
+            MarkupStyle::Underline => "Underline",
+            MarkupStyle::StrikeOut => "StrikeOut",
+            MarkupStyle::Squiggly => "Squiggly",
+        }
+    }
+}
+
+/// The rubber stamps every viewer can draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampName {
+    Approved,
+    Experimental,
+    NotApproved,
+    AsIs,
+    Expired,
+    NotForPublicRelease,
+    Confidential,
+    Final,
+    Sold,
+    Departmental,
+    ForComment,
+    TopSecret,
+    Draft,
+    ForPublicRelease,
+}
+
+impl StampName {
+    fn name(self) -> &'static str {
+        match self {
+            StampName::Approved => "Approved",
+            StampName::Experimental => "Experimental",
+            StampName::NotApproved => "NotApproved",
+            StampName::AsIs => "AsIs",
+            StampName::Expired => "Expired",
+            StampName::NotForPublicRelease => "NotForPublicRelease",
+            StampName::Confidential => "Confidential",
+            StampName::Final => "Final",
+            StampName::Sold => "Sold",
+            StampName::Departmental => "Departmental",
+            StampName::ForComment => "ForComment",
+            StampName::TopSecret => "TopSecret",
+            StampName::Draft => "Draft",
+            StampName::ForPublicRelease => "ForPublicRelease",
+        }
+    }
+}
+
+/// The icons viewers show for file attachments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttachmentIcon {
+    Graph,
+    #[default]
+    Paperclip,
+    PushPin,
+    Tag,
+}
+
+impl AttachmentIcon {
+    fn name(self) -> &'static str {
+        match self {
+            AttachmentIcon::Graph => "Graph",
+            AttachmentIcon::Paperclip => "Paperclip",
+            AttachmentIcon::PushPin => "PushPin",
+            AttachmentIcon::Tag => "Tag",
+        }
+    }
+}
+
+/// The icons viewers show for notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteIcon {
+    Comment,
+    Key,
+    #[default]
+    Note,
+    Help,
+    NewParagraph,
+    Paragraph,
+    Insert,
+}
+
+impl NoteIcon {
+    fn name(self) -> &'static str {
+        match self {
+            NoteIcon::Comment => "Comment",
+            NoteIcon::Key => "Key",
+            NoteIcon::Note => "Note",
+            NoteIcon::Help => "Help",
+            NoteIcon::NewParagraph => "NewParagraph",
+            NoteIcon::Paragraph => "Paragraph",
+            NoteIcon::Insert => "Insert",
+        }
+    }
+}
+
+impl Annotation {
+    fn new(rect: Rect, kind: Kind) -> Self {
+        Self {
+            rect,
+            kind,
+            contents: None,
+            author: None,
+            color: None,
+            modified: None,
+            interior: None,
+            border: None,
+            appearance: None,
+        }
+    }
+
+    pub(crate) fn link(rect: Rect, target: LinkTarget) -> Self {
+        Self::new(rect, Kind::Link(target))
+    }
+
+    /// A sticky note holding `contents`, its icon drawn in `rect`, closed
+    /// when the document is opened.
+    pub fn note(rect: Rect, contents: &str) -> Self {
+        Self::new(
+            rect,
+            Kind::Text {
+                icon: NoteIcon::default(),
+                open: false,
+            },
+        )
+        .contents(contents)
+    }
+
+    /// Text markup over `regions` of the page, such as the rectangles
+    /// around the words of a highlighted sentence, one per line. Highlights
+    /// are yellow, other marks are red unless colored otherwise.
+    pub fn text_markup(style: MarkupStyle, regions: &[Rect]) -> Self {
+        let rect = bounds(regions.iter().flat_map(|region| {
+            let (left, bottom, right, top) = region.edges();
+            [Point::new(left, bottom), Point::new(right, top)]
+        }));
+        let color = match style {
+            MarkupStyle::Highlight => Color::Rgb(1.0, 1.0, 0.0),
+            _ => Color::Rgb(1.0, 0.0, 0.0),
+        };
+        let regions = regions.to_vec();
+        Self::new(rect, Kind::Markup { style, regions }).color(color)
+    }
+
+    /// `text` shown on the page in `rect` in `font`, black, at `size`
+    /// points. Readers draw it from the font, size and color recorded with
+    /// it, which they know for the standard fonts only, and let the user
+    /// edit it like the text of a form field. For a look that does not
+    /// depend on the reader, such as for printing, give it one with
+    /// [`generate_appearance`](Self::generate_appearance).
+    pub fn free_text(rect: Rect, text: &str, font: StandardFont, size: f32) -> Self {
+        Self::new(
+            rect,
+            Kind::FreeText {
+                text_box: rect,
+                style: TextStyle::new(font, size),
+                callout: Vec::new(),
+                generated: false,
+            },
+        )
+        .contents(text)
+    }
+
+    /// A rectangle outlined in red, unless colored otherwise.
+    pub fn square(rect: Rect) -> Self {
+        Self::shape(rect, Shape::Square)
+    }
+
+    /// An ellipse fitting in `rect`, outlined in red unless colored
+    /// otherwise.
+    pub fn circle(rect: Rect) -> Self {
+        Self::shape(rect, Shape::Circle)
+    }
+
+    /// A closed outline through `vertices`, outlined in red unless colored
+    /// otherwise.
+    pub fn polygon(vertices: &[Point]) -> Self {
+        Self::shape(
+            bounds(vertices.iter().copied()),
+            Shape::Polygon(vertices.to_vec()),
+        )
+    }
+
+    /// An open line through `vertices`, drawn in red unless colored
+    /// otherwise.
+    pub fn polyline(vertices: &[Point]) -> Self {
+        Self::shape(
+            bounds(vertices.iter().copied()),
+            Shape::PolyLine(vertices.to_vec()),
+        )
+    }
+
+    fn shape(rect: Rect, shape: Shape) -> Self {
+        Self::new(rect, Kind::Shape(shape)).color(Color::Rgb(1.0, 0.0, 0.0))
+    }
+
+    /// Freehand `strokes`, such as those of a signature captured on a pad,
+    /// each drawn as a line through its points, in black unless colored
+    /// otherwise and as wide as the [`border`](Self::border).
+    pub fn ink(strokes: &[Vec<Point>]) -> Self {
+        let rect = bounds(strokes.iter().flatten().copied());
+        Self::new(rect, Kind::Ink(strokes.to_vec())).color(Color::BLACK)
+    }
+
+    /// One of the standard rubber stamps, drawn by the reader in `rect`.
+    pub fn stamp(rect: Rect, name: StampName) -> Self {
+        Self::new(rect, Kind::Stamp(name.name().to_string()))
+    }
+
+    /// A rubber stamp of your own, such as a company seal, drawn as `form`
+    /// says, stretched to fill `rect`. Viewers list it by `name`.
+    pub fn custom_stamp(rect: Rect, name: &str, form: FormXObject) -> Self {
+        Self::new(rect, Kind::Stamp(name.to_string())).appearance(form)
+    }
+
+    /// A paperclip icon in `rect` standing for `file`, which readers open or
+    /// save when it is clicked. The name of the file is shown in its pop-up
+    /// unless other contents are given.
+    pub fn file_attachment(rect: Rect, file: EmbeddedFile) -> Self {
+        let name = file.name().to_string();
+        Self::new(
+            rect,
+            Kind::FileAttachment {
+                file,
+                icon: AttachmentIcon::default(),
+            },
+        )
+        .contents(&name)
+    }
+
+    /// The text of the annotation, shown in its pop-up.
+    pub fn contents(mut self, contents: &str) -> Self {
+        self.contents = Some(contents.to_string());
+        self.regenerate()
+    }
+
+    /// Who wrote the annotation, shown in the title bar of its pop-up.
+    pub fn author(mut self, author: &str) -> Self {
+        self.author = Some(author.to_string());
+        self
+    }
+
+    /// The color of the icon, the pop-up title bar or the border. Only gray,
+    /// RGB and CMYK colors can be used.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// When the annotation was last changed.
+    pub fn modified(mut self, date: PdfDate) -> Self {
+        self.modified = Some(date);
+        self
+    }
+
+    /// The icon of a note. Other annotations have none.
+    pub fn icon(mut self, icon: NoteIcon) -> Self {
+        if let Kind::Text { icon: current, .. } = &mut self.kind {
+            *current = icon;
+        }
+        self
+    }
+
+    /// The icon of a file attachment.
+    pub fn attachment_icon(mut self, icon: AttachmentIcon) -> Self {
+        if let Kind::FileAttachment { icon: current, .. } = &mut self.kind {
+            *current = icon;
+        }
+        self
+    }
+
+    /// Has the pop-up of a note shown open when the document is opened.
+    pub fn open(mut self, open: bool) -> Self {
+        if let Kind::Text { open: current, .. } = &mut self.kind {
+            *current = open;
+        }
+        self
+    }
+
+    /// The color the inside of a shape is filled with. Only gray, RGB and
+    /// CMYK colors can be used.
+    pub fn interior_color(mut self, color: Color) -> Self {
+        self.interior = Some(color);
+        self
+    }
+
+    /// The width and dash pattern of the outline of a shape or of ink, 1
+    /// point and solid by default; its caps and joins are up to the
+    /// reader. A zero width leaves the outline out.
+    pub fn border(mut self, style: StrokeStyle) -> Self {
+        self.border = Some(style);
+        self
+    }
+
+    /// The color of the text of a free text annotation.
+    pub fn text_color(mut self, color: Color) -> Self {
+        if let Kind::FreeText { style, .. } = &mut self.kind {
+            style.color = Some(color);
+        }
+        self.regenerate()
+    }
+
+    /// How the lines of a free text annotation are aligned in its box. Only
+    /// left, centered and right are recorded: justified text is treated as
+    /// left aligned.
+    pub fn align(mut self, align: Align) -> Self {
+        if let Kind::FreeText { style, .. } = &mut self.kind {
+            style.align = align;
+        }
+        self.regenerate()
+    }
+
+    /// Turns a free text annotation into a callout: a line through
+    /// `points`, the first being what the text comments on, with an arrow
+    /// there, and the last where the line meets the text box, such as
+    /// `[target, knee, edge of the box]`.
+    pub fn callout(mut self, points: &[Point]) -> Self {
+        if let Kind::FreeText {
+            text_box, callout, ..
+        } = &mut self.kind
+        {
+            *callout = points.to_vec();
+            let (left, bottom, right, top) = text_box.edges();
+            // Enough room for the arrowhead.
+            let corners = points.iter().flat_map(|point| {
+                [
+                    Point::new(point.x - ARROW, point.y - ARROW),
+                    Point::new(point.x + ARROW, point.y + ARROW),
+                ]
+            });
+            self.rect = bounds(
+                [Point::new(left, bottom), Point::new(right, top)]
+                    .into_iter()
+                    .chain(corners),
+            );
+        }
+        self.regenerate()
+    }
+
+    /// Has the annotation drawn as `form` says, fitted in its rectangle,
+    /// rather than however the reader shows it.
+    pub fn appearance(mut self, form: FormXObject) -> Self {
+        if let Kind::FreeText { generated, .. } = &mut self.kind {
+            *generated = false;
+        }
+        self.appearance = Some(form);
+        self
+    }
+
+    /// Has a free text annotation drawn as it is written, the text wrapped
+    /// in a box with a thin black border, so that it shows the same in
+    /// every reader and when printed. Readers still make a new appearance
+    /// once the text is edited.
+    pub fn generate_appearance(mut self) -> Self {
+        if let Kind::FreeText { generated, .. } = &mut self.kind {
+            *generated = true;
+        }
+        self.regenerate()
+    }
+
+    /// Makes the appearance of a free text annotation again from its text
+    /// and callout, if it is generated.
+    fn regenerate(mut self) -> Self {
+        let Kind::FreeText {
+            text_box,
+            style,
+            callout,
+            generated: true,
+        } = &self.kind
+        else {
+            return self;
+        };
+        let text = self.contents.clone().unwrap_or_default();
+        let rect = self.rect;
+        let form = FormXObject::new(rect.width, rect.height, |g| {
+            // Drawn in the coordinates of the page.
+            g.translated(-rect.x, -rect.y, |g| {
+                let border = Paint::stroke(Color::BLACK).with_line_width(BORDER);
+                let (left, bottom, right, top) = text_box.edges();
+                let half = BORDER / 2.0;
+                g.rect(
+                    left + half,
+                    bottom + half,
+                    right - left - BORDER,
+                    top - bottom - BORDER,
+                    &border,
+                );
+                let padding = BORDER + 2.0;
+                let inside = Rect::new(
+                    left + padding,
+                    bottom + padding,
+                    right - left - 2.0 * padding,
+                    top - bottom - 2.0 * padding,
+                );
+                g.draw_text_wrapped(inside, &text, style);
+                if let [target, rest @ ..] = callout.as_slice()
+                    && !rest.is_empty()
+                {
+                    let mut line = PathBuilder::new().move_to(*target);
+                    for point in rest {
+                        line = line.line_to(*point);
+                    }
+                    g.draw_path(&line.build(), &border);
+                    let arrow = arrowhead(*target, rest[0]);
+                    g.draw_path(&arrow, &border);
+                }
+            });
+        });
+        self.appearance = Some(form);
+        self
+    }
+
+    /// The file the annotation carries, if it is an attachment.
+    pub(crate) fn attached_file(&self) -> Option<&EmbeddedFile> {
+        match &self.kind {
+            Kind::FileAttachment { file, .. } => Some(file),
+            _ => None,
+        }
+    }
+
+    /// The form drawing the annotation, if it has its own.
+    pub(crate) fn appearance_form(&self) -> Option<&FormXObject> {
+        self.appearance.as_ref()
+    }
+
+    /// The subtype of the annotation, such as `Text` for notes.
+    pub(crate) fn subtype(&self) -> &'static str {
+        self.subtype_and_flags().0
+    }
+
+    /// The subtype and the flags of the annotation.
+    fn subtype_and_flags(&self) -> (&'static str, u32) {
+        match self.kind {
+            Kind::Link(_) => ("Link", PRINT),
+            // Notes keep their size and orientation as the page is zoomed
+            // or rotated.
+            Kind::Text { .. } => ("Text", PRINT | NO_ZOOM | NO_ROTATE),
+            Kind::Markup { style, .. } => (style.name(), PRINT),
+            Kind::FreeText { .. } => ("FreeText", PRINT),
+            Kind::Shape(ref shape) => (shape.name(), PRINT),
+            Kind::Ink(_) => ("Ink", PRINT),
+            Kind::Stamp(_) => ("Stamp", PRINT),
+            // Icons, like those of notes.
+            Kind::FileAttachment { .. } => ("FileAttachment", PRINT | NO_ZOOM | NO_ROTATE),
+        }
+    }
+
+    /// The annotation dictionary, `pages` being the page objects
+    /// destinations refer to.
+    pub(crate) fn to_pdf(&self, pages: &[ObjectRef], refs: &ResourceRefs) -> Result<Dictionary> {
+        let (subtype, flags) = self.subtype_and_flags();
+        let rect = match &self.kind {
+            // Room for the line around the points it goes through.
+            Kind::Shape(Shape::Polygon(_) | Shape::PolyLine(_)) | Kind::Ink(_) => {
+                let half = self.border.as_ref().map_or(1.0, |border| border.width) / 2.0;
+                let (left, bottom, right, top) = self.rect.edges();
+                Rect::new(
+                    left - half,
+                    bottom - half,
+                    right - left + 2.0 * half,
+                    top - bottom + 2.0 * half,
+                )
+            }
+            _ => self.rect,
+        };
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Annot"));
+        dict.set("Subtype", Object::name(subtype));
+        dict.set("Rect", rect);
+        dict.set("F", flags);
+        if let Some(contents) = &self.contents {
+            dict.set("Contents", Object::text(contents));
+        }
+        if let Some(author) = &self.author {
+            dict.set("T", Object::text(author));
+        }
+        if let Some(color) = &self.color {
+            dict.set("C", color_array(color)?);
+        }
+        if let Some(date) = self.modified {
+            dict.set("M", date.to_pdf());
+        }
+        if let Some(color) = &self.interior {
+            dict.set("IC", color_array(color)?);
+        }
+        if let Some(border) = &self.border {
+            let mut style = Dictionary::new();
+            style.set("W", border.width);
+            if border.dash.is_empty() {
+                style.set("S", Object::name("S"));
+            } else {
+                style.set("S", Object::name("D"));
+                style.set("D", Object::numbers(&border.dash));
+            }
+            dict.set("BS", style);
+        }
+        if let Some(form) = &self.appearance {
+            let mut appearance = Dictionary::new();
+            appearance.set("N", refs.forms[form]);
+            dict.set("AP", appearance);
+        }
+        match &self.kind {
+            Kind::Link(target) => {
+                // Without the border readers draw by default.
+                dict.set("Border", vec![0.into(), 0.into(), 0.into()]);
+                match target {
+                    LinkTarget::Uri(uri) => {
+                        let mut action = Dictionary::new();
+                        action.set("S", Object::name("URI"));
+                        action.set("URI", Object::string(uri.as_str()));
+                        dict.set("A", action);
+                    }
+                    LinkTarget::Destination(destination) => {
+                        dict.set("Dest", destination.to_pdf(pages)?)
+                    }
+                }
+            }
+            Kind::Text { icon, open } => {
+                dict.set("Name", Object::name(icon.name()));
+                dict.set("Open", *open);
+            }
+            Kind::Markup { regions, .. } => {
+                ensure!(!regions.is_empty(), "text markup needs regions to mark");
+                // Corners in the order readers expect: upper left, upper
+                // right, lower left, lower right.
+                let quads: Vec<f32> = regions
+                    .iter()
+                    .flat_map(|region| {
+                        let (left, bottom, right, top) = region.edges();
+                        [left, top, right, top, left, bottom, right, bottom]
+                    })
+                    .collect();
+                dict.set("QuadPoints", Object::numbers(&quads));
+            }
+            Kind::FreeText {
+                text_box,
+                style,
+                callout,
+                ..
+            } => {
+                let font = match style.font {
+                    Font::Standard(font) => font.base_font(),
+                    Font::TrueType(_) => unreachable!("free text is set in a standard font"),
+                };
+                let color = style.color.as_ref().unwrap_or(&Color::BLACK);
+                let components = color_components(color)?;
+                let operator = match components.len() {
+                    1 => "g",
+                    3 => "rg",
+                    _ => "k",
+                };
+                let components: Vec<String> = components.into_iter().map(number).collect();
+                // What readers draw the text with, and edit it in.
+                dict.set(
+                    "DA",
+                    Object::string(format!(
+                        "/{} {} Tf {} {}",
+                        font,
+                        number(style.size),
+                        components.join(" "),
+                        operator
+                    )),
+                );
+                let quadding = match style.align {
+                    Align::Left | Align::Justify => 0,
+                    Align::Center => 1,
+                    Align::Right => 2,
+                };
+                dict.set("Q", quadding);
+                ensure!(
+                    matches!(callout.len(), 0 | 2 | 3),
+                    "a callout line has two or three points"
+                );
+                if !callout.is_empty() {
+                    let points: Vec<f32> = callout
+                        .iter()
+                        .flat_map(|point| [point.x, point.y])
+                        .collect();
+                    // How far the box is inside the rectangle on each side.
+                    let (left, bottom, right, top) = self.rect.edges();
+                    let (box_left, box_bottom, box_right, box_top) = text_box.edges();
+                    let insets = [
+                        box_left - left,
+                        box_bottom - bottom,
+                        right - box_right,
+                        top - box_top,
+                    ];
+                    dict.set("IT", Object::name("FreeTextCallout"));
+                    dict.set("CL", Object::numbers(&points));
+                    dict.set("LE", Object::name("OpenArrow"));
+                    dict.set("RD", Object::numbers(&insets));
+                }
+            }
+            Kind::Shape(Shape::Polygon(vertices)) => {
+                ensure!(vertices.len() >= 3, "a polygon needs three vertices");
+                dict.set("Vertices", points_array(vertices));
+            }
+            Kind::Shape(Shape::PolyLine(vertices)) => {
+                ensure!(vertices.len() >= 2, "a polyline needs two vertices");
+                dict.set("Vertices", points_array(vertices));
+            }
+            Kind::Shape(Shape::Square | Shape::Circle) => {}
+            Kind::Ink(strokes) => {
+                ensure!(
+                    strokes.iter().any(|stroke| !stroke.is_empty()),
+                    "ink needs points to draw"
+                );
+                let strokes = strokes.iter().map(|stroke| points_array(stroke)).collect();
+                dict.set("InkList", Object::Array(strokes));
+            }
+            Kind::Stamp(name) => dict.set("Name", Object::name(name.as_str())),
+            Kind::FileAttachment { file, icon } => {
+                dict.set("FS", file.to_filespec(refs.files[file]));
+                dict.set("Name", Object::name(icon.name()));
+            }
+        }
+        Ok(dict)
+    }
+}
+
+/// Annotation flags: printed with the page, not scaled with the zoom and not
+/// rotated with the page.
+const PRINT: u32 = 4;
+const NO_ZOOM: u32 = 8;
+const NO_ROTATE: u32 = 16;
+
+/// Width of the border and callout line of generated free text
+/// appearances, and how far the arrowhead of a callout reaches.
+const BORDER: f32 = 1.0;
+const ARROW: f32 = 6.0;
+
+/// The open arrowhead at `tip` of a line coming from `from`.
+fn arrowhead(tip: Point, from: Point) -> crate::graphics::Path {
+    let angle = (from.y - tip.y).atan2(from.x - tip.x);
+    let side = |turn: f32| {
+        let angle = angle + turn;
+        Point::new(tip.x + ARROW * angle.cos(), tip.y + ARROW * angle.sin())
+    };
+    let spread = 30f32.to_radians();
+    PathBuilder::new()
+        .move_to(side(spread))
+        .line_to(tip)
+        .line_to(side(-spread))
+        .build()
+}
+
+/// The smallest rectangle holding all of `points`, empty at the origin if
+/// there are none.
+fn bounds(points: impl IntoIterator<Item = Point>) -> Rect {
+    let mut points = points.into_iter().peekable();
+    if points.peek().is_none() {
+        return Rect::default();
+    }
+    let (x0, y0, x1, y1) = points.fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(x0, y0, x1, y1), point| {
+            (
+                x0.min(point.x),
+                y0.min(point.y),
+                x1.max(point.x),
+                y1.max(point.y),
+            )
+        },
+    );
+    Rect::new(x0, y0, x1 - x0, y1 - y0)
+}
+
+/// The components of `color`, which annotations only have in gray, RGB or
+/// CMYK.
+fn color_components(color: &Color) -> Result<Vec<f32>> {
+    Ok(match color {
+        Color::Gray(gray) => vec![*gray],
+        Color::Rgb(r, g, b) => vec![*r, *g, *b],
+        Color::Cmyk(c, m, y, k) => vec![*c, *m, *y, *k],
+        _ => bail!("annotations can only be colored in gray, RGB or CMYK"),
+    })
+}
+
+/// `color` as the components of an annotation's `/C` array.
+fn color_array(color: &Color) -> Result<Object> {
+    Ok(Object::numbers(&color_components(color)?))
+}
+
+/// The coordinates of `points`, one after the other in an array.
+fn points_array(points: &[Point]) -> Object {
+    let coordinates: Vec<f32> = points.iter().flat_map(|point| [point.x, point.y]).collect();
+    Object::numbers(&coordinates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_link() {
+        let link = Annotation::link(
+            Rect::new(100.0, 700.0, 120.0, -14.5),
+            LinkTarget::Uri("https://example.com/invoice?id=(42)".to_string()),
+        );
+        assert_eq!(
+            link.to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Link /Rect [100 685.5 220 700] /F 4 /Border [0 0 0] \
+             /A << /S /URI /URI (https://example.com/invoice?id=\\(42\\)) >> >>"
+        );
+    }
+
+    #[test]
+    fn test_goto_link() {
+        let link = Annotation::link(
+            Rect::new(72.0, 600.0, 200.0, 14.0),
+            LinkTarget::Destination(Destination::fit_width(1, 800.0)),
+        );
+        let pages = [ObjectRef::new(4), ObjectRef::new(7)];
+        assert_eq!(
+            link.to_pdf(&pages, &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Link /Rect [72 600 272 614] /F 4 /Border [0 0 0] \
+             /Dest [7 0 R /FitH 800] >>"
+        );
+        assert!(link.to_pdf(&pages[..1], &ResourceRefs::default()).is_err());
+    }
+
+    #[test]
+    fn test_text_markup() {
+        let lines = [
+            Rect::new(72.0, 700.0, 300.0, 14.0),
+            Rect::new(72.0, 686.0, 120.5, 14.0),
+        ];
+        let highlight = Annotation::text_markup(MarkupStyle::Highlight, &lines);
+        assert_eq!(
+            highlight
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Highlight /Rect [72 686 372 714] /F 4 /C [1 1 0] \
+             /QuadPoints [72 714 372 714 72 700 372 700 72 700 192.5 700 72 686 192.5 686] >>"
+        );
+        let empty = Annotation::text_markup(MarkupStyle::Underline, &[]);
+        assert!(empty.to_pdf(&[], &ResourceRefs::default()).is_err());
+        let squiggly = Annotation::text_markup(MarkupStyle::Squiggly, &lines[..1])
+            .color(Color::Rgb(0.0, 0.5, 0.0));
+        assert!(
+            squiggly
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string()
+                .starts_with(
+                    "<< /Type /Annot /Subtype /Squiggly /Rect [72 700 372 714] /F 4 /C [0 0.5 0]"
+                )
+        );
+    }
+
+    #[test]
+    fn test_free_text() {
+        let text = Annotation::free_text(
+            Rect::new(100.0, 500.0, 200.0, 40.0),
+            "Total is wrong",
+            StandardFont::HelveticaBold,
+            11.0,
+        )
+        .text_color(Color::Rgb(0.8, 0.0, 0.0))
+        .align(Align::Center);
+        assert_eq!(
+            text.to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /FreeText /Rect [100 500 300 540] /F 4 \
+             /Contents (Total is wrong) /DA (/Helvetica-Bold 11 Tf 0.8 0 0 rg) /Q 1 >>"
+        );
+        assert!(text.appearance_form().is_none());
+
+        let pointless = text.clone().callout(&[Point::new(50.0, 400.0)]);
+        let pointless = pointless.generate_appearance();
+        let mut refs = ResourceRefs::default();
+        let form = pointless.appearance_form().unwrap();
+        refs.forms.insert(form.clone(), ObjectRef::new(9));
+        assert!(pointless.to_pdf(&[], &refs).is_err());
+        let callout = text
+            .callout(&[Point::new(50.0, 400.0), Point::new(100.0, 520.0)])
+            .generate_appearance();
+        let form = callout.appearance_form().unwrap();
+        assert_eq!((form.width(), form.height()), (256.0, 146.0));
+        let mut refs = ResourceRefs::default();
+        refs.forms.insert(form.clone(), ObjectRef::new(9));
+        let dict = callout.to_pdf(&[], &refs).unwrap().to_string();
+        assert!(dict.starts_with("<< /Type /Annot /Subtype /FreeText /Rect [44 394 300 540]"));
+        assert!(dict.contains(" /AP << /N 9 0 R >>"));
+        assert!(dict.ends_with(
+            " /IT /FreeTextCallout /CL [50 400 100 520] /LE /OpenArrow /RD [56 106 0 0] >>"
+        ));
+    }
+
+    #[test]
+    fn test_shapes() {
+        let square = Annotation::square(Rect::new(100.0, 100.0, 50.0, 30.0))
+            .interior_color(Color::Gray(0.9))
+            .border(StrokeStyle::new(2.0).dashed(3.0, 1.5));
+        assert_eq!(
+            square
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Square /Rect [100 100 150 130] /F 4 /C [1 0 0] \
+             /IC [0.9] /BS << /W 2 /S /D /D [3 1.5] >> >>"
+        );
+        let circle =
+            Annotation::circle(Rect::new(0.0, 0.0, 10.0, 10.0)).border(StrokeStyle::new(0.5));
+        assert!(
+            circle
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string()
+                .ends_with(
+                    "/Subtype /Circle /Rect [0 0 10 10] /F 4 /C [1 0 0] /BS << /W 0.5 /S /S >> >>"
+                )
+        );
+
+        let vertices = [
+            Point::new(10.0, 10.0),
+            Point::new(60.0, 20.0),
+            Point::new(30.0, 50.0),
+        ];
+        let polygon = Annotation::polygon(&vertices).border(StrokeStyle::new(3.0));
+        assert_eq!(
+            polygon
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Polygon /Rect [8.5 8.5 61.5 51.5] /F 4 /C [1 0 0] \
+             /BS << /W 3 /S /S >> /Vertices [10 10 60 20 30 50] >>"
+        );
+        let polyline = Annotation::polyline(&vertices[..2]);
+        assert!(
+            polyline
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string()
+                .ends_with("/Subtype /PolyLine /Rect [9.5 9.5 60.5 20.5] /F 4 /C [1 0 0] /Vertices [10 10 60 20] >>")
+        );
+        let refs = ResourceRefs::default();
+        assert!(
+            Annotation::polygon(&vertices[..2])
+                .to_pdf(&[], &refs)
+                .is_err()
+        );
+        assert!(
+            Annotation::polyline(&vertices[..1])
+                .to_pdf(&[], &refs)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_ink() {
+        let strokes = vec![
+            vec![
+                Point::new(100.0, 50.0),
+                Point::new(110.0, 62.5),
+                Point::new(120.0, 48.0),
+            ],
+            vec![Point::new(125.0, 55.0), Point::new(140.0, 55.0)],
+        ];
+        let ink = Annotation::ink(&strokes).border(StrokeStyle::new(2.0));
+        assert_eq!(
+            ink.to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Ink /Rect [99 47 141 63.5] /F 4 /C [0] \
+             /BS << /W 2 /S /S >> /InkList [[100 50 110 62.5 120 48] [125 55 140 55]] >>"
+        );
+    }
+
+    #[test]
+    fn test_ink_without_points() {
+        let ink = Annotation::ink(&[Vec::new()]);
+        assert!(ink.to_pdf(&[], &ResourceRefs::default()).is_err());
+    }
+
+    #[test]
+    fn test_stamps() {
+        let approved = Annotation::stamp(Rect::new(400.0, 700.0, 150.0, 50.0), StampName::Approved);
+        assert_eq!(
+            approved
+                .to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Stamp /Rect [400 700 550 750] /F 4 /Name /Approved >>"
+        );
+
+        let seal = FormXObject::new(60.0, 60.0, |g| {
+            g.circle(
+                (30.0, 30.0),
+                28.0,
+                &Paint::stroke(Color::Rgb(0.0, 0.0, 0.6)),
+            );
+        });
+        let stamp =
+            Annotation::custom_stamp(Rect::new(0.0, 0.0, 90.0, 90.0), "Seal #1", seal.clone());
+        let mut refs = ResourceRefs::default();
+        refs.forms.insert(seal, ObjectRef::new(12));
+        assert_eq!(
+            stamp.to_pdf(&[], &refs).unwrap().to_string(),
+            "<< /Type /Annot /Subtype /Stamp /Rect [0 0 90 90] /F 4 /AP << /N 12 0 R >> \
+             /Name /Seal#20#231 >>"
+        );
+    }
+
+    #[test]
+    fn test_file_attachment() {
+        let file = EmbeddedFile::new("totals.csv", "text/csv", b"total,42\n".to_vec());
+        let attachment =
+            Annotation::file_attachment(Rect::new(20.0, 20.0, 16.0, 24.0), file.clone())
+                .attachment_icon(AttachmentIcon::PushPin);
+        assert_eq!(attachment.attached_file(), Some(&file));
+        let mut refs = ResourceRefs::default();
+        refs.files.insert(file, ObjectRef::new(8));
+        assert_eq!(
+            attachment.to_pdf(&[], &refs).unwrap().to_string(),
+            "<< /Type /Annot /Subtype /FileAttachment /Rect [20 20 36 44] /F 28 \
+             /Contents (totals.csv) /FS << /Type /Filespec /F (totals.csv) /UF (totals.csv) \
+             /EF << /F 8 0 R /UF 8 0 R >> >> /Name /PushPin >>"
+        );
+    }
+
+    #[test]
+    fn test_note() {
+        let note = Annotation::note(Rect::new(500.0, 780.0, 20.0, 20.0), "Check the total")
+            .author("Reviewer")
+            .icon(NoteIcon::Comment)
+            .color(Color::Rgb(1.0, 0.8, 0.0))
+            .modified(PdfDate::new(2024, 3, 1).unwrap())
+            .open(true);
+        assert_eq!(
+            note.to_pdf(&[], &ResourceRefs::default())
+                .unwrap()
+                .to_string(),
+            "<< /Type /Annot /Subtype /Text /Rect [500 780 520 800] /F 28 \
+             /Contents (Check the total) /T (Reviewer) /C [1 0.8 0] /M (D:20240301000000Z) \
+             /Name /Comment /Open true >>"
+        );
+
+        let note = Annotation::note(Rect::new(0.0, 0.0, 20.0, 20.0), "Spot").color(Color::Spot(
+            crate::SpotColor::rgb("Gold", 0.8, 0.6, 0.2),
+            1.0,
+        ));
+        assert!(note.to_pdf(&[], &ResourceRefs::default()).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_bidi::{
+    BidiClass, Direction, Level, ParagraphBidiInfo, bidi_class, get_base_direction,
+};
+
+/// First and last of the Arabic letters with presentation forms.
+const ARABIC_FIRST: char = '\u{0621}';
+const ARABIC_LAST: char = '\u{064A}';
+
+/// Number of presentation forms of each letter from U+0621 to U+064A, in
+/// the order they follow each other from U+FE80 in Arabic Presentation
+/// Forms-B: 1 for letters that never join, 2 for letters joining only the
+/// preceding one (isolated and final forms), 4 for letters joining on both
+/// sides (isolated, final, initial and medial forms).
+const FORMS: [u8; 42] = [
+    1, 2, 2, 2, 2, 4, 2, 4, 2, 4, 4, 4, 4, 4, 2, 2, 2, 2, 4, 4, 4, 4, 4, 4, 4,
+    4, // U+0621..U+063A
+    0, 0, 0, 0, 0, 0, // U+063B..U+0640, tatweel at the end
+    4, 4, 4, 4, 4, 4, 4, 2, 2, 4, // U+0641..U+064A
+];
+
+const TATWEEL: char = '\u{0640}';
+const LAM: char = '\u{0644}';
+
+/// Isolated and final forms of lam followed by alef with madda, with hamza
+/// above, with hamza below and plain alef.
+const LAM_ALEF: [(char, char); 4] = [
+    ('\u{0622}', '\u{FEF5}'),
+    ('\u{0623}', '\u{FEF7}'),
+    ('\u{0625}', '\u{FEF9}'),
+    ('\u{0627}', '\u{FEFB}'),
+];
+
+/// How a character joins its neighbours in cursive Arabic script.
+#[derive(Clone, Copy, PartialEq)]
+enum Joining {
+    None,
+    /// Joins the preceding character only.
+    Right,
+    /// Joins both neighbours.
+    Dual,
+    /// Tatweel, which joins both neighbours but has no forms of its own.
+    Causing,
+    /// Combining marks, skipped when looking for neighbours.
+    Transparent,
+}
+
+fn joining(ch: char) -> Joining {
+    if ch == TATWEEL {
+        return Joining::Causing;
+    }
+    if bidi_class(ch) == BidiClass::NSM {
+        return Joining::Transparent;
+    }
+    match forms(ch) {
+        Some((_, 2)) => Joining::Right,
+        Some((_, 4)) => Joining::Dual,
+        _ => Joining::None,
+    }
+}
+
+/// The first presentation form of an Arabic letter and how many it has.
+fn forms(ch: char) -> Option<(u32, u8)> {
+    if !(ARABIC_FIRST..=ARABIC_LAST).contains(&ch) {
+        return None;
+    }
+    let index = (ch as u32 - ARABIC_FIRST as u32) as usize;
+    let first = 0xFE80
+        + FORMS[..index]
+            .iter()
+            .map(|count| *count as u32)
+            .sum::<u32>();
+    Some((first, FORMS[index])).filter(|(_, count)| *count > 0)
+}
+
+/// Replaces Arabic letters by the presentation forms matching how they join
+/// their neighbours, including the lam-alef ligatures, so fonts without
+/// shaping tables still show connected script. Forms `has_glyph` rejects are
+/// left as the plain letter.
+pub(crate) fn shape_arabic(text: &str, has_glyph: impl Fn(char) -> bool) -> Cow<'_, str> {
+    if !text.chars().any(|ch| forms(ch).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let neighbour = |indices: &mut dyn Iterator<Item = usize>| {
+        indices
+            .map(|index| joining(chars[index]))
+            .find(|joining| *joining != Joining::Transparent)
+            .unwrap_or(Joining::None)
+    };
+    let mut shaped = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        let joins_previous = matches!(
+            neighbour(&mut (0..index).rev()),
+            Joining::Dual | Joining::Causing
+        );
+        let next = chars.get(index + 1).copied();
+        let lam_alef = LAM_ALEF.iter().find(|(alef, _)| Some(*alef) == next);
+        let form = match (ch, lam_alef) {
+            (LAM, Some((_, isolated))) => {
+                index += 1;
+                Some(*isolated as u32 + joins_previous as u32)
+            }
+            _ => forms(ch).map(|(first, count)| {
+                let joins_next = count == 4
+                    && matches!(
+                        neighbour(&mut (index + 1..chars.len())),
+                        Joining::Right | Joining::Dual | Joining::Causing
+                    );
+                // Isolated, final, initial and medial forms follow each other.
+                match count {
+                    1 => first,
+                    _ => first + joins_previous as u32 + 2 * joins_next as u32,
+                }
+            }),
+        };
+        match form
+            .and_then(char::from_u32)
+            .filter(|form| has_glyph(*form))
+        {
+            Some(form) => shaped.push(form),
+            None => {
+                shaped.push(ch);
+                if ch == LAM && lam_alef.is_some() {
+                    shaped.push(chars[index]);
+                }
+            }
+        }
+        index += 1;
+    }
+    Cow::Owned(shaped)
+}
+
+/// Embedding level of a paragraph, set by its first strong character.
+pub(crate) fn base_level(text: &str) -> Level {
+    match get_base_direction(text) {
+        Direction::Rtl => Level::rtl(),
+        _ => Level::ltr(),
+    }
+}
+
+/// Reorders a line given as `pieces` of text in logical order into the
+/// left-to-right order it is drawn in, following the Unicode bidirectional
+/// algorithm for a paragraph at level `base`. Pieces in right-to-left runs
+/// have their characters reversed, keeping combining marks behind their
+/// base character and mirroring brackets. Each piece of the result carries
+/// the index of the piece it was cut from.
+pub(crate) fn reorder_line<'t>(pieces: &[&'t str], base: Level) -> Vec<(usize, Cow<'t, str>)> {
+    let text = pieces.concat();
+    let info = ParagraphBidiInfo::new(&text, Some(base));
+    if !info.has_rtl() {
+        return pieces
+            .iter()
+            .enumerate()
+            .map(|(index, piece)| (index, Cow::Borrowed(*piece)))
+            .collect();
+    }
+    let starts: Vec<usize> = pieces
+        .iter()
+        .scan(0, |offset, piece| {
+            let start = *offset;
+            *offset += piece.len();
+            Some(start)
+        })
+        .collect();
+    let (levels, runs) = info.visual_runs(0..text.len());
+    let mut reordered = Vec::new();
+    for run in runs {
+        let rtl = levels[run.start].is_rtl();
+        let mut parts: Vec<(usize, Range<usize>)> = pieces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, piece)| {
+                let start = run.start.max(starts[index]);
+                let end = run.end.min(starts[index] + piece.len());
+                (start < end).then(|| (index, start - starts[index]..end - starts[index]))
+            })
+            .collect();
+        if rtl {
+            parts.reverse();
+        }
+        for (index, range) in parts {
+            let part = &pieces[index][range];
+            let part = if rtl {
+                Cow::Owned(reverse(part))
+            } else {
+                Cow::Borrowed(part)
+            };
+            reordered.push((index, part));
+        }
+    }
+    reordered
+}
+
+/// `text` in reverse order, combining marks staying behind their base
+/// character, with brackets mirrored.
+fn reverse(text: &str) -> String {
+    let mut clusters: Vec<String> = Vec::new();
+    for ch in text.chars() {
+        match clusters.last_mut() {
+            Some(cluster) if bidi_class(ch) == BidiClass::NSM => cluster.push(ch),
+            _ => clusters.push(mirror(ch).to_string()),
+        }
+    }
+    clusters.iter().rev().map(String::as_str).collect()
+}
+
+fn mirror(ch: char) -> char {
+    match ch {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joining_forms() {
+        // beh, alef, beh: initial beh, final alef, isolated beh (alef does
+        // not join the following letter).
+        assert_eq!(shape_arabic("باب", |_| true), "\u{FE91}\u{FE8E}\u{FE8F}");
+        // seen, lam, alef: initial seen, then the final lam-alef ligature.
+        assert_eq!(shape_arabic("سلا", |_| true), "\u{FEB3}\u{FEFC}");
+        // A combining mark between beh and teh does not break the join.
+        assert_eq!(shape_arabic("بَت", |_| true), "\u{FE91}\u{064E}\u{FE96}");
+        assert_eq!(shape_arabic("باب", |_| false), "باب");
+        assert!(matches!(shape_arabic("plain", |_| true), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_reorder_line() {
+        let texts = |pieces: Vec<(usize, Cow<str>)>| {
+            pieces
+                .into_iter()
+                .map(|(index, text)| format!("{}:{}", index, text))
+                .collect::<Vec<_>>()
+        };
+        let ltr = Level::ltr();
+        assert_eq!(
+            texts(reorder_line(&["abc ", "def"], ltr)),
+            ["0:abc ", "1:def"]
+        );
+        // Hebrew inside English: the Hebrew run is reversed in place.
+        assert_eq!(
+            texts(reorder_line(&["go ", "שלום", " now"], ltr)),
+            ["0:go ", "1:םולש", "2: now"]
+        );
+        // An English word and a number inside a Hebrew paragraph.
+        assert_eq!(
+            texts(reorder_line(&["שלום (ok) 12"], base_level("שלום"))),
+            ["0:12", "0: (", "0:ok", "0:) םולש"]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::font::Font;
+use crate::manual::PdfDocument;
+use crate::page::{Orientation, Page, PageSize, Rotation};
+
+/// Fluent front end over [`PdfDocument`] that takes care of object numbering
+/// and resource wiring, so a document is described by its content only.
+///
+/// ```
+/// use pdf_creator::PdfBuilder;
+///
+/// let mut builder = PdfBuilder::new();
+/// builder
+///     .page()
+///     .text("Hello")
+///     .text("World")
+///     .finish()
+///     .page()
+///     .text("Second page")
+///     .finish();
+/// let doc = builder.build();
+/// assert_eq!(doc.page_count(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct PdfBuilder {
+    doc: PdfDocument,
+}
+
+impl PdfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new page. The page is appended to the document once
+    /// [`PageBuilder::finish`] is called.
+    pub fn page(&mut self) -> PageBuilder<'_> {
+        self.sized_page(PageSize::default())
+    }
+
+    /// Like [`PdfBuilder::page`], for a page of the given size instead of A4.
+    pub fn sized_page(&mut self, size: PageSize) -> PageBuilder<'_> {
+        PageBuilder {
+            builder: self,
+            page: Page::new(size),
+        }
+    }
+
+    pub fn build(self) -> PdfDocument {
+        self.doc
+    }
+}
+
+#[derive(Debug)]
+pub struct PageBuilder<'a> {
+    builder: &'a mut PdfBuilder,
+    page: Page,
+}
+
+impl<'a> PageBuilder<'a> {
+    pub fn landscape(mut self) -> Self {
+        self.page.set_orientation(Orientation::Landscape);
+        self
+    }
+
+    pub fn rotate(mut self, rotation: Rotation) -> Self {
+        self.page.set_rotation(rotation);
+        self
+    }
+
+    /// Adds a line of text below the previous one.
+    pub fn text(mut self, text: &str) -> Self {
+        self.page.add_line(text);
+        self
+    }
+
+    /// Shows `text` at an arbitrary position, see [`ContentStream::draw_text`].
+    ///
+    /// [`ContentStream::draw_text`]: crate::ContentStream::draw_text
+    pub fn text_at(mut self, x: f32, y: f32, size: f32, font: impl Into<Font>, text: &str) -> Self {
+        self.page.draw_text(x, y, size, font, text);
+        self
+    }
+
+    pub fn finish(self) -> &'a mut PdfBuilder {
+        self.builder.doc.add_page(self.page);
+        self.builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_pages() {
+        let mut builder = PdfBuilder::new();
+        builder
+            .page()
+            .text("One")
+            .text("Two")
+            .finish()
+            .sized_page(PageSize::Letter)
+            .finish();
+        let mut doc = builder.build();
+        assert_eq!(doc.page_count(), 2);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/Kids [4 0 R 6 0 R] /Count 2"));
+        assert!(pdf.contains("100 700 Td\n(One) Tj\nET\n"));
+        assert!(pdf.contains("100 672 Td\n(Two) Tj\nET\n"));
+        assert!(pdf.contains("/Size 8 "));
+        assert!(pdf.contains("/MediaBox [0 0 612 792]"));
+    }
+}
@@ -0,0 +1,430 @@
+use crate::icc::IccProfile;
+use crate::object::Object;
+use crate::pattern::{Pattern, TilingPattern};
+use crate::resources::{ResourceRefs, Resources};
+use crate::shading::{Gradient, interpolation};
+use std::sync::Arc;
+
+/// A color, with components from 0 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Color {
+    /// `DeviceGray`: 0 is black, 1 white.
+    Gray(f32),
+    /// `DeviceRGB`.
+    Rgb(f32, f32, f32),
+    /// `DeviceCMYK`.
+    Cmyk(f32, f32, f32, f32),
+    /// A tint of a spot color, from 0 (no ink) to 1 (full strength).
+    Spot(SpotColor, f32),
+    /// Components in the color space of an ICC profile, made by
+    /// [`IccProfile::color`].
+    Icc(IccProfile, Vec<f32>),
+    /// A gradient painted through a shading pattern.
+    Gradient(Box<Gradient>),
+    /// A cell repeated side by side through a tiling pattern.
+    Tiling(TilingPattern),
+}
+
+/// An ink of its own, such as a PANTONE color, printed on a plate of its
+/// own: a `/Separation` color space. Devices without the ink show the
+/// alternate device color instead, lightened in proportion to the tint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotColor {
+    name: Arc<str>,
+    /// The alternate color space and the components of the full ink in it.
+    alternate: (&'static str, Arc<[f32]>),
+}
+
+impl SpotColor {
+    /// The ink `name`, looking like the given CMYK color at full strength.
+    pub fn cmyk(name: &str, c: f32, m: f32, y: f32, k: f32) -> Self {
+        Self {
+            name: name.into(),
+            alternate: ("DeviceCMYK", Arc::new([c, m, y, k])),
+        }
+    }
+
+    /// The ink `name`, looking like the given RGB color at full strength.
+    pub fn rgb(name: &str, r: f32, g: f32, b: f32) -> Self {
+        Self {
+            name: name.into(),
+            alternate: ("DeviceRGB", Arc::new([r, g, b])),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// This ink at `tint`, from 0 to 1.
+    pub fn tint(&self, tint: f32) -> Color {
+        Color::Spot(self.clone(), tint)
+    }
+
+    /// The `/Separation` color space array, its tint transform going
+    /// linearly from no ink (white) to the full alternate color.
+    fn to_color_space(&self) -> Object {
+        let (space, full) = &self.alternate;
+        let white = if *space == "DeviceCMYK" { 0.0 } else { 1.0 };
+        let none = vec![white; full.len()];
+        Object::Array(vec![
+            Object::name("Separation"),
+            Object::name(&*self.name),
+            Object::name(*space),
+            interpolation(&none, full).into(),
+        ])
+    }
+}
+
+/// A color space other than the device ones, named in the `/ColorSpace`
+/// resources of the content using it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ColorSpace {
+    Separation(SpotColor),
+    /// Refers to the profile stream, written once for the whole document.
+    IccBased(IccProfile),
+}
+
+impl ColorSpace {
+    pub(crate) fn to_pdf(&self, refs: &ResourceRefs) -> Object {
+        match self {
+            ColorSpace::Separation(spot) => spot.to_color_space(),
+            ColorSpace::IccBased(profile) => {
+                vec![Object::name("ICCBased"), refs.profiles[profile].into()].into()
+            }
+        }
+    }
+}
+
+impl Color {
+    pub const BLACK: Color = Color::Gray(0.0);
+    pub const WHITE: Color = Color::Gray(1.0);
+
+    /// An RGB color from 8-bit components, as in web and design tools.
+    pub fn rgb8(r: u8, g: u8, b: u8) -> Self {
+        Color::Rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+
+    /// Parses a `#rrggbb` or `#rgb` hex color; the `#` is optional.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        match hex.len() {
+            6 => Some(Self::from_u24(value)),
+            3 => {
+                // Each digit stands for itself twice: #f80 is #ff8800.
+                let [r, g, b] = [8, 4, 0].map(|shift| ((value >> shift) & 0xF) as u8 * 17);
+                Some(Self::rgb8(r, g, b))
+            }
+            _ => None,
+        }
+    }
+
+    /// One of the 148 named colors of CSS, such as `"navy"` or
+    /// `"rebeccapurple"`, ignoring case. Convenient for screen documents;
+    /// print work should give real CMYK values instead.
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        NAMED_COLORS
+            .binary_search_by(|(candidate, _)| (*candidate).cmp(name.as_str()))
+            .ok()
+            .map(|index| Self::from_u24(NAMED_COLORS[index].1))
+    }
+
+    fn from_u24(value: u32) -> Self {
+        let [_, r, g, b] = value.to_be_bytes();
+        Self::rgb8(r, g, b)
+    }
+
+    /// The color space of this color as written in shading dictionaries.
+    pub(crate) fn space(&self, refs: &ResourceRefs) -> Object {
+        match self {
+            Color::Gray(_) => Object::name("DeviceGray"),
+            Color::Rgb(..) => Object::name("DeviceRGB"),
+            Color::Cmyk(..) => Object::name("DeviceCMYK"),
+            Color::Spot(spot, _) => ColorSpace::Separation(spot.clone()).to_pdf(refs),
+            Color::Icc(profile, _) => ColorSpace::IccBased(profile.clone()).to_pdf(refs),
+            Color::Gradient(_) | Color::Tiling(_) => Object::name("Pattern"),
+        }
+    }
+
+    /// The components of this color in the color space of `other`, if it
+    /// can be painted there. Grays also go into RGB and CMYK.
+    pub(crate) fn components_like(&self, other: &Color) -> Option<Vec<f32>> {
+        match (self, other) {
+            (Color::Gray(gray), Color::Gray(_)) => Some(vec![*gray]),
+            (Color::Gray(gray), Color::Rgb(..)) => Some(vec![*gray; 3]),
+            (Color::Gray(gray), Color::Cmyk(..)) => Some(vec![0.0, 0.0, 0.0, 1.0 - gray]),
+            (Color::Rgb(r, g, b), Color::Rgb(..)) => Some(vec![*r, *g, *b]),
+            (Color::Cmyk(c, m, y, k), Color::Cmyk(..)) => Some(vec![*c, *m, *y, *k]),
+            (Color::Spot(spot, tint), Color::Spot(other, _)) if spot == other => Some(vec![*tint]),
+            (Color::Icc(profile, components), Color::Icc(other, _)) if profile == other => {
+                Some(components.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// The operators setting this color for filling, including text.
+    /// Color spaces other than the device ones are registered in
+    /// `resources`.
+    pub(crate) fn fill_op(&self, resources: &mut Resources) -> String {
+        self.set_op(false, resources)
+    }
+
+    /// The operators setting this color for stroking lines and outlines.
+    pub(crate) fn stroke_op(&self, resources: &mut Resources) -> String {
+        self.set_op(true, resources)
+    }
+
+    fn set_op(&self, stroke: bool, resources: &mut Resources) -> String {
+        let op = match self {
+            Color::Gray(gray) => {
+                resources.device_space("DeviceGray");
+                format!("{} g", gray)
+            }
+            Color::Rgb(r, g, b) => {
+                resources.device_space("DeviceRGB");
+                format!("{} {} {} rg", r, g, b)
+            }
+            Color::Cmyk(c, m, y, k) => {
+                resources.device_space("DeviceCMYK");
+                format!("{} {} {} {} k", c, m, y, k)
+            }
+            Color::Spot(spot, tint) => {
+                let name = resources.color_space(&ColorSpace::Separation(spot.clone()));
+                format!("/{} cs {} scn", name, tint)
+            }
+            Color::Icc(profile, components) => {
+                let name = resources.color_space(&ColorSpace::IccBased(profile.clone()));
+                let components: Vec<String> = components.iter().map(f32::to_string).collect();
+                format!("/{} cs {} scn", name, components.join(" "))
+            }
+            Color::Gradient(gradient) => {
+                let pattern = Pattern::Shading(Gradient::clone(gradient));
+                format!("/Pattern cs /{} scn", resources.pattern(&pattern))
+            }
+            Color::Tiling(tiling) => {
+                let pattern = Pattern::Tiling(tiling.clone());
+                format!("/Pattern cs /{} scn", resources.pattern(&pattern))
+            }
+        };
+        // Stroking operators are the uppercase forms of the filling ones.
+        // Spot, ICC and pattern colors select their color space first.
+        if stroke {
+            let (operands, operator) = op.rsplit_once(' ').unwrap();
+            let operands = operands.replace(" cs", " CS");
+            format!("{} {}", operands, operator.to_uppercase())
+        } else {
+            op
+        }
+    }
+}
+
+/// The CSS named colors as `0xRRGGBB`, sorted by name.
+#[rustfmt::skip]
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_web_colors() {
+        assert_eq!(Color::named("Red"), Some(Color::Rgb(1.0, 0.0, 0.0)));
+        assert_eq!(Color::named("rebeccapurple"), Color::from_hex("#639"));
+        assert_eq!(Color::named("grey"), Color::named("gray"));
+        assert_eq!(Color::named("no such color"), None);
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(Color::from_hex("ff8000"), Some(Color::rgb8(255, 128, 0)));
+        assert_eq!(Color::from_hex("#12345"), None);
+        assert_eq!(Color::from_hex("#+12"), None);
+    }
+
+    #[test]
+    fn test_fill_ops() {
+        let mut resources = Resources::default();
+        let mut fill = |color: Color| color.fill_op(&mut resources);
+        assert_eq!(fill(Color::Gray(0.5)), "0.5 g");
+        assert_eq!(fill(Color::Rgb(1.0, 0.0, 0.25)), "1 0 0.25 rg");
+        assert_eq!(fill(Color::Cmyk(0.0, 0.0, 0.0, 1.0)), "0 0 0 1 k");
+        let mut stroke = |color: Color| color.stroke_op(&mut resources);
+        assert_eq!(stroke(Color::Gray(0.5)), "0.5 G");
+        assert_eq!(stroke(Color::Rgb(1.0, 0.0, 0.25)), "1 0 0.25 RG");
+    }
+
+    #[test]
+    fn test_spot_colors() {
+        let mut resources = Resources::default();
+        let pantone = SpotColor::cmyk("PANTONE 185 C", 0.0, 0.91, 0.76, 0.0);
+        let gold = SpotColor::rgb("Gold", 0.8, 0.6, 0.2);
+        assert_eq!(pantone.tint(0.5).fill_op(&mut resources), "/CS1 cs 0.5 scn");
+        assert_eq!(gold.tint(1.0).stroke_op(&mut resources), "/CS2 CS 1 SCN");
+        assert_eq!(pantone.tint(1.0).stroke_op(&mut resources), "/CS1 CS 1 SCN");
+        assert_eq!(
+            ColorSpace::Separation(pantone)
+                .to_pdf(&ResourceRefs::default())
+                .to_string(),
+            "[/Separation /PANTONE#20185#20C /DeviceCMYK << /FunctionType 2 /Domain [0 1] \
+             /C0 [0 0 0 0] /C1 [0 0.91 0.76 0] /N 1 >>]"
+        );
+        assert!(
+            ColorSpace::Separation(gold)
+                .to_pdf(&ResourceRefs::default())
+                .to_string()
+                .contains("/C0 [1 1 1] /C1 [0.8 0.6 0.2]")
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::icc::IccProfile;
+use crate::manual::PdfVersion;
+use crate::metadata::XmpNamespace;
+use crate::object::{Dictionary, Object};
+use crate::resources::ResourceRefs;
+
+/// A standard for documents of a kind, such as archives or print jobs,
+/// which checkers hold files to. A document written to one is checked
+/// against what it asks as it is written, which fails, telling what to
+/// change, if the document breaks it. Set with
+/// [`PdfDocument::set_conformance`](crate::PdfDocument::set_conformance).
+///
+/// Colors given in RGB or CMYK, rather than an ICC profile, are taken to be
+/// in the space of the output intent, so only grays and those of the color
+/// space of its profile can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conformance {
+    /// PDF/A-1b, ISO 19005-1 level B, for archives: a document that shows
+    /// the same in any reader for as long as it is kept. It has its fonts
+    /// embedded, its colors tied to an output intent and its metadata in
+    /// XMP, and has nothing transparent, no encryption, layers or attached
+    /// files, and nothing newer than PDF 1.4.
+    PdfA1b,
+    /// PDF/A-2b, ISO 19005-2 level B: PDF/A-1b brought up to PDF 1.7, which
+    /// lets documents have transparency, layers and JPEG 2000 images, but
+    /// asks every annotation but links to have an appearance of its own.
+    PdfA2b,
+    /// PDF/A-2u, ISO 19005-2 level U: PDF/A-2b with the text of the document
+    /// mapped to Unicode, such that it can be searched and copied, as that
+    /// set in TrueType fonts always is.
+    PdfA2u,
+    /// PDF/A-3b, ISO 19005-3 level B: PDF/A-2b with files of any kind
+    /// attached to the document, each telling what it is to the document,
+    /// such as the XML of a ZUGFeRD or Factur-X invoice.
+    PdfA3b,
+    /// PDF/A-3u, ISO 19005-3 level U: PDF/A-3b with its text mapped to
+    /// Unicode, as PDF/A-2u has it.
+    PdfA3u,
+    /// PDF/X-4, ISO 15930-7, for print production: a document printers take
+    /// as is, with the size each page is cut to, its fonts embedded, its
+    /// colors tied to the output intent of the press, and whether it is
+    /// trapped. It has no annotations but links, nor form fields, which
+    /// printers leave out, and nothing newer than PDF 1.6, transparency and
+    /// layers allowed.
+    PdfX4,
+}
+
+/// The color conditions a document is meant to be shown or printed in: an
+/// ICC profile of the device, such as sRGB for screens or FOGRA39 for
+/// offset printing, and its name. Set with
+/// [`PdfDocument::set_output_intent`](crate::PdfDocument::set_output_intent).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputIntent {
+    profile: IccProfile,
+    identifier: String,
+    info: Option<String>,
+}
+
+impl Conformance {
+    /// The name of the standard, such as `PDF/A-1b`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Conformance::PdfA1b => "PDF/A-1b",
+            Conformance::PdfA2b => "PDF/A-2b",
+            Conformance::PdfA2u => "PDF/A-2u",
+            Conformance::PdfA3b => "PDF/A-3b",
+            Conformance::PdfA3u => "PDF/A-3u",
+            Conformance::PdfX4 => "PDF/X-4",
+        }
+    }
+
+    /// The part of ISO 19005 defining the standard, if it is PDF/A.
+    fn pdf_a_part(self) -> Option<u8> {
+        match self {
+            Conformance::PdfA1b => Some(1),
+            Conformance::PdfA2b | Conformance::PdfA2u => Some(2),
+            Conformance::PdfA3b | Conformance::PdfA3u => Some(3),
+            Conformance::PdfX4 => None,
+        }
+    }
+
+    /// The latest version of PDF the standard is based on.
+    pub(crate) fn version(self) -> PdfVersion {
+        match self {
+            Conformance::PdfA1b => PdfVersion::Pdf14,
+            Conformance::PdfX4 => PdfVersion::Pdf16,
+            _ => PdfVersion::Pdf17,
+        }
+    }
+
+    /// The XMP schema identifying the standard.
+    pub(crate) fn xmp(self) -> XmpNamespace {
+        let Some(part) = self.pdf_a_part() else {
+            return XmpNamespace::new("pdfxid", "http://www.npes.org/pdfx/ns/id/")
+                .property("GTS_PDFXVersion", self.name());
+        };
+        let level = match self {
+            Conformance::PdfA2u | Conformance::PdfA3u => "U",
+            _ => "B",
+        };
+        XmpNamespace::new("pdfaid", "http://www.aiim.org/pdfa/ns/id/")
+            .property("part", &part.to_string())
+            .property("conformance", level)
+    }
+
+    /// The subtype of output intents for the standard.
+    pub(crate) fn output_intent_subtype(self) -> &'static str {
+        match self {
+            Conformance::PdfX4 => "GTS_PDFX",
+            // All parts of PDF/A keep to that of the first.
+            _ => "GTS_PDFA1",
+        }
+    }
+
+    /// Whether embedded font subsets must tell the glyphs they have in a
+    /// `/CIDSet`.
+    pub(crate) fn needs_cid_set(self) -> bool {
+        matches!(self, Conformance::PdfA1b)
+    }
+
+    /// Whether things can be drawn with transparency, and in layers.
+    pub(crate) fn allows_transparency(self) -> bool {
+        self != Conformance::PdfA1b
+    }
+
+    /// Whether the annotations, links left aside, must have appearances of
+    /// their own.
+    pub(crate) fn needs_appearances(self) -> bool {
+        self.pdf_a_part().is_some_and(|part| part > 1)
+    }
+
+    /// Whether files can be attached to the document.
+    pub(crate) fn allows_attached_files(self) -> bool {
+        self.pdf_a_part() == Some(3)
+    }
+
+    /// Whether the document is meant for printing, which asks for the boxes
+    /// of its pages and whether it is trapped, and leaves out annotations
+    /// and form fields.
+    pub(crate) fn is_for_print(self) -> bool {
+        self == Conformance::PdfX4
+    }
+}
+
+impl OutputIntent {
+    /// The conditions `profile` describes, named `identifier`, such as
+    /// "sRGB IEC61966-2.1", or for printing a registered name such as
+    /// "FOGRA39" that printers know. The profile must be of a monitor or a
+    /// printer.
+    pub fn new(identifier: &str, profile: IccProfile) -> Self {
+        Self {
+            profile,
+            identifier: identifier.to_string(),
+            info: None,
+        }
+    }
+
+    /// A description of the conditions for people, such as "Coated FOGRA39
+    /// (ISO 12647-2:2004)".
+    pub fn info(mut self, info: &str) -> Self {
+        self.info = Some(info.to_string());
+        self
+    }
+
+    pub(crate) fn profile(&self) -> &IccProfile {
+        &self.profile
+    }
+
+    /// The output intent dictionary, of `subtype`.
+    pub(crate) fn to_pdf(&self, subtype: &str, refs: &ResourceRefs) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("OutputIntent"));
+        dict.set("S", Object::name(subtype));
+        dict.set("OutputConditionIdentifier", Object::text(&self.identifier));
+        if let Some(info) = &self.info {
+            dict.set("Info", Object::text(info));
+        }
+        dict.set("DestOutputProfile", refs.profiles[&self.profile]);
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icc::tests::profile_header;
+    use crate::object::ObjectRef;
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(Conformance::PdfA2u.name(), "PDF/A-2u");
+        assert_eq!(Conformance::PdfA1b.version(), PdfVersion::Pdf14);
+        assert_eq!(Conformance::PdfA3b.version(), PdfVersion::Pdf17);
+        assert!(!Conformance::PdfA3u.needs_cid_set());
+        let xmp = XmpNamespace::new("pdfaid", "http://www.aiim.org/pdfa/ns/id/")
+            .property("part", "3")
+            .property("conformance", "U");
+        assert_eq!(Conformance::PdfA3u.xmp(), xmp);
+        assert_eq!(Conformance::PdfX4.version(), PdfVersion::Pdf16);
+        assert_eq!(Conformance::PdfX4.output_intent_subtype(), "GTS_PDFX");
+        assert!(Conformance::PdfX4.allows_transparency());
+        assert!(!Conformance::PdfX4.allows_attached_files());
+        let xmp = XmpNamespace::new("pdfxid", "http://www.npes.org/pdfx/ns/id/")
+            .property("GTS_PDFXVersion", "PDF/X-4");
+        assert_eq!(Conformance::PdfX4.xmp(), xmp);
+    }
+
+    #[test]
+    fn test_output_intent() {
+        let profile = IccProfile::from_bytes(profile_header(b"RGB ")).unwrap();
+        let mut refs = ResourceRefs::default();
+        refs.profiles.insert(profile.clone(), ObjectRef::new(7));
+        let intent = OutputIntent::new("sRGB IEC61966-2.1", profile).info("sRGB");
+        assert_eq!(
+            intent
+                .to_pdf(Conformance::PdfA1b.output_intent_subtype(), &refs)
+                .to_string(),
+            "<< /Type /OutputIntent /S /GTS_PDFA1 /OutputConditionIdentifier \
+             (sRGB IEC61966-2.1) /Info (sRGB) /DestOutputProfile 7 0 R >>"
+        );
+    }
+}
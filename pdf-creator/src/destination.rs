@@ -0,0 +1,186 @@
+use crate::object::{Dictionary, Object, ObjectRef};
+use anyhow::{Result, ensure};
+use std::collections::BTreeMap;
+
+/// A place in the document that bookmarks and links take the reader to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destination {
+    target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    /// Index of the page, from 0, and how it is shown.
+    Page(usize, View),
+    /// A destination registered with
+    /// [`PdfDocument::add_named_destination`](crate::PdfDocument::add_named_destination).
+    Named(String),
+}
+
+/// What part of a page a destination shows, and how large.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    /// The whole page, fitted in the window.
+    Fit,
+    /// The page fitted to the width of the window, `top` at the top edge.
+    FitWidth { top: f32 },
+    /// The point (`left`, `top`) at the top left corner of the window, at a
+    /// zoom factor. Anything left out stays as the reader has it.
+    Xyz {
+        left: Option<f32>,
+        top: Option<f32>,
+        zoom: Option<f32>,
+    },
+}
+
+impl Destination {
+    /// The page at `index`, counting from 0, fitted in the window.
+    pub fn page(index: usize) -> Self {
+        Self {
+            target: Target::Page(index, View::Fit),
+        }
+    }
+
+    /// The page at `index` fitted to the width of the window and scrolled
+    /// for `top`, in the page's coordinates, to be at the top of the window.
+    pub fn fit_width(index: usize, top: f32) -> Self {
+        Self {
+            target: Target::Page(index, View::FitWidth { top }),
+        }
+    }
+
+    /// The page at `index` scrolled for the point (`left`, `top`) to be at
+    /// the top left corner of the window, and shown at `zoom`, 1 being
+    /// 100%. Coordinates and zoom left out keep their current value, so
+    /// `Destination::xyz(3, None, Some(500.0), None)` scrolls to y = 500 on
+    /// the fourth page without zooming.
+    pub fn xyz(index: usize, left: Option<f32>, top: Option<f32>, zoom: Option<f32>) -> Self {
+        Self {
+            target: Target::Page(index, View::Xyz { left, top, zoom }),
+        }
+    }
+
+    /// The destination registered as `name`, looked up by the reader. Other
+    /// documents may link to it too, as in `report.pdf#name`.
+    pub fn named(name: &str) -> Self {
+        Self {
+            target: Target::Named(name.to_string()),
+        }
+    }
+
+    /// The destination as written in bookmarks and links: an explicit
+    /// destination array, `pages` being the page objects in order, or a
+    /// name.
+    pub(crate) fn to_pdf(&self, pages: &[ObjectRef]) -> Result<Object> {
+        match &self.target {
+            Target::Page(page, view) => {
+                ensure!(
+                    *page < pages.len(),
+                    "destination on page {} of a document of {} pages",
+                    page + 1,
+                    pages.len()
+                );
+                let optional = |value: Option<f32>| value.map_or(Object::Null, Object::from);
+                let mut destination = vec![pages[*page].into()];
+                match *view {
+                    View::Fit => destination.push(Object::name("Fit")),
+                    View::FitWidth { top } => {
+                        destination.extend([Object::name("FitH"), top.into()]);
+                    }
+                    View::Xyz { left, top, zoom } => destination.extend([
+                        Object::name("XYZ"),
+                        optional(left),
+                        optional(top),
+                        optional(zoom),
+                    ]),
+                }
+                Ok(destination.into())
+            }
+            Target::Named(name) => Ok(Object::string(name.as_str())),
+        }
+    }
+}
+
+/// The name tree of the `/Dests` entry of the document's name dictionary,
+/// holding `destinations` in a single node sorted by name.
+pub(crate) fn name_tree(
+    destinations: &BTreeMap<String, Destination>,
+    pages: &[ObjectRef],
+) -> Result<Dictionary> {
+    let mut names = Vec::new();
+    for (name, destination) in destinations {
+        ensure!(
+            !matches!(destination.target, Target::Named(_)),
+            "the named destination {:?} must not refer to another name",
+            name
+        );
+        names.push(Object::string(name.as_str()));
+        names.push(destination.to_pdf(pages)?);
+    }
+    let mut tree = Dictionary::new();
+    tree.set("Names", names);
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_destination() {
+        let pages = [ObjectRef::new(4), ObjectRef::new(6)];
+        assert_eq!(
+            Destination::page(1).to_pdf(&pages).unwrap().to_string(),
+            "[6 0 R /Fit]"
+        );
+        assert!(Destination::page(2).to_pdf(&pages).is_err());
+        assert_eq!(
+            Destination::named("terms")
+                .to_pdf(&pages)
+                .unwrap()
+                .to_string(),
+            "(terms)"
+        );
+    }
+
+    #[test]
+    fn test_views() {
+        let pages = [ObjectRef::new(4)];
+        assert_eq!(
+            Destination::fit_width(0, 720.5)
+                .to_pdf(&pages)
+                .unwrap()
+                .to_string(),
+            "[4 0 R /FitH 720.5]"
+        );
+        assert_eq!(
+            Destination::xyz(0, Some(72.0), Some(500.0), Some(1.5))
+                .to_pdf(&pages)
+                .unwrap()
+                .to_string(),
+            "[4 0 R /XYZ 72 500 1.5]"
+        );
+        assert_eq!(
+            Destination::xyz(0, None, Some(500.0), None)
+                .to_pdf(&pages)
+                .unwrap()
+                .to_string(),
+            "[4 0 R /XYZ null 500 null]"
+        );
+    }
+
+    #[test]
+    fn test_name_tree() {
+        let pages = [ObjectRef::new(4), ObjectRef::new(6)];
+        let mut destinations = BTreeMap::new();
+        destinations.insert("terms".to_string(), Destination::page(1));
+        destinations.insert("intro".to_string(), Destination::page(0));
+        assert_eq!(
+            name_tree(&destinations, &pages).unwrap().to_string(),
+            "<< /Names [(intro) [4 0 R /Fit] (terms) [6 0 R /Fit]] >>"
+        );
+
+        destinations.insert("alias".to_string(), Destination::named("terms"));
+        assert!(name_tree(&destinations, &pages).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::metadata::PdfDate;
+use crate::object::{Dictionary, Object, ObjectRef};
+use crate::writer::PdfWriter;
+use anyhow::Result;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Source of the ids telling embedded files apart.
+static NEXT_FILE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A file carried inside the document, such as the spreadsheet a report was
+/// made from, which readers can save or open. Attached to a page with
+/// [`Annotation::file_attachment`](crate::Annotation::file_attachment) or to
+/// the document with
+/// [`PdfDocument::attach_file`](crate::PdfDocument::attach_file), and written
+/// once however often it is attached.
+///
+/// Cloning the handle shares the file.
+#[derive(Clone)]
+pub struct EmbeddedFile {
+    inner: Arc<FileData>,
+}
+
+#[derive(Clone)]
+struct FileData {
+    id: u32,
+    name: String,
+    mime_type: String,
+    data: Arc<[u8]>,
+    modified: Option<PdfDate>,
+    relationship: Option<FileRelationship>,
+}
+
+/// What an attached file is to the document, told by PDF/A-3 documents of
+/// the files associated with them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileRelationship {
+    /// The original the document was made from, such as a word processor
+    /// file.
+    Source,
+    /// Data the document shows, such as the values of a chart.
+    Data,
+    /// Another form of the same content, such as the XML of a ZUGFeRD or
+    /// Factur-X invoice.
+    Alternative,
+    /// Something adding to the content, such as a file for accessibility.
+    Supplement,
+    /// None of the others, or not known.
+    Unspecified,
+}
+
+impl FileRelationship {
+    fn name(self) -> &'static str {
+        match self {
+            FileRelationship::Source => "Source",
+            FileRelationship::Data => "Data",
+            FileRelationship::Alternative => "Alternative",
+            FileRelationship::Supplement => "Supplement",
+            FileRelationship::Unspecified => "Unspecified",
+        }
+    }
+}
+
+impl EmbeddedFile {
+    /// The file `name`, holding `data` of the MIME type `mime_type`, such as
+    /// `text/csv`.
+    pub fn new(name: &str, mime_type: &str, data: Vec<u8>) -> Self {
+        Self::from_data(FileData {
+            id: 0,
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            data: data.into(),
+            modified: None,
+            relationship: None,
+        })
+    }
+
+    fn from_data(data: FileData) -> Self {
+        Self {
+            inner: Arc::new(FileData {
+                id: NEXT_FILE_ID.fetch_add(1, Ordering::Relaxed),
+                ..data
+            }),
+        }
+    }
+
+    /// The file as last changed at `date`, which readers show and keep
+    /// when saving it. The data stays shared with this file.
+    pub fn modified(self, date: PdfDate) -> Self {
+        Self::from_data(FileData {
+            modified: Some(date),
+            ..FileData::clone(&self.inner)
+        })
+    }
+
+    /// The file as being `relationship` to the document, which files
+    /// associated with PDF/A-3 documents must tell. The data stays shared
+    /// with this file.
+    pub fn relationship(self, relationship: FileRelationship) -> Self {
+        Self::from_data(FileData {
+            relationship: Some(relationship),
+            ..FileData::clone(&self.inner)
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    pub(crate) fn has_relationship(&self) -> bool {
+        self.inner.relationship.is_some()
+    }
+
+    /// The file specification referring to the file stream `id`, as the
+    /// `/FS` of attachments.
+    pub(crate) fn to_filespec(&self, id: ObjectRef) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Filespec"));
+        dict.set("F", Object::string(self.inner.name.as_str()));
+        dict.set("UF", Object::text(&self.inner.name));
+        if let Some(relationship) = self.inner.relationship {
+            dict.set("AFRelationship", Object::name(relationship.name()));
+        }
+        let mut files = Dictionary::new();
+        files.set("F", id);
+        files.set("UF", id);
+        dict.set("EF", files);
+        dict
+    }
+
+    /// Writes the embedded file stream, compressed, its size and date
+    /// being those of the file itself.
+    pub(crate) fn write<W: Write>(&self, writer: &mut PdfWriter<W>, id: ObjectRef) -> Result<()> {
+        let mut params = Dictionary::new();
+        params.set("Size", self.inner.data.len());
+        if let Some(date) = self.inner.modified {
+            params.set("ModDate", date.to_pdf());
+        }
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("EmbeddedFile"));
+        dict.set("Subtype", Object::name(self.inner.mime_type.as_str()));
+        dict.set("Params", params);
+        writer.write_compressed_stream(id, dict, &self.inner.data)
+    }
+}
+
+impl PartialEq for EmbeddedFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for EmbeddedFile {}
+
+impl Hash for EmbeddedFile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl fmt::Debug for EmbeddedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddedFile")
+            .field("name", &self.inner.name)
+            .field("mime_type", &self.inner.mime_type)
+            .field("size", &self.inner.data.len())
+            .field("relationship", &self.inner.relationship)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_file_stream() {
+        let file = EmbeddedFile::new("data.csv", "text/csv", b"a,b\n1,2\n".to_vec());
+        let dated = file.clone().modified(PdfDate::new(2024, 6, 30).unwrap());
+        assert_eq!(file, file.clone());
+        assert_ne!(file, dated);
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        writer.set_compression(false);
+        dated.write(&mut writer, ObjectRef::new(5)).unwrap();
+        let pdf = String::from_utf8(buf.into_inner()).unwrap();
+        assert!(pdf.ends_with(
+            "5 0 obj\n<< /Length 8 /Type /EmbeddedFile /Subtype /text#2Fcsv \
+             /Params << /Size 8 /ModDate (D:20240630000000Z) >> >>\nstream\na,b\n1,2\n\
+             \nendstream\nendobj\n"
+        ));
+        assert_eq!(
+            dated.to_filespec(ObjectRef::new(5)).to_string(),
+            "<< /Type /Filespec /F (data.csv) /UF (data.csv) /EF << /F 5 0 R /UF 5 0 R >> >>"
+        );
+
+        let source = dated.relationship(FileRelationship::Source);
+        assert!(source.has_relationship() && !file.has_relationship());
+        assert_eq!(
+            source.to_filespec(ObjectRef::new(5)).to_string(),
+            "<< /Type /Filespec /F (data.csv) /UF (data.csv) /AFRelationship /Source \
+             /EF << /F 5 0 R /UF 5 0 R >> >>"
+        );
+    }
+}
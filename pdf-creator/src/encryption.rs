@@ -0,0 +1,654 @@
+use crate::manual::PdfVersion;
+use crate::object::{Dictionary, Object, ObjectRef};
+use aes::cipher::block_padding::{NoPadding, Pkcs7};
+use aes::cipher::{BlockEncryptMut, KeyIvInit};
+use anyhow::{Result, ensure};
+use md5::{Digest, Md5};
+use sha2::{Sha256, Sha384, Sha512};
+
+/// Password protection of a document with the standard security handler.
+/// Readers ask for the user password before showing anything, unless it
+/// is empty; the owner password opens the document too, and lets tools
+/// change its security settings.
+///
+/// Applied with
+/// [`PdfDocument::set_encryption`](crate::PdfDocument::set_encryption).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encryption {
+    user_password: String,
+    owner_password: String,
+    method: EncryptionMethod,
+    permissions: Permissions,
+}
+
+/// The cipher encrypting a document, and with it the revision of the
+/// standard security handler: the older, the more readers open the
+/// document, and the weaker the protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMethod {
+    /// RC4 with a 40-bit key (revision 2), for the oldest readers. Easily
+    /// broken.
+    Rc4_40,
+    /// RC4 with a 128-bit key (revision 3).
+    Rc4_128,
+    /// AES with a 128-bit key (revision 4), introduced with PDF 1.6.
+    Aes128,
+    /// AES with a 256-bit key and SHA-2 password hashing (revision 6), the
+    /// method of PDF 2.0.
+    Aes256,
+}
+
+/// What readers let those who open an encrypted document with the user
+/// password do with it; the owner password always grants everything.
+/// Readers are trusted to honor these: they protect nothing from software
+/// that ignores them. All are granted by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    pub print: bool,
+    /// Printing at full quality rather than as a low resolution image,
+    /// from revision 3 on; needs `print`.
+    pub print_high_quality: bool,
+    /// Changing the document other than by the permissions below.
+    pub modify: bool,
+    /// Copying or otherwise extracting text and graphics.
+    pub copy: bool,
+    /// Adding and changing annotations and, with `modify`, creating form
+    /// fields; filling in forms too, before revision 3.
+    pub annotate: bool,
+    /// Filling in form fields, even without `annotate`, from revision 3 on.
+    pub fill_forms: bool,
+    /// Extracting text and graphics for accessibility tools such as screen
+    /// readers, even without `copy`, from revision 3 on.
+    pub extract_for_accessibility: bool,
+    /// Inserting, rotating and deleting pages and creating bookmarks and
+    /// thumbnails, even without `modify`, from revision 3 on.
+    pub assemble: bool,
+}
+
+impl Encryption {
+    /// Encryption with `method`, opened with `user_password` or, with
+    /// full rights, `owner_password`. An empty owner password is taken to
+    /// be the user password. Readers only accept passwords of Latin-1
+    /// characters for the RC4 and AES-128 methods; AES-256 takes any
+    /// Unicode, up to 127 bytes of UTF-8.
+    pub fn new(user_password: &str, owner_password: &str, method: EncryptionMethod) -> Self {
+        Self {
+            user_password: user_password.to_string(),
+            owner_password: owner_password.to_string(),
+            method,
+            permissions: Permissions::default(),
+        }
+    }
+
+    /// Grants those opening the document with the user password only
+    /// `permissions`.
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            print: true,
+            print_high_quality: true,
+            modify: true,
+            copy: true,
+            annotate: true,
+            fill_forms: true,
+            extract_for_accessibility: true,
+            assemble: true,
+        }
+    }
+}
+
+impl Permissions {
+    /// The `/P` value: the reserved bits set, and each permission's bit
+    /// set if it is granted.
+    fn to_pdf(self) -> i32 {
+        let bits = [
+            (self.print, 3),
+            (self.modify, 4),
+            (self.copy, 5),
+            (self.annotate, 6),
+            (self.fill_forms, 9),
+            (self.extract_for_accessibility, 10),
+            (self.assemble, 11),
+            (self.print_high_quality, 12),
+        ];
+        bits.iter()
+            .filter(|(granted, _)| !granted)
+            .fold(ALL_PERMISSIONS, |value, (_, bit)| value & !(1 << (bit - 1)))
+    }
+}
+
+impl EncryptionMethod {
+    /// The revision of the standard security handler.
+    fn revision(self) -> u8 {
+        match self {
+            EncryptionMethod::Rc4_40 => 2,
+            EncryptionMethod::Rc4_128 => 3,
+            EncryptionMethod::Aes128 => 4,
+            EncryptionMethod::Aes256 => 6,
+        }
+    }
+
+    /// The length of the file key, in bytes.
+    fn key_length(self) -> usize {
+        match self {
+            EncryptionMethod::Rc4_40 => 5,
+            EncryptionMethod::Rc4_128 | EncryptionMethod::Aes128 => 16,
+            EncryptionMethod::Aes256 => 32,
+        }
+    }
+}
+
+/// The bytes passwords are padded to 32 bytes with, as the standard
+/// security handler specifies.
+const PADDING: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08,
+    0x2E, 0x2E, 0x00, 0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+/// The permissions of the document, all granted: the bits that may be
+/// cleared to deny them are set, along with the reserved bits.
+const ALL_PERMISSIONS: i32 = -4;
+
+/// The keys encrypting a document, derived from its passwords and first
+/// file identifier (or, for AES-256, random), and what the encryption
+/// dictionary tells readers to derive them again.
+#[derive(Debug)]
+pub(crate) struct SecurityHandler {
+    method: EncryptionMethod,
+    file_id: [u8; 16],
+    key: Vec<u8>,
+    owner_key: Vec<u8>,
+    user_key: Vec<u8>,
+    /// For AES-256, the file key encrypted with keys from the owner and
+    /// the user password, and the permissions encrypted with the file key;
+    /// empty for the older methods.
+    owner_file_key: Vec<u8>,
+    user_file_key: Vec<u8>,
+    encrypted_permissions: Vec<u8>,
+    permissions: i32,
+}
+
+impl SecurityHandler {
+    /// The handler encrypting with `encryption`, for a document given a
+    /// new random identifier.
+    pub(crate) fn new(encryption: &Encryption) -> Result<Self> {
+        let mut file_id = [0; 16];
+        getrandom::fill(&mut file_id)?;
+        let permissions = encryption.permissions.to_pdf();
+        match encryption.method {
+            EncryptionMethod::Aes256 => Self::with_sha2(encryption, file_id, permissions),
+            _ => Self::with_md5(encryption, file_id, permissions),
+        }
+    }
+
+    /// The keys of revisions 2 to 4, derived with MD5 and RC4.
+    fn with_md5(encryption: &Encryption, file_id: [u8; 16], permissions: i32) -> Result<Self> {
+        let user = latin1(&encryption.user_password)?;
+        let owner = match encryption.owner_password.as_str() {
+            "" => user.clone(),
+            owner => latin1(owner)?,
+        };
+        let method = encryption.method;
+        let revision = method.revision();
+        let length = method.key_length();
+
+        // The owner key: the padded user password encrypted with a key
+        // from the owner password (algorithm 3).
+        let mut hash = md5(&[&pad(&owner)]);
+        if revision >= 3 {
+            for _ in 0..50 {
+                hash = md5(&[&hash[..length]]);
+            }
+        }
+        let owner_key = rc4_rounds(&hash[..length], &pad(&user), revision);
+
+        // The file key (algorithm 2).
+        let mut hash = md5(&[
+            &pad(&user),
+            &owner_key,
+            &permissions.to_le_bytes(),
+            &file_id,
+        ]);
+        if revision >= 3 {
+            for _ in 0..50 {
+                hash = md5(&[&hash[..length]]);
+            }
+        }
+        let key = hash[..length].to_vec();
+
+        // The user key, which readers check the user password against
+        // (algorithms 4 and 5).
+        let user_key = match revision {
+            2 => rc4(&key, &PADDING),
+            _ => {
+                let mut user_key = rc4_rounds(&key, &md5(&[&PADDING, &file_id]), revision);
+                user_key.resize(32, 0);
+                user_key
+            }
+        };
+        Ok(Self {
+            method,
+            file_id,
+            key,
+            owner_key,
+            user_key,
+            owner_file_key: Vec::new(),
+            user_file_key: Vec::new(),
+            encrypted_permissions: Vec::new(),
+            permissions,
+        })
+    }
+
+    /// The keys of revision 6: a random file key, which readers decrypt
+    /// with a hash of either password, checked against a hash stored with
+    /// its salts (algorithms 8 to 10).
+    fn with_sha2(encryption: &Encryption, file_id: [u8; 16], permissions: i32) -> Result<Self> {
+        let user = utf8(&encryption.user_password);
+        let owner = match encryption.owner_password.as_str() {
+            "" => user,
+            owner => utf8(owner),
+        };
+        let mut key = vec![0; EncryptionMethod::Aes256.key_length()];
+        getrandom::fill(&mut key)?;
+        // The validation and key salts of the user password, then those of
+        // the owner password.
+        let mut salts = [0; 32];
+        getrandom::fill(&mut salts)?;
+
+        let mut user_key = sha2_hash(user, &salts[..8], &[]).to_vec();
+        user_key.extend_from_slice(&salts[..16]);
+        let user_file_key = aes256_block(&sha2_hash(user, &salts[8..16], &[]), &key);
+        // The owner password is hashed along with the user key.
+        let mut owner_key = sha2_hash(owner, &salts[16..24], &user_key).to_vec();
+        owner_key.extend_from_slice(&salts[16..]);
+        let owner_file_key = aes256_block(&sha2_hash(owner, &salts[24..], &user_key), &key);
+
+        // The permissions, which readers check have not been changed.
+        let mut perms = [0; 16];
+        perms[..4].copy_from_slice(&permissions.to_le_bytes());
+        perms[4..8].fill(0xFF);
+        perms[8..12].copy_from_slice(b"Tadb");
+        getrandom::fill(&mut perms[12..])?;
+        let encrypted_permissions = aes256_block(&key, &perms);
+        Ok(Self {
+            method: EncryptionMethod::Aes256,
+            file_id,
+            key,
+            owner_key,
+            user_key,
+            owner_file_key,
+            user_file_key,
+            encrypted_permissions,
+            permissions,
+        })
+    }
+
+    /// The version the method was introduced with, and what to call it
+    /// when the document is older.
+    pub(crate) fn required_version(&self) -> (PdfVersion, &'static str) {
+        match self.method {
+            EncryptionMethod::Rc4_40 | EncryptionMethod::Rc4_128 => {
+                (PdfVersion::Pdf14, "documents encrypted with RC4")
+            }
+            EncryptionMethod::Aes128 => (PdfVersion::Pdf16, "documents encrypted with AES-128"),
+            EncryptionMethod::Aes256 => (PdfVersion::Pdf20, "documents encrypted with AES-256"),
+        }
+    }
+
+    /// The first file identifier, which the keys are derived from, to be
+    /// written as both identifiers of the trailer.
+    pub(crate) fn file_id(&self) -> [u8; 16] {
+        self.file_id
+    }
+
+    /// The encryption dictionary.
+    pub(crate) fn to_dict(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Filter", Object::name("Standard"));
+        let (version, length, method) = match self.method {
+            EncryptionMethod::Rc4_40 => (1, None, None),
+            EncryptionMethod::Rc4_128 => (2, Some(128), None),
+            EncryptionMethod::Aes128 => (4, Some(128), Some(("AESV2", 16))),
+            EncryptionMethod::Aes256 => (5, Some(256), Some(("AESV3", 32))),
+        };
+        dict.set("V", version);
+        dict.set("R", self.method.revision() as i32);
+        if let Some(length) = length {
+            dict.set("Length", length);
+        }
+        if let Some((method, length)) = method {
+            let mut filter = Dictionary::new();
+            filter.set("Type", Object::name("CryptFilter"));
+            filter.set("CFM", Object::name(method));
+            filter.set("AuthEvent", Object::name("DocOpen"));
+            filter.set("Length", length);
+            let mut filters = Dictionary::new();
+            filters.set("StdCF", filter);
+            dict.set("CF", filters);
+            dict.set("StmF", Object::name("StdCF"));
+            dict.set("StrF", Object::name("StdCF"));
+        }
+        dict.set("O", Object::hex(self.owner_key.clone()));
+        dict.set("U", Object::hex(self.user_key.clone()));
+        if self.method == EncryptionMethod::Aes256 {
+            dict.set("OE", Object::hex(self.owner_file_key.clone()));
+            dict.set("UE", Object::hex(self.user_file_key.clone()));
+            dict.set("Perms", Object::hex(self.encrypted_permissions.clone()));
+        }
+        dict.set("P", self.permissions);
+        dict
+    }
+
+    /// Encrypts the string or stream data `data` of the object `object`
+    /// with the key of that object (algorithm 1), or for AES-256 the file
+    /// key itself, AES data starting with the random initialization vector
+    /// it needs.
+    pub(crate) fn encrypt(&self, object: ObjectRef, data: &[u8]) -> Result<Vec<u8>> {
+        if self.method == EncryptionMethod::Aes256 {
+            return aes_cbc(&self.key, data);
+        }
+        let aes = self.method == EncryptionMethod::Aes128;
+        let mut hash = Md5::new();
+        hash.update(&self.key);
+        hash.update(&object.id().to_le_bytes()[..3]);
+        hash.update(object.generation().to_le_bytes());
+        if aes {
+            hash.update(b"sAlT");
+        }
+        let hash = hash.finalize();
+        let key = &hash[..(self.key.len() + 5).min(16)];
+        match aes {
+            true => aes_cbc(key, data),
+            false => Ok(rc4(key, data)),
+        }
+    }
+
+    /// Encrypts each string it is called on, of the object `object`, into
+    /// a hex string, as [`Object::visit_mut`] calls it on every object
+    /// within another.
+    pub(crate) fn encrypt_strings(
+        &self,
+        object: ObjectRef,
+    ) -> impl FnMut(&mut Object) -> Result<()> + '_ {
+        move |value| {
+            if let Object::String(string) | Object::HexString(string) = value {
+                *value = Object::HexString(self.encrypt(object, string)?);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// `text` in Latin-1, which the passwords of the RC4 and AES-128 methods
+/// are in.
+fn latin1(text: &str) -> Result<Vec<u8>> {
+    ensure!(
+        text.chars().all(|c| (c as u32) < 0x100),
+        "passwords of documents encrypted with RC4 or AES-128 must be Latin-1"
+    );
+    Ok(text.chars().map(|c| c as u8).collect())
+}
+
+/// `text` in UTF-8 cut to the 127 bytes of a password of the AES-256
+/// method. Readers are to normalize passwords with SASLprep, which leaves
+/// most as they are.
+fn utf8(text: &str) -> &[u8] {
+    let mut length = text.len().min(127);
+    while !text.is_char_boundary(length) {
+        length -= 1;
+    }
+    &text.as_bytes()[..length]
+}
+
+/// `password` cut or padded to 32 bytes.
+fn pad(password: &[u8]) -> [u8; 32] {
+    let length = password.len().min(32);
+    let mut padded = [0; 32];
+    padded[..length].copy_from_slice(&password[..length]);
+    padded[length..].copy_from_slice(&PADDING[..32 - length]);
+    padded
+}
+
+/// The MD5 digest of `parts` one after the other.
+fn md5(parts: &[&[u8]]) -> [u8; 16] {
+    let mut hash = Md5::new();
+    for part in parts {
+        hash.update(part);
+    }
+    hash.finalize().into()
+}
+
+/// Encrypts `data` with the RC4 stream cipher and `key`.
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: [u8; 256] = std::array::from_fn(|i| i as u8);
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j.into());
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[usize::from(i)]);
+            state.swap(i.into(), j.into());
+            byte ^ state[usize::from(state[usize::from(i)].wrapping_add(state[usize::from(j)]))]
+        })
+        .collect()
+}
+
+/// The hash of revision 6 (algorithm 2.B): SHA-256 of `password`, `salt`
+/// and `user_key`, then at least 64 rounds of encrypting it with AES-128
+/// and hashing the result with SHA-256, -384 or -512, each round chosen by
+/// the one before.
+fn sha2_hash(password: &[u8], salt: &[u8], user_key: &[u8]) -> [u8; 32] {
+    let mut hash = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(user_key)
+        .finalize()
+        .to_vec();
+    let mut round = 0;
+    loop {
+        let mut block = Vec::new();
+        for _ in 0..64 {
+            block.extend_from_slice(password);
+            block.extend_from_slice(&hash);
+            block.extend_from_slice(user_key);
+        }
+        // 64 repetitions are always a whole number of AES blocks.
+        let length = block.len();
+        cbc::Encryptor::<aes::Aes128>::new(hash[..16].into(), hash[16..32].into())
+            .encrypt_padded_mut::<NoPadding>(&mut block, length)
+            .unwrap();
+        let sum: u32 = block[..16].iter().map(|&byte| u32::from(byte)).sum();
+        hash = match sum % 3 {
+            0 => Sha256::digest(&block).to_vec(),
+            1 => Sha384::digest(&block).to_vec(),
+            _ => Sha512::digest(&block).to_vec(),
+        };
+        round += 1;
+        if round >= 64 && u32::from(block[length - 1]) + 32 <= round {
+            break;
+        }
+    }
+    hash[..32].try_into().unwrap()
+}
+
+/// Encrypts the 16 or 32 bytes of `data` with AES-256 and `key`, with no
+/// initialization vector or padding, as the keys of revision 6 are.
+fn aes256_block(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut encrypted = data.to_vec();
+    cbc::Encryptor::<aes::Aes256>::new(key.into(), &[0; 16].into())
+        .encrypt_padded_mut::<NoPadding>(&mut encrypted, data.len())
+        .unwrap();
+    encrypted
+}
+
+/// Encrypts `data` with AES and the 16 or 32 bytes of `key`, after the
+/// random initialization vector it starts with.
+fn aes_cbc(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut iv = [0; 16];
+    getrandom::fill(&mut iv)?;
+    let mut encrypted = vec![0; 16 + (data.len() / 16 + 1) * 16];
+    encrypted[..16].copy_from_slice(&iv);
+    encrypted[16..16 + data.len()].copy_from_slice(data);
+    // The buffer always has room for the padding.
+    let buffer = &mut encrypted[16..];
+    match key.len() {
+        16 => cbc::Encryptor::<aes::Aes128>::new(key.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(buffer, data.len())
+            .map(|_| ()),
+        _ => cbc::Encryptor::<aes::Aes256>::new(key.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(buffer, data.len())
+            .map(|_| ()),
+    }
+    .unwrap();
+    Ok(encrypted)
+}
+
+/// Encrypts `data` with `key` and, from revision 3 on, 19 more times with
+/// `key` XORed with the number of the round.
+fn rc4_rounds(key: &[u8], data: &[u8], revision: u8) -> Vec<u8> {
+    let mut encrypted = rc4(key, data);
+    if revision >= 3 {
+        for round in 1..=19 {
+            let key: Vec<u8> = key.iter().map(|byte| byte ^ round).collect();
+            encrypted = rc4(&key, &encrypted);
+        }
+    }
+    encrypted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::hex_string;
+    use aes::cipher::BlockDecryptMut;
+
+    #[test]
+    fn test_rc4() {
+        assert_eq!(
+            hex_string(&rc4(b"Key", b"Plaintext")),
+            "<BBF316E8D940AF0AD3>"
+        );
+        assert_eq!(
+            hex_string(&rc4(b"Secret", b"Attack at dawn")),
+            "<45A01F645FC35B383552544B9BF5>"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_strings() {
+        let encryption = Encryption::new("", "owner", EncryptionMethod::Rc4_128);
+        let handler = SecurityHandler::new(&encryption).unwrap();
+        let object = ObjectRef::new(7);
+        let mut dict = Dictionary::new();
+        dict.set("T", Object::string(b"a(b)\\ Ac".to_vec()));
+        dict.set("V", Object::hex([0xFE, 0xFF, 0x00, 0x40]));
+        dict.set("Kids", vec![Object::string("x"), Object::hex(Vec::new())]);
+        dict.set("N", Object::name("A("));
+        let mut value = Object::from(dict);
+        value
+            .visit_mut(&mut handler.encrypt_strings(object))
+            .unwrap();
+        let once = value.to_string();
+        assert!(once.starts_with("<< /T <") && once.ends_with("] /N /A#28 >>"));
+        assert!(!once.contains("<78>"));
+        // RC4 decrypts by encrypting again.
+        value
+            .visit_mut(&mut handler.encrypt_strings(object))
+            .unwrap();
+        assert_eq!(
+            value.to_string(),
+            "<< /T <612862295C204163> /V <FEFF0040> /Kids [<78> <>] /N /A#28 >>"
+        );
+    }
+
+    #[test]
+    fn test_keys() {
+        let encryption = Encryption::new("user", "", EncryptionMethod::Rc4_40);
+        let handler = SecurityHandler::new(&encryption).unwrap();
+        assert_eq!(handler.key.len(), 5);
+        assert_eq!(handler.user_key.len(), 32);
+        // Revision 2 readers check the user password by encrypting the
+        // padding with the key.
+        assert_eq!(rc4(&handler.key, &handler.user_key), PADDING);
+        assert!(
+            handler
+                .to_dict()
+                .to_string()
+                .starts_with("<< /Filter /Standard /V 1 /R 2 /O <")
+        );
+
+        let encryption = Encryption::new("user", "owner", EncryptionMethod::Aes128);
+        let handler = SecurityHandler::new(&encryption).unwrap();
+        let encrypted = handler
+            .encrypt(ObjectRef::new(1), b"sixteen bytes!!!")
+            .unwrap();
+        assert_eq!(encrypted.len(), 48);
+        assert!(
+            SecurityHandler::new(&Encryption::new("пароль", "", EncryptionMethod::Aes128)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_permissions() {
+        assert_eq!(Permissions::default().to_pdf(), ALL_PERMISSIONS);
+        let permissions = Permissions {
+            print: false,
+            copy: false,
+            ..Default::default()
+        };
+        assert_eq!(permissions.to_pdf(), -24);
+        let encryption =
+            Encryption::new("", "owner", EncryptionMethod::Aes256).permissions(permissions);
+        let handler = SecurityHandler::new(&encryption).unwrap();
+        assert!(handler.to_dict().to_string().ends_with(" /P -24 >>"));
+        // The permissions are encrypted, as readers check, in the first 4
+        // bytes of /Perms.
+        let mut perms = handler.encrypted_permissions.clone();
+        cbc::Decryptor::<aes::Aes256>::new(handler.key[..].into(), &[0; 16].into())
+            .decrypt_padded_mut::<NoPadding>(&mut perms)
+            .unwrap();
+        assert_eq!(perms[..4], (-24i32).to_le_bytes());
+        assert_eq!(&perms[8..12], b"Tadb");
+    }
+
+    #[test]
+    fn test_aes256_keys() {
+        let encryption = Encryption::new("пароль", "owner", EncryptionMethod::Aes256);
+        let handler = SecurityHandler::new(&encryption).unwrap();
+        assert_eq!(handler.key.len(), 32);
+        assert_eq!(handler.user_key.len(), 48);
+        assert_eq!(handler.owner_key.len(), 48);
+        assert_eq!(handler.user_file_key.len(), 32);
+        assert_eq!(handler.encrypted_permissions.len(), 16);
+        // Readers check a password against the hash of it with the
+        // validation salt.
+        let user = "пароль".as_bytes();
+        let (hash, salt) = handler.user_key.split_at(32);
+        assert_eq!(sha2_hash(user, &salt[..8], &[]), hash);
+        let (hash, salt) = handler.owner_key.split_at(32);
+        assert_eq!(sha2_hash(b"owner", &salt[..8], &handler.user_key), hash);
+        assert_ne!(sha2_hash(b"other", &salt[..8], &handler.user_key), hash);
+        assert!(
+            handler
+                .to_dict()
+                .to_string()
+                .contains("/V 5 /R 6 /Length 256")
+        );
+        // Every string or stream grows by the initialization vector and
+        // the padding.
+        let encrypted = handler.encrypt(ObjectRef::new(1), b"data").unwrap();
+        assert_eq!(encrypted.len(), 32);
+
+        assert_eq!(utf8(&"é".repeat(100)).len(), 126);
+    }
+}
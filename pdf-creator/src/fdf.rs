@@ -0,0 +1,315 @@
+use crate::form::{FieldValue, InteractiveForm};
+use crate::metadata::xml_escape;
+use crate::object::{Dictionary, Object};
+use anyhow::{Context, Result, ensure};
+
+impl InteractiveForm {
+    /// The values of the fields as a Forms Data Format file, the format
+    /// forms servers and other PDF software exchange filled in forms in.
+    /// Fields without a value are listed by name only; push buttons and
+    /// signature fields, which take none, are left out.
+    pub fn to_fdf(&self) -> Vec<u8> {
+        let fields = self
+            .values()
+            .into_iter()
+            .map(|(name, value)| {
+                let mut field = Dictionary::new();
+                field.set("T", Object::text(name));
+                match value {
+                    Some(FieldValue::Text(text)) => field.set("V", Object::text(text)),
+                    Some(FieldValue::State(state)) => field.set("V", Object::name(state)),
+                    None => {}
+                }
+                field.into()
+            })
+            .collect();
+        let mut form = Dictionary::new();
+        form.set("Fields", Object::Array(fields));
+        let mut catalog = Dictionary::new();
+        catalog.set("FDF", form);
+        // As in PDF files, a comment of bytes above 127 marks the file as
+        // binary for transfer.
+        let mut fdf = b"%FDF-1.2\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        fdf.extend_from_slice(
+            format!(
+                "1 0 obj\n{}\nendobj\n\
+                 trailer\n<< /Root 1 0 R >>\n%%EOF\n",
+                catalog
+            )
+            .as_bytes(),
+        );
+        fdf
+    }
+
+    /// The values of the fields as an XML Forms Data Format file, like
+    /// [`to_fdf`](Self::to_fdf) in XML.
+    pub fn to_xfdf(&self) -> String {
+        let mut xfdf = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n<fields>\n",
+        );
+        for (name, value) in self.values() {
+            match value {
+                Some(FieldValue::Text(value) | FieldValue::State(value)) => {
+                    xfdf.push_str(&format!(
+                        "<field name=\"{}\"><value>{}</value></field>\n",
+                        xml_escape(name),
+                        xml_escape(value)
+                    ));
+                }
+                None => xfdf.push_str(&format!("<field name=\"{}\"/>\n", xml_escape(name))),
+            }
+        }
+        xfdf.push_str("</fields>\n</xfdf>\n");
+        xfdf
+    }
+
+    /// Fills in the form from an XML Forms Data Format file, such as one
+    /// from a forms server or [`to_xfdf`](Self::to_xfdf): each field given
+    /// a value holds it when the document is opened, the name of the state
+    /// for checkboxes and radio buttons. Fields nested in others are known
+    /// by their names joined with dots, as `address.city`.
+    ///
+    /// Fails, leaving the form as it was, if the file is not XFDF or gives
+    /// a value to a field the form does not have or that cannot take it.
+    pub fn import_xfdf(&mut self, xfdf: &str) -> Result<()> {
+        let mut form = self.clone();
+        for (name, value) in parse_xfdf(xfdf)? {
+            form.set_value(&name, &value)?;
+        }
+        *self = form;
+        Ok(())
+    }
+}
+
+/// The full names of the fields `xfdf` gives values, and the values, in
+/// order. Only the elements of fields and values are looked at, whatever
+/// their namespace prefix.
+fn parse_xfdf(xfdf: &str) -> Result<Vec<(String, String)>> {
+    let mut values = Vec::new();
+    let mut root = false;
+    // The names of the fields open, and the text of the value open.
+    let mut names: Vec<String> = Vec::new();
+    let mut value: Option<String> = None;
+    let mut rest = xfdf;
+    while let Some(start) = rest.find('<') {
+        if let Some(value) = &mut value {
+            value.push_str(&xml_unescape(&rest[..start])?);
+        }
+        rest = &rest[start..];
+        let close = if rest.starts_with("<?") {
+            "?>"
+        } else if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<![CDATA[") {
+            "]]>"
+        } else {
+            ">"
+        };
+        let end = rest.find(close).context("unclosed markup in XFDF")? + close.len();
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        if let Some(text) = tag.strip_prefix("<![CDATA[") {
+            if let Some(value) = &mut value {
+                value.push_str(&text[..text.len() - close.len()]);
+            }
+            continue;
+        }
+        if tag.starts_with("<?") || tag.starts_with("<!") {
+            continue;
+        }
+        let closing = tag.starts_with("</");
+        let empty = tag.ends_with("/>");
+        let inner = tag
+            .trim_start_matches('<')
+            .trim_start_matches('/')
+            .trim_end_matches('>')
+            .trim_end_matches('/');
+        let element = inner.split(char::is_whitespace).next().unwrap_or("");
+        let element = element.rsplit(':').next().unwrap_or(element);
+        match (element, closing) {
+            ("xfdf", false) => root = true,
+            ("field", false) => {
+                let name = attribute(inner, "name")?.context("an XFDF field has no name")?;
+                if !empty {
+                    names.push(name);
+                }
+            }
+            ("field", true) => {
+                names.pop().context("unbalanced fields in XFDF")?;
+            }
+            ("value", false) => {
+                ensure!(!names.is_empty(), "an XFDF value is outside any field");
+                match empty {
+                    true => values.push((names.join("."), String::new())),
+                    false => value = Some(String::new()),
+                }
+            }
+            ("value", true) => {
+                let value = value.take().context("unbalanced values in XFDF")?;
+                values.push((names.join("."), value));
+            }
+            _ => {}
+        }
+    }
+    ensure!(root, "not an XFDF file");
+    ensure!(
+        names.is_empty() && value.is_none(),
+        "unclosed fields in XFDF"
+    );
+    Ok(values)
+}
+
+/// The value of the attribute `name` of the tag `tag`, without its angle
+/// brackets.
+fn attribute(tag: &str, name: &str) -> Result<Option<String>> {
+    let mut rest = tag.trim_start_matches(|c: char| !c.is_whitespace());
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        let (key, after) = rest
+            .split_once('=')
+            .context("malformed attribute in XFDF")?;
+        let after = after.trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .context("unquoted attribute in XFDF")?;
+        let (value, next) = after[1..]
+            .split_once(quote)
+            .context("unterminated attribute in XFDF")?;
+        if key.trim() == name {
+            return xml_unescape(value).map(Some);
+        }
+        rest = next;
+    }
+}
+
+/// Replaces the entity and character references of XML text by the
+/// characters they stand for.
+fn xml_unescape(text: &str) -> Result<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        let (entity, next) = rest[amp + 1..]
+            .split_once(';')
+            .context("unterminated reference in XFDF")?;
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|code| code.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+                    .with_context(|| format!("unknown reference &{}; in XFDF", entity))?
+            }
+        };
+        unescaped.push(c);
+        rest = next;
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+
+    /// A form of a text field, a checkbox, a radio group, a drop-down list
+    /// and a push button, none filled in.
+    fn form() -> InteractiveForm {
+        let mut form = InteractiveForm::default();
+        let rect = Rect::new(100.0, 700.0, 100.0, 20.0);
+        form.text_field("name", rect, 0);
+        form.checkbox("married", rect, 0)
+            .set_export_value("Married");
+        form.radio_group("color")
+            .option("Red", rect, 0)
+            .option("Blue", rect, 0);
+        form.combo_box("country", rect, 0).option("DE").option("FR");
+        form.push_button("send", rect, 0);
+        form
+    }
+
+    #[test]
+    fn test_fdf_export() {
+        let mut form = form();
+        form.set_value("name", "Jane (Doe)").unwrap();
+        form.set_value("married", "Married").unwrap();
+        let fdf = form.to_fdf();
+        assert!(fdf.starts_with(b"%FDF-1.2\n%\xe2\xe3\xcf\xd3\n"));
+        assert!(String::from_utf8_lossy(&fdf).ends_with(
+            "1 0 obj\n<< /FDF << /Fields [<< /T (name) /V (Jane \\(Doe\\)) >> \
+             << /T (married) /V /Married >> << /T (color) /V /Off >> << /T (country) >>] >> >>\n\
+             endobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n"
+        ));
+    }
+
+    #[test]
+    fn test_xfdf_round_trip() {
+        let mut form = form();
+        form.set_value("name", "Q&A <1>").unwrap();
+        form.set_value("color", "Blue").unwrap();
+        let xfdf = form.to_xfdf();
+        assert_eq!(
+            xfdf,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n<fields>\n\
+             <field name=\"name\"><value>Q&amp;A &lt;1&gt;</value></field>\n\
+             <field name=\"married\"><value>Off</value></field>\n\
+             <field name=\"color\"><value>Blue</value></field>\n\
+             <field name=\"country\"/>\n</fields>\n</xfdf>\n"
+        );
+        let mut filled = self::form();
+        filled.import_xfdf(&xfdf).unwrap();
+        assert_eq!(filled.values(), form.values());
+    }
+
+    #[test]
+    fn test_xfdf_import() {
+        let mut form = form();
+        form.text_field("address.city", Rect::new(0.0, 0.0, 100.0, 20.0), 0);
+        form.import_xfdf(
+            "<?xml version='1.0'?>\n<!-- from the server -->\n\
+             <x:xfdf xmlns:x='http://ns.adobe.com/xfdf/'><x:fields>\
+             <x:field name='address'><x:field name='city'><x:value>K&#246;ln</x:value>\
+             </x:field></x:field>\
+             <x:field name=\"married\"><x:value>Married</x:value></x:field>\
+             <x:field name=\"country\"><x:value><![CDATA[<FR>]]></x:value></x:field>\
+             </x:fields></x:xfdf>",
+        )
+        .unwrap();
+        let values = form.values();
+        assert_eq!(values[4], ("address.city", Some(FieldValue::Text("Köln"))));
+        assert_eq!(values[1], ("married", Some(FieldValue::State("Married"))));
+        assert_eq!(values[3], ("country", Some(FieldValue::Text("<FR>"))));
+
+        let before = form.values().len();
+        let field = |name: &str, value: &str| {
+            format!(
+                "<xfdf><fields><field name=\"name\"><value>Jane</value></field>\
+                 <field name=\"{}\"><value>{}</value></field></fields></xfdf>",
+                name, value
+            )
+        };
+        assert!(form.import_xfdf(&field("age", "40")).is_err());
+        assert!(form.import_xfdf(&field("married", "Yes")).is_err());
+        assert!(form.import_xfdf(&field("color", "Green")).is_err());
+        assert!(form.import_xfdf(&field("send", "1")).is_err());
+        assert!(form.import_xfdf("<fields></fields>").is_err());
+        assert!(form.import_xfdf("<xfdf><field name=\"name\">").is_err());
+        // Nothing is filled in from a file that fails.
+        assert_eq!(form.values()[0], ("name", None));
+        assert_eq!(form.values().len(), before);
+    }
+}
@@ -0,0 +1,422 @@
+use crate::metrics::{self, Metrics};
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::string::{hex_string, pdf_string};
+use crate::truetype::TrueTypeFont;
+use crate::writer::PdfWriter;
+use anyhow::Result;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+/// The 14 fonts every PDF viewer provides, usable without embedding a font
+/// file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StandardFont {
+    #[default]
+    Helvetica,
+    HelveticaBold,
+    HelveticaOblique,
+    HelveticaBoldOblique,
+    TimesRoman,
+    TimesBold,
+    TimesItalic,
+    TimesBoldItalic,
+    Courier,
+    CourierBold,
+    CourierOblique,
+    CourierBoldOblique,
+    Symbol,
+    ZapfDingbats,
+}
+
+impl StandardFont {
+    pub const ALL: [StandardFont; 14] = [
+        StandardFont::Helvetica,
+        StandardFont::HelveticaBold,
+        StandardFont::HelveticaOblique,
+        StandardFont::HelveticaBoldOblique,
+        StandardFont::TimesRoman,
+        StandardFont::TimesBold,
+        StandardFont::TimesItalic,
+        StandardFont::TimesBoldItalic,
+        StandardFont::Courier,
+        StandardFont::CourierBold,
+        StandardFont::CourierOblique,
+        StandardFont::CourierBoldOblique,
+        StandardFont::Symbol,
+        StandardFont::ZapfDingbats,
+    ];
+
+    /// PostScript name written as the `/BaseFont` of the font dictionary.
+    pub fn base_font(&self) -> &'static str {
+        match self {
+            StandardFont::Helvetica => "Helvetica",
+            StandardFont::HelveticaBold => "Helvetica-Bold",
+            StandardFont::HelveticaOblique => "Helvetica-Oblique",
+            StandardFont::HelveticaBoldOblique => "Helvetica-BoldOblique",
+            StandardFont::TimesRoman => "Times-Roman",
+            StandardFont::TimesBold => "Times-Bold",
+            StandardFont::TimesItalic => "Times-Italic",
+            StandardFont::TimesBoldItalic => "Times-BoldItalic",
+            StandardFont::Courier => "Courier",
+            StandardFont::CourierBold => "Courier-Bold",
+            StandardFont::CourierOblique => "Courier-Oblique",
+            StandardFont::CourierBoldOblique => "Courier-BoldOblique",
+            StandardFont::Symbol => "Symbol",
+            StandardFont::ZapfDingbats => "ZapfDingbats",
+        }
+    }
+
+    /// Symbol and ZapfDingbats only have their own built-in encoding; the
+    /// other fonts are shown through WinAnsiEncoding.
+    fn is_symbolic(self) -> bool {
+        matches!(self, StandardFont::Symbol | StandardFont::ZapfDingbats)
+    }
+
+    /// Converts `text` to single byte codes. Characters the encoding lacks
+    /// become `?`.
+    fn encode(self, text: &str) -> Vec<u8> {
+        text.chars()
+            .map(|ch| {
+                let code = if self.is_symbolic() {
+                    u8::try_from(ch as u32).ok()
+                } else {
+                    win_ansi_code(ch)
+                };
+                code.unwrap_or(b'?')
+            })
+            .collect()
+    }
+
+    /// Built-in metrics, in thousandths of the font size.
+    fn metrics(self) -> &'static Metrics {
+        match self {
+            StandardFont::Helvetica => &metrics::HELVETICA,
+            StandardFont::HelveticaBold => &metrics::HELVETICA_BOLD,
+            StandardFont::HelveticaOblique => &metrics::HELVETICA_OBLIQUE,
+            StandardFont::HelveticaBoldOblique => &metrics::HELVETICA_BOLD_OBLIQUE,
+            StandardFont::TimesRoman => &metrics::TIMES_ROMAN,
+            StandardFont::TimesBold => &metrics::TIMES_BOLD,
+            StandardFont::TimesItalic => &metrics::TIMES_ITALIC,
+            StandardFont::TimesBoldItalic => &metrics::TIMES_BOLD_ITALIC,
+            StandardFont::Courier => &metrics::COURIER,
+            StandardFont::CourierBold => &metrics::COURIER_BOLD,
+            StandardFont::CourierOblique => &metrics::COURIER_OBLIQUE,
+            StandardFont::CourierBoldOblique => &metrics::COURIER_BOLD_OBLIQUE,
+            StandardFont::Symbol => &metrics::SYMBOL,
+            StandardFont::ZapfDingbats => &metrics::ZAPF_DINGBATS,
+        }
+    }
+
+    /// Width of `text` in thousandths of the font size.
+    fn text_width(self, text: &str) -> u32 {
+        let widths = &self.metrics().widths;
+        self.encode(text)
+            .iter()
+            .map(|code| widths[*code as usize] as u32)
+            .sum()
+    }
+
+    pub(crate) fn to_dict(self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Font"));
+        dict.set("Subtype", Object::name("Type1"));
+        dict.set("BaseFont", Object::name(self.base_font()));
+        if !self.is_symbolic() {
+            dict.set("Encoding", Object::name("WinAnsiEncoding"));
+        }
+        dict
+    }
+}
+
+/// Handle to a font usable for drawing text, either one of the standard 14
+/// fonts or a font program embedded in the document.
+#[derive(Debug, Clone)]
+pub enum Font {
+    Standard(StandardFont),
+    TrueType(TrueTypeFont),
+}
+
+/// What a font is written from, the same for fonts written as one object
+/// whatever handles they are drawn with: everything that changes what is
+/// written is part of it.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) enum FontContent<'a> {
+    Standard(StandardFont),
+    /// The font program, and whether the handle writes vertically, the
+    /// encoding and the metrics written depending on it.
+    TrueType {
+        data: &'a [u8],
+        vertical: bool,
+    },
+}
+
+impl Font {
+    pub(crate) fn content(&self) -> FontContent<'_> {
+        match self {
+            Font::Standard(font) => FontContent::Standard(*font),
+            Font::TrueType(font) => font.content(),
+        }
+    }
+
+    /// Converts `text` to the string operand shown by `Tj` with this font,
+    /// recording the glyphs shown (and the characters they stand for) in
+    /// `used`.
+    pub(crate) fn encode(&self, text: &str, used: &mut BTreeMap<u16, char>) -> String {
+        match self {
+            Font::Standard(font) => pdf_string(&font.encode(text)),
+            Font::TrueType(font) => hex_string(&font.encode(text, used)),
+        }
+    }
+
+    /// Horizontal advance of `text` shown at `size`, in points. Standard
+    /// fonts use the built-in Adobe metrics, embedded fonts their own
+    /// tables, kerning included.
+    pub fn measure(&self, text: &str, size: f32) -> f32 {
+        let width = match self {
+            Font::Standard(font) => font.text_width(text) as f32,
+            Font::TrueType(font) => font.text_width(text),
+        };
+        width * size / 1000.0
+    }
+
+    /// Length of `text` written top to bottom at `size`, as by
+    /// [`ContentStream::draw_text_vertical`](crate::ContentStream::draw_text_vertical),
+    /// in points. Embedded fonts use their vertical metrics, one em per glyph
+    /// where they have none; standard fonts stack one em per character.
+    pub fn measure_vertical(&self, text: &str, size: f32) -> f32 {
+        match self {
+            Font::Standard(_) => text.chars().count() as f32 * size,
+            Font::TrueType(font) => font.text_height(text) * size / 1000.0,
+        }
+    }
+
+    /// How far the font reaches above the baseline at `size`, in points.
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.vertical_metrics().0 as f32 * size / 1000.0
+    }
+
+    /// How far the font reaches below the baseline at `size`, in points, as
+    /// a negative number.
+    pub fn descent(&self, size: f32) -> f32 {
+        self.vertical_metrics().1 as f32 * size / 1000.0
+    }
+
+    /// The baseline-to-baseline distance the font is designed for at
+    /// `size`, in points. Standard fonts, which carry no line gap, use the
+    /// height of their bounding box.
+    pub fn line_height(&self, size: f32) -> f32 {
+        self.vertical_metrics().2 as f32 * size / 1000.0
+    }
+
+    /// The font for writing top to bottom: embedded fonts switch to their
+    /// vertical mode, standard fonts have none and stay as they are.
+    pub(crate) fn vertical(&self) -> Font {
+        match self {
+            Font::Standard(font) => Font::Standard(*font),
+            Font::TrueType(font) => Font::TrueType(font.vertical()),
+        }
+    }
+
+    /// `text` as it should be shown with this font: Arabic letters take
+    /// their joining forms, where an embedded font has them.
+    pub(crate) fn shape<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match self {
+            Font::Standard(_) => Cow::Borrowed(text),
+            Font::TrueType(font) => font.shape(text),
+        }
+    }
+
+    /// Splits `text` between kerned pairs of glyphs, each piece followed by
+    /// the `TJ` adjustment of the next. Only embedded fonts are kerned.
+    pub(crate) fn kern_runs<'t>(&self, text: &'t str) -> Vec<(&'t str, f32)> {
+        match self {
+            Font::Standard(_) => vec![(text, 0.0)],
+            Font::TrueType(font) => font.kern_runs(text),
+        }
+    }
+
+    /// Ascent, descent and line height in thousandths of the font size.
+    fn vertical_metrics(&self) -> (i32, i32, i32) {
+        match self {
+            Font::Standard(font) => {
+                let metrics = font.metrics();
+                (
+                    metrics.ascent.into(),
+                    metrics.descent.into(),
+                    metrics.line_height.into(),
+                )
+            }
+            Font::TrueType(font) => font.vertical_metrics(),
+        }
+    }
+
+    /// Writes the font dictionary as `id`, along with any objects it needs.
+    /// `used` holds every glyph shown with the font in the document, and
+    /// `cid_set` has embedded subsets tell which glyphs they have.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        alloc: &mut ObjectAllocator,
+        id: ObjectRef,
+        used: &BTreeMap<u16, char>,
+        cid_set: bool,
+    ) -> Result<()> {
+        match self {
+            Font::Standard(font) => writer.write_object(id, font.to_dict()),
+            Font::TrueType(font) => font.write(writer, alloc, id, used, cid_set),
+        }
+    }
+}
+
+/// Standard fonts compare by name, embedded fonts by identity: two handles
+/// are the same font if they were cloned from the same loaded font.
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Font::Standard(a), Font::Standard(b)) => a == b,
+            (Font::TrueType(a), Font::TrueType(b)) => a.id() == b.id(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Font {}
+
+impl Hash for Font {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Font::Standard(font) => font.hash(state),
+            Font::TrueType(font) => font.id().hash(state),
+        }
+    }
+}
+
+impl From<StandardFont> for Font {
+    fn from(font: StandardFont) -> Self {
+        Font::Standard(font)
+    }
+}
+
+impl From<TrueTypeFont> for Font {
+    fn from(font: TrueTypeFont) -> Self {
+        Font::TrueType(font)
+    }
+}
+
+impl From<&TrueTypeFont> for Font {
+    fn from(font: &TrueTypeFont) -> Self {
+        Font::TrueType(font.clone())
+    }
+}
+
+/// Characters of the WinAnsiEncoding byte codes 0x80 to 0x9F, the only range
+/// where the encoding differs from Latin-1. `None` marks unused codes.
+const WIN_ANSI_HIGH: [Option<char>; 32] = [
+    Some('\u{20AC}'),
+    None,
+    Some('\u{201A}'),
+    Some('\u{0192}'),
+    Some('\u{201E}'),
+    Some('\u{2026}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{02C6}'),
+    Some('\u{2030}'),
+    Some('\u{0160}'),
+    Some('\u{2039}'),
+    Some('\u{0152}'),
+    None,
+    Some('\u{017D}'),
+    None,
+    None,
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2022}'),
+    Some('\u{2013}'),
+    Some('\u{2014}'),
+    Some('\u{02DC}'),
+    Some('\u{2122}'),
+    Some('\u{0161}'),
+    Some('\u{203A}'),
+    Some('\u{0153}'),
+    None,
+    Some('\u{017E}'),
+    Some('\u{0178}'),
+];
+
+/// Unicode character shown for a WinAnsiEncoding byte code.
+#[cfg(test)]
+fn win_ansi_char(code: u8) -> Option<char> {
+    match code {
+        0x80..=0x9F => WIN_ANSI_HIGH[(code - 0x80) as usize],
+        _ => Some(code as char),
+    }
+}
+
+/// WinAnsiEncoding byte code of `ch`, if the encoding covers it.
+fn win_ansi_code(ch: char) -> Option<u8> {
+    match ch as u32 {
+        0..=0x7F | 0xA0..=0xFF => Some(ch as u8),
+        _ => WIN_ANSI_HIGH
+            .iter()
+            .position(|c| *c == Some(ch))
+            .map(|i| 0x80 + i as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::truetype::test_font;
+
+    #[test]
+    fn test_win_ansi_round_trip() {
+        for code in 0..=255u8 {
+            if let Some(ch) = win_ansi_char(code) {
+                assert_eq!(win_ansi_code(ch), Some(code));
+            }
+        }
+        assert_eq!(win_ansi_code('€'), Some(0x80));
+        assert_eq!(win_ansi_code('é'), Some(0xE9));
+        assert_eq!(win_ansi_code('Ж'), None);
+    }
+
+    #[test]
+    fn test_standard_font_encoding() {
+        assert_eq!(
+            StandardFont::Helvetica.encode("Zürich €5"),
+            b"Z\xfcrich \x805"
+        );
+        assert_eq!(StandardFont::Helvetica.encode("Жук"), b"???");
+        assert!(StandardFont::Symbol.to_dict().get("Encoding").is_none());
+    }
+
+    #[test]
+    fn test_measure_standard_fonts() {
+        let helvetica = Font::from(StandardFont::Helvetica);
+        assert_eq!(helvetica.measure("Hello", 10.0), 22.78);
+        assert_eq!(helvetica.measure("€", 1000.0), 556.0);
+        assert_eq!(
+            Font::from(StandardFont::Courier).measure("any text", 10.0),
+            48.0
+        );
+    }
+
+    #[test]
+    fn test_vertical_metrics() {
+        let times = Font::from(StandardFont::TimesRoman);
+        assert_eq!(times.ascent(10.0), 6.83);
+        assert_eq!(times.descent(10.0), -2.17);
+        assert_eq!(times.line_height(10.0), 11.16);
+
+        let dejavu = Font::from(test_font());
+        assert_eq!(dejavu.ascent(1000.0), 928.0);
+        assert_eq!(dejavu.descent(1000.0), -236.0);
+        assert_eq!(dejavu.line_height(1000.0), 1164.0);
+        assert_eq!(dejavu.measure("Hello", 10.0).round(), 25.0);
+    }
+}
@@ -0,0 +1,1453 @@
+use crate::color::Color;
+use crate::font::{Font, StandardFont};
+use crate::geometry::{Rect, number};
+use crate::graphics::{Paint, Path};
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::page::ContentStream;
+use crate::resources::ResourceRefs;
+use crate::text::TextStyle;
+use crate::writer::PdfWriter;
+use crate::xobject::FormXObject;
+use anyhow::{Result, bail, ensure};
+use std::io::Write;
+
+/// The fields of a fillable form laid over the pages, such as the boxes of
+/// an application form, which readers let the user fill in, save and
+/// print: the interactive form of the document. Built with
+/// [`PdfDocument::form`](crate::PdfDocument::form).
+#[derive(Debug, Clone, Default)]
+pub struct InteractiveForm {
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Text(TextField),
+    CheckBox(CheckBox),
+    Radio(RadioGroup),
+    Choice(ChoiceField),
+    Button(PushButton),
+    Signature(SignatureField),
+}
+
+/// The value of a field as exchanged with other forms software: text, or
+/// for checkboxes and radio buttons the name of the state they are in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FieldValue<'a> {
+    Text(&'a str),
+    State(&'a str),
+}
+
+impl Field {
+    fn name(&self) -> &str {
+        match self {
+            Field::Text(field) => &field.name,
+            Field::CheckBox(field) => &field.name,
+            Field::Radio(group) => &group.name,
+            Field::Choice(field) => &field.name,
+            Field::Button(button) => &button.name,
+            Field::Signature(field) => &field.name,
+        }
+    }
+}
+
+/// A box the user types text into.
+#[derive(Debug, Clone)]
+pub struct TextField {
+    name: String,
+    page: usize,
+    rect: Rect,
+    font: StandardFont,
+    size: f32,
+    max_length: Option<u32>,
+    multiline: bool,
+    comb: bool,
+    value: Option<String>,
+    look: FormXObject,
+}
+
+/// A box the user ticks or leaves empty.
+#[derive(Debug, Clone)]
+pub struct CheckBox {
+    name: String,
+    page: usize,
+    rect: Rect,
+    export_value: String,
+    checked: bool,
+    look: OnOff,
+}
+
+/// Buttons of which the user picks one, such as the answers to a multiple
+/// choice question, the value of the group being that of the button picked.
+#[derive(Debug, Clone)]
+pub struct RadioGroup {
+    name: String,
+    options: Vec<RadioOption>,
+    selected: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct RadioOption {
+    value: String,
+    page: usize,
+    rect: Rect,
+    look: OnOff,
+}
+
+/// A list of options the user picks from, either dropped down from a box
+/// or shown in a scrolling list.
+#[derive(Debug, Clone)]
+pub struct ChoiceField {
+    name: String,
+    page: usize,
+    rect: Rect,
+    font: StandardFont,
+    size: f32,
+    combo: bool,
+    /// The value of each option and the text shown for it, if different.
+    options: Vec<(String, Option<String>)>,
+    editable: bool,
+    selected: Option<String>,
+    look: FormXObject,
+}
+
+/// A button that does something when clicked, such as sending the form to
+/// a server.
+#[derive(Debug, Clone)]
+pub struct PushButton {
+    name: String,
+    page: usize,
+    rect: Rect,
+    caption: String,
+    action: Option<ButtonAction>,
+    look: FormXObject,
+}
+
+/// A place for a digital signature, left empty for the document to be
+/// signed later, in a reader or by a signing service.
+#[derive(Debug, Clone)]
+pub struct SignatureField {
+    name: String,
+    page: usize,
+    rect: Rect,
+    look: FormXObject,
+}
+
+/// What clicking a [`PushButton`] does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ButtonAction {
+    /// Sends the values of the fields to `url`, in `format`.
+    SubmitForm { url: String, format: SubmitFormat },
+    /// Sets the fields back to their default values.
+    ResetForm,
+    /// Runs a script, for the readers that run them.
+    JavaScript(String),
+    /// Opens a web address or other URI.
+    Uri(String),
+}
+
+/// How a form is sent to a server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubmitFormat {
+    /// Forms Data Format, the values as a PDF-like file.
+    #[default]
+    Fdf,
+    /// XML Forms Data Format.
+    Xfdf,
+    /// As an HTML form would be posted.
+    Html,
+    /// The whole document, filled in.
+    Pdf,
+}
+
+impl ButtonAction {
+    /// The action dictionary.
+    fn to_pdf(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        match self {
+            ButtonAction::SubmitForm { url, format } => {
+                let flags = match format {
+                    SubmitFormat::Fdf => 0,
+                    SubmitFormat::Xfdf => XFDF,
+                    SubmitFormat::Html => EXPORT_FORMAT,
+                    SubmitFormat::Pdf => SUBMIT_PDF,
+                };
+                let mut file = Dictionary::new();
+                file.set("FS", Object::name("URL"));
+                file.set("F", Object::string(url.as_bytes()));
+                dict.set("S", Object::name("SubmitForm"));
+                dict.set("F", file);
+                dict.set("Flags", flags);
+            }
+            ButtonAction::ResetForm => dict.set("S", Object::name("ResetForm")),
+            ButtonAction::JavaScript(script) => {
+                dict.set("S", Object::name("JavaScript"));
+                dict.set("JS", Object::text(script));
+            }
+            ButtonAction::Uri(uri) => {
+                dict.set("S", Object::name("URI"));
+                dict.set("URI", Object::string(uri.as_bytes()));
+            }
+        }
+        dict
+    }
+}
+
+/// The appearances of a button when on and when off.
+#[derive(Debug, Clone)]
+struct OnOff {
+    on: FormXObject,
+    off: FormXObject,
+}
+
+/// The objects of a field: its own dictionary and those of the widget
+/// annotations showing it on the pages, the same object for a field shown
+/// once.
+#[derive(Debug)]
+pub(crate) struct FieldRefs {
+    field: ObjectRef,
+    widgets: Vec<ObjectRef>,
+}
+
+impl InteractiveForm {
+    /// Adds a text field called `name`, by which the filled in value is
+    /// known, in `rect` on the page at index `page`, counting from 0. It is
+    /// empty, a single line and in 12 point Helvetica until told otherwise.
+    pub fn text_field(&mut self, name: &str, rect: Rect, page: usize) -> &mut TextField {
+        let mut field = TextField {
+            name: name.to_string(),
+            page,
+            rect,
+            font: StandardFont::Helvetica,
+            size: 12.0,
+            max_length: None,
+            multiline: false,
+            comb: false,
+            value: None,
+            look: blank(),
+        };
+        field.regenerate();
+        self.fields.push(Field::Text(field));
+        match self.fields.last_mut() {
+            Some(Field::Text(field)) => field,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds an unticked checkbox called `name` in `rect` on the page at
+    /// index `page`, its value `Yes` when ticked and `Off` otherwise.
+    pub fn checkbox(&mut self, name: &str, rect: Rect, page: usize) -> &mut CheckBox {
+        self.fields.push(Field::CheckBox(CheckBox {
+            name: name.to_string(),
+            page,
+            rect,
+            export_value: String::from("Yes"),
+            checked: false,
+            look: OnOff::new(rect, CHECK),
+        }));
+        match self.fields.last_mut() {
+            Some(Field::CheckBox(field)) => field,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds a group of radio buttons called `name`, its buttons added with
+    /// [`RadioGroup::option`] and none of them picked at first.
+    pub fn radio_group(&mut self, name: &str) -> &mut RadioGroup {
+        self.fields.push(Field::Radio(RadioGroup {
+            name: name.to_string(),
+            options: Vec::new(),
+            selected: None,
+        }));
+        match self.fields.last_mut() {
+            Some(Field::Radio(group)) => group,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds a drop-down list called `name` in `rect` on the page at index
+    /// `page`, its options added with [`ChoiceField::option`], in 12 point
+    /// Helvetica and with nothing picked until told otherwise.
+    pub fn combo_box(&mut self, name: &str, rect: Rect, page: usize) -> &mut ChoiceField {
+        self.choice(name, rect, page, true)
+    }
+
+    /// Adds a scrolling list of options called `name` in `rect` on the page
+    /// at index `page`, like [`combo_box`](Self::combo_box) otherwise.
+    pub fn list_box(&mut self, name: &str, rect: Rect, page: usize) -> &mut ChoiceField {
+        self.choice(name, rect, page, false)
+    }
+
+    fn choice(&mut self, name: &str, rect: Rect, page: usize, combo: bool) -> &mut ChoiceField {
+        let mut field = ChoiceField {
+            name: name.to_string(),
+            page,
+            rect,
+            font: StandardFont::Helvetica,
+            size: 12.0,
+            combo,
+            options: Vec::new(),
+            editable: false,
+            selected: None,
+            look: blank(),
+        };
+        field.regenerate();
+        self.fields.push(Field::Choice(field));
+        match self.fields.last_mut() {
+            Some(Field::Choice(field)) => field,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds a push button called `name` in `rect` on the page at index
+    /// `page`, gray and without a caption or action until given them.
+    pub fn push_button(&mut self, name: &str, rect: Rect, page: usize) -> &mut PushButton {
+        self.fields.push(Field::Button(PushButton {
+            name: name.to_string(),
+            page,
+            rect,
+            caption: String::new(),
+            action: None,
+            look: button_face(rect, ""),
+        }));
+        match self.fields.last_mut() {
+            Some(Field::Button(button)) => button,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds an empty signature field called `name` in `rect` on the page at
+    /// index `page`, outlined until given an appearance of its own.
+    pub fn signature_field(&mut self, name: &str, rect: Rect, page: usize) -> &mut SignatureField {
+        self.fields.push(Field::Signature(SignatureField {
+            name: name.to_string(),
+            page,
+            rect,
+            look: signature_outline(rect),
+        }));
+        match self.fields.last_mut() {
+            Some(Field::Signature(field)) => field,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The rectangle of the signature field called `name`, if the form has
+    /// one.
+    pub(crate) fn signature_rect(&self, name: &str) -> Option<Rect> {
+        self.fields.iter().find_map(|field| match field {
+            Field::Signature(field) if field.name == name => Some(field.rect),
+            _ => None,
+        })
+    }
+
+    /// Has the signature field called `name` show `look`, as it does once
+    /// signed.
+    pub(crate) fn set_signature_appearance(&mut self, name: &str, look: FormXObject) {
+        for field in &mut self.fields {
+            match field {
+                Field::Signature(field) if field.name == name => field.look = look.clone(),
+                _ => {}
+            }
+        }
+    }
+
+    /// The names of the fields taking values, in order, and their values,
+    /// if they have any.
+    pub(crate) fn values(&self) -> Vec<(&str, Option<FieldValue<'_>>)> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let value = match field {
+                    Field::Text(field) => field.value.as_deref().map(FieldValue::Text),
+                    Field::Choice(field) => field.selected.as_deref().map(FieldValue::Text),
+                    Field::CheckBox(field) => Some(FieldValue::State(match field.checked {
+                        true => &field.export_value,
+                        false => "Off",
+                    })),
+                    Field::Radio(group) => Some(FieldValue::State(
+                        group.selected.as_deref().unwrap_or("Off"),
+                    )),
+                    Field::Button(_) | Field::Signature(_) => return None,
+                };
+                Some((field.name(), value))
+            })
+            .collect()
+    }
+
+    /// Sets the value of the field `name` to `value`, the name of a state
+    /// for checkboxes and radio buttons, as it is when the document is
+    /// opened. Fails if there is no such field or it takes no such value.
+    pub(crate) fn set_value(&mut self, name: &str, value: &str) -> Result<()> {
+        let Some(field) = self.fields.iter_mut().find(|field| field.name() == name) else {
+            bail!("the form has no field named {:?}", name);
+        };
+        match field {
+            Field::Text(field) => {
+                field.set_default_value(value);
+            }
+            Field::Choice(field) => {
+                field.set_selected(value);
+            }
+            Field::CheckBox(field) => {
+                ensure!(
+                    value == "Off" || value == field.export_value,
+                    "{:?} is not a value of the checkbox {:?}",
+                    value,
+                    name
+                );
+                field.set_checked(value != "Off");
+            }
+            Field::Radio(group) => {
+                if value == "Off" {
+                    group.selected = None;
+                } else {
+                    ensure!(
+                        group.options.iter().any(|option| option.value == value),
+                        "{:?} is not a value of the radio group {:?}",
+                        value,
+                        name
+                    );
+                    group.set_selected(value);
+                }
+            }
+            Field::Button(_) | Field::Signature(_) => {
+                bail!("the form field {:?} takes no value", name)
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the fields show as they are now: the name, page index and
+    /// rectangle of each widget and the appearance it has.
+    pub(crate) fn placements(&self) -> Vec<(&str, usize, Rect, &FormXObject)> {
+        let mut placements = Vec::new();
+        for field in &self.fields {
+            let name = field.name();
+            match field {
+                Field::Text(field) => placements.push((name, field.page, field.rect, &field.look)),
+                Field::Choice(field) => {
+                    placements.push((name, field.page, field.rect, &field.look))
+                }
+                Field::Button(button) => {
+                    placements.push((name, button.page, button.rect, &button.look))
+                }
+                Field::Signature(field) => {
+                    placements.push((name, field.page, field.rect, &field.look))
+                }
+                Field::CheckBox(field) => {
+                    let look = match field.checked {
+                        true => &field.look.on,
+                        false => &field.look.off,
+                    };
+                    placements.push((name, field.page, field.rect, look));
+                }
+                Field::Radio(group) => {
+                    for option in &group.options {
+                        let look = match group.selected.as_ref() == Some(&option.value) {
+                            true => &option.look.on,
+                            false => &option.look.off,
+                        };
+                        placements.push((name, option.page, option.rect, look));
+                    }
+                }
+            }
+        }
+        placements
+    }
+
+    /// What clicking the push buttons does.
+    pub(crate) fn actions(&self) -> impl Iterator<Item = &ButtonAction> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::Button(button) => button.action.as_ref(),
+            _ => None,
+        })
+    }
+
+    /// The fonts the fields are set in, possibly repeated.
+    pub(crate) fn fonts(&self) -> impl Iterator<Item = Font> + '_ {
+        self.fields.iter().filter_map(|field| match field {
+            Field::Text(field) => Some(Font::Standard(field.font)),
+            Field::Choice(field) => Some(Font::Standard(field.font)),
+            Field::Button(_) => Some(Font::Standard(CAPTION_FONT)),
+            Field::CheckBox(_) | Field::Radio(_) => {
+                Some(Font::Standard(StandardFont::ZapfDingbats))
+            }
+            Field::Signature(_) => None,
+        })
+    }
+
+    /// The forms drawing the fields, those of buttons that can be on and
+    /// off both ways.
+    pub(crate) fn appearances(&self) -> impl Iterator<Item = &FormXObject> {
+        self.fields.iter().flat_map(|field| -> Vec<&FormXObject> {
+            match field {
+                Field::Text(field) => vec![&field.look],
+                Field::Choice(field) => vec![&field.look],
+                Field::CheckBox(field) => vec![&field.look.on, &field.look.off],
+                Field::Radio(group) => group
+                    .options
+                    .iter()
+                    .flat_map(|option| [&option.look.on, &option.look.off])
+                    .collect(),
+                Field::Button(button) => vec![&button.look],
+                Field::Signature(field) => vec![&field.look],
+            }
+        })
+    }
+
+    /// Numbers the objects of the fields, in order.
+    pub(crate) fn allocate(&self, alloc: &mut ObjectAllocator) -> Vec<FieldRefs> {
+        self.fields
+            .iter()
+            .map(|field| match field {
+                Field::Text(_)
+                | Field::CheckBox(_)
+                | Field::Choice(_)
+                | Field::Button(_)
+                | Field::Signature(_) => {
+                    let field = alloc.allocate();
+                    FieldRefs {
+                        field,
+                        widgets: vec![field],
+                    }
+                }
+                Field::Radio(group) => FieldRefs {
+                    field: alloc.allocate(),
+                    widgets: group.options.iter().map(|_| alloc.allocate()).collect(),
+                },
+            })
+            .collect()
+    }
+
+    /// The widget annotations on the page at index `page`, the fields being
+    /// numbered `ids`.
+    pub(crate) fn widgets_on(&self, page: usize, ids: &[FieldRefs]) -> Vec<ObjectRef> {
+        let mut widgets = Vec::new();
+        for (field, ids) in self.fields.iter().zip(ids) {
+            let pages: Vec<usize> = match field {
+                Field::Text(field) => vec![field.page],
+                Field::CheckBox(field) => vec![field.page],
+                Field::Choice(field) => vec![field.page],
+                Field::Button(button) => vec![button.page],
+                Field::Signature(field) => vec![field.page],
+                Field::Radio(group) => group.options.iter().map(|option| option.page).collect(),
+            };
+            for (on, widget) in pages.into_iter().zip(&ids.widgets) {
+                if on == page {
+                    widgets.push(*widget);
+                }
+            }
+        }
+        widgets
+    }
+
+    /// Writes the fields, numbered `ids`, and the interactive form
+    /// dictionary as `id`, `pages` being the page objects. If the document
+    /// is signed, `signed` is the name of the signature field holding the
+    /// signature and the number of the signature dictionary.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        id: ObjectRef,
+        ids: &[FieldRefs],
+        pages: &[ObjectRef],
+        refs: &ResourceRefs,
+        signed: Option<(&str, ObjectRef)>,
+    ) -> Result<()> {
+        let mut names: Vec<&str> = Vec::new();
+        for (field, ids) in self.fields.iter().zip(ids) {
+            let name = field.name();
+            ensure!(
+                !names.contains(&name),
+                "two form fields are named {:?}",
+                name
+            );
+            names.push(name);
+            match field {
+                Field::Text(field) => {
+                    let mut dict = widget(name, field.page, field.rect, pages)?;
+                    dict.set("FT", Object::name("Tx"));
+                    dict.set("T", Object::text(name));
+                    dict.set("DA", text_look(field.font.base_font(), field.size));
+                    dict.set("MK", black_border());
+                    dict.set("AP", appearance(refs.forms[&field.look]));
+                    ensure!(
+                        !field.comb || field.max_length.is_some(),
+                        "the comb field {:?} has no maximum length",
+                        name
+                    );
+                    let mut flags = if field.multiline { MULTILINE } else { 0 };
+                    if field.comb {
+                        flags |= COMB;
+                    }
+                    if flags != 0 {
+                        dict.set("Ff", flags);
+                    }
+                    if let Some(max_length) = field.max_length {
+                        dict.set("MaxLen", max_length);
+                    }
+                    if let Some(value) = &field.value {
+                        dict.set("V", Object::text(value));
+                        dict.set("DV", Object::text(value));
+                    }
+                    writer.write_object(ids.field, dict)?;
+                }
+                Field::CheckBox(field) => {
+                    ensure!(
+                        field.export_value != "Off",
+                        "the checkbox {:?} cannot take Off as the value when ticked",
+                        name
+                    );
+                    let state = match field.checked {
+                        true => Object::name(field.export_value.as_bytes()),
+                        false => Object::name("Off"),
+                    };
+                    let mut dict = widget(name, field.page, field.rect, pages)?;
+                    dict.set("FT", Object::name("Btn"));
+                    dict.set("T", Object::text(name));
+                    dict.set("V", state.clone());
+                    dict.set("DV", state.clone());
+                    dict.set("AS", state);
+                    dict.set("AP", field.look.to_pdf(&field.export_value, refs));
+                    button_look(&mut dict, CHECK);
+                    writer.write_object(ids.field, dict)?;
+                }
+                Field::Choice(field) => {
+                    let mut flags = if field.combo { COMBO } else { 0 };
+                    if field.combo && field.editable {
+                        flags |= EDIT;
+                    }
+                    let options = field
+                        .options
+                        .iter()
+                        .map(|(value, label)| match label {
+                            Some(label) => {
+                                Object::Array(vec![Object::text(value), Object::text(label)])
+                            }
+                            None => Object::text(value),
+                        })
+                        .collect();
+                    let mut dict = widget(name, field.page, field.rect, pages)?;
+                    dict.set("FT", Object::name("Ch"));
+                    dict.set("T", Object::text(name));
+                    dict.set("DA", text_look(field.font.base_font(), field.size));
+                    dict.set("MK", black_border());
+                    dict.set("AP", appearance(refs.forms[&field.look]));
+                    dict.set("Ff", flags);
+                    dict.set("Opt", Object::Array(options));
+                    if let Some(value) = &field.selected {
+                        // Lists also tell the option picked by its place.
+                        let index = field.options.iter().position(|(option, _)| option == value);
+                        ensure!(
+                            index.is_some() || field.combo && field.editable,
+                            "{:?} is not an option of the form field {:?}",
+                            value,
+                            name
+                        );
+                        dict.set("V", Object::text(value));
+                        dict.set("DV", Object::text(value));
+                        if let (false, Some(index)) = (field.combo, index) {
+                            dict.set("I", vec![index.into()]);
+                        }
+                    }
+                    writer.write_object(ids.field, dict)?;
+                }
+                Field::Button(button) => {
+                    let mut look = Dictionary::new();
+                    look.set("BG", vec![BUTTON_GRAY.into()]);
+                    look.set("BC", vec![0.into()]);
+                    look.set("CA", Object::text(&button.caption));
+                    let mut dict = widget(name, button.page, button.rect, pages)?;
+                    dict.set("FT", Object::name("Btn"));
+                    dict.set("Ff", PUSH_BUTTON);
+                    dict.set("T", Object::text(name));
+                    dict.set("MK", look);
+                    dict.set(
+                        "DA",
+                        text_look(CAPTION_FONT.base_font(), caption_size(button.rect)),
+                    );
+                    dict.set("AP", appearance(refs.forms[&button.look]));
+                    if let Some(action) = &button.action {
+                        dict.set("A", action.to_pdf());
+                    }
+                    writer.write_object(ids.field, dict)?;
+                }
+                Field::Signature(field) => {
+                    // The value is the signature dictionary, if the document
+                    // is signed in this field.
+                    let mut dict = widget(name, field.page, field.rect, pages)?;
+                    dict.set("FT", Object::name("Sig"));
+                    dict.set("T", Object::text(name));
+                    dict.set("AP", appearance(refs.forms[&field.look]));
+                    if let Some((signed, signature)) = signed
+                        && signed == name
+                    {
+                        dict.set("V", signature);
+                    }
+                    writer.write_object(ids.field, dict)?;
+                }
+                Field::Radio(group) => {
+                    ensure!(
+                        !group.options.is_empty(),
+                        "the radio group {:?} has no buttons",
+                        name
+                    );
+                    ensure!(
+                        group.options.iter().all(|option| option.value != "Off"),
+                        "the radio group {:?} cannot take Off as the value of a button",
+                        name
+                    );
+                    let kids = ids.widgets.iter().map(|&kid| kid.into()).collect();
+                    let state = group
+                        .selected
+                        .as_ref()
+                        .map_or(Object::name("Off"), |value| Object::name(value.as_bytes()));
+                    let mut dict = Dictionary::new();
+                    dict.set("FT", Object::name("Btn"));
+                    dict.set("Ff", RADIO | NO_TOGGLE_TO_OFF);
+                    dict.set("T", Object::text(name));
+                    dict.set("V", state.clone());
+                    dict.set("DV", state);
+                    dict.set("Kids", Object::Array(kids));
+                    writer.write_object(ids.field, dict)?;
+                    for (option, widget_id) in group.options.iter().zip(&ids.widgets) {
+                        let state = match group.selected.as_ref() == Some(&option.value) {
+                            true => Object::name(option.value.as_bytes()),
+                            false => Object::name("Off"),
+                        };
+                        let mut dict = widget(name, option.page, option.rect, pages)?;
+                        dict.set("Parent", ids.field);
+                        dict.set("AS", state);
+                        dict.set("AP", option.look.to_pdf(&option.value, refs));
+                        button_look(&mut dict, DOT);
+                        writer.write_object(*widget_id, dict)?;
+                    }
+                }
+            }
+        }
+
+        let fields = ids.iter().map(|ids| ids.field.into()).collect();
+        let mut fonts: Vec<Font> = Vec::new();
+        for font in self.fonts() {
+            if !fonts.contains(&font) {
+                fonts.push(font);
+            }
+        }
+        let mut dict = Dictionary::new();
+        dict.set("Fields", Object::Array(fields));
+        if !fonts.is_empty() {
+            let mut names = Dictionary::new();
+            for font in &fonts {
+                match font {
+                    Font::Standard(standard) => names.set(standard.base_font(), refs.fonts[font]),
+                    Font::TrueType(_) => unreachable!("form fields are set in standard fonts"),
+                }
+            }
+            let mut resources = Dictionary::new();
+            resources.set("Font", names);
+            dict.set("DR", resources);
+        }
+        if self
+            .fields
+            .iter()
+            .any(|field| matches!(field, Field::Signature(_)))
+        {
+            let flags = match signed {
+                Some(_) => SIGNATURES_EXIST | APPEND_ONLY,
+                None => SIGNATURES_EXIST,
+            };
+            dict.set("SigFlags", flags);
+        }
+        writer.write_object(id, dict)
+    }
+}
+
+/// The start of the dictionary of a widget annotation of the field `name`,
+/// shown in `rect` on the page at index `page`.
+pub(crate) fn widget(
+    name: &str,
+    page: usize,
+    rect: Rect,
+    pages: &[ObjectRef],
+) -> Result<Dictionary> {
+    ensure!(
+        page < pages.len(),
+        "form field {:?} on page {} of a document of {} pages",
+        name,
+        page + 1,
+        pages.len()
+    );
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::name("Annot"));
+    dict.set("Subtype", Object::name("Widget"));
+    dict.set("Rect", rect);
+    dict.set("P", pages[page]);
+    dict.set("F", 4);
+    Ok(dict)
+}
+
+/// The `/AP` entry of a widget drawn by `form`.
+pub(crate) fn appearance(form: ObjectRef) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("N", form);
+    dict
+}
+
+/// The `/DA` entry of text set in black in the standard font `font` at
+/// `size`, 0 for a size fitting the widget.
+fn text_look(font: &str, size: f32) -> Object {
+    Object::string(format!("/{} {} Tf 0 g", font, number(size)))
+}
+
+/// The `/MK` entry of a widget with a black border.
+fn black_border() -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("BC", vec![0.into()]);
+    dict
+}
+
+/// What readers draw a button with when making its appearance anew: a
+/// black border, and `mark`, a character of ZapfDingbats, when it is on.
+fn button_look(dict: &mut Dictionary, mark: char) {
+    let mut look = black_border();
+    look.set("CA", Object::string(mark.to_string()));
+    dict.set("MK", look);
+    dict.set("DA", text_look("ZapfDingbats", 0.0));
+}
+
+impl TextField {
+    /// The font and size the text is shown in. Only the standard fonts can
+    /// be used, as readers set the text anew as it is typed; a size of 0
+    /// has it fit the box.
+    pub fn set_font(&mut self, font: StandardFont, size: f32) -> &mut Self {
+        self.font = font;
+        self.size = size;
+        self.regenerate();
+        self
+    }
+
+    /// The most characters the field takes.
+    pub fn set_max_length(&mut self, max_length: u32) -> &mut Self {
+        self.max_length = Some(max_length);
+        self.regenerate();
+        self
+    }
+
+    /// Has the text wrap onto more lines, for comments and addresses, rather
+    /// than scroll along a single line, the default.
+    pub fn set_multiline(&mut self, multiline: bool) -> &mut Self {
+        self.multiline = multiline;
+        self.regenerate();
+        self
+    }
+
+    /// Spreads the characters evenly over as many boxes as the field takes
+    /// characters, one to a box, as for the digits of a date or an account
+    /// number. Writing the document fails if the field has no
+    /// [maximum length](Self::set_max_length).
+    pub fn set_comb(&mut self, comb: bool) -> &mut Self {
+        self.comb = comb;
+        self.regenerate();
+        self
+    }
+
+    /// The text the field holds when the document is opened, and when the
+    /// form is reset.
+    pub fn set_default_value(&mut self, value: &str) -> &mut Self {
+        self.value = Some(value.to_string());
+        self.regenerate();
+        self
+    }
+
+    /// Draws the field anew as it now is, showing its value in boxes for a
+    /// comb field, wrapped for a field of several lines and on a single
+    /// line otherwise.
+    fn regenerate(&mut self) {
+        let (width, height) = (self.rect.width.abs(), self.rect.height.abs());
+        let font = Font::Standard(self.font);
+        let value = self.value.as_deref().unwrap_or("");
+        self.look = field_face(width, height, |g| match (self.comb, self.max_length) {
+            (true, Some(cells)) if cells > 0 => {
+                let cell = width / cells as f32;
+                let size = auto_size(self.size, height).min(cell);
+                let y = baseline(&font, size, height);
+                for (i, c) in value.chars().take(cells as usize).enumerate() {
+                    let text = c.to_string();
+                    let x = cell * (i as f32 + 0.5) - font.measure(&text, size) / 2.0;
+                    g.draw_text(x, y, size, font.clone(), &text);
+                }
+            }
+            _ if self.multiline => {
+                let size = match self.size {
+                    0.0 => 12.0,
+                    size => size,
+                };
+                let area = Rect::new(
+                    PADDING,
+                    PADDING,
+                    width - 2.0 * PADDING,
+                    height - 2.0 * PADDING,
+                );
+                g.draw_text_wrapped(area, value, &TextStyle::new(font.clone(), size));
+            }
+            _ => {
+                let size = auto_size(self.size, height);
+                g.draw_text(
+                    PADDING,
+                    baseline(&font, size, height),
+                    size,
+                    font.clone(),
+                    value,
+                );
+            }
+        });
+    }
+}
+
+impl CheckBox {
+    /// The value of the field when it is ticked, such as `Married` for a
+    /// field `status`. It must not be `Off`, the value when it is not, or
+    /// the form fails to be written.
+    pub fn set_export_value(&mut self, value: &str) -> &mut Self {
+        self.export_value = value.to_string();
+        self
+    }
+
+    /// Has the box ticked when the document is opened, and when the form is
+    /// reset.
+    pub fn set_checked(&mut self, checked: bool) -> &mut Self {
+        self.checked = checked;
+        self
+    }
+}
+
+impl RadioGroup {
+    /// Adds a button in `rect` on the page at index `page`, the value of
+    /// the group being `value` when it is picked. Buttons sharing a value
+    /// are on and off together. It must not be `Off`, the value when none
+    /// is, or the form fails to be written.
+    pub fn option(&mut self, value: &str, rect: Rect, page: usize) -> &mut Self {
+        self.options.push(RadioOption {
+            value: value.to_string(),
+            page,
+            rect,
+            look: OnOff::new(rect, DOT),
+        });
+        self
+    }
+
+    /// Has the button of `value` picked when the document is opened, and
+    /// when the form is reset.
+    pub fn set_selected(&mut self, value: &str) -> &mut Self {
+        self.selected = Some(value.to_string());
+        self
+    }
+}
+
+impl ChoiceField {
+    /// Adds `value` to the options, after those already there.
+    pub fn option(&mut self, value: &str) -> &mut Self {
+        self.options.push((value.to_string(), None));
+        self.regenerate();
+        self
+    }
+
+    /// Adds an option showing `label` whose value is `value`, such as a
+    /// country code for the name of the country.
+    pub fn option_labeled(&mut self, value: &str, label: &str) -> &mut Self {
+        self.options
+            .push((value.to_string(), Some(label.to_string())));
+        self.regenerate();
+        self
+    }
+
+    /// The font and size the options are shown in, one of the standard
+    /// fonts as for [`TextField::set_font`].
+    pub fn set_font(&mut self, font: StandardFont, size: f32) -> &mut Self {
+        self.font = font;
+        self.size = size;
+        self.regenerate();
+        self
+    }
+
+    /// Lets the user type a value of their own in a drop-down list, rather
+    /// than only pick one of the options. Lists never take other values.
+    pub fn set_editable(&mut self, editable: bool) -> &mut Self {
+        self.editable = editable;
+        self
+    }
+
+    /// Has the option of `value` picked when the document is opened, and
+    /// when the form is reset. Writing the document fails if there is no
+    /// such option, unless the drop-down list is editable.
+    pub fn set_selected(&mut self, value: &str) -> &mut Self {
+        self.selected = Some(value.to_string());
+        self.regenerate();
+        self
+    }
+
+    /// The text shown for the option of `value`, the value itself for one
+    /// typed in.
+    fn label<'a>(&'a self, value: &'a str) -> &'a str {
+        self.options
+            .iter()
+            .find(|(option, _)| option == value)
+            .and_then(|(_, label)| label.as_deref())
+            .unwrap_or(value)
+    }
+
+    /// Draws the field anew as it now is: a drop-down list showing the
+    /// option picked, a list showing the options from the top with the one
+    /// picked highlighted.
+    fn regenerate(&mut self) {
+        let (width, height) = (self.rect.width.abs(), self.rect.height.abs());
+        let font = Font::Standard(self.font);
+        self.look = field_face(width, height, |g| {
+            if self.combo {
+                let size = auto_size(self.size, height);
+                let text = self
+                    .selected
+                    .as_deref()
+                    .map_or("", |value| self.label(value));
+                g.draw_text(
+                    PADDING,
+                    baseline(&font, size, height),
+                    size,
+                    font.clone(),
+                    text,
+                );
+                return;
+            }
+            let size = match self.size {
+                0.0 => 12.0,
+                size => size,
+            };
+            let line = font.line_height(size);
+            let mut top = height - PADDING;
+            for (value, label) in &self.options {
+                if self.selected.as_ref() == Some(value) {
+                    g.rect(0.0, top - line, width, line, &Paint::fill(HIGHLIGHT));
+                }
+                let text = label.as_deref().unwrap_or(value);
+                let y = top - line + baseline(&font, size, line);
+                g.draw_text(PADDING, y, size, font.clone(), text);
+                top -= line;
+            }
+        });
+    }
+}
+
+impl PushButton {
+    /// The text shown on the button, such as `Send`.
+    pub fn set_caption(&mut self, caption: &str) -> &mut Self {
+        self.caption = caption.to_string();
+        self.look = button_face(self.rect, caption);
+        self
+    }
+
+    /// What clicking the button does.
+    pub fn set_action(&mut self, action: ButtonAction) -> &mut Self {
+        self.action = Some(action);
+        self
+    }
+}
+
+impl SignatureField {
+    /// What the field shows until signed, such as a line to sign on, drawn
+    /// stretched over the field.
+    pub fn set_appearance(&mut self, look: FormXObject) -> &mut Self {
+        self.look = look;
+        self
+    }
+}
+
+/// How far the text of text and choice fields keeps from their edges.
+const PADDING: f32 = 2.0;
+
+/// The background of the option picked in a list.
+const HIGHLIGHT: Color = Color::Rgb(0.6, 0.75, 0.9);
+
+/// The form the fields show until drawn.
+fn blank() -> FormXObject {
+    FormXObject::new(0.0, 0.0, |_| {})
+}
+
+/// The appearance of a text or choice field of `width` by `height`:
+/// outlined in black, with what `draw` shows cut to the inside.
+fn field_face(width: f32, height: f32, draw: impl FnOnce(&mut ContentStream)) -> FormXObject {
+    FormXObject::new(width, height, |g| {
+        g.rect(
+            0.5,
+            0.5,
+            width - 1.0,
+            height - 1.0,
+            &Paint::stroke(Color::BLACK),
+        );
+        let inside = Path::rect(Rect::new(1.0, 1.0, width - 2.0, height - 2.0));
+        g.clip(&inside, draw);
+    })
+}
+
+/// The size of text of a single line in a field `height` high: `size`, or
+/// for a size of 0 as large as fits, up to 12 points.
+fn auto_size(size: f32, height: f32) -> f32 {
+    match size {
+        0.0 => ((height - 2.0 * PADDING) * 0.8).clamp(1.0, 12.0),
+        size => size,
+    }
+}
+
+/// The baseline of a line of `font` at `size` centered in a field `height`
+/// high.
+fn baseline(font: &Font, size: f32, height: f32) -> f32 {
+    (height - font.ascent(size) - font.descent(size)) / 2.0
+}
+
+/// The look of empty signature fields: a thin gray outline.
+pub(crate) fn signature_outline(rect: Rect) -> FormXObject {
+    let (width, height) = (rect.width.abs(), rect.height.abs());
+    FormXObject::new(width, height, |g| {
+        let border = Paint::stroke(Color::Gray(0.5));
+        g.rect(0.5, 0.5, width - 1.0, height - 1.0, &border);
+    })
+}
+
+/// The look of push buttons: gray, outlined in black, with a caption
+/// centered in Helvetica, as large as fits the height up to 12 points.
+const BUTTON_GRAY: f32 = 0.75;
+const CAPTION_FONT: StandardFont = StandardFont::Helvetica;
+
+fn caption_size(rect: Rect) -> f32 {
+    (rect.height.abs() * 0.6).min(12.0)
+}
+
+/// The appearance of a push button of the size of `rect` showing `caption`.
+fn button_face(rect: Rect, caption: &str) -> FormXObject {
+    let (width, height) = (rect.width.abs(), rect.height.abs());
+    FormXObject::new(width, height, |g| {
+        let paint = Paint::fill_and_stroke(Color::Gray(BUTTON_GRAY), Color::BLACK);
+        g.rect(0.5, 0.5, width - 1.0, height - 1.0, &paint);
+        if !caption.is_empty() {
+            let font = Font::Standard(CAPTION_FONT);
+            let size = caption_size(rect);
+            let x = (width - font.measure(caption, size)) / 2.0;
+            // The baseline so that capitals sit in the middle.
+            let y = (height - size * 0.7) / 2.0;
+            g.draw_text(x, y, size, font, caption);
+        }
+    })
+}
+
+/// The marks of checkboxes and radio buttons in ZapfDingbats: a tick and a
+/// dot.
+const CHECK: char = '4';
+const DOT: char = 'l';
+
+impl OnOff {
+    /// A button of the size of `rect`, outlined, showing `mark` when on:
+    /// round for the dot of a radio button and square otherwise.
+    fn new(rect: Rect, mark: char) -> Self {
+        let (width, height) = (rect.width.abs(), rect.height.abs());
+        let outline = |g: &mut ContentStream| {
+            let border = Paint::stroke(Color::BLACK);
+            match mark {
+                DOT => {
+                    let radius = width.min(height) / 2.0 - 0.5;
+                    g.circle((width / 2.0, height / 2.0), radius, &border);
+                }
+                _ => g.rect(0.5, 0.5, width - 1.0, height - 1.0, &border),
+            }
+        };
+        let on = FormXObject::new(width, height, |g| {
+            outline(g);
+            let font = Font::Standard(StandardFont::ZapfDingbats);
+            let size = width.min(height) * 0.8;
+            let text = mark.to_string();
+            // Centered, the marks reaching about 0.7 of the font size up.
+            let x = (width - font.measure(&text, size)) / 2.0;
+            let y = (height - size * 0.7) / 2.0;
+            g.draw_text(x, y, size, font, &text);
+        });
+        let off = FormXObject::new(width, height, outline);
+        Self { on, off }
+    }
+
+    /// The `/AP` entry of a button whose on state is called `value`.
+    fn to_pdf(&self, value: &str, refs: &ResourceRefs) -> Dictionary {
+        let mut states = Dictionary::new();
+        states.set(value, refs.forms[&self.on]);
+        states.set("Off", refs.forms[&self.off]);
+        let mut dict = Dictionary::new();
+        dict.set("N", states);
+        dict
+    }
+}
+
+/// Field flags: the text of a text field may span several lines; a button
+/// is one of a group of radio buttons, one of which stays on once picked;
+/// a button is a push button; a choice field is a drop-down list, which
+/// may take values typed in; a text field is spread over boxes.
+const MULTILINE: u32 = 1 << 12;
+const NO_TOGGLE_TO_OFF: u32 = 1 << 14;
+const RADIO: u32 = 1 << 15;
+const PUSH_BUTTON: u32 = 1 << 16;
+const COMBO: u32 = 1 << 17;
+const EDIT: u32 = 1 << 18;
+const COMB: u32 = 1 << 24;
+
+/// Submit form flags: the values sent as an HTML form, as XFDF and as the
+/// whole document. FDF is the default.
+const EXPORT_FORMAT: u32 = 1 << 2;
+const XFDF: u32 = 1 << 5;
+const SUBMIT_PDF: u32 = 1 << 8;
+
+/// Signature flags: the document has signature fields, and is signed so
+/// that it should only be changed by incremental updates.
+pub(crate) const SIGNATURES_EXIST: u32 = 1;
+pub(crate) const APPEND_ONLY: u32 = 1 << 1;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `form` on the pages `pages`, the fields numbered from 10, and
+    /// returns what is written.
+    fn write(form: &InteractiveForm, pages: &[ObjectRef]) -> Result<String> {
+        let mut refs = ResourceRefs::default();
+        for (i, font) in StandardFont::ALL.into_iter().enumerate() {
+            refs.fonts
+                .insert(font.into(), ObjectRef::new(100 + i as u32));
+        }
+        for (i, form) in form.appearances().enumerate() {
+            refs.forms
+                .insert(form.clone(), ObjectRef::new(200 + i as u32));
+        }
+        let mut alloc = ObjectAllocator::new();
+        for _ in 1..10 {
+            alloc.allocate();
+        }
+        let ids = form.allocate(&mut alloc);
+        let id = alloc.allocate();
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        form.write(&mut writer, id, &ids, pages, &refs, None)?;
+        Ok(String::from_utf8(buf.into_inner()).unwrap())
+    }
+
+    #[test]
+    fn test_text_fields() {
+        let mut form = InteractiveForm::default();
+        form.text_field("name", Rect::new(100.0, 700.0, 200.0, 20.0), 0)
+            .set_max_length(40)
+            .set_default_value("Jane Doe");
+        form.text_field("comments", Rect::new(100.0, 500.0, 300.0, 100.0), 1)
+            .set_font(StandardFont::Courier, 10.0)
+            .set_multiline(true);
+        let pages = [ObjectRef::new(4), ObjectRef::new(6)];
+        let pdf = write(&form, &pages).unwrap();
+        assert!(pdf.contains(
+            "10 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 700 300 720] /P 4 0 R /F 4 \
+             /FT /Tx /T (name) /DA (/Helvetica 12 Tf 0 g) /MK << /BC [0] >> \
+             /AP << /N 200 0 R >> /MaxLen 40 /V (Jane Doe) /DV (Jane Doe) >>"
+        ));
+        assert!(pdf.contains(
+            "11 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 500 400 600] /P 6 0 R /F 4 \
+             /FT /Tx /T (comments) /DA (/Courier 10 Tf 0 g) /MK << /BC [0] >> \
+             /AP << /N 201 0 R >> /Ff 4096 >>"
+        ));
+        assert!(pdf.contains(
+            "12 0 obj\n<< /Fields [10 0 R 11 0 R] /DR << /Font << /Helvetica 100 0 R \
+             /Courier 108 0 R >> >> >>"
+        ));
+        assert!(write(&form, &pages[..1]).is_err());
+    }
+
+    #[test]
+    fn test_comb_fields() {
+        let mut form = InteractiveForm::default();
+        let rect = Rect::new(100.0, 700.0, 60.0, 20.0);
+        form.text_field("zip", rect, 0).set_comb(true);
+        assert!(write(&form, &[ObjectRef::new(4)]).is_err());
+
+        let mut form = InteractiveForm::default();
+        form.text_field("zip", rect, 0)
+            .set_comb(true)
+            .set_max_length(3)
+            .set_default_value("12");
+        let pdf = write(&form, &[ObjectRef::new(4)]).unwrap();
+        assert!(pdf.contains("/AP << /N 200 0 R >> /Ff 16777216 /MaxLen 3 /V (12) /DV (12) >>"));
+
+        // The digits centered in boxes 20 points wide.
+        let mut refs = ResourceRefs::default();
+        refs.fonts
+            .insert(StandardFont::Helvetica.into(), ObjectRef::new(3));
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        writer.set_compression(false);
+        let look = form.appearances().next().unwrap();
+        look.write(&mut writer, ObjectRef::new(5), &refs).unwrap();
+        let pdf = String::from_utf8(buf.into_inner()).unwrap();
+        assert!(pdf.contains(
+            "0.5 0.5 59 19 re\nS\nq\n1 1 58 18 re\nW n\nBT\n/F1 12 Tf\n6.664 6.934 Td\n(1) Tj\nET\n\
+             BT\n/F1 12 Tf\n26.664 6.934 Td\n(2) Tj\nET\nQ\n"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let mut form = InteractiveForm::default();
+        let rect = Rect::new(0.0, 0.0, 100.0, 20.0);
+        form.text_field("email", rect, 0);
+        form.checkbox("email", rect, 0);
+        assert!(write(&form, &[ObjectRef::new(3)]).is_err());
+    }
+
+    #[test]
+    fn test_choice_fields() {
+        let mut form = InteractiveForm::default();
+        form.combo_box("country", Rect::new(100.0, 700.0, 150.0, 20.0), 0)
+            .option_labeled("DE", "Germany")
+            .option_labeled("FR", "France")
+            .set_editable(true)
+            .set_selected("FR");
+        form.list_box("fruit", Rect::new(100.0, 500.0, 150.0, 60.0), 0)
+            .option("Apple")
+            .option("Pear (green)")
+            .set_font(StandardFont::TimesRoman, 10.0)
+            .set_selected("Pear (green)");
+        let pages = [ObjectRef::new(4)];
+        let pdf = write(&form, &pages).unwrap();
+        assert!(pdf.contains(
+            "10 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 700 250 720] /P 4 0 R /F 4 \
+             /FT /Ch /T (country) /DA (/Helvetica 12 Tf 0 g) /MK << /BC [0] >> \
+             /AP << /N 200 0 R >> /Ff 393216 \
+             /Opt [[(DE) (Germany)] [(FR) (France)]] /V (FR) /DV (FR) >>"
+        ));
+        assert!(pdf.contains(
+            "/FT /Ch /T (fruit) /DA (/Times-Roman 10 Tf 0 g) /MK << /BC [0] >> \
+             /AP << /N 201 0 R >> /Ff 0 \
+             /Opt [(Apple) (Pear \\(green\\))] /V (Pear \\(green\\)) \
+             /DV (Pear \\(green\\)) /I [1] >>"
+        ));
+
+        form.list_box("size", Rect::new(0.0, 0.0, 50.0, 50.0), 0)
+            .option("S")
+            .set_selected("XL");
+        assert!(write(&form, &pages).is_err());
+    }
+
+    #[test]
+    fn test_push_buttons() {
+        let mut form = InteractiveForm::default();
+        form.push_button("send", Rect::new(100.0, 100.0, 80.0, 24.0), 0)
+            .set_caption("Send")
+            .set_action(ButtonAction::SubmitForm {
+                url: String::from("https://example.com/forms"),
+                format: SubmitFormat::Html,
+            });
+        form.push_button("reset", Rect::new(200.0, 100.0, 80.0, 10.0), 0)
+            .set_action(ButtonAction::ResetForm);
+        form.push_button("check", Rect::new(300.0, 100.0, 80.0, 24.0), 0)
+            .set_action(ButtonAction::JavaScript(String::from("app.alert(\"Hi\");")));
+        let pdf = write(&form, &[ObjectRef::new(4)]).unwrap();
+        assert!(pdf.contains(
+            "10 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 100 180 124] /P 4 0 R /F 4 \
+             /FT /Btn /Ff 65536 /T (send) /MK << /BG [0.75] /BC [0] /CA (Send) >> \
+             /DA (/Helvetica 12 Tf 0 g) /AP << /N 200 0 R >> /A << /S /SubmitForm \
+             /F << /FS /URL /F (https://example.com/forms) >> /Flags 4 >> >>"
+        ));
+        assert!(pdf.contains(
+            "/CA () >> /DA (/Helvetica 6 Tf 0 g) /AP << /N 201 0 R >> /A << /S /ResetForm >> >>"
+        ));
+        assert!(pdf.contains("/A << /S /JavaScript /JS (app.alert\\(\"Hi\"\\);) >> >>"));
+    }
+
+    #[test]
+    fn test_signature_fields() {
+        let mut form = InteractiveForm::default();
+        form.signature_field("approval", Rect::new(100.0, 100.0, 200.0, 50.0), 0);
+        let line = FormXObject::new(150.0, 30.0, |g| {
+            g.line((0.0, 5.0), (150.0, 5.0), &Paint::stroke(Color::BLACK));
+        });
+        form.signature_field("witness", Rect::new(350.0, 100.0, 150.0, 30.0), 0)
+            .set_appearance(line);
+        let pdf = write(&form, &[ObjectRef::new(4)]).unwrap();
+        assert!(pdf.contains(
+            "10 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 100 300 150] /P 4 0 R /F 4 \
+             /FT /Sig /T (approval) /AP << /N 200 0 R >> >>"
+        ));
+        assert!(pdf.contains("/FT /Sig /T (witness) /AP << /N 201 0 R >> >>"));
+        assert!(pdf.contains("12 0 obj\n<< /Fields [10 0 R 11 0 R] /SigFlags 1 >>"));
+    }
+
+    #[test]
+    fn test_buttons() {
+        let mut form = InteractiveForm::default();
+        form.checkbox("married", Rect::new(100.0, 700.0, 12.0, 12.0), 0)
+            .set_export_value("Married")
+            .set_checked(true);
+        form.radio_group("color")
+            .option("Red", Rect::new(100.0, 600.0, 12.0, 12.0), 0)
+            .option("Blue", Rect::new(100.0, 580.0, 12.0, 12.0), 1)
+            .set_selected("Blue");
+        let mut alloc = ObjectAllocator::new();
+        let ids = form.allocate(&mut alloc);
+        assert_eq!(
+            form.widgets_on(0, &ids),
+            [ObjectRef::new(1), ObjectRef::new(3)]
+        );
+        assert_eq!(form.widgets_on(1, &ids), [ObjectRef::new(4)]);
+
+        let pages = [ObjectRef::new(4), ObjectRef::new(6)];
+        let pdf = write(&form, &pages).unwrap();
+        assert!(pdf.contains(
+            "10 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 700 112 712] /P 4 0 R /F 4 \
+             /FT /Btn /T (married) /V /Married /DV /Married /AS /Married \
+             /AP << /N << /Married 200 0 R /Off 201 0 R >> >> \
+             /MK << /BC [0] /CA (4) >> /DA (/ZapfDingbats 0 Tf 0 g) >>"
+        ));
+        assert!(pdf.contains(
+            "11 0 obj\n<< /FT /Btn /Ff 49152 /T (color) /V /Blue /DV /Blue \
+             /Kids [12 0 R 13 0 R] >>"
+        ));
+        assert!(pdf.contains(
+            "12 0 obj\n<< /Type /Annot /Subtype /Widget /Rect [100 600 112 612] /P 4 0 R /F 4 \
+             /Parent 11 0 R /AS /Off /AP << /N << /Red 202 0 R /Off 203 0 R >> >>"
+        ));
+        assert!(pdf.contains(
+            "/P 6 0 R /F 4 /Parent 11 0 R /AS /Blue /AP << /N << /Blue 204 0 R /Off 205 0 R >> >>"
+        ));
+        assert!(
+            pdf.contains("/Fields [10 0 R 11 0 R] /DR << /Font << /ZapfDingbats 113 0 R >> >>")
+        );
+
+        form.radio_group("empty");
+        assert!(write(&form, &pages).is_err());
+    }
+
+    #[test]
+    fn test_off_values() {
+        let mut form = InteractiveForm::default();
+        form.checkbox("married", Rect::new(100.0, 700.0, 12.0, 12.0), 0)
+            .set_export_value("Off");
+        let error = write(&form, &[ObjectRef::new(4)]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the checkbox \"married\" cannot take Off as the value when ticked"
+        );
+
+        let mut form = InteractiveForm::default();
+        form.radio_group("color")
+            .option("Red", Rect::new(100.0, 600.0, 12.0, 12.0), 0)
+            .option("Off", Rect::new(100.0, 580.0, 12.0, 12.0), 0);
+        let error = write(&form, &[ObjectRef::new(4)]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the radio group \"color\" cannot take Off as the value of a button"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+
+/// A position in page space, in points from the lower-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<(f32, f32)> for Point {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+/// An axis-aligned rectangle in page space: (`x`, `y`) is its lower-left
+/// corner, with the y axis pointing up as in PDF user space.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The y coordinate of the upper edge.
+    pub fn top(&self) -> f32 {
+        self.y + self.height
+    }
+
+    /// The left, bottom, right and top edges, whatever the signs of the
+    /// width and height.
+    pub(crate) fn edges(self) -> (f32, f32, f32, f32) {
+        let (x0, x1) = (self.x, self.x + self.width);
+        let (y0, y1) = (self.y, self.top());
+        (x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+    }
+
+    /// Whether `other` lies within the rectangle, edges included.
+    pub(crate) fn contains(self, other: Rect) -> bool {
+        let (left, bottom, right, top) = self.edges();
+        let (other_left, other_bottom, other_right, other_top) = other.edges();
+        left <= other_left && bottom <= other_bottom && other_right <= right && other_top <= top
+    }
+}
+
+/// An affine transformation `[a b c d e f]` as used by the PDF `cm` and
+/// `Tm` operators, mapping (x, y) to (a·x + c·y + e, b·x + d·y + f).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    pub const fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    /// Counterclockwise rotation by `degrees` about the origin.
+    pub fn rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Self::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    /// Slants the x axis by `y_degrees` (counterclockwise) and the y axis by
+    /// `x_degrees` (clockwise), as for a shear or faux italics.
+    pub fn skew(x_degrees: f32, y_degrees: f32) -> Self {
+        let (x, y) = (x_degrees.to_radians().tan(), y_degrees.to_radians().tan());
+        Self::new(1.0, y, x, 1.0, 0.0, 0.0)
+    }
+
+    /// Counterclockwise rotation by `degrees` about `center`.
+    pub fn rotate_about(center: impl Into<Point>, degrees: f32) -> Self {
+        let center = center.into();
+        Self::translate(-center.x, -center.y)
+            .then(&Self::rotate(degrees))
+            .then(&Self::translate(center.x, center.y))
+    }
+
+    /// The transformation applying `self` first, then `next`.
+    pub fn then(&self, next: &Matrix) -> Matrix {
+        Matrix::new(
+            self.a * next.a + self.b * next.c,
+            self.a * next.b + self.b * next.d,
+            self.c * next.a + self.d * next.c,
+            self.c * next.b + self.d * next.d,
+            self.e * next.a + self.f * next.c + next.e,
+            self.e * next.b + self.f * next.d + next.f,
+        )
+    }
+
+    /// Where the point (`x`, `y`) is mapped to.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Whether the transformation only moves things, without rotating,
+    /// scaling or skewing them.
+    pub fn is_translation(&self) -> bool {
+        (self.a, self.b, self.c, self.d) == (1.0, 0.0, 0.0, 1.0)
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// The six operands of `cm` or `Tm`.
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values = [self.a, self.b, self.c, self.d, self.e, self.f];
+        let values: Vec<String> = values.iter().map(|value| number(*value)).collect();
+        write!(f, "{}", values.join(" "))
+    }
+}
+
+/// Writes a computed coordinate or factor as an operand, rounded to 5
+/// decimals so that the noise of trigonometry (such as the cosine of a right
+/// angle) comes out as exact numbers.
+pub(crate) fn number(value: f32) -> String {
+    // Adding 0.0 turns -0 into 0.
+    ((value * 100000.0).round() / 100000.0 + 0.0).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let m = Matrix::rotate(90.0).then(&Matrix::translate(10.0, 20.0));
+        assert_eq!(m.to_string(), "0 1 -1 0 10 20");
+        let (x, y) = m.apply(1.0, 0.0);
+        assert!((x - 10.0).abs() < 1e-6 && (y - 21.0).abs() < 1e-6);
+        assert_eq!(
+            Matrix::rotate(45.0).to_string(),
+            "0.70711 0.70711 -0.70711 0.70711 0 0"
+        );
+        assert!(Matrix::translate(3.0, 4.0).is_translation());
+        assert!(!Matrix::scale(2.0, 2.0).is_translation());
+    }
+
+    #[test]
+    fn test_skew_and_rotate_about() {
+        assert_eq!(Matrix::skew(45.0, 0.0).to_string(), "1 0 1 1 0 0");
+        let m = Matrix::rotate_about((10.0, 10.0), 90.0);
+        assert_eq!(m.to_string(), "0 1 -1 0 20 0");
+        let (x, y) = m.apply(10.0, 10.0);
+        assert!((x - 10.0).abs() < 1e-5 && (y - 10.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,710 @@
+use crate::color::Color;
+use crate::geometry::{Point, Rect, number};
+use crate::object::{Dictionary, Object};
+use crate::resources::Resources;
+
+/// Which parts of a path count as its inside when filling it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// Points around which the path winds a non-zero number of times, taking
+    /// its direction into account: a subpath drawn inside another in the
+    /// same direction is filled.
+    #[default]
+    NonZero,
+    /// Points enclosed by an odd number of crossings of the path: a subpath
+    /// within another cuts a hole whatever its direction, as in a donut.
+    EvenOdd,
+}
+
+/// A piece of a [`Path`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    /// Starts a new subpath.
+    MoveTo(Point),
+    LineTo(Point),
+    /// A cubic Bézier curve through two control points.
+    CurveTo(Point, Point, Point),
+    /// A closed rectangular subpath of its own.
+    Rect(Rect),
+    /// Closes the subpath with a straight line back to its start.
+    Close,
+}
+
+fn coordinates(points: &[&Point]) -> String {
+    let values: Vec<String> = points
+        .iter()
+        .flat_map(|point| [number(point.x), number(point.y)])
+        .collect();
+    values.join(" ")
+}
+
+/// A vector shape made of straight lines and cubic Bézier curves, drawn with
+/// [`ContentStream::draw_path`](crate::ContentStream::draw_path). Built with
+/// [`PathBuilder`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    segments: Vec<Segment>,
+    fill_rule: FillRule,
+}
+
+impl Path {
+    /// A circle of `radius` around `center`.
+    pub fn circle(center: impl Into<Point>, radius: f32) -> Self {
+        Self::ellipse(center, radius, radius)
+    }
+
+    /// An axis-aligned ellipse around `center`, with horizontal radius `rx`
+    /// and vertical radius `ry`.
+    pub fn ellipse(center: impl Into<Point>, rx: f32, ry: f32) -> Self {
+        PathBuilder::new()
+            .ellipse_arc(center, rx, ry, 0.0, 360.0)
+            .close()
+            .build()
+    }
+
+    /// A pie slice: the sector of the circle of `radius` around `center`
+    /// from `start` degrees, counterclockwise from the positive x axis, over
+    /// `sweep` degrees (clockwise if negative).
+    pub fn pie(center: impl Into<Point>, radius: f32, start: f32, sweep: f32) -> Self {
+        let center = center.into();
+        PathBuilder::new()
+            .move_to(center)
+            .arc(center, radius, start, sweep)
+            .close()
+            .build()
+    }
+
+    /// `rect` with its corners rounded by quarter circles of `radii`, which
+    /// may be a single radius for all corners. Radii too large for the sides
+    /// are scaled down together, so adjacent corners meet at most.
+    pub fn rounded_rect(rect: Rect, radii: impl Into<CornerRadii>) -> Self {
+        let radii = radii.into();
+        let sums = [
+            (radii.top_left + radii.top_right, rect.width),
+            (radii.bottom_left + radii.bottom_right, rect.width),
+            (radii.top_left + radii.bottom_left, rect.height),
+            (radii.top_right + radii.bottom_right, rect.height),
+        ];
+        let scale = sums
+            .iter()
+            .filter(|(sum, _)| *sum > 0.0)
+            .map(|(sum, side)| side / sum)
+            .fold(1.0, f32::min);
+        let (left, bottom, right, top) = (rect.x, rect.y, rect.x + rect.width, rect.top());
+        // Each corner leaves its arc at `start` degrees around its center.
+        let corners = [
+            (radii.bottom_right, right, bottom, -1.0, 1.0, 270.0),
+            (radii.top_right, right, top, -1.0, -1.0, 0.0),
+            (radii.top_left, left, top, 1.0, -1.0, 90.0),
+            (radii.bottom_left, left, bottom, 1.0, 1.0, 180.0),
+        ];
+        let mut builder = PathBuilder::new();
+        for (radius, x, y, dx, dy, start) in corners {
+            let radius = radius * scale;
+            builder = if radius > 0.0 {
+                let center = (x + dx * radius, y + dy * radius);
+                builder.arc(center, radius, start, 90.0)
+            } else if builder.path.is_empty() {
+                builder.move_to((x, y))
+            } else {
+                builder.line_to((x, y))
+            };
+        }
+        builder.close().build()
+    }
+
+    /// A path holding the single rectangle `rect`.
+    pub fn rect(rect: Rect) -> Self {
+        Self {
+            segments: vec![Segment::Rect(rect)],
+            fill_rule: FillRule::NonZero,
+        }
+    }
+
+    /// The path with its inside decided by `fill_rule`.
+    pub fn with_fill_rule(self, fill_rule: FillRule) -> Self {
+        Self { fill_rule, ..self }
+    }
+
+    pub fn fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The path construction operators (`m`, `l`, `c`, `re`, `h`), one per
+    /// line.
+    pub(crate) fn to_ops(&self) -> String {
+        let mut ops = String::new();
+        for segment in &self.segments {
+            let op = match segment {
+                Segment::MoveTo(p) => format!("{} m", coordinates(&[p])),
+                Segment::LineTo(p) => format!("{} l", coordinates(&[p])),
+                Segment::CurveTo(c1, c2, p) => format!("{} c", coordinates(&[c1, c2, p])),
+                Segment::Rect(r) => {
+                    let values = [r.x, r.y, r.width, r.height].map(number);
+                    format!("{} re", values.join(" "))
+                }
+                Segment::Close => String::from("h"),
+            };
+            ops.push_str(&op);
+            ops.push('\n');
+        }
+        ops
+    }
+}
+
+/// The radii of the corners of a [`Path::rounded_rect`], in points.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+/// The same radius for every corner.
+impl From<f32> for CornerRadii {
+    fn from(radius: f32) -> Self {
+        Self {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+}
+
+/// Builds a [`Path`] segment by segment, like a pen moving over the page.
+///
+/// ```
+/// use pdf_creator::PathBuilder;
+///
+/// let shape = PathBuilder::new()
+///     .move_to((0.0, 0.0))
+///     .line_to((100.0, 0.0))
+///     .curve_to((100.0, 50.0), (50.0, 100.0), (0.0, 100.0))
+///     .close()
+///     .build();
+/// assert!(!shape.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PathBuilder {
+    path: Path,
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lifts the pen and starts a new subpath at `to`.
+    pub fn move_to(mut self, to: impl Into<Point>) -> Self {
+        self.path.segments.push(Segment::MoveTo(to.into()));
+        self
+    }
+
+    /// A straight line from the current point to `to`.
+    pub fn line_to(mut self, to: impl Into<Point>) -> Self {
+        self.path.segments.push(Segment::LineTo(to.into()));
+        self
+    }
+
+    /// A cubic Bézier curve from the current point to `to`, leaving in the
+    /// direction of `control1` and arriving from that of `control2`.
+    pub fn curve_to(
+        mut self,
+        control1: impl Into<Point>,
+        control2: impl Into<Point>,
+        to: impl Into<Point>,
+    ) -> Self {
+        let segment = Segment::CurveTo(control1.into(), control2.into(), to.into());
+        self.path.segments.push(segment);
+        self
+    }
+
+    /// An arc of the circle of `radius` around `center`, from `start`
+    /// degrees counterclockwise from the positive x axis over `sweep` degrees
+    /// (clockwise if negative). A straight line joins the current point to
+    /// the start of the arc; on an empty path, the arc starts a subpath.
+    pub fn arc(self, center: impl Into<Point>, radius: f32, start: f32, sweep: f32) -> Self {
+        self.ellipse_arc(center, radius, radius, start, sweep)
+    }
+
+    /// Like [`arc`](Self::arc), for an axis-aligned ellipse with horizontal
+    /// radius `rx` and vertical radius `ry`. The arc is approximated by one
+    /// cubic Bézier curve per quarter turn or less, close to within 0.03% of
+    /// the radius.
+    pub fn ellipse_arc(
+        mut self,
+        center: impl Into<Point>,
+        rx: f32,
+        ry: f32,
+        start: f32,
+        sweep: f32,
+    ) -> Self {
+        let center = center.into();
+        let at = |angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            Point::new(center.x + rx * cos, center.y + ry * sin)
+        };
+        // Direction of the tangent, scaled by the radii.
+        let tangent = |angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            Point::new(-rx * sin, ry * cos)
+        };
+        let first = at(start.to_radians());
+        self = if self.path.is_empty() {
+            self.move_to(first)
+        } else {
+            self.line_to(first)
+        };
+        let count = (sweep.abs() / 90.0).ceil().max(1.0);
+        let step = sweep.to_radians() / count;
+        // Control points at 4/3·tan(θ/4) of the radius along the tangents.
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        let mut angle = start.to_radians();
+        for _ in 0..count as usize {
+            let next = angle + step;
+            let (from, to) = (at(angle), at(next));
+            let (t1, t2) = (tangent(angle), tangent(next));
+            self = self.curve_to(
+                (from.x + k * t1.x, from.y + k * t1.y),
+                (to.x - k * t2.x, to.y - k * t2.y),
+                to,
+            );
+            angle = next;
+        }
+        self
+    }
+
+    /// A rectangle as a closed subpath of its own.
+    pub fn rect(mut self, rect: Rect) -> Self {
+        self.path.segments.push(Segment::Rect(rect));
+        self
+    }
+
+    /// Closes the current subpath with a straight line back to where it
+    /// started, joining the ends when stroked.
+    pub fn close(mut self) -> Self {
+        self.path.segments.push(Segment::Close);
+        self
+    }
+
+    /// Adds the subpaths of `path`, such as a circle cutting a hole into
+    /// the shape drawn so far.
+    pub fn add_path(mut self, path: &Path) -> Self {
+        self.path.segments.extend_from_slice(&path.segments);
+        self
+    }
+
+    /// Sets how the inside of the path is decided when filling it.
+    pub fn fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.path.fill_rule = fill_rule;
+        self
+    }
+
+    pub fn build(self) -> Path {
+        self.path
+    }
+}
+
+/// How the ends of open lines are drawn (`J`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    /// Squared off at the end point.
+    #[default]
+    Butt,
+    /// A half circle around the end point; with a zero-length dash, a dot.
+    Round,
+    /// Squared off half the line width beyond the end point.
+    Square,
+}
+
+/// How the corners between segments are drawn (`j`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// A sharp point, beveled beyond the miter limit.
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// The shape of outlines: width, dash pattern, caps and joins (the `w`, `d`,
+/// `J`, `j` and `M` operators).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    /// Width of the line, in points.
+    pub width: f32,
+    /// Lengths of alternating dashes and gaps, in points; a solid line if
+    /// empty.
+    pub dash: Vec<f32>,
+    /// How far into the dash pattern the line starts.
+    pub dash_phase: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// Longest a miter join may be, as a ratio of the line width, before it
+    /// is beveled instead.
+    pub miter_limit: f32,
+}
+
+impl StrokeStyle {
+    /// A solid line of `width` points with the default caps and joins.
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    /// Dashes of `dash` points separated by gaps of `gap` points.
+    pub fn dashed(self, dash: f32, gap: f32) -> Self {
+        Self {
+            dash: vec![dash, gap],
+            ..self
+        }
+    }
+
+    /// Round dots `gap` points apart, such as dotted leaders in a table of
+    /// contents.
+    pub fn dotted(self, gap: f32) -> Self {
+        Self {
+            dash: vec![0.0, gap],
+            cap: LineCap::Round,
+            ..self
+        }
+    }
+
+    /// The operators setting this style, leaving out what matches the
+    /// initial graphics state.
+    pub(crate) fn ops(&self) -> String {
+        let default = Self::default();
+        let mut ops = String::new();
+        if self.width != default.width {
+            ops.push_str(&format!("{} w\n", self.width));
+        }
+        if !self.dash.is_empty() {
+            let dash: Vec<String> = self.dash.iter().map(|length| length.to_string()).collect();
+            ops.push_str(&format!("[{}] {} d\n", dash.join(" "), self.dash_phase));
+        }
+        if self.cap != default.cap {
+            ops.push_str(&format!("{} J\n", self.cap as u8));
+        }
+        if self.join != default.join {
+            ops.push_str(&format!("{} j\n", self.join as u8));
+        }
+        if self.miter_limit != default.miter_limit {
+            ops.push_str(&format!("{} M\n", self.miter_limit));
+        }
+        ops
+    }
+}
+
+/// A solid 1pt line with butt caps and miter joins, as in the initial
+/// graphics state.
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            dash: Vec::new(),
+            dash_phase: 0.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 10.0,
+        }
+    }
+}
+
+/// How a shape is painted: its outline stroked, its inside filled, or both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paint {
+    /// Color of the outline, which is not drawn if not set.
+    pub stroke: Option<Color>,
+    /// Color of the inside, which is not filled if not set.
+    pub fill: Option<Color>,
+    /// Shape of the outline.
+    pub stroke_style: StrokeStyle,
+}
+
+impl Paint {
+    /// A 1pt outline in `color`.
+    pub fn stroke(color: Color) -> Self {
+        Self {
+            stroke: Some(color),
+            fill: None,
+            stroke_style: StrokeStyle::default(),
+        }
+    }
+
+    /// The inside filled with `color`, without an outline.
+    pub fn fill(color: Color) -> Self {
+        Self {
+            stroke: None,
+            fill: Some(color),
+            stroke_style: StrokeStyle::default(),
+        }
+    }
+
+    /// The inside filled with `fill`, outlined by 1pt of `stroke`.
+    pub fn fill_and_stroke(fill: Color, stroke: Color) -> Self {
+        Self {
+            stroke: Some(stroke),
+            fill: Some(fill),
+            stroke_style: StrokeStyle::default(),
+        }
+    }
+
+    pub fn with_line_width(mut self, width: f32) -> Self {
+        self.stroke_style.width = width;
+        self
+    }
+
+    pub fn with_stroke_style(self, stroke_style: StrokeStyle) -> Self {
+        Self {
+            stroke_style,
+            ..self
+        }
+    }
+
+    /// The operators setting the colors and stroke style, leaving out those
+    /// matching the initial graphics state (black, solid 1pt lines).
+    pub(crate) fn state_ops(&self, resources: &mut Resources) -> String {
+        let black = Color::BLACK;
+        let mut ops = String::new();
+        if let Some(color) = self.stroke.as_ref().filter(|color| **color != black) {
+            ops.push_str(&format!("{}\n", color.stroke_op(resources)));
+        }
+        if let Some(color) = self.fill.as_ref().filter(|color| **color != black) {
+            ops.push_str(&format!("{}\n", color.fill_op(resources)));
+        }
+        if self.stroke.is_some() {
+            ops.push_str(&self.stroke_style.ops());
+        }
+        ops
+    }
+
+    /// The operator painting the current path: `S` to stroke it, `f` to
+    /// fill it, `B` for both and `n` to discard it, with a `*` for filling by
+    /// the even-odd rule.
+    pub(crate) fn paint_op(&self, fill_rule: FillRule) -> &'static str {
+        match (self.fill.is_some(), self.stroke.is_some(), fill_rule) {
+            (true, true, FillRule::NonZero) => "B",
+            (true, true, FillRule::EvenOdd) => "B*",
+            (true, false, FillRule::NonZero) => "f",
+            (true, false, FillRule::EvenOdd) => "f*",
+            (false, true, _) => "S",
+            (false, false, _) => "n",
+        }
+    }
+}
+
+/// A black 1pt outline.
+impl Default for Paint {
+    fn default() -> Self {
+        Self::stroke(Color::BLACK)
+    }
+}
+
+/// How colors painted combine with those already on the page (`/BM`). The
+/// separable modes work on each color component alone; `Hue`,
+/// `Saturation`, `Color` and `Luminosity` mix properties of both colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The new color replaces the old one.
+    #[default]
+    Normal,
+    /// Darkens by multiplying the colors; white leaves the backdrop as is.
+    Multiply,
+    /// Lightens by multiplying the complements; black leaves the backdrop.
+    Screen,
+    /// Multiplies or screens depending on the backdrop, keeping its
+    /// highlights and shadows.
+    Overlay,
+    /// Keeps the darker of the two colors.
+    Darken,
+    /// Keeps the lighter of the two colors.
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            BlendMode::Normal => "Normal",
+            BlendMode::Multiply => "Multiply",
+            BlendMode::Screen => "Screen",
+            BlendMode::Overlay => "Overlay",
+            BlendMode::Darken => "Darken",
+            BlendMode::Lighten => "Lighten",
+            BlendMode::ColorDodge => "ColorDodge",
+            BlendMode::ColorBurn => "ColorBurn",
+            BlendMode::HardLight => "HardLight",
+            BlendMode::SoftLight => "SoftLight",
+            BlendMode::Difference => "Difference",
+            BlendMode::Exclusion => "Exclusion",
+            BlendMode::Hue => "Hue",
+            BlendMode::Saturation => "Saturation",
+            BlendMode::Color => "Color",
+            BlendMode::Luminosity => "Luminosity",
+        }
+    }
+}
+
+/// Parameters of the graphics state without operators of their own, set
+/// through an `/ExtGState` resource and the `gs` operator.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct ExtGState {
+    /// Constant opacity of outlines (`/CA`).
+    pub(crate) stroke_alpha: Option<f32>,
+    /// Constant opacity of everything else painted (`/ca`).
+    pub(crate) fill_alpha: Option<f32>,
+    pub(crate) blend_mode: Option<BlendMode>,
+}
+
+impl ExtGState {
+    /// Whether what is painted with the state shows what is below it.
+    pub(crate) fn is_transparent(&self) -> bool {
+        let translucent = |alpha: Option<f32>| alpha.is_some_and(|alpha| alpha < 1.0);
+        translucent(self.stroke_alpha)
+            || translucent(self.fill_alpha)
+            || self
+                .blend_mode
+                .is_some_and(|mode| mode != BlendMode::Normal)
+    }
+
+    pub(crate) fn to_pdf(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        if let Some(alpha) = self.stroke_alpha {
+            dict.set("CA", alpha);
+        }
+        if let Some(alpha) = self.fill_alpha {
+            dict.set("ca", alpha);
+        }
+        if let Some(mode) = self.blend_mode {
+            dict.set("BM", Object::name(mode.name()));
+        }
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_ops() {
+        let path = PathBuilder::new()
+            .move_to((0.0, 0.0))
+            .line_to((10.0, 0.0))
+            .curve_to((10.0, 5.5), (5.0, 10.0), (0.0, 10.0))
+            .close()
+            .rect(Rect::new(20.0, 20.0, 5.0, 5.0))
+            .build();
+        assert_eq!(
+            path.to_ops(),
+            "0 0 m\n10 0 l\n10 5.5 5 10 0 10 c\nh\n20 20 5 5 re\n"
+        );
+    }
+
+    #[test]
+    fn test_circle() {
+        let ops = Path::circle((100.0, 100.0), 10.0).to_ops();
+        assert_eq!(
+            ops.lines().collect::<Vec<_>>(),
+            [
+                "110 100 m",
+                "110 105.52285 105.52285 110 100 110 c",
+                "94.47715 110 90 105.52285 90 100 c",
+                "90 94.47715 94.47715 90 100 90 c",
+                "105.52285 90 110 94.47715 110 100 c",
+                "h",
+            ]
+        );
+        let ops = Path::pie((0.0, 0.0), 10.0, 90.0, -45.0).to_ops();
+        assert!(ops.starts_with("0 0 m\n0 10 l\n"));
+        assert!(ops.ends_with(" 7.07107 7.07107 c\nh\n"));
+    }
+
+    #[test]
+    fn test_rounded_rect() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 50.0);
+        let square = CornerRadii {
+            top_right: 10.0,
+            ..CornerRadii::default()
+        };
+        let ops = Path::rounded_rect(rect, square).to_ops();
+        assert_eq!(
+            ops,
+            "100 0 m\n100 40 l\n100 45.52285 95.52285 50 90 50 c\n0 50 l\n0 0 l\nh\n"
+        );
+        // Radii of 40 on a 50pt high rectangle are scaled to 25.
+        let ops = Path::rounded_rect(rect, 40.0).to_ops();
+        assert!(ops.starts_with("75 0 m\n"));
+        assert!(ops.contains("\n100 25 l\n"));
+    }
+
+    #[test]
+    fn test_stroke_style() {
+        assert_eq!(StrokeStyle::default().ops(), "");
+        let style = StrokeStyle {
+            join: LineJoin::Round,
+            miter_limit: 4.0,
+            ..StrokeStyle::new(2.0).dashed(6.0, 3.0)
+        };
+        assert_eq!(style.ops(), "2 w\n[6 3] 0 d\n1 j\n4 M\n");
+        assert_eq!(StrokeStyle::new(1.0).dotted(4.0).ops(), "[0 4] 0 d\n1 J\n");
+        // Without an outline the stroke style has no effect.
+        let paint = Paint::fill(Color::Gray(0.5)).with_stroke_style(style);
+        assert_eq!(paint.state_ops(&mut Resources::default()), "0.5 g\n");
+    }
+
+    #[test]
+    fn test_ext_g_state() {
+        let state = ExtGState {
+            stroke_alpha: Some(0.5),
+            fill_alpha: Some(0.25),
+            blend_mode: None,
+        };
+        assert_eq!(state.to_pdf().to_string(), "<< /CA 0.5 /ca 0.25 >>");
+        let state = ExtGState {
+            blend_mode: Some(BlendMode::ColorDodge),
+            ..ExtGState::default()
+        };
+        assert_eq!(state.to_pdf().to_string(), "<< /BM /ColorDodge >>");
+        assert_eq!(ExtGState::default().to_pdf().to_string(), "<< >>");
+    }
+
+    #[test]
+    fn test_paint_ops() {
+        let paint = Paint::fill_and_stroke(Color::Gray(0.5), Color::Rgb(1.0, 0.0, 0.0));
+        assert_eq!(
+            paint.state_ops(&mut Resources::default()),
+            "1 0 0 RG\n0.5 g\n"
+        );
+        assert_eq!(paint.paint_op(FillRule::NonZero), "B");
+        assert_eq!(paint.paint_op(FillRule::EvenOdd), "B*");
+        let paint = Paint::default().with_line_width(0.5);
+        assert_eq!(paint.state_ops(&mut Resources::default()), "0.5 w\n");
+        assert_eq!(paint.paint_op(FillRule::EvenOdd), "S");
+        assert_eq!(
+            Paint::fill(Color::Gray(0.0)).paint_op(FillRule::EvenOdd),
+            "f*"
+        );
+        assert_eq!(
+            Paint::fill(Color::Gray(0.0)).state_ops(&mut Resources::default()),
+            ""
+        );
+    }
+}
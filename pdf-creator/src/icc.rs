@@ -0,0 +1,170 @@
+use crate::color::Color;
+use crate::object::{Dictionary, Object, ObjectRef};
+use crate::writer::PdfWriter;
+use anyhow::{Context, Result, bail, ensure};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Source of the ids telling loaded profiles apart.
+static NEXT_PROFILE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// An ICC color profile embedded in the document, such as sRGB or FOGRA39,
+/// making colors painted in it device independent (`/ICCBased`). Cloning
+/// the handle shares the loaded profile, which is written once however many
+/// pages use it.
+#[derive(Clone)]
+pub struct IccProfile {
+    inner: Arc<IccData>,
+}
+
+struct IccData {
+    id: u32,
+    data: Vec<u8>,
+    components: usize,
+}
+
+impl IccProfile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .with_context(|| format!("failed to read ICC profile {}", path.display()))?;
+        Self::from_bytes(data)
+    }
+
+    /// Loads a profile for gray, RGB or CMYK colors, as told by its header.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        ensure!(
+            data.len() >= 128 && &data[36..40] == b"acsp",
+            "invalid ICC profile: missing header"
+        );
+        let components = match &data[16..20] {
+            b"GRAY" => 1,
+            b"RGB " => 3,
+            b"CMYK" => 4,
+            space => bail!(
+                "unsupported ICC profile color space {:?}",
+                String::from_utf8_lossy(space).trim_end()
+            ),
+        };
+        Ok(Self {
+            inner: Arc::new(IccData {
+                id: NEXT_PROFILE_ID.fetch_add(1, Ordering::Relaxed),
+                data,
+                components,
+            }),
+        })
+    }
+
+    /// Number of color components: 1 for gray, 3 for RGB, 4 for CMYK.
+    pub fn components(&self) -> usize {
+        self.inner.components
+    }
+
+    /// The color with the given components, from 0 to 1, in this profile.
+    ///
+    /// # Panics
+    ///
+    /// If there are not as many components as the profile has.
+    pub fn color(&self, components: &[f32]) -> Color {
+        assert_eq!(
+            components.len(),
+            self.components(),
+            "wrong number of components for the ICC profile"
+        );
+        Color::Icc(self.clone(), components.to_vec())
+    }
+
+    /// The device color space of as many components, which readers unable
+    /// to use the profile fall back to.
+    pub(crate) fn alternate(&self) -> &'static str {
+        match self.inner.components {
+            1 => "DeviceGray",
+            3 => "DeviceRGB",
+            _ => "DeviceCMYK",
+        }
+    }
+
+    /// Whether the profile is of a display or a printer, as those of output
+    /// intents must be, rather than of a scanner or a color space.
+    pub(crate) fn is_output(&self) -> bool {
+        matches!(&self.inner.data[12..16], b"mntr" | b"prtr")
+    }
+
+    /// Whether the profile is of a printer, as those of output intents for
+    /// print production must be.
+    pub(crate) fn is_printer(&self) -> bool {
+        &self.inner.data[12..16] == b"prtr"
+    }
+
+    /// The profile as loaded, which profiles of the same bytes share a
+    /// single object by.
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.inner.data
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut PdfWriter<W>, id: ObjectRef) -> Result<()> {
+        let mut dict = Dictionary::new();
+        dict.set("N", self.components());
+        dict.set("Alternate", Object::name(self.alternate()));
+        writer.write_compressed_stream(id, dict, &self.inner.data)
+    }
+}
+
+impl PartialEq for IccProfile {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for IccProfile {}
+
+impl Hash for IccProfile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl fmt::Debug for IccProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IccProfile")
+            .field("components", &self.inner.components)
+            .field("len", &self.inner.data.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The 128-byte header of a profile for the color space `space`.
+    pub(crate) fn profile_header(space: &[u8; 4]) -> Vec<u8> {
+        let mut data = vec![0; 128];
+        data[12..16].copy_from_slice(b"mntr");
+        data[16..20].copy_from_slice(space);
+        data[36..40].copy_from_slice(b"acsp");
+        data
+    }
+
+    #[test]
+    fn test_profile_header() {
+        let rgb = IccProfile::from_bytes(profile_header(b"RGB ")).unwrap();
+        assert_eq!(rgb.components(), 3);
+        assert_eq!(rgb.alternate(), "DeviceRGB");
+        let cmyk = IccProfile::from_bytes(profile_header(b"CMYK")).unwrap();
+        assert_eq!(cmyk.components(), 4);
+        assert_ne!(rgb, cmyk);
+        assert_eq!(rgb, rgb.clone());
+
+        let lab = IccProfile::from_bytes(profile_header(b"Lab "));
+        assert_eq!(
+            lab.unwrap_err().to_string(),
+            "unsupported ICC profile color space \"Lab\""
+        );
+        assert!(IccProfile::from_bytes(vec![0; 64]).is_err());
+    }
+}
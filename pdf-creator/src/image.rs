@@ -0,0 +1,1261 @@
+use crate::geometry::{Matrix, Rect};
+use crate::manual::PdfVersion;
+use crate::object::{self, Dictionary, Object, ObjectRef, Stream};
+use crate::writer::{PdfWriter, deflate};
+use anyhow::{Context, Result, bail, ensure};
+use png::{ColorType, Transformations};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Source of the ids telling images apart.
+static NEXT_IMAGE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// The color space of the pixels of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+}
+
+impl ImageColorSpace {
+    /// Number of color components per pixel.
+    pub fn components(self) -> usize {
+        match self {
+            ImageColorSpace::Gray => 1,
+            ImageColorSpace::Rgb => 3,
+            ImageColorSpace::Cmyk => 4,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ImageColorSpace::Gray => "DeviceGray",
+            ImageColorSpace::Rgb => "DeviceRGB",
+            ImageColorSpace::Cmyk => "DeviceCMYK",
+        }
+    }
+
+    /// The name abbreviated as inline images may.
+    fn abbreviation(self) -> &'static str {
+        match self {
+            ImageColorSpace::Gray => "G",
+            ImageColorSpace::Rgb => "RGB",
+            ImageColorSpace::Cmyk => "CMYK",
+        }
+    }
+}
+
+/// Shrinks images placed at a higher resolution than needed as the document
+/// is written, see [`PdfDocument::set_downsampling`](crate::PdfDocument::set_downsampling).
+#[cfg(feature = "image")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Downsampling {
+    /// The resolution, in pixels per inch at the largest size an image is
+    /// placed at, above which it is resampled down to it.
+    pub dpi: f32,
+    /// How well resampled photos are JPEG-encoded, from 1 to 100.
+    pub jpeg_quality: u8,
+}
+
+#[cfg(feature = "image")]
+impl Default for Downsampling {
+    /// 150 ppi at quality 80: enough for office printers.
+    fn default() -> Self {
+        Self {
+            dpi: 150.0,
+            jpeg_quality: 80,
+        }
+    }
+}
+
+/// A raster image embedded in the document, placed with
+/// [`ContentStream::draw_image`](crate::ContentStream::draw_image) at any
+/// size. Cloning the handle shares the pixels, which are written once however
+/// many times the image is placed.
+#[derive(Clone)]
+pub struct Image {
+    inner: Arc<ImageData>,
+}
+
+struct ImageData {
+    id: u32,
+    width: u32,
+    height: u32,
+    color_space: ImageColorSpace,
+    bits_per_component: u8,
+    filter: Filter,
+    data: Vec<u8>,
+    /// The gray image whose pixels tell how opaque those of this one are.
+    soft_mask: Option<Image>,
+    /// How the pixels are turned from upright, as an EXIF orientation from
+    /// 1 (upright) to 8.
+    orientation: u8,
+}
+
+impl Image {
+    /// Embeds JPEG data as is, decoded by the reader (`DCTDecode`). Gray,
+    /// RGB and CMYK baseline and progressive JPEGs are supported.
+    ///
+    /// Photos taken sideways, as told by their EXIF orientation, are drawn
+    /// upright unless [`Image::ignore_orientation`] is called.
+    pub fn from_jpeg(data: Vec<u8>) -> Result<Self> {
+        let info = jpeg_info(&data)?;
+        let color_space = match info.components {
+            1 => ImageColorSpace::Gray,
+            3 => ImageColorSpace::Rgb,
+            4 => ImageColorSpace::Cmyk,
+            n => bail!("unsupported JPEG with {} color components", n),
+        };
+        // Adobe applications write CMYK JPEGs with inverted components.
+        let inverted = info.components == 4 && info.adobe;
+        let mut image = Self::new(
+            info.width,
+            info.height,
+            color_space,
+            info.precision,
+            Filter::Dct { inverted },
+            data,
+            None,
+        );
+        image.set_orientation(info.orientation);
+        Ok(image)
+    }
+
+    /// Embeds JPEG 2000 data as is, decoded by the reader: either a JP2 file
+    /// or a bare codestream. Gray, RGB and CMYK images are supported, not
+    /// those with a palette. `JPXDecode` is a PDF 1.5 filter, see
+    /// [`PdfDocument::set_version`](crate::PdfDocument::set_version).
+    pub fn from_jpx(data: Vec<u8>) -> Result<Self> {
+        let codestream = if data.starts_with(JP2_SIGNATURE) {
+            jp2_codestream(&data)?
+        } else {
+            &data[..]
+        };
+        let info = jpx_info(codestream)?;
+        let color_space = match info.components {
+            1 => ImageColorSpace::Gray,
+            3 => ImageColorSpace::Rgb,
+            4 => ImageColorSpace::Cmyk,
+            n => bail!("unsupported JPEG 2000 image with {} color components", n),
+        };
+        Ok(Self::new(
+            info.width,
+            info.height,
+            color_space,
+            info.precision,
+            Filter::Jpx,
+            data,
+            None,
+        ))
+    }
+
+    /// Decodes a PNG image, written Flate-compressed. Palette and low bit
+    /// depth images are expanded to 8 bits per component. The alpha channel,
+    /// unless every pixel is opaque, becomes a soft mask blending the image
+    /// with what is drawn below it.
+    pub fn from_png(data: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(Cursor::new(data));
+        decoder.set_transformations(Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().context("invalid PNG image")?;
+        let size = reader.output_buffer_size().context("PNG image too large")?;
+        let mut pixels = vec![0; size];
+        let info = reader
+            .next_frame(&mut pixels)
+            .context("invalid PNG image")?;
+        pixels.truncate(info.buffer_size());
+
+        let color_space = match info.color_type {
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => ImageColorSpace::Gray,
+            _ => ImageColorSpace::Rgb,
+        };
+        let alpha = info.color_type.samples() > color_space.components();
+        Ok(Self::from_pixels(
+            info.width,
+            info.height,
+            color_space,
+            pixels,
+            alpha,
+        ))
+    }
+
+    /// Writes 8-bit `pixels` Flate-compressed, splitting off their last
+    /// component if `alpha`, into a soft mask unless it is opaque throughout.
+    fn from_pixels(
+        width: u32,
+        height: u32,
+        color_space: ImageColorSpace,
+        mut pixels: Vec<u8>,
+        alpha: bool,
+    ) -> Self {
+        let components = color_space.components();
+        let mut soft_mask = None;
+        if alpha {
+            let pixel_size = components + 1;
+            let alpha: Vec<u8> = pixels
+                .chunks(pixel_size)
+                .map(|pixel| pixel[components])
+                .collect();
+            pixels = pixels
+                .chunks(pixel_size)
+                .flat_map(|pixel| &pixel[..components])
+                .copied()
+                .collect();
+            if alpha.iter().any(|&a| a < 255) {
+                soft_mask = Some(Self::new(
+                    width,
+                    height,
+                    ImageColorSpace::Gray,
+                    8,
+                    Filter::Flate,
+                    deflate(&alpha),
+                    None,
+                ));
+            }
+        }
+        Self::new(
+            width,
+            height,
+            color_space,
+            8,
+            Filter::Flate,
+            deflate(&pixels),
+            soft_mask,
+        )
+    }
+
+    /// Embeds an image decoded with the `image` crate, converted to 8 bits
+    /// per component. Photos, told by their many colors, are JPEG-encoded;
+    /// graphics and images with transparency are Flate-compressed, losslessly.
+    #[cfg(feature = "image")]
+    pub fn from_dynamic_image(image: &::image::DynamicImage) -> Result<Self> {
+        Self::encode(image, 90)
+    }
+
+    /// Encodes `image` as told by [`Image::from_dynamic_image`], photos with
+    /// the JPEG `quality` from 1 to 100.
+    #[cfg(feature = "image")]
+    fn encode(image: &::image::DynamicImage, quality: u8) -> Result<Self> {
+        use ::image::ExtendedColorType;
+        use ::image::codecs::jpeg::JpegEncoder;
+
+        let (width, height) = (image.width(), image.height());
+        let color = image.color();
+        let (color_space, pixels, jpeg_color) = if color.has_color() {
+            let pixels = match color.has_alpha() {
+                true => image.to_rgba8().into_raw(),
+                false => image.to_rgb8().into_raw(),
+            };
+            (ImageColorSpace::Rgb, pixels, ExtendedColorType::Rgb8)
+        } else {
+            let pixels = match color.has_alpha() {
+                true => image.to_luma_alpha8().into_raw(),
+                false => image.to_luma8().into_raw(),
+            };
+            (ImageColorSpace::Gray, pixels, ExtendedColorType::L8)
+        };
+        let black_and_white = pixels.iter().all(|&value| value == 0 || value == 255);
+        if color_space == ImageColorSpace::Gray && !color.has_alpha() && black_and_white {
+            return Self::bilevel(width, height, &pixels);
+        }
+        if color.has_alpha() || !is_photo(&pixels, color_space.components()) {
+            return Ok(Self::from_pixels(
+                width,
+                height,
+                color_space,
+                pixels,
+                color.has_alpha(),
+            ));
+        }
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, quality)
+            .encode(&pixels, width, height, jpeg_color)
+            .context("failed to encode image as JPEG")?;
+        Self::from_jpeg(jpeg)
+    }
+
+    /// Decodes a TIFF image, whatever its strips, tiles and compression,
+    /// with [`Image::from_dynamic_image`]. Black and white scans are written
+    /// with 1 bit per pixel.
+    #[cfg(feature = "image")]
+    pub fn from_tiff(data: &[u8]) -> Result<Self> {
+        Self::decode(data, ::image::ImageFormat::Tiff)
+    }
+
+    /// Decodes a BMP image with [`Image::from_dynamic_image`].
+    #[cfg(feature = "image")]
+    pub fn from_bmp(data: &[u8]) -> Result<Self> {
+        Self::decode(data, ::image::ImageFormat::Bmp)
+    }
+
+    /// Decodes the first frame of a GIF image with
+    /// [`Image::from_dynamic_image`], its transparent color becoming a soft
+    /// mask.
+    #[cfg(feature = "image")]
+    pub fn from_gif(data: &[u8]) -> Result<Self> {
+        Self::decode(data, ::image::ImageFormat::Gif)
+    }
+
+    #[cfg(feature = "image")]
+    fn decode(data: &[u8], format: ::image::ImageFormat) -> Result<Self> {
+        let image = ::image::load_from_memory_with_format(data, format)
+            .with_context(|| format!("invalid {:?} image", format))?;
+        Self::from_dynamic_image(&image)
+    }
+
+    /// Writes 8-bit gray `pixels`, all black or white, with 1 bit each.
+    #[cfg(feature = "image")]
+    fn bilevel(width: u32, height: u32, pixels: &[u8]) -> Result<Self> {
+        let mut bits = Vec::with_capacity((width as usize).div_ceil(8) * height as usize);
+        for row in pixels.chunks(width as usize) {
+            for byte in row.chunks(8) {
+                let packed = byte
+                    .iter()
+                    .enumerate()
+                    .fold(0, |packed, (i, &value)| packed | ((value & 1) << (7 - i)));
+                bits.push(packed);
+            }
+        }
+        Self::from_raw(width, height, ImageColorSpace::Gray, 1, &bits)
+    }
+
+    /// Embeds pixels its caller already has, say from a rendering pipeline,
+    /// written Flate-compressed, or CCITT Group 4 encoded for black and white
+    /// (1-bit gray) images. Rows go from top to bottom, pixels from
+    /// left to right, each made of the components of `color_space` with
+    /// `bits_per_component` bits each: 1, 2, 4, 8 or 16, the latter big
+    /// endian. Rows of less than 8 bits per component start on a byte.
+    pub fn from_raw(
+        width: u32,
+        height: u32,
+        color_space: ImageColorSpace,
+        bits_per_component: u8,
+        pixels: &[u8],
+    ) -> Result<Self> {
+        ensure!(
+            matches!(bits_per_component, 1 | 2 | 4 | 8 | 16),
+            "unsupported image with {} bits per component",
+            bits_per_component
+        );
+        let row_bits = width as usize * color_space.components() * bits_per_component as usize;
+        let size = row_bits.div_ceil(8) * height as usize;
+        ensure!(
+            pixels.len() == size,
+            "expected {} bytes of pixels for a {}x{} image, got {}",
+            size,
+            width,
+            height,
+            pixels.len()
+        );
+        if color_space == ImageColorSpace::Gray
+            && bits_per_component == 1
+            && let Some(data) = ccitt_g4(width, height, pixels)
+        {
+            return Ok(Self::from_ccitt_g4(width, height, data));
+        }
+        Ok(Self::new(
+            width,
+            height,
+            color_space,
+            bits_per_component,
+            Filter::Flate,
+            deflate(pixels),
+            None,
+        ))
+    }
+
+    /// Embeds CCITT Group 4 encoded data of a black and white image as is,
+    /// such as the strips of a compressed TIFF scan joined together.
+    pub fn from_ccitt_g4(width: u32, height: u32, data: Vec<u8>) -> Self {
+        Self::new(
+            width,
+            height,
+            ImageColorSpace::Gray,
+            1,
+            Filter::CcittG4,
+            data,
+            None,
+        )
+    }
+
+    fn new(
+        width: u32,
+        height: u32,
+        color_space: ImageColorSpace,
+        bits_per_component: u8,
+        filter: Filter,
+        data: Vec<u8>,
+        soft_mask: Option<Image>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ImageData {
+                id: NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+                width,
+                height,
+                color_space,
+                bits_per_component,
+                filter,
+                data,
+                soft_mask,
+                orientation: 1,
+            }),
+        }
+    }
+
+    /// Sets the orientation of a newly made image, not shared yet.
+    fn set_orientation(&mut self, orientation: u8) {
+        Arc::get_mut(&mut self.inner).unwrap().orientation = orientation;
+    }
+
+    /// Draws the pixels the way they are stored, whatever the orientation
+    /// the camera recorded. The pixels are shared with `self`.
+    pub fn ignore_orientation(self) -> Self {
+        if self.inner.orientation == 1 {
+            return self;
+        }
+        let image = &self.inner;
+        Self::new(
+            image.width,
+            image.height,
+            image.color_space,
+            image.bits_per_component,
+            image.filter,
+            image.data.clone(),
+            image.soft_mask.clone(),
+        )
+    }
+
+    /// Width in pixels, upright.
+    pub fn width(&self) -> u32 {
+        match self.is_sideways() {
+            true => self.inner.height,
+            false => self.inner.width,
+        }
+    }
+
+    /// Height in pixels, upright.
+    pub fn height(&self) -> u32 {
+        match self.is_sideways() {
+            true => self.inner.width,
+            false => self.inner.height,
+        }
+    }
+
+    /// Whether the pixels are stored a quarter turn from upright.
+    fn is_sideways(&self) -> bool {
+        self.inner.orientation >= 5
+    }
+
+    /// The transformation drawing the image upright in `rect`, from the
+    /// unit square images are painted in.
+    pub(crate) fn placement(&self, rect: Rect) -> Matrix {
+        // Each maps the stored pixels to where they show upright.
+        let upright = match self.inner.orientation {
+            2 => Matrix::new(-1.0, 0.0, 0.0, 1.0, 1.0, 0.0),
+            3 => Matrix::new(-1.0, 0.0, 0.0, -1.0, 1.0, 1.0),
+            4 => Matrix::new(1.0, 0.0, 0.0, -1.0, 0.0, 1.0),
+            5 => Matrix::new(0.0, -1.0, -1.0, 0.0, 1.0, 1.0),
+            6 => Matrix::new(0.0, -1.0, 1.0, 0.0, 0.0, 1.0),
+            7 => Matrix::new(0.0, 1.0, 1.0, 0.0, 0.0, 0.0),
+            8 => Matrix::new(0.0, 1.0, -1.0, 0.0, 1.0, 0.0),
+            _ => Matrix::IDENTITY,
+        };
+        upright.then(&Matrix::new(
+            rect.width,
+            0.0,
+            0.0,
+            rect.height,
+            rect.x,
+            rect.y,
+        ))
+    }
+
+    /// The name of the device color space the pixels are in.
+    pub(crate) fn device_space(&self) -> &'static str {
+        self.inner.color_space.name()
+    }
+
+    /// The transparency of the image, written as an image of its own.
+    pub(crate) fn soft_mask(&self) -> Option<&Image> {
+        self.inner.soft_mask.as_ref()
+    }
+
+    /// What the image is written from, the same for images loaded apart
+    /// from the same data, which share a single object.
+    pub(crate) fn content(&self) -> ImageContent<'_> {
+        let image = &self.inner;
+        ImageContent {
+            width: image.width,
+            height: image.height,
+            color_space: image.color_space,
+            bits_per_component: image.bits_per_component,
+            filter: image.filter,
+            data: &image.data,
+            soft_mask: image
+                .soft_mask
+                .as_ref()
+                .map(|mask| Box::new(mask.content())),
+        }
+    }
+
+    /// The image resampled to the resolution `downsampling` asks for when
+    /// placed at `width` by `height` points, if it has more pixels than that
+    /// and can be decoded: 8-bit images and JPEGs, gray or RGB. The image
+    /// resampled has a soft mask if and only if the image has one.
+    #[cfg(feature = "image")]
+    pub(crate) fn downsampled(
+        &self,
+        downsampling: &Downsampling,
+        (width, height): (f32, f32),
+    ) -> Result<Option<Image>> {
+        use ::image::imageops::FilterType;
+
+        let needed = |points: f32, pixels: u32| {
+            let needed = (points / 72.0 * downsampling.dpi).ceil().max(1.0);
+            (needed as u32).min(pixels)
+        };
+        // Resampled as stored, keeping the orientation.
+        let (width, height) = match self.is_sideways() {
+            true => (height, width),
+            false => (width, height),
+        };
+        let stored = (self.inner.width, self.inner.height);
+        let (columns, rows) = (needed(width, stored.0), needed(height, stored.1));
+        if (columns, rows) == stored {
+            return Ok(None);
+        }
+        let Some(decoded) = self.to_dynamic_image() else {
+            return Ok(None);
+        };
+        let mut resized = decoded.resize_exact(columns, rows, FilterType::Triangle);
+        let mut resized_alpha = None;
+        if let Some(mask) = self.soft_mask() {
+            let Some(alpha) = mask.to_dynamic_image() else {
+                return Ok(None);
+            };
+            let alpha = alpha
+                .resize_exact(columns, rows, FilterType::Triangle)
+                .to_luma8();
+            resized = match resized.color().has_color() {
+                true => {
+                    let mut pixels = resized.to_rgba8();
+                    for (pixel, a) in pixels.pixels_mut().zip(alpha.pixels()) {
+                        pixel[3] = a[0];
+                    }
+                    pixels.into()
+                }
+                false => {
+                    let mut pixels = resized.to_luma_alpha8();
+                    for (pixel, a) in pixels.pixels_mut().zip(alpha.pixels()) {
+                        pixel[1] = a[0];
+                    }
+                    pixels.into()
+                }
+            };
+            resized_alpha = Some(alpha);
+        }
+        let mut image = Self::encode(&resized, downsampling.jpeg_quality)?;
+        // An alpha channel that comes out opaque throughout is not split
+        // off, but the mask is kept all the same, as what it blends with
+        // is written for an image with one.
+        if let Some(alpha) = resized_alpha
+            && image.soft_mask().is_none()
+        {
+            let mask = Self::new(
+                columns,
+                rows,
+                ImageColorSpace::Gray,
+                8,
+                Filter::Flate,
+                deflate(alpha.as_raw()),
+                None,
+            );
+            Arc::get_mut(&mut image.inner).unwrap().soft_mask = Some(mask);
+        }
+        image.set_orientation(self.inner.orientation);
+        Ok(Some(image))
+    }
+
+    /// The pixels of 8-bit gray and RGB images and of JPEGs but those in
+    /// CMYK.
+    #[cfg(feature = "image")]
+    fn to_dynamic_image(&self) -> Option<::image::DynamicImage> {
+        use ::image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+        use std::io::Read;
+
+        let image = &self.inner;
+        let (width, height) = (image.width, image.height);
+        match (image.filter, image.color_space, image.bits_per_component) {
+            (Filter::Dct { .. }, ImageColorSpace::Gray | ImageColorSpace::Rgb, _) => {
+                ::image::load_from_memory_with_format(&image.data, ImageFormat::Jpeg).ok()
+            }
+            (Filter::Flate, color_space, 8) => {
+                let mut pixels = Vec::new();
+                flate2::read::ZlibDecoder::new(&image.data[..])
+                    .read_to_end(&mut pixels)
+                    .ok()?;
+                match color_space {
+                    ImageColorSpace::Gray => {
+                        GrayImage::from_raw(width, height, pixels).map(DynamicImage::from)
+                    }
+                    ImageColorSpace::Rgb => {
+                        RgbImage::from_raw(width, height, pixels).map(DynamicImage::from)
+                    }
+                    ImageColorSpace::Cmyk => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Writes the image XObject, blended through the soft mask written as
+    /// `soft_mask`, if any.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        id: ObjectRef,
+        soft_mask: Option<ObjectRef>,
+    ) -> Result<()> {
+        if self.inner.filter == Filter::Jpx {
+            writer.require(PdfVersion::Pdf15, "JPEG 2000 images")?;
+        }
+        writer.write(id, &self.inner.stream(soft_mask).into())
+    }
+}
+
+impl ImageData {
+    /// The image XObject, blended through `soft_mask`, if any.
+    fn stream(&self, soft_mask: Option<ObjectRef>) -> Stream {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("XObject"));
+        dict.set("Subtype", Object::name("Image"));
+        dict.set("Width", self.width);
+        dict.set("Height", self.height);
+        dict.set("ColorSpace", Object::name(self.color_space.name()));
+        dict.set("BitsPerComponent", self.bits_per_component as u32);
+        if let Filter::Dct { inverted: true } = self.filter {
+            dict.set("Decode", INVERTED_CMYK.map(Object::from).to_vec());
+        }
+        if let Some(mask) = soft_mask {
+            dict.set("SMask", mask);
+        }
+        Stream::new(dict, self.data.clone()).encoded(self.filter.filter(), self.filter.parms(self))
+    }
+}
+
+impl Image {
+    /// The `BI`, `ID` and `EI` operators painting the image in the unit
+    /// square, its data written in the content stream itself, unless the
+    /// image has a soft mask or is JPEG 2000, neither of which inline
+    /// images allow.
+    pub(crate) fn inline_ops(&self) -> Option<Vec<u8>> {
+        let image = &self.inner;
+        if image.soft_mask.is_some() || image.filter == Filter::Jpx {
+            return None;
+        }
+        let mut ops = format!(
+            "BI /W {} /H {} /CS /{} /BPC {}{}\nID\n",
+            image.width,
+            image.height,
+            image.color_space.abbreviation(),
+            image.bits_per_component,
+            image.filter.inline_entries(image)
+        )
+        .into_bytes();
+        ops.extend_from_slice(&image.data);
+        ops.extend_from_slice(b"\nEI\n");
+        Some(ops)
+    }
+}
+
+/// The image XObject an image is written as, its orientation left aside.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ImageContent<'a> {
+    width: u32,
+    height: u32,
+    color_space: ImageColorSpace,
+    bits_per_component: u8,
+    filter: Filter,
+    data: &'a [u8],
+    soft_mask: Option<Box<ImageContent<'a>>>,
+}
+
+/// How the data of an image is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Filter {
+    Flate,
+    /// JPEG, with CMYK components stored inverted or not.
+    Dct {
+        inverted: bool,
+    },
+    CcittG4,
+    Jpx,
+}
+
+/// The `/Decode` array of CMYK JPEG images stored inverted.
+const INVERTED_CMYK: [i32; 8] = [1, 0, 1, 0, 1, 0, 1, 0];
+
+impl Filter {
+    fn filter(self) -> object::Filter {
+        match self {
+            Filter::Flate => object::Filter::Flate,
+            Filter::Dct { .. } => object::Filter::Dct,
+            Filter::CcittG4 => object::Filter::CcittFax,
+            Filter::Jpx => object::Filter::Jpx,
+        }
+    }
+
+    /// The parameters decoding `image` takes, if any.
+    fn parms(self, image: &ImageData) -> Option<Dictionary> {
+        if self != Filter::CcittG4 {
+            return None;
+        }
+        let mut parms = Dictionary::new();
+        parms.set("K", -1);
+        parms.set("Columns", image.width);
+        parms.set("Rows", image.height);
+        Some(parms)
+    }
+
+    /// The entries telling how to decode `image` inline, with the keys and
+    /// names abbreviated.
+    fn inline_entries(self, image: &ImageData) -> String {
+        let name = match self {
+            Filter::Flate => "Fl",
+            Filter::Dct { .. } => "DCT",
+            Filter::CcittG4 => "CCF",
+            Filter::Jpx => "JPXDecode",
+        };
+        let mut entries = format!(" /F /{}", name);
+        if let Filter::Dct { inverted: true } = self {
+            entries.push_str(&format!(
+                " /D {}",
+                Object::from(INVERTED_CMYK.map(Object::from).to_vec())
+            ));
+        }
+        if let Some(parms) = self.parms(image) {
+            entries.push_str(&format!(" /DP {}", parms));
+        }
+        entries
+    }
+}
+
+impl PartialEq for Image {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for Image {}
+
+impl Hash for Image {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl fmt::Debug for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.inner.width)
+            .field("height", &self.inner.height)
+            .field("color_space", &self.inner.color_space)
+            .finish()
+    }
+}
+
+/// Whether pixels of `components` bytes have more distinct colors than
+/// graphics usually do, compressing better with the lossy JPEG encoding.
+#[cfg(feature = "image")]
+fn is_photo(pixels: &[u8], components: usize) -> bool {
+    const GRAPHICS_COLORS: usize = 256;
+    let mut colors = std::collections::HashSet::new();
+    pixels
+        .chunks(components)
+        .any(|pixel| colors.insert(pixel) && colors.len() > GRAPHICS_COLORS)
+}
+
+/// Encodes rows of 1-bit `pixels`, 0 for black, for the `CCITTFaxDecode`
+/// filter with `/K -1`, if the image is no larger than the encoding allows.
+fn ccitt_g4(width: u32, height: u32, pixels: &[u8]) -> Option<Vec<u8>> {
+    let columns = u16::try_from(width).ok()?;
+    u16::try_from(height).ok()?;
+    let mut encoder = fax::encoder::Encoder::new(fax::VecWriter::new());
+    let row_size = (width as usize).div_ceil(8);
+    for row in pixels.chunks(row_size).take(height as usize) {
+        let pels = (0..width as usize).map(|x| match row[x / 8] >> (7 - x % 8) & 1 {
+            0 => fax::Color::Black,
+            _ => fax::Color::White,
+        });
+        encoder.encode_line(pels, columns).ok()?;
+    }
+    Some(encoder.finish().ok()?.finish())
+}
+
+/// Reads the orientation tag of the first directory of EXIF (TIFF) data.
+fn exif_orientation(tiff: &[u8]) -> Option<u8> {
+    let u16_at = |at: usize| -> Option<u16> {
+        let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(match &tiff[..2] {
+            b"II" => u16::from_le_bytes(bytes),
+            _ => u16::from_be_bytes(bytes),
+        })
+    };
+    let u32_at = |at: usize| -> Option<u32> {
+        let (high, low) = (u16_at(at)? as u32, u16_at(at + 2)? as u32);
+        Some(match &tiff[..2] {
+            b"II" => low << 16 | high,
+            _ => high << 16 | low,
+        })
+    };
+    if !matches!(tiff.get(..2)?, b"II" | b"MM") {
+        return None;
+    }
+    let directory = u32_at(4)? as usize;
+    let entries = u16_at(directory)? as usize;
+    (0..entries)
+        .map(|i| directory + 2 + 12 * i)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+        .filter(|orientation| (1..=8).contains(orientation))
+        .map(|orientation| orientation as u8)
+}
+
+/// The signature box starting JP2 files.
+const JP2_SIGNATURE: &[u8] = b"\0\0\0\x0CjP  \r\n\x87\n";
+
+/// Finds the codestream in the boxes of a JP2 file.
+fn jp2_codestream(data: &[u8]) -> Result<&[u8]> {
+    let mut rest = data;
+    while rest.len() >= 8 {
+        let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let kind = &rest[4..8];
+        let (header, length) = match length {
+            // Up to the end of the file.
+            0 => (8, rest.len()),
+            // With a 64-bit length.
+            1 => {
+                let Some(length) = rest.get(8..16) else { break };
+                (16, u64::from_be_bytes(length.try_into().unwrap()) as usize)
+            }
+            length => (8, length),
+        };
+        let Some(content) = rest.get(header..length) else {
+            break;
+        };
+        match kind {
+            b"jp2c" => return Ok(content),
+            b"jp2h" => ensure!(
+                !content.windows(4).any(|window| window == b"pclr"),
+                "unsupported JPEG 2000 image with a palette"
+            ),
+            _ => {}
+        }
+        rest = &rest[length..];
+    }
+    bail!("invalid JPEG 2000 image: no codestream")
+}
+
+/// Reads the image and tile size (`SIZ`) marker following the start of a
+/// JPEG 2000 codestream.
+fn jpx_info(codestream: &[u8]) -> Result<JpegInfo> {
+    ensure!(
+        codestream.starts_with(&[0xFF, 0x4F, 0xFF, 0x51]),
+        "not a JPEG 2000 image"
+    );
+    let Some(segment) = codestream.get(6..43) else {
+        bail!("invalid JPEG 2000 image: truncated header");
+    };
+    let int = |at: usize| u32::from_be_bytes(segment[at..at + 4].try_into().unwrap());
+    let (Some(width), Some(height)) = (int(2).checked_sub(int(10)), int(6).checked_sub(int(14)))
+    else {
+        bail!("invalid JPEG 2000 image: offset past the image size");
+    };
+    Ok(JpegInfo {
+        width,
+        height,
+        components: u16::from_be_bytes([segment[34], segment[35]]).min(255) as u8,
+        // Of the first component: readers take it from the data anyway.
+        precision: (segment[36] & 0x7F) + 1,
+        adobe: false,
+        orientation: 1,
+    })
+}
+
+/// What the header of a JPEG or JPEG 2000 image tells about its pixels.
+struct JpegInfo {
+    width: u32,
+    height: u32,
+    components: u8,
+    precision: u8,
+    /// Whether an Adobe `APP14` segment is present.
+    adobe: bool,
+    /// The EXIF orientation, 1 if there is none.
+    orientation: u8,
+}
+
+/// Reads the segments of a JPEG up to its start-of-frame header.
+fn jpeg_info(data: &[u8]) -> Result<JpegInfo> {
+    ensure!(data.starts_with(&[0xFF, 0xD8]), "not a JPEG image");
+    let mut adobe = false;
+    let mut orientation = 1;
+    let mut pos = 2;
+    loop {
+        // Markers may be preceded by any number of fill bytes.
+        while data.get(pos) == Some(&0xFF) && data.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let (Some(0xFF), Some(&marker)) = (data.get(pos), data.get(pos + 1)) else {
+            bail!("invalid JPEG image: no frame header");
+        };
+        pos += 2;
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+        let Some(length) = data.get(pos..pos + 2) else {
+            bail!("invalid JPEG image: truncated segment");
+        };
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        let Some(segment) = data.get(pos + 2..pos + length) else {
+            bail!("invalid JPEG image: truncated segment");
+        };
+        match marker {
+            0xEE if segment.starts_with(b"Adobe") => adobe = true,
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                orientation = exif_orientation(&segment[6..]).unwrap_or(1);
+            }
+            // Start of frame, except for the DHT, JPG and DAC markers
+            // sharing the range.
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                ensure!(segment.len() >= 6, "invalid JPEG image: short frame header");
+                return Ok(JpegInfo {
+                    precision: segment[0],
+                    height: u16::from_be_bytes([segment[1], segment[2]]) as u32,
+                    width: u16::from_be_bytes([segment[3], segment[4]]) as u32,
+                    components: segment[5],
+                    adobe,
+                    orientation,
+                });
+            }
+            0xDA | 0xD9 => bail!("invalid JPEG image: no frame header"),
+            _ => {}
+        }
+        pos += length;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Read;
+
+    /// The headers of a JPEG of `width` by `height` pixels, without scan
+    /// data: enough for embedding as is.
+    pub(crate) fn jpeg_header(width: u16, height: u16, components: u8) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        // A JFIF segment, then a fill byte before the frame header.
+        data.extend([0xFF, 0xE0, 0x00, 0x07, b'J', b'F', b'I', b'F', 0x00]);
+        data.extend([0xFF, 0xFF, 0xC0, 0x00, 8 + 3 * components, 8]);
+        data.extend(height.to_be_bytes());
+        data.extend(width.to_be_bytes());
+        data.push(components);
+        for id in 1..=components {
+            data.extend([id, 0x11, 0x00]);
+        }
+        data.extend([0xFF, 0xD9]);
+        data
+    }
+
+    /// Encodes a PNG of `width` by `height` 8-bit `pixels`.
+    pub(crate) fn png_image(width: u32, height: u32, color: ColorType, pixels: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, width, height);
+        encoder.set_color(color);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(pixels).unwrap();
+        writer.finish().unwrap();
+        data
+    }
+
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        flate2::read::ZlibDecoder::new(data)
+            .read_to_end(&mut pixels)
+            .unwrap();
+        pixels
+    }
+
+    #[test]
+    fn test_png_alpha() {
+        let pixels = [10, 20, 30, 255, 40, 50, 60, 128];
+        let image = Image::from_png(&png_image(2, 1, ColorType::Rgba, &pixels)).unwrap();
+        assert_eq!(image.inner.color_space, ImageColorSpace::Rgb);
+        assert_eq!(inflate(&image.inner.data), [10, 20, 30, 40, 50, 60]);
+        let mask = image.soft_mask().unwrap();
+        assert_eq!(mask.inner.color_space, ImageColorSpace::Gray);
+        assert_eq!(inflate(&mask.inner.data), [255, 128]);
+
+        // Opaque throughout: no mask.
+        let pixels = [10, 255, 20, 255];
+        let image = Image::from_png(&png_image(1, 2, ColorType::GrayscaleAlpha, &pixels)).unwrap();
+        assert_eq!(image.inner.color_space, ImageColorSpace::Gray);
+        assert_eq!(inflate(&image.inner.data), [10, 20]);
+        assert!(image.soft_mask().is_none());
+
+        assert!(Image::from_png(b"\x89PNG").is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_dynamic_image_encoding() {
+        use ::image::{DynamicImage, GrayImage, Rgb, RgbImage, RgbaImage};
+
+        let photo = RgbImage::from_fn(64, 64, |x, y| Rgb([x as u8 * 4, y as u8 * 4, 128]));
+        let image = Image::from_dynamic_image(&DynamicImage::ImageRgb8(photo)).unwrap();
+        assert_eq!(image.inner.filter, Filter::Dct { inverted: false });
+        assert_eq!((image.width(), image.height()), (64, 64));
+
+        let chart = GrayImage::from_fn(64, 64, |x, _| [(x / 16) as u8 * 80].into());
+        let image = Image::from_dynamic_image(&DynamicImage::ImageLuma8(chart)).unwrap();
+        assert_eq!(image.inner.filter, Filter::Flate);
+        assert_eq!(image.inner.color_space, ImageColorSpace::Gray);
+
+        let logo = RgbaImage::from_fn(4, 4, |x, y| [255, 0, 0, (x * y * 16) as u8].into());
+        let image = Image::from_dynamic_image(&DynamicImage::ImageRgba8(logo)).unwrap();
+        assert_eq!(image.inner.filter, Filter::Flate);
+        assert!(image.soft_mask().is_some());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_decoded_formats() {
+        use ::image::{DynamicImage, ImageFormat, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+
+        let encode = |image: DynamicImage, format| {
+            let mut data = Cursor::new(Vec::new());
+            image.write_to(&mut data, format).unwrap();
+            data.into_inner()
+        };
+        // Large enough for the encoder to write several strips.
+        let scan = ::image::GrayImage::from_fn(100, 400, |x, y| Luma([((x + y) % 2 * 255) as u8]));
+        let tiff = encode(DynamicImage::ImageLuma8(scan), ImageFormat::Tiff);
+        let image = Image::from_tiff(&tiff).unwrap();
+        assert_eq!((image.width(), image.height()), (100, 400));
+        assert_eq!(image.inner.bits_per_component, 1);
+        // Rows start on a byte, the second on a white pixel.
+        let bits = unfax(&image.inner.data, 100, 400);
+        assert_eq!(
+            bits[11..15],
+            [0b0101_0101, 0b0101_0000, 0b1010_1010, 0b1010_1010]
+        );
+
+        let bmp = encode(
+            DynamicImage::ImageRgb8(RgbImage::from_pixel(3, 2, Rgb([1, 2, 3]))),
+            ImageFormat::Bmp,
+        );
+        let image = Image::from_bmp(&bmp).unwrap();
+        assert_eq!(image.inner.color_space, ImageColorSpace::Rgb);
+        assert_eq!(inflate(&image.inner.data), [1, 2, 3].repeat(6));
+
+        let mut dot = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 0]));
+        dot.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        let gif = encode(DynamicImage::ImageRgba8(dot), ImageFormat::Gif);
+        let image = Image::from_gif(&gif).unwrap();
+        let mask = image.soft_mask().unwrap();
+        assert_eq!(inflate(&mask.inner.data), [0, 0, 0, 255]);
+
+        assert!(Image::from_tiff(&bmp).is_err());
+    }
+
+    /// Decodes Group 4 data of `width` by `height` pixels into rows of bits.
+    fn unfax(data: &[u8], width: u16, height: u16) -> Vec<u8> {
+        let mut bits = Vec::new();
+        fax::decoder::decode_g4(data.iter().copied(), width, Some(height), |line| {
+            let pels: Vec<_> = fax::decoder::pels(line, width).collect();
+            for byte in pels.chunks(8) {
+                let packed = byte.iter().enumerate().fold(0, |packed, (i, color)| {
+                    packed | ((*color == fax::Color::White) as u8) << (7 - i)
+                });
+                bits.push(packed);
+            }
+        })
+        .unwrap();
+        bits
+    }
+
+    #[test]
+    fn test_bilevel_ccitt_g4() {
+        // A 20 by 3 scan: a black bar on the middle row.
+        let pixels = [0xFF, 0xFF, 0xF0, 0xC0, 0x00, 0x30, 0xFF, 0xFF, 0xF0];
+        let image = Image::from_raw(20, 3, ImageColorSpace::Gray, 1, &pixels).unwrap();
+        assert_eq!(
+            image.inner.stream(None).encode().0.get("DecodeParms"),
+            Some(&Object::from(Filter::CcittG4.parms(&image.inner).unwrap()))
+        );
+        assert_eq!(
+            image.inner.filter.inline_entries(&image.inner),
+            " /F /CCF /DP << /K -1 /Columns 20 /Rows 3 >>"
+        );
+        assert_eq!(unfax(&image.inner.data, 20, 3), pixels);
+
+        // Wider than Group 4 allows.
+        let wide = vec![0; 70_000 / 8];
+        let image = Image::from_raw(70_000, 1, ImageColorSpace::Gray, 1, &wide).unwrap();
+        assert_eq!(image.inner.filter, Filter::Flate);
+    }
+
+    #[test]
+    fn test_raw_pixels() {
+        let image = Image::from_raw(3, 2, ImageColorSpace::Gray, 2, &[0b1010_0000, 0]).unwrap();
+        assert_eq!(inflate(&image.inner.data), [0b1010_0000, 0]);
+        assert_eq!(image.inner.bits_per_component, 2);
+
+        let cmyk = Image::from_raw(2, 1, ImageColorSpace::Cmyk, 16, &[0; 16]).unwrap();
+        assert_eq!(cmyk.inner.color_space.name(), "DeviceCMYK");
+        let error = Image::from_raw(2, 2, ImageColorSpace::Rgb, 8, &[0; 11]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected 12 bytes of pixels for a 2x2 image, got 11"
+        );
+        assert!(Image::from_raw(1, 1, ImageColorSpace::Gray, 3, &[0]).is_err());
+    }
+
+    /// The start of a JPEG 2000 codestream of `width` by `height` pixels.
+    pub(crate) fn j2k_header(width: u32, height: u32, components: u16) -> Vec<u8> {
+        let mut data = vec![0xFF, 0x4F, 0xFF, 0x51];
+        data.extend((38 + 3 * components).to_be_bytes());
+        data.extend([0, 0]);
+        // Image size and offset: the image starts at (1, 2) on the grid.
+        data.extend((width + 1).to_be_bytes());
+        data.extend((height + 2).to_be_bytes());
+        data.extend(
+            [1u32, 2, width, height, 0, 0]
+                .iter()
+                .flat_map(|n| n.to_be_bytes()),
+        );
+        data.extend(components.to_be_bytes());
+        for _ in 0..components {
+            data.extend([7, 1, 1]);
+        }
+        data.extend([0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn test_jpx_header() {
+        let image = Image::from_jpx(j2k_header(300, 200, 3)).unwrap();
+        assert_eq!((image.width(), image.height()), (300, 200));
+        assert_eq!(image.inner.color_space, ImageColorSpace::Rgb);
+        assert_eq!(image.inner.filter, Filter::Jpx);
+
+        let codestream = j2k_header(16, 8, 1);
+        let mut jp2 = JP2_SIGNATURE.to_vec();
+        jp2.extend(b"\0\0\0\x14ftypjp2 \0\0\0\0jp2 ");
+        jp2.extend((8 + codestream.len() as u32).to_be_bytes());
+        jp2.extend(b"jp2c");
+        jp2.extend(&codestream);
+        let image = Image::from_jpx(jp2.clone()).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 8));
+        assert_eq!(image.inner.color_space, ImageColorSpace::Gray);
+        assert_eq!(image.inner.data, jp2);
+
+        assert!(Image::from_jpx(jp2[..40].to_vec()).is_err());
+        assert!(Image::from_jpx(jpeg_header(1, 1, 3)).is_err());
+    }
+
+    /// `jpeg` rotated as told by an EXIF segment, with the byte order of
+    /// `order`.
+    fn with_orientation(mut jpeg: Vec<u8>, order: &[u8; 2], orientation: u16) -> Vec<u8> {
+        let little = order == b"II";
+        let u16_bytes = |n: u16| {
+            if little {
+                n.to_le_bytes()
+            } else {
+                n.to_be_bytes()
+            }
+        };
+        let u32_bytes = |n: u32| {
+            if little {
+                n.to_le_bytes()
+            } else {
+                n.to_be_bytes()
+            }
+        };
+        let mut exif = b"Exif\0\0".to_vec();
+        exif.extend(order);
+        exif.extend(u16_bytes(42));
+        exif.extend(u32_bytes(8));
+        exif.extend(u16_bytes(2));
+        // Image width, then orientation, both shorts.
+        for (tag, value) in [(0x0100, 640), (0x0112, orientation)] {
+            exif.extend(u16_bytes(tag));
+            exif.extend(u16_bytes(3));
+            exif.extend(u32_bytes(1));
+            exif.extend(u16_bytes(value));
+            exif.extend([0, 0]);
+        }
+        exif.extend(u32_bytes(0));
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend((exif.len() as u16 + 2).to_be_bytes());
+        segment.extend(exif);
+        jpeg.splice(2..2, segment);
+        jpeg
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        let photo = with_orientation(jpeg_header(640, 480, 3), b"II", 6);
+        let image = Image::from_jpeg(photo).unwrap();
+        assert_eq!((image.width(), image.height()), (480, 640));
+        // Stored 640 wide, turned a quarter clockwise into a 48 by 64 frame.
+        let matrix = image.placement(Rect::new(100.0, 0.0, 48.0, 64.0));
+        assert_eq!(matrix.to_string(), "0 -64 48 0 100 64");
+        assert_eq!(matrix.apply(0.0, 1.0), (148.0, 64.0));
+
+        let image = image.ignore_orientation();
+        assert_eq!((image.width(), image.height()), (640, 480));
+        assert_eq!(
+            image.placement(Rect::new(0.0, 0.0, 64.0, 48.0)).to_string(),
+            "64 0 0 48 0 0"
+        );
+
+        let photo = with_orientation(jpeg_header(10, 10, 1), b"MM", 3);
+        let image = Image::from_jpeg(photo).unwrap();
+        assert_eq!(
+            image.placement(Rect::new(0.0, 0.0, 10.0, 10.0)).to_string(),
+            "-10 0 0 -10 10 10"
+        );
+        let photo = with_orientation(jpeg_header(10, 10, 1), b"MM", 9);
+        assert_eq!(Image::from_jpeg(photo).unwrap().inner.orientation, 1);
+    }
+
+    #[test]
+    fn test_jpeg_header() {
+        let image = Image::from_jpeg(jpeg_header(640, 480, 3)).unwrap();
+        assert_eq!((image.width(), image.height()), (640, 480));
+        assert_eq!(image.inner.color_space, ImageColorSpace::Rgb);
+        assert_eq!(image.inner.filter, Filter::Dct { inverted: false });
+
+        let mut cmyk = jpeg_header(10, 20, 4);
+        cmyk.splice(2..2, [0xFF, 0xEE, 0x00, 0x07, b'A', b'd', b'o', b'b', b'e']);
+        let image = Image::from_jpeg(cmyk).unwrap();
+        assert_eq!(image.inner.color_space, ImageColorSpace::Cmyk);
+        assert_eq!(
+            Object::from(image.inner.stream(None)).to_string(),
+            format!(
+                "<< /Length {} /Type /XObject /Subtype /Image /Width 10 /Height 20 \
+                 /ColorSpace /DeviceCMYK /BitsPerComponent 8 /Decode [1 0 1 0 1 0 1 0] \
+                 /Filter /DCTDecode >>",
+                image.inner.data.len()
+            )
+        );
+
+        assert_eq!(image.inner.orientation, 1);
+
+        assert!(Image::from_jpeg(b"\x89PNG".to_vec()).is_err());
+        assert!(Image::from_jpeg(jpeg_header(1, 1, 3)[..12].to_vec()).is_err());
+    }
+}
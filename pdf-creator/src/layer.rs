@@ -0,0 +1,88 @@
+use crate::object::{Dictionary, Object};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Source of the ids telling layers apart.
+static NEXT_LAYER_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A named layer of content, such as "Dimensions" or "Watermark", that
+/// readers of the document can show or hide: an optional content group.
+/// Content goes into it with
+/// [`ContentStream::in_layer`](crate::ContentStream::in_layer), on any
+/// number of pages.
+///
+/// Layers appear in the viewer in the order they are first used. They are
+/// a PDF 1.5 feature: older viewers show all content.
+#[derive(Clone)]
+pub struct Layer {
+    inner: Arc<LayerData>,
+}
+
+struct LayerData {
+    id: u32,
+    name: String,
+    visible: bool,
+}
+
+impl Layer {
+    /// A layer shown when the document is opened.
+    pub fn new(name: &str) -> Self {
+        Self::with_visibility(name, true)
+    }
+
+    /// A layer hidden until the reader turns it on, such as print marks.
+    pub fn hidden(name: &str) -> Self {
+        Self::with_visibility(name, false)
+    }
+
+    fn with_visibility(name: &str, visible: bool) -> Self {
+        Self {
+            inner: Arc::new(LayerData {
+                id: NEXT_LAYER_ID.fetch_add(1, Ordering::Relaxed),
+                name: name.to_owned(),
+                visible,
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.inner.visible
+    }
+
+    /// The `/OCG` dictionary.
+    pub(crate) fn to_pdf(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("OCG"));
+        dict.set("Name", Object::text(&self.inner.name));
+        dict
+    }
+}
+
+impl PartialEq for Layer {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for Layer {}
+
+impl Hash for Layer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl fmt::Debug for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Layer")
+            .field("name", &self.inner.name)
+            .field("visible", &self.inner.visible)
+            .finish()
+    }
+}
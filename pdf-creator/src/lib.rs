@@ -1,2 +1,74 @@
+mod annotation;
+mod bidi;
+mod builder;
+mod color;
+mod conformance;
+mod destination;
+mod embedded;
+mod encryption;
+mod fdf;
+mod font;
+mod form;
+mod geometry;
+mod graphics;
+mod icc;
+mod image;
+mod layer;
+mod linearization;
+#[cfg(test)]
 mod lopdf;
 mod manual;
+mod metadata;
+mod metrics;
+mod object;
+mod outline;
+mod page;
+mod page_tree;
+mod pattern;
+mod resources;
+mod shading;
+mod signing;
+mod string;
+mod structure;
+mod subset;
+mod text;
+mod timestamp;
+mod truetype;
+mod update;
+mod writer;
+mod xobject;
+
+pub use annotation::{Annotation, AttachmentIcon, MarkupStyle, NoteIcon, StampName};
+pub use builder::{PageBuilder, PdfBuilder};
+pub use color::{Color, SpotColor};
+pub use conformance::{Conformance, OutputIntent};
+pub use destination::Destination;
+pub use embedded::{EmbeddedFile, FileRelationship};
+pub use encryption::{Encryption, EncryptionMethod, Permissions};
+pub use font::{Font, StandardFont};
+pub use form::{
+    ButtonAction, CheckBox, ChoiceField, InteractiveForm, PushButton, RadioGroup, SignatureField,
+    SubmitFormat, TextField,
+};
+pub use geometry::{Matrix, Point, Rect};
+pub use graphics::{
+    BlendMode, CornerRadii, FillRule, LineCap, LineJoin, Paint, Path, PathBuilder, StrokeStyle,
+};
+pub use icc::IccProfile;
+#[cfg(feature = "image")]
+pub use image::Downsampling;
+pub use image::{Image, ImageColorSpace};
+pub use layer::Layer;
+pub use manual::{PdfDocument, PdfVersion};
+pub use metadata::{Metadata, PdfDate, XmpNamespace};
+pub use object::{Dictionary, Encoding, Filter, Object, ObjectRef, Stream};
+pub use outline::{Outline, OutlineItem};
+pub use page::{ContentStream, Orientation, Page, PageSize, Rotation};
+pub use pattern::TilingPattern;
+pub use shading::Gradient;
+pub use signing::{SignatureAlgorithm, SignatureAppearance, Signer};
+pub use structure::{StructureType, Tag};
+pub use text::{Align, RichText, Span, TextRendering, TextStyle};
+pub use truetype::TrueTypeFont;
+pub use update::PdfUpdate;
+pub use xobject::FormXObject;
@@ -0,0 +1,528 @@
+use crate::object::{Object, ObjectRef};
+use anyhow::{Result, ensure};
+use std::collections::{HashMap, HashSet};
+
+/// An object of a document being linearized, held back until every object
+/// is written to be laid out and renumbered, its strings and stream data
+/// not encrypted yet.
+#[derive(Debug)]
+pub(crate) struct Held {
+    pub(crate) object: ObjectRef,
+    /// The object, or the dictionary of a stream but its `/Length`.
+    pub(crate) body: Object,
+    pub(crate) data: Option<Vec<u8>>,
+    /// Whether its strings and data are encrypted, as those of all objects
+    /// but the encryption dictionary are when the document is.
+    pub(crate) encrypted: bool,
+}
+
+/// The order objects are laid out in (ISO 32000-1, annex F), each part told
+/// by the indexes of the objects held.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Plan {
+    /// The catalog and the encryption dictionary, which opening the
+    /// document takes.
+    pub(crate) document: Vec<usize>,
+    /// The objects of the first page, its page object first, including
+    /// those other pages use too.
+    pub(crate) first: Vec<usize>,
+    /// The objects only each other page uses, its page object first.
+    pub(crate) pages: Vec<Vec<usize>>,
+    /// The objects several pages use besides the first.
+    pub(crate) shared: Vec<usize>,
+    /// For each page but the first, the objects of the first page and the
+    /// shared ones it uses, by their index in the shared object hint table,
+    /// where the objects of the first page come first.
+    pub(crate) uses: Vec<Vec<usize>>,
+    /// Every other object, such as the page tree, the outline and the
+    /// information dictionary.
+    pub(crate) rest: Vec<usize>,
+}
+
+impl Plan {
+    /// Lays out the objects `held` for the pages `pages`, in order, opening
+    /// the document taking the objects `document`. The objects of a page
+    /// are those it refers to, directly or not, along with those the nodes
+    /// of the page tree above it do, such as the resources it inherits, bar
+    /// other pages, the nodes of the page tree and `document`, which
+    /// objects such as annotations refer back to.
+    pub(crate) fn new(held: &[Held], pages: &[ObjectRef], document: &[ObjectRef]) -> Result<Self> {
+        ensure!(!pages.is_empty(), "linearized documents need a page");
+        let index: HashMap<u32, usize> = held
+            .iter()
+            .enumerate()
+            .map(|(i, held)| (held.object.id(), i))
+            .collect();
+        let kids: Vec<Vec<usize>> = held
+            .iter()
+            .map(|held| {
+                held.body
+                    .references()
+                    .into_iter()
+                    .filter_map(|object| index.get(&object.id()).copied())
+                    .collect()
+            })
+            .collect();
+        let pages: Vec<usize> = pages.iter().map(|page| index[&page.id()]).collect();
+        let document: Vec<usize> = document.iter().map(|object| index[&object.id()]).collect();
+        let mut blocked: HashSet<usize> = pages.iter().chain(&document).copied().collect();
+        let mut nodes = pages.clone();
+        while let Some(node) = nodes.pop() {
+            let Some(&parent) = parent(&held[node].body).and_then(|id| index.get(&id)) else {
+                continue;
+            };
+            if blocked.insert(parent) {
+                nodes.push(parent);
+            }
+        }
+        let ancestors = |page: usize| {
+            let mut nodes = Vec::new();
+            let mut node = page;
+            while let Some(&parent) = parent(&held[node].body).and_then(|id| index.get(&id)) {
+                if nodes.contains(&parent) {
+                    break;
+                }
+                nodes.push(parent);
+                node = parent;
+            }
+            nodes
+        };
+        let objects = |page: usize| {
+            let mut seen = HashSet::from([page]);
+            let mut stack = vec![page];
+            stack.extend(ancestors(page));
+            while let Some(object) = stack.pop() {
+                for &kid in &kids[object] {
+                    if !blocked.contains(&kid) && seen.insert(kid) {
+                        stack.push(kid);
+                    }
+                }
+            }
+            seen.remove(&page);
+            let mut objects: Vec<usize> = seen.into_iter().collect();
+            objects.sort_unstable();
+            objects.insert(0, page);
+            objects
+        };
+
+        let first = objects(pages[0]);
+        let in_first: HashSet<usize> = first.iter().copied().collect();
+        let others: Vec<Vec<usize>> = pages[1..].iter().map(|&page| objects(page)).collect();
+        let mut users: HashMap<usize, usize> = HashMap::new();
+        for &object in others.iter().flatten() {
+            *users.entry(object).or_default() += 1;
+        }
+        let shared: Vec<usize> = (0..held.len())
+            .filter(|object| {
+                !in_first.contains(object) && users.get(object).is_some_and(|&n| n > 1)
+            })
+            .collect();
+        let mut identifiers: HashMap<usize, usize> = HashMap::new();
+        for (identifier, &object) in first.iter().chain(&shared).enumerate() {
+            identifiers.insert(object, identifier);
+        }
+        let own = |objects: &[usize]| -> Vec<usize> {
+            objects
+                .iter()
+                .copied()
+                .filter(|object| !identifiers.contains_key(object))
+                .collect()
+        };
+        let uses = others
+            .iter()
+            .map(|objects| {
+                let mut uses: Vec<usize> = objects
+                    .iter()
+                    .filter_map(|object| identifiers.get(object).copied())
+                    .collect();
+                uses.sort_unstable();
+                uses
+            })
+            .collect();
+        let pages: Vec<Vec<usize>> = others.iter().map(|objects| own(objects)).collect();
+        let placed: HashSet<usize> = document
+            .iter()
+            .chain(&first)
+            .chain(pages.iter().flatten())
+            .chain(&shared)
+            .copied()
+            .collect();
+        let rest = (0..held.len())
+            .filter(|object| !placed.contains(object))
+            .collect();
+        Ok(Self {
+            document,
+            first,
+            pages,
+            shared,
+            uses,
+            rest,
+        })
+    }
+}
+
+/// The lengths in bytes of the objects held, as laid out, and where some
+/// of them start, leaving out the hint stream itself as the hint tables do.
+#[derive(Debug)]
+pub(crate) struct Lengths<'a> {
+    pub(crate) lengths: &'a [usize],
+    /// Where the page object of the first page starts.
+    pub(crate) first_page: u64,
+    /// Where the first shared object starts, if any, and its number.
+    pub(crate) first_shared: Option<(u64, u32)>,
+}
+
+/// The data of the primary hint stream of `plan` (ISO 32000-1, F.4), its
+/// page offset hint table followed by its shared object hint table, and
+/// where the latter begins, for the `/S` entry. Each shared object is a
+/// group of its own. The content stream offsets and lengths of pages are
+/// written as readers such as Acrobat take them: as 0 and as the length of
+/// the whole page.
+pub(crate) fn hint_stream(plan: &Plan, lengths: &Lengths) -> (Vec<u8>, usize) {
+    let length = |objects: &[usize]| -> u64 {
+        objects
+            .iter()
+            .map(|&object| lengths.lengths[object] as u64)
+            .sum()
+    };
+    let pages: Vec<&[usize]> = std::iter::once(&plan.first[..])
+        .chain(plan.pages.iter().map(Vec::as_slice))
+        .collect();
+    let no_uses = Vec::new();
+    let uses: Vec<&Vec<usize>> = std::iter::once(&no_uses).chain(&plan.uses).collect();
+    let counts: Vec<u64> = pages.iter().map(|objects| objects.len() as u64).collect();
+    let page_lengths: Vec<u64> = pages.iter().map(|objects| length(objects)).collect();
+    let least_count = counts.iter().copied().min().unwrap_or_default();
+    let least_length = page_lengths.iter().copied().min().unwrap_or_default();
+    let count_bits = bits(counts.iter().map(|count| count - least_count).max());
+    let length_bits = bits(
+        page_lengths
+            .iter()
+            .map(|length| length - least_length)
+            .max(),
+    );
+    let uses_bits = bits(uses.iter().map(|uses| uses.len() as u64).max());
+    let identifier_bits = bits(
+        uses.iter()
+            .flat_map(|uses| uses.iter())
+            .map(|&i| i as u64)
+            .max(),
+    );
+
+    let mut hints = BitWriter::default();
+    hints.write(least_count, 32);
+    hints.write(lengths.first_page, 32);
+    hints.write(count_bits.into(), 16);
+    hints.write(least_length, 32);
+    hints.write(length_bits.into(), 16);
+    // Content stream offsets, all 0.
+    hints.write(0, 32);
+    hints.write(0, 16);
+    hints.write(least_length, 32);
+    hints.write(length_bits.into(), 16);
+    hints.write(uses_bits.into(), 16);
+    hints.write(identifier_bits.into(), 16);
+    // No fractional positions of shared objects within pages, over 1.
+    hints.write(0, 16);
+    hints.write(1, 16);
+    for count in &counts {
+        hints.write(count - least_count, count_bits);
+    }
+    hints.align();
+    for length in &page_lengths {
+        hints.write(length - least_length, length_bits);
+    }
+    hints.align();
+    for uses in &uses {
+        hints.write(uses.len() as u64, uses_bits);
+    }
+    hints.align();
+    for &identifier in uses.iter().flat_map(|uses| uses.iter()) {
+        hints.write(identifier as u64, identifier_bits);
+    }
+    hints.align();
+    for length in &page_lengths {
+        hints.write(length - least_length, length_bits);
+    }
+    hints.align();
+
+    let shared_table = hints.bytes.len();
+    let groups: Vec<u64> = plan
+        .first
+        .iter()
+        .chain(&plan.shared)
+        .map(|&object| lengths.lengths[object] as u64)
+        .collect();
+    let least_group = groups.iter().copied().min().unwrap_or_default();
+    let group_bits = bits(groups.iter().map(|length| length - least_group).max());
+    let (first_shared_offset, first_shared) = lengths.first_shared.unwrap_or_default();
+    hints.write(first_shared.into(), 32);
+    hints.write(first_shared_offset, 32);
+    hints.write(plan.first.len() as u64, 32);
+    hints.write(groups.len() as u64, 32);
+    // One object per group.
+    hints.write(0, 16);
+    hints.write(least_group, 32);
+    hints.write(group_bits.into(), 16);
+    for length in &groups {
+        hints.write(length - least_group, group_bits);
+    }
+    hints.align();
+    // No signatures of the groups.
+    for _ in &groups {
+        hints.write(0, 1);
+    }
+    hints.align();
+    (hints.bytes, shared_table)
+}
+
+/// The number of bits it takes to write `largest`, 0 for 0.
+fn bits(largest: Option<u64>) -> u8 {
+    (u64::BITS - largest.unwrap_or_default().leading_zeros()) as u8
+}
+
+/// Writes numbers of any number of bits, most significant bit first.
+#[derive(Debug, Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// How many bits of the last byte are written.
+    used: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u8) {
+        for bit in (0..bits).rev() {
+            if self.used == 0 || self.used == 8 {
+                self.bytes.push(0);
+                self.used = 0;
+            }
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= (((value >> bit) & 1) as u8) << (7 - self.used);
+            self.used += 1;
+        }
+    }
+
+    /// Pads the last byte with zeros, for what follows to begin a byte.
+    fn align(&mut self) {
+        self.used = 0;
+    }
+}
+
+/// Rewrites each reference it is called on to an object of `numbers` to
+/// the number it has there, as [`Object::visit_mut`] calls it on every
+/// object within another.
+pub(crate) fn renumber(numbers: &HashMap<u32, u32>) -> impl FnMut(&mut Object) -> Result<()> + '_ {
+    |value| {
+        if let Object::Reference(object) = value
+            && let Some(&number) = numbers.get(&object.id())
+        {
+            *object = ObjectRef::new(number);
+        }
+        Ok(())
+    }
+}
+
+/// The object the `/Parent` entry of the dictionary `value` refers to,
+/// such as the page tree node of a page object.
+fn parent(value: &Object) -> Option<u32> {
+    match value {
+        Object::Dictionary(dict) => match dict.get("Parent") {
+            Some(Object::Reference(parent)) => Some(parent.id()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Dictionary;
+
+    /// The dictionary of `entries`.
+    fn dict(entries: Vec<(&str, Object)>) -> Object {
+        let mut dict = Dictionary::new();
+        for (key, value) in entries {
+            dict.set(key, value);
+        }
+        dict.into()
+    }
+
+    fn to(id: u32) -> Object {
+        ObjectRef::new(id).into()
+    }
+
+    fn held(id: u32, body: Object) -> Held {
+        Held {
+            object: ObjectRef::new(id),
+            body,
+            data: None,
+            encrypted: true,
+        }
+    }
+
+    /// A page of the page tree 2, of the contents `contents` and the
+    /// resources `resources`.
+    fn page(id: u32, contents: u32, resources: Vec<(&str, Object)>) -> Held {
+        let page = vec![
+            ("Type", Object::name("Page")),
+            ("Parent", to(2)),
+            ("Contents", to(contents)),
+            ("Resources", dict(resources)),
+        ];
+        held(id, dict(page))
+    }
+
+    #[test]
+    fn test_renumber() {
+        let mut annot = dict(vec![
+            ("Type", Object::name("Annot")),
+            ("P", to(4)),
+            ("Contents", Object::string("see 4 0 R")),
+            ("F1", to(4)),
+            ("Rect", vec![4.into(), 0.into(), 2.into(), 4.into()].into()),
+            (
+                "D",
+                vec![Object::hex([4, 0]), to(12), Object::name("XYZ"), 0.into()].into(),
+            ),
+        ]);
+        let ids: Vec<u32> = annot.references().iter().map(ObjectRef::id).collect();
+        assert_eq!(ids, [4, 4, 12]);
+        let numbers = HashMap::from([(4, 30), (12, 7)]);
+        annot.visit_mut(&mut renumber(&numbers)).unwrap();
+        assert_eq!(
+            annot.to_string(),
+            "<< /Type /Annot /P 30 0 R /Contents (see 4 0 R) /F1 30 0 R \
+             /Rect [4 0 2 4] /D [<0400> 7 0 R /XYZ 0] >>"
+        );
+        let kid = dict(vec![
+            ("Type", Object::name("Page")),
+            ("Parent", to(2)),
+            ("Annots", vec![to(5)].into()),
+        ]);
+        assert_eq!(parent(&kid), Some(2));
+        assert_eq!(parent(&to(2)), None);
+    }
+
+    #[test]
+    fn test_plan() {
+        // A catalog, the page tree, three pages with their content, a font
+        // used by the first and the last, an image used by the last two,
+        // and the information dictionary.
+        let catalog = || {
+            let catalog = vec![("Type", Object::name("Catalog")), ("Pages", to(2))];
+            held(1, dict(catalog))
+        };
+        let font = || {
+            let font = vec![
+                ("Type", Object::name("Font")),
+                ("Subtype", Object::name("Type1")),
+                ("BaseFont", Object::name("Helvetica")),
+            ];
+            held(3, dict(font))
+        };
+        let fonts = || dict(vec![("F1", to(3))]);
+        let images = || dict(vec![("X1", to(4))]);
+        let mut last = page(9, 10, vec![("Font", fonts()), ("XObject", images())]);
+        if let Object::Dictionary(last) = &mut last.body {
+            last.set("Annots", vec![to(11)]);
+        }
+        let objects = [
+            catalog(),
+            held(
+                2,
+                dict(vec![
+                    ("Type", Object::name("Pages")),
+                    ("Kids", vec![to(5), to(7), to(9)].into()),
+                    ("Count", 3.into()),
+                ]),
+            ),
+            font(),
+            held(
+                4,
+                dict(vec![
+                    ("Type", Object::name("XObject")),
+                    ("Subtype", Object::name("Image")),
+                ]),
+            ),
+            page(5, 6, vec![("Font", fonts())]),
+            held(6, dict(Vec::new())),
+            page(7, 8, vec![("XObject", images())]),
+            held(8, dict(Vec::new())),
+            last,
+            held(10, dict(Vec::new())),
+            held(
+                11,
+                dict(vec![
+                    ("Type", Object::name("Annot")),
+                    ("Subtype", Object::name("Link")),
+                    ("P", to(9)),
+                    ("Dest", vec![to(5), Object::name("Fit")].into()),
+                ]),
+            ),
+            held(12, dict(vec![("Title", Object::string("Report"))])),
+        ];
+        let pages = [5, 7, 9].map(ObjectRef::new);
+        let plan = Plan::new(&objects, &pages, &[ObjectRef::new(1)]).unwrap();
+        assert_eq!(
+            plan,
+            Plan {
+                document: vec![0],
+                first: vec![4, 2, 5],
+                pages: vec![vec![6, 7], vec![8, 9, 10]],
+                shared: vec![3],
+                uses: vec![vec![3], vec![1, 3]],
+                rest: vec![1, 11],
+            }
+        );
+
+        // A font the pages inherit from the page tree is theirs.
+        let inherited = [
+            catalog(),
+            held(
+                2,
+                dict(vec![
+                    ("Type", Object::name("Pages")),
+                    ("Kids", vec![to(4), to(6)].into()),
+                    ("Count", 2.into()),
+                    ("Resources", dict(vec![("Font", fonts())])),
+                ]),
+            ),
+            font(),
+            page(4, 5, Vec::new()),
+            held(5, dict(Vec::new())),
+            page(6, 7, Vec::new()),
+            held(7, dict(Vec::new())),
+        ];
+        let pages = [4, 6].map(ObjectRef::new);
+        let inherited = Plan::new(&inherited, &pages, &[ObjectRef::new(1)]).unwrap();
+        assert_eq!(inherited.first, [3, 2, 4]);
+        assert_eq!(inherited.pages, [[5, 6]]);
+        assert_eq!(inherited.uses, [[1]]);
+        assert_eq!(inherited.rest, [1]);
+
+        let lengths = Lengths {
+            lengths: &[0, 0, 40, 30, 100, 200, 60, 300, 80, 100, 50, 0],
+            first_page: 1000,
+            first_shared: Some((2000, 9)),
+        };
+        let (data, shared_table) = hint_stream(&plan, &lengths);
+        // Pages of 3, 2 and 3 objects, 340, 360 and 230 bytes long.
+        assert_eq!(data[..4], 2u32.to_be_bytes());
+        assert_eq!(data[4..8], 1000u32.to_be_bytes());
+        assert_eq!(data[8..10], 1u16.to_be_bytes());
+        assert_eq!(data[10..14], 230u32.to_be_bytes());
+        assert_eq!(data[14..16], 8u16.to_be_bytes());
+        assert_eq!(data[36], 0b1010_0000);
+        assert_eq!(data[37..40], [110, 130, 0]);
+        // The second page uses the image, the last also the font.
+        assert_eq!(data[40..42], [0b0001_1000, 0b1101_1100]);
+        assert_eq!(shared_table, 45);
+        assert_eq!(data.len(), shared_table + 29);
+        assert_eq!(data[shared_table..shared_table + 4], 9u32.to_be_bytes());
+        assert_eq!(
+            data[shared_table + 12..shared_table + 16],
+            4u32.to_be_bytes()
+        );
+    }
+}
@@ -1,192 +1,1049 @@
-use anyhow::Result;
+use crate::annotation::Annotation;
+use crate::conformance::{Conformance, OutputIntent};
+use crate::destination::{Destination, name_tree};
+use crate::embedded::EmbeddedFile;
+use crate::encryption::{Encryption, SecurityHandler};
+use crate::font::Font;
+use crate::form::{ButtonAction, InteractiveForm};
+use crate::geometry::{Matrix, Rect};
+use crate::graphics::ExtGState;
+#[cfg(feature = "image")]
+use crate::image::Downsampling;
+use crate::image::Image;
+use crate::metadata::{Metadata, XmpNamespace};
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::outline::Outline;
+use crate::page::{InheritedAttributes, Page};
+use crate::page_tree::PageTree;
+use crate::resources::{ResourceRefs, Resources, SharedResources};
+use crate::signing::{SignaturePlaceholder, Signer};
+use crate::structure::{PageStructure, write_tree};
+use crate::writer::{PdfWriter, Trailer};
+use anyhow::{Context, Result, bail, ensure};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{Seek, Write};
+use std::io::{Cursor, Write};
+use std::path::Path;
 
 #[derive(Debug)]
 pub struct PdfDocument {
     version: PdfVersion,
-    catalog: Catalog,
-    pages: Pages,
-    page: Page,
-    contents: ContentStream,
+    pages: Vec<Page>,
+    metadata: Metadata,
+    xmp: bool,
+    outline: Outline,
+    named_destinations: BTreeMap<String, Destination>,
+    /// Files attached to the document as a whole, by name.
+    attachments: BTreeMap<String, EmbeddedFile>,
+    form: InteractiveForm,
+    compress: bool,
+    object_streams: bool,
+    xref_stream: bool,
+    linearized: bool,
+    /// Whether pages inherit the media box and the resources most have.
+    inherited_attributes: bool,
+    encryption: Option<Encryption>,
+    /// The signature field to sign the document in, and how.
+    signature: Option<(String, Signer)>,
+    conformance: Option<Conformance>,
+    output_intent: Option<OutputIntent>,
+    #[cfg(feature = "image")]
+    downsampling: Option<Downsampling>,
+    /// The objects added as they are, numbered first, those reserved but
+    /// not set yet being `None`.
+    objects: Vec<Option<Object>>,
+    /// Entries added to the catalog as they are.
+    catalog_entries: Dictionary,
 }
 
-#[derive(Debug)]
+impl Default for PdfDocument {
+    fn default() -> Self {
+        Self {
+            version: PdfVersion::default(),
+            pages: Vec::new(),
+            metadata: Metadata::default(),
+            xmp: false,
+            outline: Outline::default(),
+            named_destinations: BTreeMap::new(),
+            attachments: BTreeMap::new(),
+            form: InteractiveForm::default(),
+            compress: true,
+            object_streams: false,
+            xref_stream: false,
+            linearized: false,
+            inherited_attributes: false,
+            encryption: None,
+            signature: None,
+            conformance: None,
+            output_intent: None,
+            #[cfg(feature = "image")]
+            downsampling: None,
+            objects: Vec::new(),
+            catalog_entries: Dictionary::new(),
+        }
+    }
+}
+
+/// Version of the PDF specification a document is written for, told by its
+/// header. Writing a document that uses a feature its version lacks, such as
+/// object streams in PDF 1.4, fails.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PdfVersion {
+    #[default]
     Pdf14,
+    Pdf15,
+    Pdf16,
+    Pdf17,
+    Pdf20,
 }
 
 impl PdfVersion {
     pub fn to_str(&self) -> &'static str {
         match self {
             PdfVersion::Pdf14 => "%PDF-1.4\n",
+            PdfVersion::Pdf15 => "%PDF-1.5\n",
+            PdfVersion::Pdf16 => "%PDF-1.6\n",
+            PdfVersion::Pdf17 => "%PDF-1.7\n",
+            PdfVersion::Pdf20 => "%PDF-2.0\n",
         }
     }
+
+    /// The version number, such as `1.7`.
+    pub fn number(&self) -> &'static str {
+        &self.to_str()[5..8]
+    }
 }
 
 #[derive(Debug)]
 pub struct Catalog {
     pages: ObjectRef,
+    /// The XMP metadata stream.
+    metadata: Option<ObjectRef>,
+    /// The root of the bookmarks, shown when the document opens.
+    outlines: Option<ObjectRef>,
+    /// The name tree of the named destinations.
+    dests: Option<ObjectRef>,
+    /// The file specifications of the files attached to the document, by
+    /// name, which are its associated files too.
+    attachments: Vec<(String, ObjectRef)>,
+    /// The interactive form dictionary.
+    form: Option<ObjectRef>,
+    /// The output intent dictionary, written in place.
+    output_intent: Option<Dictionary>,
+    /// Optional content groups and whether each is on when opened.
+    layers: Vec<(ObjectRef, bool)>,
+    /// The structure tree of tagged documents.
+    structure: Option<ObjectRef>,
+    language: Option<String>,
+    /// Whether readers show the title rather than the file name.
+    display_title: bool,
 }
 
 impl Catalog {
-    pub fn to_string(&self) -> String {
-        format!(
-            "1 0 obj\n<< /Type /Catalog /Pages {} {} R >>\nendobj\n",
-            self.pages.id, self.pages.generation
-        )
+    fn to_dict(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Catalog"));
+        dict.set("Pages", self.pages);
+        if let Some(metadata) = self.metadata {
+            dict.set("Metadata", metadata);
+        }
+        if self.dests.is_some() || !self.attachments.is_empty() {
+            let mut names = Dictionary::new();
+            if let Some(dests) = self.dests {
+                names.set("Dests", dests);
+            }
+            if !self.attachments.is_empty() {
+                let mut files = Dictionary::new();
+                let pairs = self.attachments.iter().flat_map(|(name, filespec)| {
+                    [Object::string(name.as_str()), (*filespec).into()]
+                });
+                files.set("Names", pairs.collect::<Vec<_>>());
+                names.set("EmbeddedFiles", files);
+            }
+            dict.set("Names", names);
+        }
+        if let Some(outlines) = self.outlines {
+            dict.set("Outlines", outlines);
+            dict.set("PageMode", Object::name("UseOutlines"));
+        }
+        if let Some(form) = self.form {
+            dict.set("AcroForm", form);
+        }
+        if let Some(output_intent) = &self.output_intent {
+            dict.set("OutputIntents", vec![output_intent.clone().into()]);
+        }
+        if !self.attachments.is_empty() {
+            let filespecs = self
+                .attachments
+                .iter()
+                .map(|(_, filespec)| (*filespec).into());
+            dict.set("AF", filespecs.collect::<Vec<_>>());
+        }
+        if !self.layers.is_empty() {
+            let refs = |visible: Option<bool>| {
+                self.layers
+                    .iter()
+                    .filter(|(_, on)| visible.is_none_or(|visible| *on == visible))
+                    .map(|(layer, _)| (*layer).into())
+                    .collect::<Vec<Object>>()
+            };
+            let mut config = Dictionary::new();
+            config.set("Name", Object::string("Layers"));
+            config.set("Order", refs(None));
+            let hidden = refs(Some(false));
+            if !hidden.is_empty() {
+                config.set("OFF", hidden);
+            }
+            let mut properties = Dictionary::new();
+            properties.set("OCGs", refs(None));
+            properties.set("D", config);
+            dict.set("OCProperties", properties);
+        }
+        if let Some(structure) = self.structure {
+            let mut mark_info = Dictionary::new();
+            mark_info.set("Marked", true);
+            dict.set("MarkInfo", mark_info);
+            dict.set("StructTreeRoot", structure);
+        }
+        if let Some(language) = &self.language {
+            dict.set("Lang", Object::text(language));
+        }
+        if self.display_title {
+            let mut preferences = Dictionary::new();
+            preferences.set("DisplayDocTitle", true);
+            dict.set("ViewerPreferences", preferences);
+        }
+        dict
     }
 }
 
-#[derive(Debug)]
-pub struct ObjectRef {
-    id: u32,
-    generation: u16,
-}
+impl PdfDocument {
+    pub fn new(content: &str) -> Self {
+        let mut doc = Self::default();
+        doc.add_page(content);
+        doc
+    }
 
-#[derive(Debug)]
-pub struct Pages {
-    kids: Vec<ObjectRef>,
-    count: usize,
-}
+    /// Appends a page and registers it as a kid of the page tree. A plain
+    /// `&str` becomes an A4 page showing that text.
+    pub fn add_page(&mut self, page: impl Into<Page>) {
+        self.pages.push(page.into());
+    }
 
-impl Pages {
-    pub fn to_string(&self) -> String {
-        assert_eq!(self.kids.len(), self.count);
-        assert_eq!(self.count, 1);
-        format!(
-            "2 0 obj\n<< /Type /Pages /Kids [{} {} R] /Count {} >>\nendobj\n",
-            self.kids[0].id, self.kids[0].generation, self.count
-        )
+    /// Loads a JPEG image for placing on pages with
+    /// [`ContentStream::draw_image`](crate::ContentStream::draw_image). The
+    /// data is embedded as is, without re-encoding, and written once with
+    /// the pages placing it.
+    pub fn add_jpeg(&self, data: Vec<u8>) -> Result<Image> {
+        Image::from_jpeg(data)
     }
-}
 
-#[derive(Debug)]
-pub struct Page {
-    parent: ObjectRef,
-    media_box: [f32; 4],
-    contents: ObjectRef,
-    font_resources: FontResources,
-}
+    /// Loads a JPEG 2000 image, embedded as is like [`PdfDocument::add_jpeg`].
+    pub fn add_jpx(&self, data: Vec<u8>) -> Result<Image> {
+        Image::from_jpx(data)
+    }
 
-impl Page {
-    pub fn to_string(&self) -> String {
-        format!(
-            "3 0 obj\n<< /Type /Page /Parent {} {} R /MediaBox [{} {} {} {}] \
-         /Contents {} {} R /Resources << /Font << /{} {} {} R >> >> >>\nendobj\n",
-            self.parent.id,
-            self.parent.generation,
-            self.media_box[0],
-            self.media_box[1],
-            self.media_box[2],
-            self.media_box[3],
-            self.contents.id,
-            self.contents.generation,
-            self.font_resources.name,
-            self.font_resources.object.id,
-            self.font_resources.object.generation
-        )
+    /// Loads a PNG image for placing on pages with
+    /// [`ContentStream::draw_image`](crate::ContentStream::draw_image),
+    /// transparency included.
+    pub fn add_png(&self, data: &[u8]) -> Result<Image> {
+        Image::from_png(data)
     }
-}
 
-#[derive(Debug)]
-pub struct FontResources {
-    name: String,
-    object: ObjectRef,
-}
+    /// Adds an image decoded with the `image` crate, encoded for its content
+    /// as told by [`Image::from_dynamic_image`].
+    #[cfg(feature = "image")]
+    pub fn add_image(&self, image: &::image::DynamicImage) -> Result<Image> {
+        Image::from_dynamic_image(image)
+    }
 
-#[derive(Debug)]
-pub struct ContentStream {
-    content: String,
-}
+    /// Adds a TIFF image, such as a scan, decoded with [`Image::from_tiff`].
+    #[cfg(feature = "image")]
+    pub fn add_tiff(&self, data: &[u8]) -> Result<Image> {
+        Image::from_tiff(data)
+    }
 
-impl ContentStream {
-    pub fn to_string(&self) -> String {
-        let stream = format!("BT\n/F1 24 Tf\n100 700 Td\n({}) Tj\nET\n", self.content);
+    #[cfg(feature = "image")]
+    pub fn add_bmp(&self, data: &[u8]) -> Result<Image> {
+        Image::from_bmp(data)
+    }
 
-        format!(
-            "4 0 obj\n<< /Length {} >>\nstream\n{}endstream\nendobj\n",
-            stream.len(),
-            stream
-        )
+    /// Adds the first frame of a GIF image.
+    #[cfg(feature = "image")]
+    pub fn add_gif(&self, data: &[u8]) -> Result<Image> {
+        Image::from_gif(data)
     }
-}
 
-impl PdfDocument {
-    pub fn new(content: &str) -> Self {
-        Self {
-            version: PdfVersion::Pdf14,
-            catalog: Catalog {
-                pages: ObjectRef {
-                    id: 2,
-                    generation: 0,
-                },
-            },
-            pages: Pages {
-                kids: vec![ObjectRef {
-                    id: 3,
-                    generation: 0,
-                }],
-                count: 1,
-            },
-            page: Page {
-                parent: ObjectRef {
-                    id: 2,
-                    generation: 0,
-                },
-                media_box: [0.0, 0.0, 595.0, 842.0],
-                contents: ObjectRef {
-                    id: 4,
-                    generation: 0,
-                },
-                font_resources: FontResources {
-                    name: String::from("F1"),
-                    object: ObjectRef {
-                        id: 5,
-                        generation: 0,
-                    },
-                },
-            },
-            contents: ContentStream {
-                content: content.to_string(),
-            },
+    /// Sets the title, author and other properties of the document, written
+    /// as its information dictionary. None are written by default.
+    pub fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = metadata;
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Has the metadata written as an XMP packet too, the form archives and
+    /// asset management systems read, custom namespaces included. The
+    /// packet is left uncompressed for tools that scan files for it.
+    pub fn set_xmp(&mut self, xmp: bool) {
+        self.xmp = xmp;
+    }
+
+    /// The bookmarks of the document, empty until added to:
+    ///
+    /// ```
+    /// # use pdf_creator::{Destination, PdfDocument};
+    /// let mut doc = PdfDocument::new("Introduction");
+    /// doc.add_page("Details");
+    /// doc.outline()
+    ///     .add("Chapter 1", Destination::page(0))
+    ///     .child("1.1", Destination::page(1));
+    /// ```
+    pub fn outline(&mut self) -> &mut Outline {
+        &mut self.outline
+    }
+
+    /// Registers `destination` as `name`, for bookmarks and links to go to
+    /// with [`Destination::named`], replacing any destination of that name.
+    /// The name is kept in the document, so other documents can link to it
+    /// as well.
+    pub fn add_named_destination(&mut self, name: &str, destination: Destination) {
+        self.named_destinations
+            .insert(name.to_string(), destination);
+    }
+
+    /// Attaches `file` to the document as a whole, which readers list among
+    /// its attachments, replacing any file of the same name. Such files are
+    /// the associated files of PDF/A-3 documents, which must tell their
+    /// [`relationship`](EmbeddedFile::relationship) to the document: the
+    /// XML of a ZUGFeRD or Factur-X invoice, for one, is attached so.
+    pub fn attach_file(&mut self, file: EmbeddedFile) {
+        self.attachments.insert(file.name().to_string(), file);
+    }
+
+    /// The fillable fields of the document, none until added:
+    ///
+    /// ```
+    /// # use pdf_creator::{PdfDocument, Rect};
+    /// let mut doc = PdfDocument::new("Application");
+    /// doc.form()
+    ///     .text_field("name", Rect::new(100.0, 600.0, 250.0, 20.0), 0)
+    ///     .set_max_length(60);
+    /// ```
+    pub fn form(&mut self) -> &mut InteractiveForm {
+        &mut self.form
+    }
+
+    /// Turns the form fields, as filled in so far, into plain content of
+    /// their pages showing what they show, and removes them: for final
+    /// copies to archive or send out, which can no longer be changed.
+    /// Fails, leaving the document as it was, if a field is on a page the
+    /// document does not have.
+    pub fn flatten_form(&mut self) -> Result<()> {
+        let placements = self.form.placements();
+        for (name, page, _, _) in &placements {
+            ensure!(
+                *page < self.pages.len(),
+                "form field {:?} on page {} of a document of {} pages",
+                name,
+                page + 1,
+                self.pages.len()
+            );
         }
+        for (_, page, rect, look) in placements {
+            // Appearances are stretched over their widgets, as readers do.
+            if look.width() == 0.0 || look.height() == 0.0 {
+                continue;
+            }
+            let (left, bottom, right, top) = rect.edges();
+            let matrix = Matrix::scale(
+                (right - left) / look.width(),
+                (top - bottom) / look.height(),
+            )
+            .then(&Matrix::translate(left, bottom));
+            self.pages[page].draw_form_transformed(look, matrix);
+        }
+        self.form = InteractiveForm::default();
+        Ok(())
+    }
+
+    /// Adds `object` to be written as it is, and tells how to refer to it,
+    /// for what the document has no API of its own for yet. Objects added
+    /// can refer only to one another, and are tied into the document with
+    /// [`set_catalog_entry`](Self::set_catalog_entry):
+    ///
+    /// ```
+    /// use pdf_creator::{Dictionary, Object, PdfDocument};
+    ///
+    /// let mut doc = PdfDocument::new("Viewer settings");
+    /// let mut print = Dictionary::new();
+    /// print.set("Type", Object::name("Action"));
+    /// print.set("S", Object::name("JavaScript"));
+    /// print.set("JS", Object::text("this.print();"));
+    /// let print = doc.add_object(print);
+    /// doc.set_catalog_entry("OpenAction", print);
+    /// ```
+    pub fn add_object(&mut self, object: impl Into<Object>) -> ObjectRef {
+        self.objects.push(Some(object.into()));
+        ObjectRef::new(self.objects.len() as u32)
+    }
+
+    /// Keeps a number for an object set later with
+    /// [`set_object`](Self::set_object), for objects that refer to each
+    /// other. Writing fails if it is not set by then.
+    pub fn reserve_object(&mut self) -> ObjectRef {
+        self.objects.push(None);
+        ObjectRef::new(self.objects.len() as u32)
+    }
+
+    /// Sets the object `object` kept with
+    /// [`reserve_object`](Self::reserve_object) to `value`. Fails if it was
+    /// not reserved, or is set already.
+    pub fn set_object(&mut self, object: ObjectRef, value: impl Into<Object>) -> Result<()> {
+        let index = object.id() as usize;
+        let Some(slot @ None) = index
+            .checked_sub(1)
+            .and_then(|index| self.objects.get_mut(index))
+        else {
+            bail!("object {} was not reserved, or is set already", object.id());
+        };
+        *slot = Some(value.into());
+        Ok(())
+    }
+
+    /// Has the catalog, the dictionary reading the document starts from,
+    /// written with the entry `key` as it is, for objects added with
+    /// [`add_object`](Self::add_object) to be found. Writing fails if the
+    /// document writes the entry itself.
+    pub fn set_catalog_entry(&mut self, key: &str, value: impl Into<Object>) {
+        self.catalog_entries.set(key, value);
+    }
+
+    /// Has content streams, embedded fonts and ICC profiles written
+    /// Flate-compressed, the default, or as is to read them in a text
+    /// editor while debugging.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    /// The version the document is written for, PDF 1.4 by default.
+    pub fn set_version(&mut self, version: PdfVersion) {
+        self.version = version;
+    }
+
+    /// Has the dictionaries of the document, such as pages, fonts and
+    /// annotations, packed together into compressed object streams, which
+    /// makes for much smaller files when there are thousands of them. Off by
+    /// default, as it takes PDF 1.5; the cross-reference section is then a
+    /// stream too.
+    pub fn set_object_streams(&mut self, object_streams: bool) {
+        self.object_streams = object_streams;
+    }
+
+    /// Has the cross-reference section, the index of where each object
+    /// starts, written as a compressed stream (PDF 1.5), a fraction of the
+    /// size of the classic table for large documents. The classic table, for
+    /// PDF 1.4 readers, is the default unless object streams are on, which
+    /// take a stream.
+    pub fn set_xref_stream(&mut self, xref_stream: bool) {
+        self.xref_stream = xref_stream;
+    }
+
+    /// Has the document written linearized, or optimized for the web: the
+    /// first page and what it takes first, then each other page in turn,
+    /// along with hint tables telling where each page is, for a viewer
+    /// fetching the file by ranges of bytes, over HTTP, to show the first
+    /// page before the rest has arrived and to go to any page without
+    /// reading the ones before. Off by default; the document is then put
+    /// together in memory, with cross-reference tables, so not with object
+    /// streams, and cannot be signed.
+    pub fn set_linearized(&mut self, linearized: bool) {
+        self.linearized = linearized;
+    }
+
+    /// Has the media box and the resources most pages have alike written
+    /// once, on the root of the page tree, for those pages to inherit
+    /// rather than repeat them, which makes for smaller files of uniform
+    /// pages, such as reports set in the same fonts throughout. Pages of
+    /// another size, or drawn with other resources, keep their own. Off by
+    /// default.
+    pub fn set_inherited_attributes(&mut self, inherited: bool) {
+        self.inherited_attributes = inherited;
+    }
+
+    /// Has the document written encrypted as `encryption` says, and so
+    /// protected by its passwords, or as is with `None`, the default. Each
+    /// document written is given a new random identifier, which the keys
+    /// are derived from.
+    pub fn set_encryption(&mut self, encryption: Option<Encryption>) {
+        self.encryption = encryption;
+    }
+
+    /// Has the document signed by `signer` each time it is written, the
+    /// signature held by the signature field called `field`, which the
+    /// form must have by then: one of no size makes the signature
+    /// invisible, otherwise it shows as the field does, or as
+    /// [`Signer::appearance`] has it. The document should not be changed
+    /// afterwards but by appending to it, or the signature no longer holds.
+    pub fn sign(&mut self, field: &str, signer: Signer) {
+        self.signature = Some((field.to_string(), signer));
+    }
+
+    /// Has the document written to the standard `conformance`, such as
+    /// PDF/A for archives, or to none with `None`, the default. Writing
+    /// fails, telling what to change, if the document breaks it. The
+    /// metadata is then written as XMP too, naming the standard.
+    pub fn set_conformance(&mut self, conformance: Option<Conformance>) {
+        self.conformance = conformance;
+    }
+
+    /// Tells the color conditions the document is meant for, which PDF/A
+    /// and PDF/X documents must have, or none with `None`, the default.
+    pub fn set_output_intent(&mut self, output_intent: Option<OutputIntent>) {
+        self.output_intent = output_intent;
+    }
+
+    /// Has images placed at more than `downsampling.dpi` resampled down to
+    /// it as the document is written, or kept as is with `None`, the
+    /// default. The size an image is placed at is that of the largest
+    /// rectangle it is drawn in, leaving out transformations.
+    #[cfg(feature = "image")]
+    pub fn set_downsampling(&mut self, downsampling: Option<Downsampling>) {
+        self.downsampling = downsampling;
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
     }
 
     pub fn create(&self) -> Result<()> {
-        let mut file = File::create("./manual.pdf")?;
-        file.write_all(self.version.to_str().as_bytes())?;
+        self.create_to_path("./manual.pdf")
+    }
+
+    /// Writes the document to a newly created (or truncated) file at `path`.
+    pub fn create_to_path(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_to(File::create(path)?)
+    }
+
+    /// Serializes the whole document in memory, without touching the
+    /// filesystem, failing as [`PdfDocument::write_to`] does.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        self.write_to(&mut buf)?;
+        Ok(buf.into_inner())
+    }
+
+    /// Serializes the document into any sink, seekable or not, such as a
+    /// file, a socket, the standard output or a gzip encoder. Offsets are
+    /// counted from where the document starts, so `w` may already contain
+    /// data.
+    ///
+    /// Object numbers are handed out here, in the order the objects are
+    /// written: objects added as they are, catalog, the nodes of the page
+    /// tree, the root first, one font object per font used anywhere in the
+    /// document, one stream per ICC profile painted in, one
+    /// per tiling pattern, one per form (annotation appearances included),
+    /// one per image followed by its soft mask, one dictionary per layer,
+    /// one stream per attached file, one file specification per file
+    /// attached to the document, then every page followed by its
+    /// content stream and its annotations, the form fields and the
+    /// interactive form dictionary if there are any, the signature
+    /// dictionary if the document is signed, the information dictionary if
+    /// there is metadata, the XMP metadata stream if asked for or written
+    /// to a standard, the structure tree root if the document is tagged,
+    /// the tree of named destinations and the outline, followed by its
+    /// bookmarks level by level, then the structure elements and the
+    /// parent tree. The encryption dictionary and object streams, if any,
+    /// come last, followed by the cross-reference stream. Linearized
+    /// documents are laid out and numbered again once all is written, as
+    /// [`set_linearized`](Self::set_linearized) says.
+    ///
+    /// A signed document is put together in memory first, the signature
+    /// being of the whole file.
+    pub fn write_to<W: Write>(&self, mut w: W) -> Result<()> {
+        let Some((field, signer)) = &self.signature else {
+            return self.write_document(w, &self.form, None).map(|_| ());
+        };
+        let rect = self
+            .form
+            .signature_rect(field)
+            .with_context(|| format!("the form has no signature field {:?} to sign in", field))?;
+        // The time is settled once, for what the signature shows to agree
+        // with it.
+        let signer = signer.clone().time(signer.signing_time());
+        let mut form = Cow::Borrowed(&self.form);
+        if let Some(look) = signer.look(rect)? {
+            form.to_mut().set_signature_appearance(field, look);
+        }
+        let mut pdf = Cursor::new(Vec::new());
+        let placeholder = self.write_document(&mut pdf, &form, Some((field, &signer)))?;
+        let mut pdf = pdf.into_inner();
+        let placeholder = placeholder.expect("signed documents have a signature dictionary");
+        signer.sign_file(&mut pdf, &placeholder)?;
+        w.write_all(&pdf)?;
+        Ok(())
+    }
 
-        let mut offsets: Vec<u64> = Vec::new();
+    /// Writes the document as [`write_to`](Self::write_to) says, with
+    /// `form` as its form and signed in the field and by the signer of
+    /// `signature`, if any, and tells where the signature is to be filled
+    /// in.
+    fn write_document<W: Write>(
+        &self,
+        w: W,
+        form: &InteractiveForm,
+        signature: Option<(&str, &Signer)>,
+    ) -> Result<Option<SignaturePlaceholder>> {
+        let mut writer = PdfWriter::new(w, &self.version)?;
+        writer.set_compression(self.compress);
+        writer.set_object_streams(self.object_streams)?;
+        writer.set_xref_stream(self.xref_stream)?;
+        if let Some(encryption) = &self.encryption {
+            writer.set_encryption(SecurityHandler::new(encryption)?)?;
+        }
+        let mut alloc = ObjectAllocator::new();
 
-        macro_rules! write_pdf {
-            ($s:expr) => {{
-                offsets.push(file.stream_position()?);
-                file.write_all($s.as_bytes())?;
-            }};
+        let objects: Vec<ObjectRef> = self.objects.iter().map(|_| alloc.allocate()).collect();
+        let catalog_id = alloc.allocate();
+        let page_tree = PageTree::new(self.pages.len(), &mut alloc);
+        let mut refs = ResourceRefs::default();
+        let appearances = self
+            .pages
+            .iter()
+            .flat_map(|page| &page.annotations)
+            .filter_map(Annotation::appearance_form);
+        let mut shared = SharedResources::gather(
+            self.pages.iter().map(|page| &page.resources),
+            appearances.chain(form.appearances()),
+        );
+        if let Some(conformance) = self.conformance {
+            self.check_conformance(conformance, form, shared.resources())?;
+        }
+        let attached = self
+            .pages
+            .iter()
+            .flat_map(|page| &page.annotations)
+            .filter_map(Annotation::attached_file);
+        // Standard fonts, which readers set the text of form fields in.
+        shared.allocate(
+            &mut alloc,
+            &mut refs,
+            form.fonts(),
+            self.output_intent.as_ref().map(OutputIntent::profile),
+            attached.chain(self.attachments.values()),
+        );
+        let filespecs: Vec<(&EmbeddedFile, ObjectRef)> = self
+            .attachments
+            .values()
+            .map(|file| (file, alloc.allocate()))
+            .collect();
+        let kids: Vec<(ObjectRef, ObjectRef, Vec<ObjectRef>)> = self
+            .pages
+            .iter()
+            .map(|page| {
+                let page_id = alloc.allocate();
+                let contents_id = alloc.allocate();
+                let annots = page.annotations.iter().map(|_| alloc.allocate());
+                (page_id, contents_id, annots.collect())
+            })
+            .collect();
+        if self.linearized {
+            writer.set_linearized(kids.iter().map(|(page, _, _)| *page).collect())?;
+        }
+        let fields = form.allocate(&mut alloc);
+        let form_id = (!form.is_empty()).then(|| alloc.allocate());
+        let signature_id = signature.map(|_| alloc.allocate());
+        let info = (!self.metadata.is_empty()).then(|| alloc.allocate());
+        let xmp = (self.xmp || self.conformance.is_some()).then(|| alloc.allocate());
+        let tagged = self
+            .pages
+            .iter()
+            .any(|page| !page.contents.structure.is_empty());
+        let structure = tagged.then(|| alloc.allocate());
+        let dests = (!self.named_destinations.is_empty()).then(|| alloc.allocate());
+        let outline = (!self.outline.is_empty()).then(|| alloc.allocate());
+
+        let catalog = Catalog {
+            pages: page_tree.root(),
+            metadata: xmp,
+            outlines: outline,
+            dests,
+            attachments: filespecs
+                .iter()
+                .map(|(file, filespec)| (file.name().to_string(), *filespec))
+                .collect(),
+            form: form_id,
+            output_intent: self.output_intent.as_ref().map(|intent| {
+                let subtype = self
+                    .conformance
+                    .map_or("GTS_PDFA1", Conformance::output_intent_subtype);
+                intent.to_pdf(subtype, &refs)
+            }),
+            layers: shared
+                .layers()
+                .iter()
+                .map(|layer| (refs.layers[*layer], layer.is_visible()))
+                .collect(),
+            structure,
+            language: self.metadata.language.clone(),
+            // As accessibility asks of tagged documents.
+            display_title: tagged && self.metadata.title.is_some(),
+        };
+        for (object, id) in self.objects.iter().zip(objects) {
+            let object = object
+                .as_ref()
+                .with_context(|| format!("object {} was reserved but never set", id.id()))?;
+            self.check_references(object)?;
+            writer.write(id, object)?;
+        }
+        let mut catalog = catalog.to_dict();
+        for (key, value) in self.catalog_entries.iter() {
+            ensure!(
+                catalog.get(key).is_none(),
+                "the catalog entry /{} is written by the document itself",
+                String::from_utf8_lossy(key)
+            );
+            self.check_references(value)?;
+            catalog.set(key, value.clone());
+        }
+        writer.write(catalog_id, &catalog.into())?;
+        let resources: Vec<Dictionary> = self
+            .pages
+            .iter()
+            .map(|page| page.contents.resources.to_dict(&refs))
+            .collect();
+        let inherited = match self.inherited_attributes {
+            true => InheritedAttributes::of(&self.pages, &resources),
+            false => InheritedAttributes::default(),
+        };
+        let pages: Vec<ObjectRef> = kids.iter().map(|(page, _, _)| *page).collect();
+        for (node, mut dict) in page_tree.to_dicts(&pages) {
+            if node == page_tree.root() {
+                inherited.set_on(&mut dict);
+            }
+            writer.write(node, &dict.into())?;
+        }
+        let cid_set = self.conformance.is_some_and(Conformance::needs_cid_set);
+        shared.write(
+            &mut writer,
+            &mut alloc,
+            &refs,
+            cid_set,
+            #[cfg(feature = "image")]
+            self.downsampling.as_ref(),
+        )?;
+        for (file, filespec) in filespecs {
+            writer.write_object(filespec, file.to_filespec(refs.files[file]))?;
         }
 
-        write_pdf!(self.catalog.to_string());
-        write_pdf!(self.pages.to_string());
-        write_pdf!(self.page.to_string());
-        write_pdf!(self.contents.to_string());
+        let parents = page_tree.parents();
+        let written = self.pages.iter().zip(&kids).zip(resources);
+        for (i, ((page, (page_id, contents_id, annots)), resources)) in written.enumerate() {
+            // Form fields go over the annotations of their page.
+            let widgets = form.widgets_on(i, &fields);
+            let all: Vec<ObjectRef> = annots.iter().copied().chain(widgets).collect();
+            let dict = page.to_dict(parents[i], *contents_id, &all, i, resources, &inherited);
+            writer.write_object(*page_id, dict)?;
+            writer.write_compressed_stream(*contents_id, Dictionary::new(), page.contents.ops())?;
+            for (annotation, id) in page.annotations.iter().zip(annots) {
+                writer.write_object(*id, annotation.to_pdf(&pages, &refs)?)?;
+            }
+        }
 
-        write_pdf!("5 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>\nendobj\n");
+        if let Some(form_id) = form_id {
+            let signed = signature
+                .zip(signature_id)
+                .map(|((field, _), signature)| (field, signature));
+            form.write(&mut writer, form_id, &fields, &pages, &refs, signed)?;
+        }
+        let placeholder = match signature.zip(signature_id) {
+            Some(((_, signer), signature)) => {
+                Some(writer.write_signature(signature, signer.to_dict(), signer.reserved_size())?)
+            }
+            _ => None,
+        };
+        if let Some(info) = info {
+            writer.write_object(info, self.metadata.to_info())?;
+        }
+        // Standards ask for files to be identified.
+        let id = match self.conformance {
+            Some(_) => {
+                let mut id = [0; 16];
+                getrandom::fill(&mut id)?;
+                Some(id)
+            }
+            None => None,
+        };
+        if let Some(xmp) = xmp {
+            // With the standard the document keeps to, and the identifier
+            // as a UUID.
+            let packet = match (self.conformance, id) {
+                (Some(conformance), Some(id)) => {
+                    let hex: String = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+                    let uuid = format!(
+                        "uuid:{}-{}-{}-{}-{}",
+                        &hex[..8],
+                        &hex[8..12],
+                        &hex[12..16],
+                        &hex[16..20],
+                        &hex[20..]
+                    );
+                    let media = XmpNamespace::new("xmpMM", "http://ns.adobe.com/xap/1.0/mm/")
+                        .property("DocumentID", &uuid)
+                        .property("InstanceID", &uuid)
+                        .property("VersionID", "1")
+                        .property("RenditionClass", "default");
+                    self.metadata.to_standard_xmp(&[conformance.xmp(), media])
+                }
+                _ => self.metadata.to_xmp(),
+            };
+            let mut dict = Dictionary::new();
+            dict.set("Type", Object::name("Metadata"));
+            dict.set("Subtype", Object::name("XML"));
+            writer.write_stream(xmp, dict, packet.as_bytes())?;
+        }
+        if let Some(dests) = dests {
+            writer.write_object(dests, name_tree(&self.named_destinations, &pages)?)?;
+        }
+        if let Some(outline) = outline {
+            self.outline
+                .write(&mut writer, &mut alloc, outline, &pages)?;
+        }
+        if let Some(structure) = structure {
+            let tagged: Vec<(ObjectRef, &PageStructure)> = pages
+                .iter()
+                .zip(&self.pages)
+                .map(|(id, page)| (*id, &page.contents.structure))
+                .collect();
+            write_tree(&mut writer, &mut alloc, structure, &tagged)?;
+        }
 
-        let xref_pos = file.stream_position()?;
-        file.write_all(b"xref\n0 6\n0000000000 65535 f\n")?;
+        let trailer = Trailer {
+            root: catalog_id,
+            info,
+            id,
+            original_id: None,
+            prev: None,
+        };
+        writer.finish(alloc.size(), trailer)?;
+        Ok(placeholder)
+    }
 
-        for off in offsets {
-            file.write_all(format!("{:010} 00000 n\n", off).as_bytes())?;
+    /// Fails unless `object`, added as it is, refers only to objects added
+    /// so.
+    fn check_references(&self, object: &Object) -> Result<()> {
+        for reference in object.references() {
+            ensure!(
+                (1..=self.objects.len()).contains(&(reference.id() as usize)),
+                "objects added as they are can only refer to one another, not to object {}",
+                reference.id()
+            );
         }
+        Ok(())
+    }
 
-        file.write_all(
-            format!(
-                "trailer\n<< /Size 6 /Root 1 0 R >>\nstartxref\n{}\n%%EOF",
-                xref_pos
-            )
-            .as_bytes(),
-        )?;
+    /// Fails, telling what to change, unless the document, its content
+    /// drawn with `resources`, keeps to `conformance`.
+    fn check_conformance(
+        &self,
+        conformance: Conformance,
+        form: &InteractiveForm,
+        resources: &[&Resources],
+    ) -> Result<()> {
+        let standard = conformance.name();
+        ensure!(
+            self.version <= conformance.version(),
+            "{} documents are based on PDF {}, not {}: set that version",
+            standard,
+            conformance.version().number(),
+            self.version.number()
+        );
+        ensure!(
+            self.encryption.is_none(),
+            "{} documents cannot be encrypted: remove the encryption",
+            standard
+        );
+        let Some(intent) = &self.output_intent else {
+            bail!(
+                "{} documents need an output intent: set one with \
+                 PdfDocument::set_output_intent, such as of {}",
+                standard,
+                if conformance.is_for_print() {
+                    "a FOGRA39 profile"
+                } else {
+                    "an sRGB profile"
+                }
+            );
+        };
+        if conformance.is_for_print() {
+            ensure!(
+                intent.profile().is_printer(),
+                "the output intent of {} documents needs the ICC profile of a printer",
+                standard
+            );
+            self.check_print_production(standard, form)?;
+        } else {
+            ensure!(
+                intent.profile().is_output(),
+                "the output intent of {} documents needs the ICC profile of a monitor or a \
+                 printer",
+                standard
+            );
+        }
+        let output_space = intent.profile().alternate();
+        for resources in resources {
+            for used in resources.fonts() {
+                if let Font::Standard(font) = used.font {
+                    bail!(
+                        "{} documents need their fonts embedded, but text, possibly of a form \
+                         field or a signature, is set in the standard font {:?}: set it in a \
+                         TrueTypeFont",
+                        standard,
+                        font
+                    );
+                }
+            }
+            for space in resources.device_spaces() {
+                ensure!(
+                    space == "DeviceGray" || space == output_space,
+                    "{} documents can only paint in the device colors of their output \
+                     intent, {}, but something is painted in {}: convert it, or give its colors \
+                     in an ICC profile",
+                    standard,
+                    output_space,
+                    space
+                );
+            }
+            if conformance.allows_transparency() {
+                continue;
+            }
+            ensure!(
+                !resources
+                    .ext_g_states()
+                    .iter()
+                    .any(ExtGState::is_transparent),
+                "{} documents cannot have transparency, but something is drawn with an \
+                 opacity or a blend mode: use PDF/A-2 or later",
+                standard
+            );
+            ensure!(
+                resources.images().all(|image| image.soft_mask().is_none()),
+                "{} documents cannot have transparency, but an image has an alpha channel or \
+                 soft mask: flatten it onto its background, or use PDF/A-2 or later",
+                standard
+            );
+            ensure!(
+                resources.layers().is_empty(),
+                "{} documents cannot have layers: use PDF/A-2 or later",
+                standard
+            );
+        }
+        let annotations = self.pages.iter().flat_map(|page| &page.annotations);
+        for annotation in annotations {
+            ensure!(
+                !conformance.needs_appearances()
+                    || annotation.subtype() == "Link"
+                    || annotation.appearance_form().is_some(),
+                "{} documents need their annotations to have appearances of their own, but a \
+                 {} annotation has none: give it one with Annotation::appearance, or for \
+                 free text Annotation::generate_appearance",
+                standard,
+                annotation.subtype()
+            );
+            // Files are associated from the document only.
+            ensure!(
+                annotation.attached_file().is_none(),
+                "{} documents cannot have files attached to their pages{}",
+                standard,
+                if conformance.allows_attached_files() {
+                    ": attach them to the document with PdfDocument::attach_file"
+                } else {
+                    ""
+                }
+            );
+        }
+        // PDF/A-2 allows only files that are PDF/A themselves, which cannot
+        // be told here.
+        ensure!(
+            conformance.allows_attached_files() || self.attachments.is_empty(),
+            "{} documents cannot have attached files{}",
+            standard,
+            if conformance.is_for_print() {
+                ""
+            } else {
+                ": use PDF/A-3"
+            }
+        );
+        for file in self.attachments.values() {
+            ensure!(
+                file.has_relationship(),
+                "the file {:?} attached to a {} document needs to tell its relationship to \
+                 the document with EmbeddedFile::relationship",
+                file.name(),
+                standard
+            );
+        }
+        for action in form.actions() {
+            ensure!(
+                !matches!(
+                    action,
+                    ButtonAction::JavaScript(_) | ButtonAction::ResetForm
+                ),
+                "{} documents cannot run JavaScript or reset forms, as a push button does",
+                standard
+            );
+        }
+        Ok(())
+    }
+
+    /// Fails, telling what to change, unless the document has what print
+    /// production asks of the standard `standard`: the metadata printers
+    /// go by, the boxes of its pages, and nothing they leave out.
+    fn check_print_production(&self, standard: &str, form: &InteractiveForm) -> Result<()> {
+        let metadata = &self.metadata;
+        ensure!(
+            metadata.trapped.is_some(),
+            "{} documents need to tell whether they are trapped: set Metadata::trapped",
+            standard
+        );
+        ensure!(
+            metadata.title.is_some()
+                && metadata.creation_date.is_some()
+                && metadata.modification_date.is_some(),
+            "{} documents need a title, a creation date and a modification date: set them in \
+             the Metadata",
+            standard
+        );
+        for (i, page) in self.pages.iter().enumerate() {
+            let Some(trim_box) = page.trim_box() else {
+                bail!(
+                    "{} documents need the size their pages are cut to, but page {} has none: \
+                     set it with Page::set_trim_box",
+                    standard,
+                    i
+                );
+            };
+            let [left, bottom, right, top] = page.media_box();
+            let media_box = Rect::new(left, bottom, right - left, top - bottom);
+            let bleed_box = page.bleed_box().unwrap_or(media_box);
+            ensure!(
+                media_box.contains(bleed_box) && bleed_box.contains(trim_box),
+                "the boxes of page {} of a {} document must lie within one another: the trim \
+                 box within the bleed box, and that within the media box",
+                i,
+                standard
+            );
+        }
+        let mut annotations = self.pages.iter().flat_map(|page| &page.annotations);
+        ensure!(
+            form.is_empty() && annotations.all(|annotation| annotation.subtype() == "Link"),
+            "{} documents cannot have annotations but links, nor form fields, which printers \
+             leave out: draw them on the page, as PdfDocument::flatten_form does the form",
+            standard
+        );
         Ok(())
     }
 }
@@ -194,10 +1051,1257 @@ impl PdfDocument {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::color::Color;
+    use crate::embedded::FileRelationship;
+    use crate::encryption::EncryptionMethod;
+    use crate::font::StandardFont;
+    use crate::geometry::Matrix;
+    use crate::graphics::Paint;
+    use crate::icc::IccProfile;
+    use crate::icc::tests::profile_header;
+    use crate::image::tests::{j2k_header, jpeg_header, png_image};
+    use crate::layer::Layer;
+    use crate::metadata::PdfDate;
+    use crate::page::{ContentStream, PageSize};
+    use crate::pattern::TilingPattern;
+    use crate::signing::SignatureAppearance;
+    use crate::signing::tests::test_signer;
+    use crate::structure::StructureType;
+    use crate::truetype::test_font;
+    use crate::xobject::FormXObject;
+    use sha2::{Digest, Sha256};
 
     #[test]
     pub fn test_create_pdf() {
         let doc = PdfDocument::new("Hello Gaurav!. Great work");
-        doc.create().unwrap();
+        let pdf = doc.to_bytes().unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(pdf.ends_with(b"%%EOF"));
+    }
+
+    #[test]
+    pub fn test_page_sizes_per_page() {
+        let mut doc = PdfDocument::new("A4");
+        doc.add_page(Page::new(PageSize::Legal));
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/MediaBox [0 0 595 842]"));
+        assert!(pdf.contains("/MediaBox [0 0 612 1008]"));
+    }
+
+    #[test]
+    pub fn test_fonts_shared_between_pages() {
+        let mut doc = PdfDocument::default();
+        let mut first = Page::new(PageSize::A4);
+        first.draw_text(10.0, 10.0, 12.0, StandardFont::TimesRoman, "a");
+        first.draw_text(10.0, 30.0, 12.0, StandardFont::Symbol, "b");
+        let mut second = Page::new(PageSize::A4);
+        second.draw_text(10.0, 10.0, 12.0, StandardFont::Symbol, "c");
+        doc.add_page(first);
+        doc.add_page(second);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("3 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Times-Roman /Encoding /WinAnsiEncoding >>"));
+        assert!(pdf.contains("4 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Symbol >>"));
+        assert!(pdf.contains("/Resources << /Font << /F1 3 0 R /F2 4 0 R >> >>"));
+        assert!(pdf.contains("/Resources << /Font << /F1 4 0 R >> >>"));
+    }
+
+    #[test]
+    pub fn test_icc_profile_written_once() {
+        let profile = IccProfile::from_bytes(profile_header(b"CMYK")).unwrap();
+        let paint = Paint::fill(profile.color(&[0.0, 0.5, 1.0, 0.1]));
+        let mut doc = PdfDocument::default();
+        for _ in 0..2 {
+            let mut page = Page::new(PageSize::A4);
+            page.rect(10.0, 10.0, 50.0, 50.0, &paint);
+            doc.add_page(page);
+        }
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8_lossy(&doc.to_bytes().unwrap()).into_owned();
+        assert!(pdf.contains("3 0 obj\n<< /Length 128 /N 4 /Alternate /DeviceCMYK >>\nstream\n"));
+        assert_eq!(pdf.matches("/ICCBased").count(), 2);
+        assert!(pdf.contains("/Resources << /ColorSpace << /CS1 [/ICCBased 3 0 R] >> >>"));
+        assert!(pdf.contains("q\n/CS1 cs 0 0.5 1 0.1 scn\n10 10 50 50 re\nf\nQ\n"));
+    }
+
+    #[test]
+    pub fn test_tiling_pattern_resources() {
+        let pattern = TilingPattern::new(20.0, 20.0, |cell| {
+            cell.draw_text(0.0, 5.0, 10.0, StandardFont::Courier, "x");
+        });
+        let paint = Paint::fill(pattern.into());
+        let mut doc = PdfDocument::default();
+        for _ in 0..2 {
+            let mut page = Page::new(PageSize::A4);
+            page.rect(10.0, 10.0, 50.0, 50.0, &paint);
+            doc.add_page(page);
+        }
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("3 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Courier"));
+        assert!(pdf.contains("4 0 obj\n<< /Length 30 /PatternType 1 "));
+        assert!(pdf.contains("/Resources << /Font << /F1 3 0 R >> >> >>\nstream\n"));
+        assert_eq!(
+            pdf.matches("/Resources << /Pattern << /P1 4 0 R >> >>")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    pub fn test_form_placed_many_times() {
+        let letterhead = FormXObject::new(200.0, 40.0, |g| {
+            g.draw_text(0.0, 10.0, 18.0, StandardFont::HelveticaBold, "ACME");
+        });
+        let mut doc = PdfDocument::default();
+        for _ in 0..2 {
+            let mut page = Page::new(PageSize::A4);
+            page.draw_form(&letterhead, 50.0, 780.0);
+            page.draw_form_transformed(&letterhead, Matrix::scale(0.5, 0.5));
+            doc.add_page(page);
+        }
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert_eq!(pdf.matches("/Subtype /Form").count(), 1);
+        assert!(pdf.contains(
+            "4 0 obj\n<< /Length 34 /Type /XObject /Subtype /Form /BBox [0 0 200 40] \
+             /Resources << /Font << /F1 3 0 R >> >> >>\nstream\n"
+        ));
+        assert_eq!(
+            pdf.matches("/Resources << /XObject << /X1 4 0 R >> >>")
+                .count(),
+            2
+        );
+        assert_eq!(
+            pdf.matches("q\n1 0 0 1 50 780 cm\n/X1 Do\nQ\nq\n0.5 0 0 0.5 0 0 cm\n/X1 Do\nQ\n")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    pub fn test_layers() {
+        let dimensions = Layer::new("Dimensions");
+        let watermark = Layer::hidden("Watermark");
+        let mut page = Page::new(PageSize::A4);
+        page.in_layer(&watermark, |g| {
+            g.draw_text(10.0, 10.0, 40.0, StandardFont::Courier, "DRAFT")
+        });
+        page.in_layer(&dimensions, |g| {
+            g.line((0.0, 0.0), (10.0, 0.0), &Paint::default())
+        });
+        page.in_layer(&watermark, |_| {});
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains(
+            "<< /Type /Catalog /Pages 2 0 R /OCProperties << /OCGs [4 0 R 5 0 R] \
+             /D << /Name (Layers) /Order [4 0 R 5 0 R] /OFF [4 0 R] >> >> >>"
+        ));
+        assert!(pdf.contains("4 0 obj\n<< /Type /OCG /Name (Watermark) >>"));
+        assert!(pdf.contains("5 0 obj\n<< /Type /OCG /Name (Dimensions) >>"));
+        assert!(pdf.contains("/Properties << /OC1 4 0 R /OC2 5 0 R >>"));
+        assert!(pdf.contains("stream\n/OC /OC1 BDC\nBT\n"));
+        assert!(pdf.contains("ET\nEMC\n/OC /OC2 BDC\n0 0 m\n10 0 l\nS\nEMC\n/OC /OC1 BDC\nEMC\n"));
+    }
+
+    #[test]
+    pub fn test_jpeg_embedded_once() {
+        let mut doc = PdfDocument::default();
+        let jpeg = jpeg_header(64, 32, 1);
+        let image = doc.add_jpeg(jpeg.clone()).unwrap();
+        let mut page = Page::new(PageSize::A4);
+        page.draw_image(&image, Rect::new(10.0, 20.0, 128.0, 64.0));
+        page.draw_image(&image, Rect::new(200.0, 20.0, 64.0, 32.0));
+        doc.add_page(page);
+
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+        let header = format!(
+            "3 0 obj\n<< /Length {} /Type /XObject /Subtype /Image /Width 64 /Height 32 \
+             /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /DCTDecode >>\nstream\n",
+            jpeg.len()
+        );
+        let start = pdf
+            .windows(header.len())
+            .position(|window| window == header.as_bytes())
+            .unwrap();
+        assert!(pdf[start + header.len()..].starts_with(&jpeg));
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/Resources << /XObject << /X1 3 0 R >> >>"));
+        assert!(
+            pdf.contains("q\n128 0 0 64 10 20 cm\n/X1 Do\nQ\nq\n64 0 0 32 200 20 cm\n/X1 Do\nQ\n")
+        );
+    }
+
+    #[test]
+    pub fn test_png_soft_mask() {
+        let mut doc = PdfDocument::default();
+        let png = png_image(2, 1, png::ColorType::Rgba, &[255, 0, 0, 255, 0, 0, 255, 0]);
+        let image = doc.add_png(&png).unwrap();
+        let mut page = Page::new(PageSize::A4);
+        page.draw_image(&image, Rect::new(0.0, 0.0, 20.0, 10.0));
+        doc.add_page(page);
+
+        let pdf = String::from_utf8_lossy(&doc.to_bytes().unwrap()).into_owned();
+        assert!(pdf.contains(
+            "/Subtype /Image /Width 2 /Height 1 /ColorSpace /DeviceRGB /BitsPerComponent 8 \
+             /SMask 4 0 R /Filter /FlateDecode >>"
+        ));
+        assert!(pdf.contains(
+            "4 0 obj\n<< /Length 10 /Type /XObject /Subtype /Image /Width 2 /Height 1 \
+             /ColorSpace /DeviceGray"
+        ));
+        assert!(pdf.contains("/XObject << /X1 3 0 R >>"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    pub fn test_downsampling() {
+        use ::image::{DynamicImage, Rgb, RgbImage};
+
+        let photo = RgbImage::from_fn(400, 300, |x, y| Rgb([x as u8, y as u8, (x ^ y) as u8]));
+        let mut doc = PdfDocument::default();
+        let image = doc.add_image(&DynamicImage::ImageRgb8(photo)).unwrap();
+        let logo = png_image(2, 1, png::ColorType::Rgba, &[255, 0, 0, 255, 0, 0, 255, 0]);
+        let logo = doc.add_png(&logo).unwrap();
+        let mut page = Page::new(PageSize::A4);
+        // 400 pixels over two inches, then one.
+        page.draw_image(&image, Rect::new(0.0, 0.0, 144.0, 108.0));
+        page.draw_image(&image, Rect::new(0.0, 200.0, 72.0, 54.0));
+        page.draw_image(&logo, Rect::new(0.0, 400.0, 10.0, 5.0));
+        doc.add_page(page);
+        doc.set_downsampling(Some(Downsampling {
+            dpi: 100.0,
+            jpeg_quality: 50,
+        }));
+
+        let pdf = String::from_utf8_lossy(&doc.to_bytes().unwrap()).into_owned();
+        assert!(pdf.contains(
+            "/Subtype /Image /Width 200 /Height 150 /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /DCTDecode >>"
+        ));
+        // The logo has fewer pixels than needed, its soft mask is kept.
+        assert!(pdf.contains("/Width 2 /Height 1 /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask 5 0 R /Filter /FlateDecode >>"));
+        assert!(pdf.contains("5 0 obj\n<< /Length 10 /Type /XObject /Subtype /Image /Width 2"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    pub fn test_downsampling_nearly_opaque() {
+        use ::image::{DynamicImage, Rgba, RgbaImage};
+
+        // Resampling blends the one pixel not quite opaque away.
+        let pixels = RgbaImage::from_fn(400, 400, |x, y| match (x, y) {
+            (0, 0) => Rgba([0, 0, 0, 254]),
+            _ => Rgba([255, 0, 0, 255]),
+        });
+        let mut doc = PdfDocument::default();
+        let image = doc.add_image(&DynamicImage::ImageRgba8(pixels)).unwrap();
+        let mut page = Page::new(PageSize::A4);
+        page.draw_image(&image, Rect::new(0.0, 0.0, 72.0, 72.0));
+        doc.add_page(page);
+        doc.set_downsampling(Some(Downsampling::default()));
+
+        let pdf = String::from_utf8_lossy(&doc.to_bytes().unwrap()).into_owned();
+        assert!(pdf.contains(
+            "/Width 150 /Height 150 /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask 4 0 R"
+        ));
+        assert!(pdf.contains(
+            "4 0 obj\n<< /Length 44 /Type /XObject /Subtype /Image /Width 150 /Height 150 \
+             /ColorSpace /DeviceGray"
+        ));
+    }
+
+    #[test]
+    pub fn test_compressed_content() {
+        use std::io::Read;
+
+        let pdf = PdfDocument::new("Squeeze me").to_bytes().unwrap();
+        let find = |needle: &[u8]| pdf.windows(needle.len()).position(|w| w == needle).unwrap();
+        let header = b"5 0 obj\n<< /Length 54 /Filter /FlateDecode >>\nstream\n";
+        let start = find(header) + header.len();
+        assert_eq!(&pdf[start + 54..start + 64], b"\nendstream");
+
+        let mut ops = String::new();
+        flate2::read::ZlibDecoder::new(&pdf[start..start + 54])
+            .read_to_string(&mut ops)
+            .unwrap();
+        assert!(ops.ends_with("/F1 24 Tf\n100 700 Td\n(Squeeze me) Tj\nET\n"));
+    }
+
+    #[test]
+    pub fn test_object_streams() {
+        let mut doc = PdfDocument::new("Packed");
+        doc.add_page("Tightly");
+        doc.set_compression(false);
+        doc.set_version(PdfVersion::Pdf15);
+        doc.set_object_streams(true);
+        let bytes = doc.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.5\n"));
+
+        // Only the content streams are left outside the object stream.
+        let pdf = String::from_utf8_lossy(&bytes);
+        assert_eq!(pdf.matches(" obj\n").count(), 4);
+        assert!(pdf.contains(
+            "8 0 obj\n<< /Length 412 /Type /ObjStm /N 5 /First 26 >>\nstream\n\
+             1 0 2 34 3 82 4 164 6 275 << /Type /Catalog /Pages 2 0 R >>\n"
+        ));
+        assert!(
+            pdf.contains("9 0 obj\n<< /Length 50 /Type /XRef /Size 10 /W [1 2 2] /Root 1 0 R >>")
+        );
+
+        let parsed = ::lopdf::Document::load_mem(&bytes).unwrap();
+        assert_eq!(parsed.get_pages()[&2], (6, 0));
+        assert_eq!(parsed.extract_text(&[2]).unwrap().trim(), "Tightly");
+    }
+
+    #[test]
+    pub fn test_xref_stream() {
+        let mut doc = PdfDocument::new("Indexed");
+        doc.set_version(PdfVersion::Pdf15);
+        doc.set_xref_stream(true);
+        let bytes = doc.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.5\n"));
+
+        let pdf = String::from_utf8_lossy(&bytes);
+        assert!(!pdf.contains("\nxref\n"));
+        assert!(!pdf.contains("trailer"));
+        let tail = pdf.rsplit("startxref\n").next().unwrap();
+        let xref_pos: usize = tail.lines().next().unwrap().parse().unwrap();
+        let xref = String::from_utf8_lossy(&bytes[xref_pos..]);
+        assert!(xref.starts_with("6 0 obj\n<< /Length "));
+        assert!(
+            xref.contains(" /Filter /FlateDecode /Type /XRef /Size 7 /W [1 2 2] /Root 1 0 R >>")
+        );
+
+        let parsed = ::lopdf::Document::load_mem(&bytes).unwrap();
+        assert_eq!(parsed.extract_text(&[1]).unwrap().trim(), "Indexed");
+    }
+
+    #[test]
+    pub fn test_linearized() {
+        let mut doc = PdfDocument::new("One");
+        doc.add_page("Two");
+        doc.add_page("Three");
+        doc.set_linearized(true);
+        let bytes = doc.to_bytes().unwrap();
+        let pdf = String::from_utf8_lossy(&bytes);
+        // The catalog and the objects of the first page, its font included,
+        // numbered after those of the other pages and the page tree.
+        assert!(pdf.starts_with(&format!(
+            "%PDF-1.4\n6 0 obj\n<< /Linearized 1 /L {} /H [",
+            bytes.len()
+        )));
+        assert!(pdf.contains(" /O 9 /E "));
+        assert!(pdf.contains(" /N 3 /T "));
+        assert!(pdf.contains("endobj\nxref\n6 6\n0000000009 00000 n \n"));
+        assert!(pdf.contains("trailer\n<< /Size 12 /Root 7 0 R /Prev "));
+        assert!(pdf.contains("7 0 obj\n<< /Type /Catalog /Pages 5 0 R >>\nendobj\n8 0 obj\n"));
+        assert!(pdf.contains("9 0 obj\n<< /Type /Page /Parent 5 0 R "));
+        assert!(pdf.contains("/Resources << /Font << /F1 10 0 R >> >> >>\nendobj\n10 0 obj\n"));
+        assert!(pdf.contains("xref\n0 6\n0000000000 65535 f \n"));
+        assert!(pdf.ends_with(&format!(
+            "trailer\n<< /Size 6 >>\nstartxref\n{}\n%%EOF",
+            pdf.find("xref").unwrap()
+        )));
+
+        let parsed = ::lopdf::Document::load_mem(&bytes).unwrap();
+        assert_eq!(parsed.get_pages().len(), 3);
+        assert_eq!(parsed.extract_text(&[3]).unwrap().trim(), "Three");
+
+        doc.set_version(PdfVersion::Pdf15);
+        doc.set_object_streams(true);
+        let mut buf = Cursor::new(Vec::new());
+        let err = doc.write_to(&mut buf).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "linearized documents are written with cross-reference tables, \
+             without object streams"
+        );
+    }
+
+    #[test]
+    pub fn test_added_objects() {
+        let mut doc = PdfDocument::new("Custom");
+        doc.set_compression(false);
+        let parent = doc.reserve_object();
+        let mut child = Dictionary::new();
+        child.set("Parent", parent);
+        let child = doc.add_object(child);
+        let mut dict = Dictionary::new();
+        dict.set("Kids", vec![child.into()]);
+        doc.set_object(parent, dict).unwrap();
+        doc.set_catalog_entry("PieceInfo", parent);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("1 0 obj\n<< /Kids [2 0 R] >>\nendobj\n"));
+        assert!(pdf.contains("2 0 obj\n<< /Parent 1 0 R >>\nendobj\n"));
+        assert!(pdf.contains("3 0 obj\n<< /Type /Catalog /Pages 4 0 R /PieceInfo 1 0 R >>"));
+        let loaded = lopdf::Document::load_mem(pdf.as_bytes()).unwrap();
+        assert_eq!(loaded.get_pages().len(), 1);
+
+        let error = doc.set_object(parent, Object::Null).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "object 1 was not reserved, or is set already"
+        );
+        doc.set_catalog_entry("Pages", child);
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the catalog entry /Pages is written by the document itself"
+        );
+
+        let mut doc = PdfDocument::new("Custom");
+        doc.reserve_object();
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(error.to_string(), "object 1 was reserved but never set");
+        let mut doc = PdfDocument::new("Custom");
+        doc.add_object(vec![ObjectRef::new(7).into()]);
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "objects added as they are can only refer to one another, not to object 7"
+        );
+    }
+
+    #[test]
+    pub fn test_versions() {
+        let mut doc = PdfDocument::new("Versioned");
+        doc.set_version(PdfVersion::Pdf20);
+        assert!(doc.to_bytes().unwrap().starts_with(b"%PDF-2.0\n"));
+        assert_eq!(PdfVersion::Pdf17.number(), "1.7");
+
+        doc.set_version(PdfVersion::Pdf14);
+        doc.set_object_streams(true);
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "object streams need PDF 1.5 or later, the document is PDF 1.4"
+        );
+
+        let mut doc = PdfDocument::default();
+        let image = doc.add_jpx(j2k_header(4, 4, 1)).unwrap();
+        let mut page = Page::new(PageSize::A4);
+        page.draw_image(&image, Rect::new(0.0, 0.0, 4.0, 4.0));
+        doc.add_page(page);
+        assert!(doc.write_to(Cursor::new(Vec::new())).is_err());
+        doc.set_version(PdfVersion::Pdf15);
+        assert!(doc.write_to(Cursor::new(Vec::new())).is_ok());
+    }
+
+    #[test]
+    pub fn test_info_in_trailer() {
+        let mut doc = PdfDocument::new("Described");
+        doc.set_metadata(Metadata {
+            title: Some("Described".into()),
+            ..Default::default()
+        });
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("6 0 obj\n<< /Title (Described) >>\nendobj\n"));
+        assert!(pdf.contains("trailer\n<< /Size 7 /Root 1 0 R /Info 6 0 R >>"));
+
+        doc.set_version(PdfVersion::Pdf15);
+        doc.set_object_streams(true);
+        let parsed = ::lopdf::Document::load_mem(&doc.to_bytes().unwrap()).unwrap();
+        let info = parsed.trailer.get(b"Info").unwrap().as_reference().unwrap();
+        let title = parsed.get_dictionary(info).unwrap().get(b"Title").unwrap();
+        assert_eq!(title.as_str().unwrap(), b"Described");
+    }
+
+    #[test]
+    pub fn test_encryption() {
+        let methods = [
+            EncryptionMethod::Rc4_40,
+            EncryptionMethod::Rc4_128,
+            EncryptionMethod::Aes128,
+            EncryptionMethod::Aes256,
+        ];
+        for method in methods {
+            for object_streams in [false, true] {
+                let mut doc = PdfDocument::new("Salaries (2024)");
+                doc.set_metadata(Metadata {
+                    title: Some("Confidential".into()),
+                    ..Default::default()
+                });
+                doc.set_version(match method {
+                    EncryptionMethod::Aes256 => PdfVersion::Pdf20,
+                    _ => PdfVersion::Pdf16,
+                });
+                doc.set_object_streams(object_streams);
+                doc.set_encryption(Some(Encryption::new("", "owner", method)));
+                let bytes = doc.to_bytes().unwrap();
+                assert!(!bytes.windows(12).any(|w| w == b"Confidential"));
+
+                // An empty user password opens the document for anyone.
+                let parsed = ::lopdf::Document::load_mem(&bytes).unwrap();
+                assert_eq!(parsed.extract_text(&[1]).unwrap().trim(), "Salaries (2024)");
+                let info = parsed.trailer.get(b"Info").unwrap().as_reference().unwrap();
+                let title = parsed.get_dictionary(info).unwrap().get(b"Title").unwrap();
+                assert_eq!(title.as_str().unwrap(), b"Confidential");
+            }
+        }
+
+        let mut doc = PdfDocument::new("Salaries");
+        doc.set_encryption(Some(Encryption::new(
+            "user",
+            "owner",
+            EncryptionMethod::Rc4_128,
+        )));
+        let parsed = ::lopdf::Document::load_mem(&doc.to_bytes().unwrap()).unwrap();
+        assert!(parsed.authenticate_raw_user_password("user").is_ok());
+        assert!(parsed.authenticate_raw_owner_password("owner").is_ok());
+        assert!(parsed.authenticate_raw_user_password("guess").is_err());
+
+        doc.set_version(PdfVersion::Pdf20);
+        doc.set_encryption(Some(Encryption::new(
+            "user",
+            "owner",
+            EncryptionMethod::Aes256,
+        )));
+        let parsed = ::lopdf::Document::load_mem(&doc.to_bytes().unwrap()).unwrap();
+        assert!(parsed.authenticate_raw_user_password("user").is_ok());
+        assert!(parsed.authenticate_raw_owner_password("owner").is_ok());
+        assert!(parsed.authenticate_raw_owner_password("guess").is_err());
+
+        doc.set_version(PdfVersion::Pdf16);
+        assert!(doc.write_to(Cursor::new(Vec::new())).is_err());
+        doc.set_encryption(Some(Encryption::new(
+            "user",
+            "owner",
+            EncryptionMethod::Aes128,
+        )));
+        doc.set_version(PdfVersion::Pdf14);
+        assert!(doc.write_to(Cursor::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    pub fn test_signing() {
+        let mut doc = PdfDocument::new("Contract");
+        doc.form().signature_field("Signature1", Rect::default(), 0);
+        doc.sign("Signature1", test_signer().reason("Approved"));
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/FT /Sig /T (Signature1) /AP << /N 4 0 R >> /V 9 0 R >>"));
+        assert!(text.contains("<< /Fields [7 0 R] /SigFlags 3 >>"));
+        assert!(text.contains(
+            "9 0 obj\n<< /Type /Sig /Filter /Adobe.PPKLite /SubFilter /adbe.pkcs7.detached /M (D:"
+        ));
+
+        // The byte range is all of the file but the signature, whose
+        // digest the signature holds.
+        let start = text.find("/ByteRange [").unwrap() + 12;
+        let end = start + text[start..].find(']').unwrap();
+        let range: Vec<usize> = text[start..end]
+            .split(' ')
+            .map(|number| number.parse().unwrap())
+            .collect();
+        assert_eq!(range[0], 0);
+        assert_eq!(range[2] + range[3], pdf.len());
+        assert_eq!(&pdf[range[1]..range[1] + 5], b"<3082");
+        assert_eq!(pdf[range[2] - 1], b'>');
+        let digest = Sha256::new()
+            .chain_update(&pdf[..range[1]])
+            .chain_update(&pdf[range[2]..])
+            .finalize();
+        let hex = crate::string::hex_string(&digest);
+        assert!(text[range[1]..range[2]].contains(&hex[1..hex.len() - 1]));
+
+        // The contents of the signature are not encrypted, nor packed.
+        doc.set_version(PdfVersion::Pdf16);
+        doc.set_object_streams(true);
+        doc.set_encryption(Some(Encryption::new("", "", EncryptionMethod::Aes128)));
+        let pdf = doc.to_bytes().unwrap();
+        assert!(String::from_utf8_lossy(&pdf).contains("/Contents <3082"));
+        let parsed = ::lopdf::Document::load_mem(&pdf).unwrap();
+        assert_eq!(parsed.extract_text(&[1]).unwrap().trim(), "Contract");
+
+        doc.sign("Signature2", test_signer());
+        assert!(doc.write_to(Cursor::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    pub fn test_visible_signature() {
+        let mut doc = PdfDocument::new("Contract");
+        doc.set_compression(false);
+        let logo = png_image(2, 1, png::ColorType::Rgb, &[255, 0, 0, 0, 0, 255]);
+        let logo = doc.add_png(&logo).unwrap();
+        let rect = Rect::new(300.0, 100.0, 250.0, 60.0);
+        doc.form().signature_field("Signature1", rect, 0);
+        let appearance = SignatureAppearance::new().logo(logo);
+        doc.sign("Signature1", test_signer().appearance(appearance));
+        let text = String::from_utf8_lossy(&doc.to_bytes().unwrap()).into_owned();
+
+        // The widget shows the signature, its image and text as resources.
+        assert!(text.contains(
+            "/Rect [300 100 550 160] /P 6 0 R /F 4 /FT /Sig /T (Signature1) /AP << /N 4 0 R >> \
+             /V 10 0 R >>"
+        ));
+        assert!(text.contains(
+            "/Type /XObject /Subtype /Form /BBox [0 0 250 60] \
+             /Resources << /Font << /F1 3 0 R >> /XObject << /X1 5 0 R >> >> >>"
+        ));
+        // The logo keeps its aspect ratio, centered in the left 40%.
+        assert!(text.contains("q\n96 0 0 48 2 6 cm\n/X1 Do\nQ\n"));
+        assert!(text.contains("(Digitally signed by pdf-creator test signer) Tj"));
+        // The form itself keeps the look of the empty field.
+        assert_eq!(
+            doc.form
+                .appearances()
+                .next()
+                .unwrap()
+                .resources()
+                .images()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    pub fn test_pdf_a1b() {
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(72.0, 720.0, 14.0, test_font(), "Minutes");
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.set_compression(false);
+        doc.set_conformance(Some(Conformance::PdfA1b));
+        let error = |doc: &PdfDocument| {
+            let result = doc.write_to(Cursor::new(Vec::new()));
+            result.unwrap_err().to_string()
+        };
+        assert!(error(&doc).starts_with("PDF/A-1b documents need an output intent"));
+
+        let profile = IccProfile::from_bytes(profile_header(b"RGB ")).unwrap();
+        doc.set_output_intent(Some(OutputIntent::new("sRGB IEC61966-2.1", profile)));
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(
+            "/OutputIntents [<< /Type /OutputIntent /S /GTS_PDFA1 \
+             /OutputConditionIdentifier (sRGB IEC61966-2.1) /DestOutputProfile 4 0 R >>]"
+        ));
+        assert!(
+            text.contains("<pdfaid:part>1</pdfaid:part><pdfaid:conformance>B</pdfaid:conformance>")
+        );
+        assert!(text.contains("/FontFile2 10 0 R /CIDSet 12 0 R >>"));
+        assert!(text.contains("/ID [<"));
+        ::lopdf::Document::load_mem(&pdf).unwrap();
+
+        // Standard fonts are not embedded.
+        doc.add_page("Helvetica");
+        assert!(error(&doc).contains("is set in the standard font Helvetica: set it in a"));
+        doc.pages.pop();
+        let mut page = Page::new(PageSize::A4);
+        page.with_opacity(0.5, |g| {
+            g.rect(0.0, 0.0, 10.0, 10.0, &Paint::fill(Color::BLACK))
+        });
+        doc.add_page(page);
+        assert!(error(&doc).contains("cannot have transparency"));
+        doc.pages.pop();
+        doc.set_encryption(Some(Encryption::new("", "", EncryptionMethod::Rc4_128)));
+        assert!(error(&doc).contains("cannot be encrypted"));
+        doc.set_encryption(None);
+        doc.set_version(PdfVersion::Pdf17);
+        assert_eq!(
+            error(&doc),
+            "PDF/A-1b documents are based on PDF 1.4, not 1.7: set that version"
+        );
+    }
+
+    #[test]
+    pub fn test_pdf_a3_invoice() {
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(72.0, 720.0, 14.0, test_font(), "Invoice 2024-001");
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.set_compression(false);
+        doc.set_version(PdfVersion::Pdf17);
+        doc.set_conformance(Some(Conformance::PdfA3b));
+        let profile = IccProfile::from_bytes(profile_header(b"RGB ")).unwrap();
+        doc.set_output_intent(Some(OutputIntent::new("sRGB IEC61966-2.1", profile)));
+        doc.set_metadata(Metadata {
+            xmp_namespaces: vec![
+                XmpNamespace::new("fx", "urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#")
+                    .property("DocumentType", "INVOICE")
+                    .property("DocumentFileName", "factur-x.xml"),
+            ],
+            ..Default::default()
+        });
+        let xml = EmbeddedFile::new("factur-x.xml", "text/xml", b"<rsm:Invoice/>".to_vec())
+            .modified(PdfDate::new(2024, 7, 1).unwrap());
+        doc.attach_file(xml.clone());
+        let error = |doc: &PdfDocument| {
+            let result = doc.write_to(Cursor::new(Vec::new()));
+            result.unwrap_err().to_string()
+        };
+        assert_eq!(
+            error(&doc),
+            "the file \"factur-x.xml\" attached to a PDF/A-3b document needs to tell its \
+             relationship to the document with EmbeddedFile::relationship"
+        );
+
+        doc.attach_file(xml.relationship(FileRelationship::Alternative));
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(
+            "/Names << /EmbeddedFiles << /Names [(factur-x.xml) 6 0 R] >> >> \
+             /OutputIntents [<< /Type /OutputIntent /S /GTS_PDFA1"
+        ));
+        assert!(text.contains("/DestOutputProfile 4 0 R >>] /AF [6 0 R] >>"));
+        assert!(text.contains(
+            "6 0 obj\n<< /Type /Filespec /F (factur-x.xml) /UF (factur-x.xml) \
+             /AFRelationship /Alternative /EF << /F 5 0 R /UF 5 0 R >> >>"
+        ));
+        assert!(text.contains(" /Type /EmbeddedFile /Subtype /text#2Fxml /Params"));
+        assert!(
+            text.contains("<pdfaid:part>3</pdfaid:part><pdfaid:conformance>B</pdfaid:conformance>")
+        );
+        assert!(text.contains("<pdfaSchema:prefix>fx</pdfaSchema:prefix>"));
+        // Only PDF/A-1 asks for a CIDSet.
+        assert!(!text.contains("/CIDSet"));
+        ::lopdf::Document::load_mem(&pdf).unwrap();
+
+        // PDF/A-2 allows transparency, but not attached files.
+        let mut page = Page::new(PageSize::A4);
+        page.with_opacity(0.5, |g| {
+            g.rect(0.0, 0.0, 10.0, 10.0, &Paint::fill(Color::BLACK))
+        });
+        doc.add_page(page);
+        doc.set_conformance(Some(Conformance::PdfA2u));
+        assert_eq!(
+            error(&doc),
+            "PDF/A-2u documents cannot have attached files: use PDF/A-3"
+        );
+        doc.attachments.clear();
+        doc.to_bytes().unwrap();
+        doc.pages[1].add_annotation(Annotation::note(Rect::new(10.0, 10.0, 20.0, 20.0), "Paid"));
+        assert!(error(&doc).starts_with(
+            "PDF/A-2u documents need their annotations to have appearances of their own, \
+             but a Text annotation has none"
+        ));
+    }
+
+    #[test]
+    pub fn test_pdf_x4() {
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(72.0, 720.0, 14.0, test_font(), "Flyer");
+        page.rect(
+            0.0,
+            0.0,
+            595.0,
+            100.0,
+            &Paint::fill(Color::Cmyk(1.0, 0.0, 0.0, 0.0)),
+        );
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.set_compression(false);
+        doc.set_version(PdfVersion::Pdf16);
+        doc.set_conformance(Some(Conformance::PdfX4));
+        let mut header = profile_header(b"CMYK");
+        header[12..16].copy_from_slice(b"prtr");
+        let profile = IccProfile::from_bytes(header).unwrap();
+        doc.set_output_intent(Some(OutputIntent::new("FOGRA39", profile)));
+        let error = |doc: &PdfDocument| {
+            let result = doc.write_to(Cursor::new(Vec::new()));
+            result.unwrap_err().to_string()
+        };
+        assert_eq!(
+            error(&doc),
+            "PDF/X-4 documents need to tell whether they are trapped: set Metadata::trapped"
+        );
+        doc.set_metadata(Metadata {
+            title: Some("Flyer".into()),
+            creation_date: Some(PdfDate::new(2024, 9, 1).unwrap()),
+            modification_date: Some(PdfDate::new(2024, 9, 2).unwrap()),
+            trapped: Some(false),
+            ..Default::default()
+        });
+        assert!(error(&doc).starts_with("PDF/X-4 documents need the size their pages are cut to"));
+        doc.pages[0].set_bleed_box(Some(Rect::new(0.0, 0.0, 595.0, 842.0)));
+        doc.pages[0].set_trim_box(Some(Rect::new(8.5, 8.5, 578.0, 825.0)));
+
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(
+            "/OutputIntents [<< /Type /OutputIntent /S /GTS_PDFX /OutputConditionIdentifier \
+             (FOGRA39) /DestOutputProfile 4 0 R >>]"
+        ));
+        assert!(text.contains("/BleedBox [0 0 595 842] /TrimBox [8.5 8.5 586.5 833.5]"));
+        assert!(text.contains("/Trapped /False >>"));
+        assert!(text.contains("<pdf:Trapped>False</pdf:Trapped>"));
+        assert!(text.contains("<pdfxid:GTS_PDFXVersion>PDF/X-4</pdfxid:GTS_PDFXVersion>"));
+        assert!(text.contains("<xmpMM:DocumentID>uuid:"));
+        ::lopdf::Document::load_mem(&pdf).unwrap();
+
+        // Device colors are of the output intent, and nothing is left out.
+        doc.pages[0].rect(
+            0.0,
+            0.0,
+            10.0,
+            10.0,
+            &Paint::fill(Color::Rgb(1.0, 0.0, 0.0)),
+        );
+        assert!(error(&doc).starts_with(
+            "PDF/X-4 documents can only paint in the device colors of their output intent, \
+             DeviceCMYK, but something is painted in DeviceRGB"
+        ));
+        doc.pages[0] = Page::new(PageSize::A4);
+        doc.pages[0].set_trim_box(Some(Rect::new(8.5, 8.5, 578.0, 825.0)));
+        doc.pages[0].link(Rect::new(0.0, 0.0, 10.0, 10.0), "https://example.com");
+        doc.to_bytes().unwrap();
+        doc.form()
+            .text_field("name", Rect::new(100.0, 600.0, 250.0, 20.0), 0);
+        assert!(error(&doc).contains("cannot have annotations but links, nor form fields"));
+        doc.form = InteractiveForm::default();
+        doc.pages[0].set_bleed_box(Some(Rect::new(10.0, 10.0, 500.0, 500.0)));
+        assert!(error(&doc).starts_with("the boxes of page 0 of a PDF/X-4 document must lie"));
+    }
+
+    #[test]
+    pub fn test_tagged_document() {
+        let mut page = Page::new(PageSize::A4);
+        page.artifact(|g| g.draw_text(72.0, 800.0, 9.0, StandardFont::Helvetica, "Draft"));
+        page.tagged(StructureType::H1, |g| {
+            g.draw_text(72.0, 760.0, 24.0, StandardFont::HelveticaBold, "Minutes")
+        });
+        page.tagged(StructureType::P, |g| {
+            g.draw_text(72.0, 730.0, 12.0, StandardFont::Helvetica, "Present: ");
+            g.tagged(StructureType::Span, |g| {
+                g.draw_text(130.0, 730.0, 12.0, StandardFont::Helvetica, "all")
+            });
+        });
+        let mut doc = PdfDocument::default();
+        doc.set_metadata(Metadata {
+            title: Some("Minutes".into()),
+            language: Some("en-US".into()),
+            ..Default::default()
+        });
+        doc.add_page("Cover");
+        doc.add_page(page);
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(
+            "<< /Type /Catalog /Pages 2 0 R /MarkInfo << /Marked true >> /StructTreeRoot 10 0 R \
+             /Lang (en-US) /ViewerPreferences << /DisplayDocTitle true >> >>"
+        ));
+        assert!(text.contains("/MediaBox [0 0 595 842] /StructParents 1 /Contents 8 0 R"));
+        assert!(text.contains(
+            "10 0 obj\n<< /Type /StructTreeRoot /K 11 0 R /ParentTree 12 0 R \
+             /ParentTreeNextKey 2 >>"
+        ));
+        assert!(text.contains("<< /Type /StructElem /S /Document /P 10 0 R /K [13 0 R 14 0 R] >>"));
+        assert!(text.contains("<< /Type /StructElem /S /P /P 11 0 R /Pg 7 0 R /K [1 15 0 R] >>"));
+        assert!(text.contains("<< /Type /StructElem /S /Span /P 14 0 R /Pg 7 0 R /K [2] >>"));
+        assert!(text.contains("12 0 obj\n<< /Nums [1 [13 0 R 14 0 R 15 0 R]] >>"));
+        ::lopdf::Document::load_mem(&pdf).unwrap();
+    }
+
+    #[test]
+    pub fn test_misplaced_tags() {
+        let heading = |g: &mut ContentStream| {
+            g.tagged(StructureType::H1, |g| {
+                g.draw_text(0.0, 0.0, 24.0, StandardFont::Helvetica, "Minutes")
+            })
+        };
+        let mut page = Page::new(PageSize::A4);
+        page.artifact(heading);
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(error.to_string(), "artifacts cannot hold tagged content");
+
+        let mut page = Page::new(PageSize::A4);
+        page.draw_form(&FormXObject::new(200.0, 30.0, heading), 72.0, 760.0);
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "forms cannot be tagged, only the content they are drawn in"
+        );
+
+        let pattern = TilingPattern::new(20.0, 20.0, heading);
+        let mut page = Page::new(PageSize::A4);
+        page.rect(0.0, 0.0, 100.0, 100.0, &Paint::fill(pattern.into()));
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "tiling patterns cannot be tagged, only the content they are painted in"
+        );
+    }
+
+    #[test]
+    pub fn test_xmp_metadata() {
+        let mut doc = PdfDocument::new("Archived");
+        doc.set_metadata(Metadata {
+            author: Some("Records office".into()),
+            ..Default::default()
+        });
+        doc.set_xmp(true);
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("<< /Type /Catalog /Pages 2 0 R /Metadata 7 0 R >>"));
+        assert!(text.contains("6 0 obj\n<< /Author (Records office) >>"));
+        assert!(text.contains("7 0 obj\n<< /Length "));
+        assert!(text.contains(" /Type /Metadata /Subtype /XML >>\nstream\n<?xpacket begin="));
+        assert!(text.contains("<rdf:li>Records office</rdf:li>"));
+    }
+
+    #[test]
+    pub fn test_outline() {
+        let mut doc = PdfDocument::new("One");
+        doc.add_page("Two");
+        let chapter = doc.outline().add("Chapter 1", Destination::page(0));
+        chapter.child("1.1", Destination::page(0));
+        chapter.child("1.2", Destination::page(1));
+        chapter.set_open(true);
+        doc.outline().add("Chapter 2", Destination::page(1));
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/Outlines 8 0 R /PageMode /UseOutlines >>"));
+        assert!(pdf.contains("8 0 obj\n<< /Type /Outlines /First 9 0 R /Last 10 0 R /Count 4 >>"));
+        assert!(pdf.contains(
+            "9 0 obj\n<< /Title (Chapter 1) /Parent 8 0 R /Next 10 0 R \
+             /First 11 0 R /Last 12 0 R /Count 2 /Dest [4 0 R /Fit] >>"
+        ));
+        assert!(pdf.contains(
+            "12 0 obj\n<< /Title (1.2) /Parent 9 0 R /Prev 11 0 R /Dest [6 0 R /Fit] >>"
+        ));
+        assert!(pdf.contains(
+            "10 0 obj\n<< /Title (Chapter 2) /Parent 8 0 R /Prev 9 0 R /Dest [6 0 R /Fit] >>"
+        ));
+
+        doc.outline().add("Nowhere", Destination::page(2));
+        let error = doc.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "destination on page 3 of a document of 2 pages"
+        );
+    }
+
+    #[test]
+    pub fn test_named_destinations() {
+        let mut doc = PdfDocument::new("Contents");
+        doc.add_page("Terms");
+        doc.add_named_destination("terms", Destination::page(1));
+        doc.outline().add("Terms", Destination::named("terms"));
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/Names << /Dests 8 0 R >> /Outlines 9 0 R"));
+        assert!(pdf.contains("8 0 obj\n<< /Names [(terms) [6 0 R /Fit]] >>"));
+        assert!(pdf.contains("/Title (Terms) /Parent 9 0 R /Dest (terms) >>"));
+    }
+
+    #[test]
+    pub fn test_links() {
+        let mut page = Page::from("Visit us");
+        page.link(Rect::new(100.0, 695.0, 90.0, 24.0), "https://example.com");
+        page.link(
+            Rect::new(100.0, 600.0, 90.0, 24.0),
+            "mailto:info@example.com",
+        );
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.add_page("No links");
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/Contents 5 0 R /Annots [6 0 R 7 0 R] /Resources"));
+        assert!(pdf.contains("6 0 obj\n<< /Type /Annot /Subtype /Link /Rect [100 695 190 719]"));
+        assert!(pdf.contains("7 0 obj\n<< /Type /Annot /Subtype /Link /Rect [100 600 190 624]"));
+        assert!(pdf.contains("/Contents 9 0 R /Resources"));
+    }
+
+    #[test]
+    pub fn test_internal_links() {
+        let mut contents = Page::from("Contents");
+        contents.link_to(
+            Rect::new(100.0, 695.0, 200.0, 24.0),
+            Destination::xyz(1, Some(0.0), Some(842.0), Some(2.0)),
+        );
+        let mut doc = PdfDocument::default();
+        doc.add_page(contents);
+        doc.add_page("Chapter");
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/Border [0 0 0] /Dest [7 0 R /XYZ 0 842 2] >>"));
+    }
+
+    #[test]
+    pub fn test_notes() {
+        let mut page = Page::from("Draft");
+        page.add_annotation(
+            Annotation::note(Rect::new(500.0, 780.0, 20.0, 20.0), "Add figures").author("Anna"),
+        );
+        page.add_annotation(Annotation::note(
+            Rect::new(500.0, 740.0, 20.0, 20.0),
+            "Typo",
+        ));
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("/Annots [6 0 R 7 0 R]"));
+        assert!(pdf.contains(
+            "6 0 obj\n<< /Type /Annot /Subtype /Text /Rect [500 780 520 800] /F 28 \
+             /Contents (Add figures) /T (Anna) /Name /Note /Open false >>"
+        ));
+
+        let parsed = ::lopdf::Document::load_mem(&doc.to_bytes().unwrap()).unwrap();
+        let annotations = parsed.get_page_annotations(parsed.page_iter().next().unwrap());
+        assert_eq!(annotations.unwrap().len(), 2);
+    }
+
+    #[test]
+    pub fn test_file_attachments() {
+        let file = EmbeddedFile::new("data.csv", "text/csv", b"year,total\n2024,42\n".to_vec());
+        let mut page = Page::from("Report");
+        page.add_annotation(Annotation::file_attachment(
+            Rect::new(500.0, 700.0, 16.0, 24.0),
+            file.clone(),
+        ));
+        page.add_annotation(Annotation::file_attachment(
+            Rect::new(500.0, 100.0, 16.0, 24.0),
+            file,
+        ));
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert_eq!(pdf.matches("/Type /EmbeddedFile").count(), 1);
+        assert!(pdf.contains("4 0 obj\n<< /Length 19 /Type /EmbeddedFile /Subtype /text#2Fcsv"));
+        assert!(pdf.contains("/Annots [7 0 R 8 0 R]"));
+        assert_eq!(pdf.matches("/EF << /F 4 0 R /UF 4 0 R >>").count(), 2);
+
+        doc.set_compression(true);
+        let parsed = ::lopdf::Document::load_mem(&doc.to_bytes().unwrap()).unwrap();
+        let stream = parsed.get_object((4, 0)).unwrap().as_stream().unwrap();
+        assert_eq!(
+            stream.decompressed_content().unwrap(),
+            b"year,total\n2024,42\n"
+        );
+    }
+
+    #[test]
+    pub fn test_text_fields() {
+        let mut page = Page::from("Application");
+        page.link(Rect::new(100.0, 100.0, 100.0, 12.0), "https://example.com");
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.add_page(Page::new(PageSize::A4));
+        doc.form()
+            .text_field("name", Rect::new(100.0, 600.0, 250.0, 20.0), 0)
+            .set_font(StandardFont::TimesRoman, 11.0);
+        doc.form()
+            .text_field("notes", Rect::new(100.0, 300.0, 250.0, 120.0), 1)
+            .set_multiline(true);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("4 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Times-Roman"));
+        assert!(pdf.contains("/AcroForm 14 0 R"));
+        assert!(pdf.contains("/Annots [9 0 R 12 0 R]"));
+        assert!(pdf.contains("/Annots [13 0 R]"));
+        assert!(pdf.contains(
+            "/T (name) /DA (/Times-Roman 11 Tf 0 g) /MK << /BC [0] >> /AP << /N 5 0 R >>"
+        ));
+        assert!(pdf.contains(
+            "14 0 obj\n<< /Fields [12 0 R 13 0 R] /DR << /Font << /Times-Roman 4 0 R \
+             /Helvetica 3 0 R >> >> >>"
+        ));
+
+        let parsed = ::lopdf::Document::load_mem(&doc.to_bytes().unwrap()).unwrap();
+        let field = parsed.get_dictionary((13, 0)).unwrap();
+        assert_eq!(field.get(b"FT").unwrap().as_name().unwrap(), b"Tx");
+        assert_eq!(field.get(b"P").unwrap().as_reference().unwrap(), (10, 0));
+
+        doc.form()
+            .text_field("name", Rect::new(0.0, 0.0, 10.0, 10.0), 1);
+        assert!(doc.write_to(Cursor::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    pub fn test_flatten_form() {
+        let mut doc = PdfDocument::new("Application");
+        doc.form()
+            .text_field("name", Rect::new(100.0, 600.0, 250.0, 20.0), 0)
+            .set_default_value("Jane Doe");
+        doc.form()
+            .checkbox("agree", Rect::new(100.0, 560.0, 12.0, 12.0), 0)
+            .set_checked(true);
+        doc.form()
+            .text_field("notes", Rect::new(100.0, 300.0, 250.0, 120.0), 1);
+        assert!(doc.flatten_form().is_err());
+        assert!(!doc.form().is_empty());
+
+        doc.add_page(Page::new(PageSize::A4));
+        doc.flatten_form().unwrap();
+        assert!(doc.form().is_empty());
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(!pdf.contains("/AcroForm"));
+        assert!(!pdf.contains("/Annots"));
+        assert!(pdf.contains("q\n1 0 0 1 100 600 cm\n/X1 Do\nQ\n"));
+        assert!(pdf.contains("q\n1 0 0 1 100 560 cm\n/X2 Do\nQ\n"));
+        assert!(
+            pdf.contains(
+                "/Resources << /Font << /F1 3 0 R >> /XObject << /X1 5 0 R /X2 6 0 R >> >>"
+            )
+        );
+        assert!(pdf.contains("/Resources << /XObject << /X1 7 0 R >> >>"));
+    }
+
+    #[test]
+    pub fn test_free_text_appearance() {
+        let mut page = Page::from("Invoice");
+        let text = Annotation::free_text(
+            Rect::new(300.0, 600.0, 150.0, 30.0),
+            "Paid in full",
+            StandardFont::HelveticaBold,
+            12.0,
+        );
+        page.add_annotation(text.clone());
+        page.add_annotation(text.generate_appearance());
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("4 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold"));
+        assert!(pdf.contains(
+            "/Type /XObject /Subtype /Form /BBox [0 0 150 30] /Resources << /Font << /F1 4 0 R >> >>"
+        ));
+        assert!(pdf.contains("/Annots [8 0 R 9 0 R]"));
+        assert!(pdf.contains("/DA (/Helvetica-Bold 12 Tf 0 g) /Q 0 >>"));
+        assert!(pdf.contains(" /AP << /N 5 0 R >>"));
+    }
+
+    #[test]
+    pub fn test_page_tree() {
+        let mut doc = PdfDocument::new("First");
+        doc.add_page("Second");
+        doc.add_page("Third");
+        assert_eq!(doc.page_count(), 3);
+
+        doc.set_compression(false);
+        let pdf = String::from_utf8(doc.to_bytes().unwrap()).unwrap();
+        assert!(pdf.contains("2 0 obj\n<< /Type /Pages /Kids [4 0 R 6 0 R 8 0 R] /Count 3 >>\n"));
+        assert!(pdf.contains("8 0 obj\n<< /Type /Page /Parent 2 0 R "));
+        assert!(pdf.contains("/Contents 9 0 R "));
+        assert!(pdf.contains("/Size 10 "));
+    }
+
+    #[test]
+    pub fn test_inherited_attributes() {
+        let mut doc = PdfDocument::new("First");
+        doc.add_page("Second");
+        doc.add_page(Page::new(PageSize::Letter));
+        doc.set_inherited_attributes(true);
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(
+            "2 0 obj\n<< /Type /Pages /Kids [4 0 R 6 0 R 8 0 R] /Count 3 \
+             /MediaBox [0 0 595 842] /Resources << /Font << /F1 3 0 R >> >> >>"
+        ));
+        assert!(text.contains("4 0 obj\n<< /Type /Page /Parent 2 0 R /Contents 5 0 R >>"));
+        assert!(text.contains(
+            "8 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Contents 9 0 R /Resources << >> >>"
+        ));
+        ::lopdf::Document::load_mem(&pdf).unwrap();
+    }
+
+    #[test]
+    pub fn test_balanced_page_tree() {
+        let mut doc = PdfDocument::default();
+        for i in 0..1000 {
+            doc.add_page(format!("Page {}", i + 1).as_str());
+        }
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("<< /Type /Pages /Kids [3 0 R 4 0 R "));
+        assert!(text.contains("<< /Type /Pages /Parent 2 0 R /Kids ["));
+
+        let loaded = ::lopdf::Document::load_mem(&pdf).unwrap();
+        let pages = loaded.get_pages();
+        assert_eq!(pages.len(), 1000);
+        let last = loaded.get_dictionary(pages[&1000]).unwrap();
+        let parent = loaded
+            .get_dictionary(last.get(b"Parent").unwrap().as_reference().unwrap())
+            .unwrap();
+        assert_eq!(parent.get(b"Count").unwrap().as_i64().unwrap(), 31);
+    }
+
+    #[test]
+    pub fn test_write_to_offsets() {
+        use std::io::Seek;
+        let mut doc = PdfDocument::new("Offsets");
+        let mut buf = Cursor::new(b"junk".to_vec());
+        buf.seek(std::io::SeekFrom::End(0)).unwrap();
+        doc.set_compression(false);
+        doc.write_to(&mut buf).unwrap();
+        let pdf = &buf.get_ref()[4..];
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+
+        let text = String::from_utf8_lossy(pdf);
+        let tail = text.rsplit("startxref\n").next().unwrap();
+        let xref_pos: usize = tail.lines().next().unwrap().parse().unwrap();
+        assert!(text[xref_pos..].starts_with("xref\n"));
+    }
+
+    #[test]
+    pub fn test_write_to_stream() {
+        use flate2::read::GzDecoder;
+        use flate2::write::GzEncoder;
+        use std::io::Read;
+
+        let mut doc = PdfDocument::new("Streamed");
+        doc.add_page("Through gzip");
+        let mut gzip = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        doc.write_to(&mut gzip).unwrap();
+        let mut pdf = Vec::new();
+        GzDecoder::new(gzip.finish().unwrap().as_slice())
+            .read_to_end(&mut pdf)
+            .unwrap();
+        assert_eq!(pdf, doc.to_bytes().unwrap());
+        ::lopdf::Document::load_mem(&pdf).unwrap();
+    }
+
+    #[test]
+    pub fn test_to_bytes() {
+        let doc = PdfDocument::new("In memory");
+        let bytes = doc.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.4\n"));
+        assert!(bytes.ends_with(b"%%EOF"));
+
+        let mut buf = Cursor::new(Vec::new());
+        doc.write_to(&mut buf).unwrap();
+        assert_eq!(bytes, buf.into_inner());
+    }
+
+    #[test]
+    pub fn test_create_to_path() {
+        let path = std::env::temp_dir().join("pdf-creator-create-to-path.pdf");
+        PdfDocument::new("Temp").create_to_path(&path).unwrap();
+        assert!(std::fs::read(&path).unwrap().ends_with(b"%%EOF"));
+        std::fs::remove_file(path).unwrap();
     }
 }
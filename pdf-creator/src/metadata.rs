@@ -0,0 +1,568 @@
+use crate::object::{Dictionary, Object};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What a document is about and where it comes from, shown by readers in
+/// the document properties and written as the document information
+/// dictionary. Set with
+/// [`PdfDocument::set_metadata`](crate::PdfDocument::set_metadata).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+    /// The language of the text, such as `en-US`, which screen readers
+    /// read it in.
+    pub language: Option<String>,
+    /// The application the document was authored in.
+    pub creator: Option<String>,
+    /// The application that wrote the PDF file.
+    pub producer: Option<String>,
+    pub creation_date: Option<PdfDate>,
+    pub modification_date: Option<PdfDate>,
+    /// Whether the document has been trapped for printing, with overlaps
+    /// where colors meet, which PDF/X documents must tell.
+    pub trapped: Option<bool>,
+    /// Properties of schemas of your own, only written to the XMP packet
+    /// (see [`PdfDocument::set_xmp`](crate::PdfDocument::set_xmp)).
+    pub xmp_namespaces: Vec<XmpNamespace>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The document information dictionary.
+    pub(crate) fn to_info(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let texts = [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Subject", &self.subject),
+        ];
+        for (key, value) in texts {
+            if let Some(value) = value {
+                dict.set(key, Object::text(value));
+            }
+        }
+        if !self.keywords.is_empty() {
+            dict.set("Keywords", Object::text(&self.keywords.join(", ")));
+        }
+        for (key, value) in [("Creator", &self.creator), ("Producer", &self.producer)] {
+            if let Some(value) = value {
+                dict.set(key, Object::text(value));
+            }
+        }
+        let dates = [
+            ("CreationDate", self.creation_date),
+            ("ModDate", self.modification_date),
+        ];
+        for (key, date) in dates {
+            if let Some(date) = date {
+                dict.set(key, date.to_pdf());
+            }
+        }
+        if let Some(trapped) = self.trapped {
+            let trapped = if trapped { "True" } else { "False" };
+            dict.set("Trapped", Object::name(trapped));
+        }
+        dict
+    }
+
+    /// The same metadata as an XMP packet, for the `/Metadata` stream of the
+    /// catalog: Dublin Core, PDF and XMP basic properties, then those of
+    /// the custom namespaces.
+    pub(crate) fn to_xmp(&self) -> String {
+        self.packet(&[])
+    }
+
+    /// The XMP packet of a document written to a standard, with the
+    /// namespaces `standard` identifying the standard and the document,
+    /// which describes the custom namespaces in PDF/A extension schemas as
+    /// PDF/A asks.
+    pub(crate) fn to_standard_xmp(&self, standard: &[XmpNamespace]) -> String {
+        self.packet(standard)
+    }
+
+    fn packet(&self, standard: &[XmpNamespace]) -> String {
+        let mut dc = String::from("<dc:format>application/pdf</dc:format>");
+        if let Some(title) = &self.title {
+            dc.push_str(&format!("<dc:title>{}</dc:title>", xmp_alt(title)));
+        }
+        if let Some(author) = &self.author {
+            dc.push_str(&format!(
+                "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+                xml_escape(author)
+            ));
+        }
+        if let Some(subject) = &self.subject {
+            dc.push_str(&format!(
+                "<dc:description>{}</dc:description>",
+                xmp_alt(subject)
+            ));
+        }
+        if !self.keywords.is_empty() {
+            dc.push_str("<dc:subject><rdf:Bag>");
+            for keyword in &self.keywords {
+                dc.push_str(&format!("<rdf:li>{}</rdf:li>", xml_escape(keyword)));
+            }
+            dc.push_str("</rdf:Bag></dc:subject>");
+        }
+        if let Some(language) = &self.language {
+            dc.push_str(&format!(
+                "<dc:language><rdf:Bag><rdf:li>{}</rdf:li></rdf:Bag></dc:language>",
+                xml_escape(language)
+            ));
+        }
+
+        let mut pdf = XmpNamespace::new("pdf", "http://ns.adobe.com/pdf/1.3/");
+        if !self.keywords.is_empty() {
+            pdf = pdf.property("Keywords", &self.keywords.join(", "));
+        }
+        if let Some(producer) = &self.producer {
+            pdf = pdf.property("Producer", producer);
+        }
+        if let Some(trapped) = self.trapped {
+            pdf = pdf.property("Trapped", if trapped { "True" } else { "False" });
+        }
+        let mut xmp = XmpNamespace::new("xmp", "http://ns.adobe.com/xap/1.0/");
+        if let Some(creator) = &self.creator {
+            xmp = xmp.property("CreatorTool", creator);
+        }
+        if let Some(date) = self.creation_date {
+            xmp = xmp.property("CreateDate", &date.to_xmp());
+        }
+        if let Some(date) = self.modification_date {
+            xmp = xmp.property("ModifyDate", &date.to_xmp());
+        }
+
+        let mut packet = String::from(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+        );
+        packet.push_str(&format!(
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\
+             {}</rdf:Description>\n",
+            dc
+        ));
+        let custom = self.xmp_namespaces.iter();
+        for namespace in [&pdf, &xmp].into_iter().chain(standard).chain(custom) {
+            if !namespace.properties.is_empty() {
+                packet.push_str(&namespace.to_description());
+            }
+        }
+        if !standard.is_empty() && !self.xmp_namespaces.is_empty() {
+            packet.push_str(
+                "<rdf:Description rdf:about=\"\" \
+                 xmlns:pdfaExtension=\"http://www.aiim.org/pdfa/ns/extension/\" \
+                 xmlns:pdfaSchema=\"http://www.aiim.org/pdfa/ns/schema#\" \
+                 xmlns:pdfaProperty=\"http://www.aiim.org/pdfa/ns/property#\">\
+                 <pdfaExtension:schemas><rdf:Bag>",
+            );
+            for namespace in &self.xmp_namespaces {
+                packet.push_str(&namespace.to_extension_schema());
+            }
+            packet.push_str("</rdf:Bag></pdfaExtension:schemas></rdf:Description>\n");
+        }
+        packet.push_str("</rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>");
+        packet
+    }
+}
+
+/// A schema of XMP properties beyond those [`Metadata`] knows, such as a
+/// company's own asset management fields: simple text properties named
+/// `prefix:name` in the namespace `uri`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmpNamespace {
+    prefix: String,
+    uri: String,
+    properties: Vec<(String, String)>,
+}
+
+impl XmpNamespace {
+    /// An empty schema, its properties written with `prefix`, which must be
+    /// an XML name, such as `acme` for `http://ns.acme.com/assets/1.0/`.
+    pub fn new(prefix: &str, uri: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            uri: uri.to_string(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Adds the property `name`, an XML name, with a text `value`.
+    pub fn property(mut self, name: &str, value: &str) -> Self {
+        self.properties.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn to_description(&self) -> String {
+        let mut description = format!(
+            "<rdf:Description rdf:about=\"\" xmlns:{}=\"{}\">",
+            self.prefix,
+            xml_escape(&self.uri)
+        );
+        for (name, value) in &self.properties {
+            description.push_str(&format!(
+                "<{prefix}:{name}>{}</{prefix}:{name}>",
+                xml_escape(value),
+                prefix = self.prefix,
+            ));
+        }
+        description.push_str("</rdf:Description>\n");
+        description
+    }
+
+    /// The PDF/A extension schema describing the namespace, its
+    /// properties being external text.
+    fn to_extension_schema(&self) -> String {
+        let mut schema = format!(
+            "<rdf:li rdf:parseType=\"Resource\"><pdfaSchema:schema>{prefix}</pdfaSchema:schema>\
+             <pdfaSchema:namespaceURI>{}</pdfaSchema:namespaceURI>\
+             <pdfaSchema:prefix>{prefix}</pdfaSchema:prefix><pdfaSchema:property><rdf:Seq>",
+            xml_escape(&self.uri),
+            prefix = self.prefix,
+        );
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in &self.properties {
+            if names.contains(&name.as_str()) {
+                continue;
+            }
+            names.push(name);
+            schema.push_str(&format!(
+                "<rdf:li rdf:parseType=\"Resource\"><pdfaProperty:name>{name}</pdfaProperty:name>\
+                 <pdfaProperty:valueType>Text</pdfaProperty:valueType>\
+                 <pdfaProperty:category>external</pdfaProperty:category>\
+                 <pdfaProperty:description>{name}</pdfaProperty:description></rdf:li>"
+            ));
+        }
+        schema.push_str("</rdf:Seq></pdfaSchema:property></rdf:li>");
+        schema
+    }
+}
+
+/// A language alternative holding `text` as the default.
+fn xmp_alt(text: &str) -> String {
+    format!(
+        "<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>",
+        xml_escape(text)
+    )
+}
+
+/// Escapes `text` for XML character data and attribute values.
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A point in time as kept in document metadata: a local date and time of
+/// day, to the second, and how far ahead of UTC that local time is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PdfDate {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    /// Minutes ahead of UTC, negative west of Greenwich.
+    offset: i16,
+}
+
+impl PdfDate {
+    /// Midnight UTC of the given day, `month` and `day` counting from 1,
+    /// or `None` if there is no such day.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        if year > 9999 || !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+        Some(Self {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            offset: 0,
+        })
+    }
+
+    /// The current time, in UTC.
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+        let time = seconds.rem_euclid(86400);
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+            offset: 0,
+        }
+    }
+
+    /// The same day at the given time, or `None` if it is out of range.
+    pub fn at(self, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        if hour >= 24 || minute >= 60 || second >= 60 {
+            return None;
+        }
+        Some(Self {
+            hour,
+            minute,
+            second,
+            ..self
+        })
+    }
+
+    /// The same local date and time, `minutes` ahead of UTC (negative for
+    /// time zones west of Greenwich), or `None` if that is a day or more.
+    pub fn with_offset(self, minutes: i16) -> Option<Self> {
+        if minutes.unsigned_abs() >= 24 * 60 {
+            return None;
+        }
+        Some(Self {
+            offset: minutes,
+            ..self
+        })
+    }
+
+    /// The date as a PDF date string, such as `(D:20240501103000+02'00')`.
+    pub(crate) fn to_pdf(self) -> Object {
+        let zone = match self.offset {
+            0 => "Z".to_string(),
+            offset => format!(
+                "{}{:02}'{:02}'",
+                if offset < 0 { '-' } else { '+' },
+                offset.abs() / 60,
+                offset.abs() % 60
+            ),
+        };
+        let date = format!(
+            "D:{:04}{:02}{:02}{:02}{:02}{:02}{}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, zone
+        );
+        Object::string(date)
+    }
+
+    /// The date as people read it, such as `2024-05-01 10:30:00 +02:00`.
+    pub(crate) fn to_text(self) -> String {
+        let zone = match self.offset {
+            0 => "UTC".to_string(),
+            offset => format!(
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.abs() / 60,
+                offset.abs() % 60
+            ),
+        };
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, zone
+        )
+    }
+
+    /// The date as XMP writes it, such as `2024-05-01T10:30:00+02:00`.
+    pub(crate) fn to_xmp(self) -> String {
+        let zone = match self.offset {
+            0 => "Z".to_string(),
+            offset => format!(
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.abs() / 60,
+                offset.abs() % 60
+            ),
+        };
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, zone
+        )
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// The year, month and day `days` days after 1970-01-01, in the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: i64) -> (u16, u8, u8) {
+    // Counted from 0000-03-01, so that leap days end each 400-year era.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as u16, month as u8, day as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_dates() {
+        let date = PdfDate::new(2024, 5, 1).unwrap().at(10, 30, 0).unwrap();
+        assert_eq!(date.to_pdf().to_string(), "(D:20240501103000Z)");
+        let east = date.with_offset(120).unwrap().to_pdf();
+        assert_eq!(east.to_string(), "(D:20240501103000+02'00')");
+        let west = date.with_offset(-210).unwrap().to_pdf();
+        assert_eq!(west.to_string(), "(D:20240501103000-03'30')");
+        assert_eq!(PdfDate::new(2024, 2, 29).unwrap().day, 29);
+    }
+
+    #[test]
+    fn test_invalid_date() {
+        assert_eq!(PdfDate::new(2023, 2, 29), None);
+        assert_eq!(PdfDate::new(2024, 13, 1), None);
+        assert_eq!(PdfDate::new(10000, 1, 1), None);
+        let date = PdfDate::new(2024, 5, 1).unwrap();
+        assert_eq!(date.at(24, 0, 0), None);
+        assert_eq!(date.at(23, 60, 0), None);
+        assert_eq!(date.with_offset(-24 * 60), None);
+    }
+
+    #[test]
+    fn test_xmp_dates() {
+        let date = PdfDate::new(2024, 5, 1).unwrap().at(10, 30, 0).unwrap();
+        assert_eq!(date.to_xmp(), "2024-05-01T10:30:00Z");
+        let west = date.with_offset(-210).unwrap();
+        assert_eq!(west.to_xmp(), "2024-05-01T10:30:00-03:30");
+        assert_eq!(date.to_text(), "2024-05-01 10:30:00 UTC");
+        assert_eq!(
+            date.with_offset(120).unwrap().to_text(),
+            "2024-05-01 10:30:00 +02:00"
+        );
+    }
+
+    #[test]
+    fn test_xmp_packet() {
+        let metadata = Metadata {
+            title: Some("Q&A".into()),
+            keywords: vec!["faq".into(), "help".into()],
+            creation_date: Some(PdfDate::new(2024, 12, 31).unwrap()),
+            xmp_namespaces: vec![
+                XmpNamespace::new("acme", "http://ns.acme.com/assets/1.0/")
+                    .property("AssetId", "A-<42>"),
+            ],
+            ..Default::default()
+        };
+        let xmp = metadata.to_xmp();
+        assert!(xmp.starts_with("<?xpacket begin=\"\u{feff}\" id="));
+        assert!(xmp.ends_with("</x:xmpmeta>\n<?xpacket end=\"w\"?>"));
+        assert!(xmp.contains(
+            "<dc:format>application/pdf</dc:format><dc:title><rdf:Alt>\
+             <rdf:li xml:lang=\"x-default\">Q&amp;A</rdf:li></rdf:Alt></dc:title>\
+             <dc:subject><rdf:Bag><rdf:li>faq</rdf:li><rdf:li>help</rdf:li></rdf:Bag>\
+             </dc:subject></rdf:Description>\n"
+        ));
+        assert!(xmp.contains(
+            "<rdf:Description rdf:about=\"\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\
+             <pdf:Keywords>faq, help</pdf:Keywords></rdf:Description>\n"
+        ));
+        assert!(xmp.contains("<xmp:CreateDate>2024-12-31T00:00:00Z</xmp:CreateDate>"));
+        assert!(xmp.contains(
+            "<rdf:Description rdf:about=\"\" xmlns:acme=\"http://ns.acme.com/assets/1.0/\">\
+             <acme:AssetId>A-&lt;42&gt;</acme:AssetId></rdf:Description>\n"
+        ));
+    }
+
+    #[test]
+    fn test_pdfa_xmp_packet() {
+        let metadata = Metadata {
+            xmp_namespaces: vec![
+                XmpNamespace::new("acme", "http://ns.acme.com/assets/1.0/")
+                    .property("AssetId", "A-42")
+                    .property("AssetId", "A-43"),
+            ],
+            ..Default::default()
+        };
+        let id =
+            XmpNamespace::new("pdfaid", "http://www.aiim.org/pdfa/ns/id/").property("part", "3");
+        let xmp = metadata.to_standard_xmp(std::slice::from_ref(&id));
+        assert!(xmp.contains("<pdfaid:part>3</pdfaid:part></rdf:Description>\n"));
+        assert!(xmp.contains(
+            "<pdfaExtension:schemas><rdf:Bag><rdf:li rdf:parseType=\"Resource\">\
+             <pdfaSchema:schema>acme</pdfaSchema:schema>\
+             <pdfaSchema:namespaceURI>http://ns.acme.com/assets/1.0/</pdfaSchema:namespaceURI>\
+             <pdfaSchema:prefix>acme</pdfaSchema:prefix><pdfaSchema:property><rdf:Seq>\
+             <rdf:li rdf:parseType=\"Resource\"><pdfaProperty:name>AssetId</pdfaProperty:name>\
+             <pdfaProperty:valueType>Text</pdfaProperty:valueType>\
+             <pdfaProperty:category>external</pdfaProperty:category>\
+             <pdfaProperty:description>AssetId</pdfaProperty:description></rdf:li>\
+             </rdf:Seq></pdfaSchema:property></rdf:li></rdf:Bag></pdfaExtension:schemas>\
+             </rdf:Description>\n</rdf:RDF>"
+        ));
+        assert!(!metadata.to_xmp().contains("pdfaExtension"));
+        assert!(
+            !Metadata::default()
+                .to_standard_xmp(&[id])
+                .contains("pdfaExtension")
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert!(PdfDate::now().year >= 2024);
+    }
+
+    #[test]
+    fn test_info_dictionary() {
+        assert!(Metadata::default().is_empty());
+        let metadata = Metadata {
+            title: Some("Annual report".into()),
+            author: Some("Jürgen Weiß".into()),
+            keywords: vec!["finance".into(), "2024".into()],
+            producer: Some("pdf-creator".into()),
+            creation_date: Some(PdfDate::new(2024, 12, 31).unwrap().at(23, 59, 59).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata.to_info().to_string(),
+            "<< /Title (Annual report) \
+             /Author <FEFF004A00FC007200670065006E002000570065006900DF> \
+             /Keywords (finance, 2024) /Producer (pdf-creator) \
+             /CreationDate (D:20241231235959Z) >>"
+        );
+        let trapped = Metadata {
+            trapped: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(trapped.to_info().to_string(), "<< /Trapped /False >>");
+        assert!(
+            trapped
+                .to_xmp()
+                .contains("<pdf:Trapped>False</pdf:Trapped>")
+        );
+    }
+}
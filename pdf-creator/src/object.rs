@@ -0,0 +1,728 @@
+use crate::geometry::{Rect, number};
+use crate::string::{hex_string, pdf_name, pdf_string, text_bytes};
+use crate::writer::deflate;
+use anyhow::Result;
+use std::fmt;
+
+/// Indirect reference to an object of the document, serialized as `12 0 R`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectRef {
+    id: u32,
+    generation: u16,
+}
+
+impl ObjectRef {
+    pub(crate) fn new(id: u32) -> Self {
+        Self { id, generation: 0 }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn generation(&self) -> u16 {
+        self.generation
+    }
+}
+
+impl fmt::Display for ObjectRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} R", self.id, self.generation)
+    }
+}
+
+/// A value of the document: one of the kinds of objects files are made of,
+/// written in the syntax of the file by its `Display`.
+///
+/// ```
+/// use pdf_creator::{Dictionary, Object};
+///
+/// let mut dict = Dictionary::new();
+/// dict.set("Type", Object::name("Annot"));
+/// dict.set("Contents", Object::text("Café"));
+/// dict.set("Border", vec![0.into(), 0.into(), 1.5.into()]);
+/// assert_eq!(
+///     dict.to_string(),
+///     "<< /Type /Annot /Contents <FEFF00430061006600E9> /Border [0 0 1.5] >>"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    /// Written rounded to 5 decimals.
+    Real(f32),
+    /// A name, such as `/Type`, of any bytes, those that are not regular
+    /// characters escaped.
+    Name(Vec<u8>),
+    /// A string of any bytes, written as it is between parentheses if it is
+    /// printable ASCII, or in hexadecimal otherwise.
+    String(Vec<u8>),
+    /// A string written in hexadecimal whatever its bytes, as binary data
+    /// such as identifiers and UTF-16 text is.
+    HexString(Vec<u8>),
+    Array(Vec<Object>),
+    Dictionary(Dictionary),
+    /// A stream, only ever an object of its own, which others refer to.
+    Stream(Stream),
+    Reference(ObjectRef),
+}
+
+impl Object {
+    pub fn name(name: impl Into<Vec<u8>>) -> Self {
+        Object::Name(name.into())
+    }
+
+    pub fn string(bytes: impl Into<Vec<u8>>) -> Self {
+        Object::String(bytes.into())
+    }
+
+    pub fn hex(bytes: impl Into<Vec<u8>>) -> Self {
+        Object::HexString(bytes.into())
+    }
+
+    /// A string of text readers show, such as a title: printable ASCII as
+    /// is, anything else in UTF-16BE with a byte order mark, in
+    /// hexadecimal.
+    pub fn text(text: &str) -> Self {
+        let bytes = text_bytes(text);
+        match bytes.starts_with(&[0xFE, 0xFF]) {
+            true => Object::HexString(bytes),
+            false => Object::String(bytes),
+        }
+    }
+
+    /// The array of the numbers `values`.
+    pub(crate) fn numbers(values: &[f32]) -> Self {
+        Object::Array(values.iter().map(|&value| value.into()).collect())
+    }
+
+    /// The objects referred to from within the object.
+    pub(crate) fn references(&self) -> Vec<ObjectRef> {
+        match self {
+            Object::Array(items) => items.iter().flat_map(Object::references).collect(),
+            Object::Dictionary(dict) => dict.values().flat_map(Object::references).collect(),
+            Object::Stream(stream) => stream
+                .dict
+                .values()
+                .chain(
+                    stream
+                        .filters
+                        .iter()
+                        .filter_map(|(_, parms)| parms.as_ref())
+                        .flat_map(Dictionary::values),
+                )
+                .flat_map(Object::references)
+                .collect(),
+            Object::Reference(object) => vec![*object],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Calls `f` on the object, then on each object within it, those of
+    /// the dictionary and the filter parameters of a stream included,
+    /// stopping at the first error.
+    pub(crate) fn visit_mut(
+        &mut self,
+        f: &mut impl FnMut(&mut Object) -> Result<()>,
+    ) -> Result<()> {
+        f(self)?;
+        match self {
+            Object::Array(items) => items.iter_mut().try_for_each(|item| item.visit_mut(f)),
+            Object::Dictionary(dict) => dict.visit_mut(f),
+            Object::Stream(stream) => {
+                stream.dict.visit_mut(f)?;
+                stream
+                    .filters
+                    .iter_mut()
+                    .filter_map(|(_, parms)| parms.as_mut())
+                    .try_for_each(|parms| parms.visit_mut(f))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a stream is found within the object, which is then no
+    /// object that can be written but as a stream of its own.
+    pub(crate) fn contains_stream(&self) -> bool {
+        match self {
+            Object::Array(items) => items.iter().any(Object::contains_stream),
+            Object::Dictionary(dict) => dict.values().any(Object::contains_stream),
+            Object::Stream(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Writes the object, a stream as its dictionary along with its
+/// `/Length` and its filters, its data being written after it as an object
+/// of its own.
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Object::Null => write!(f, "null"),
+            Object::Bool(value) => write!(f, "{}", value),
+            Object::Integer(value) => write!(f, "{}", value),
+            Object::Real(value) => write!(f, "{}", number(*value)),
+            Object::Name(name) => write!(f, "{}", pdf_name(name)),
+            Object::String(bytes) => write!(f, "{}", pdf_string(bytes)),
+            Object::HexString(bytes) => write!(f, "{}", hex_string(bytes)),
+            Object::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Object::Dictionary(dict) => write!(f, "{}", dict),
+            Object::Stream(stream) => {
+                let (dict, data) = stream.encode();
+                write!(f, "<< /Length {}{} >>", data.len(), dict.entries())
+            }
+            Object::Reference(object) => write!(f, "{}", object),
+        }
+    }
+}
+
+impl From<bool> for Object {
+    fn from(value: bool) -> Self {
+        Object::Bool(value)
+    }
+}
+
+impl From<i32> for Object {
+    fn from(value: i32) -> Self {
+        Object::Integer(value.into())
+    }
+}
+
+impl From<i64> for Object {
+    fn from(value: i64) -> Self {
+        Object::Integer(value)
+    }
+}
+
+impl From<u32> for Object {
+    fn from(value: u32) -> Self {
+        Object::Integer(value.into())
+    }
+}
+
+impl From<usize> for Object {
+    fn from(value: usize) -> Self {
+        Object::Integer(value as i64)
+    }
+}
+
+impl From<f32> for Object {
+    fn from(value: f32) -> Self {
+        Object::Real(value)
+    }
+}
+
+impl From<ObjectRef> for Object {
+    fn from(object: ObjectRef) -> Self {
+        Object::Reference(object)
+    }
+}
+
+impl From<Vec<Object>> for Object {
+    fn from(items: Vec<Object>) -> Self {
+        Object::Array(items)
+    }
+}
+
+impl From<Dictionary> for Object {
+    fn from(dict: Dictionary) -> Self {
+        Object::Dictionary(dict)
+    }
+}
+
+impl From<Stream> for Object {
+    fn from(stream: Stream) -> Self {
+        Object::Stream(stream)
+    }
+}
+
+/// The rectangle as its left, bottom, right and top edges.
+impl From<Rect> for Object {
+    fn from(rect: Rect) -> Self {
+        let (left, bottom, right, top) = rect.edges();
+        Object::Array(vec![left.into(), bottom.into(), right.into(), top.into()])
+    }
+}
+
+/// The entries of a dictionary, kept in the order first set, as they are
+/// written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dictionary {
+    entries: Vec<(Vec<u8>, Object)>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the entry `key`, in its place if it is already set.
+    pub fn set(&mut self, key: impl Into<Vec<u8>>, value: impl Into<Object>) {
+        let key = key.into();
+        let value = value.into();
+        match self.entries.iter_mut().find(|(known, _)| *known == key) {
+            Some((_, known)) => *known = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&Object> {
+        let key = key.as_ref();
+        self.entries
+            .iter()
+            .find(|(known, _)| known.as_slice() == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<Object> {
+        let key = key.as_ref();
+        let index = self
+            .entries
+            .iter()
+            .position(|(known, _)| known.as_slice() == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &Object)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value))
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Object> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    /// Calls `f` on each value of the dictionary and each object within it,
+    /// as [`Object::visit_mut`] does.
+    pub(crate) fn visit_mut(
+        &mut self,
+        f: &mut impl FnMut(&mut Object) -> Result<()>,
+    ) -> Result<()> {
+        self.entries
+            .iter_mut()
+            .try_for_each(|(_, value)| value.visit_mut(f))
+    }
+
+    /// Sets the entry `key` first, before those set already, as `/Filter`
+    /// is written right after `/Length`.
+    pub(crate) fn set_first(&mut self, key: impl Into<Vec<u8>>, value: impl Into<Object>) {
+        let key = key.into();
+        self.remove(&key);
+        self.entries.insert(0, (key, value.into()));
+    }
+
+    /// The entries as written within the dictionary, each preceded by a
+    /// space.
+    pub(crate) fn entries(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(key, value)| format!(" {} {}", pdf_name(key), value))
+            .collect()
+    }
+}
+
+impl fmt::Display for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<<{} >>", self.entries())
+    }
+}
+
+/// A filter the data of a stream is encoded with, which readers decode it
+/// by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// zlib compression, for data of any kind.
+    Flate,
+    /// Two hexadecimal digits a byte, which keeps the data ASCII.
+    AsciiHex,
+    /// Four bytes in five ASCII characters.
+    Ascii85,
+    /// Runs of the same byte in two.
+    RunLength,
+    /// LZW compression, the forerunner of Flate.
+    Lzw,
+    /// CCITT Group 3 or 4 fax compression, for images of one bit a pixel.
+    CcittFax,
+    /// JBIG2 compression, for images of one bit a pixel.
+    Jbig2,
+    /// JPEG compression, for photographs.
+    Dct,
+    /// JPEG 2000 compression.
+    Jpx,
+}
+
+impl Filter {
+    /// The name `/Filter` gives the filter by.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Filter::Flate => "FlateDecode",
+            Filter::AsciiHex => "ASCIIHexDecode",
+            Filter::Ascii85 => "ASCII85Decode",
+            Filter::RunLength => "RunLengthDecode",
+            Filter::Lzw => "LZWDecode",
+            Filter::CcittFax => "CCITTFaxDecode",
+            Filter::Jbig2 => "JBIG2Decode",
+            Filter::Dct => "DCTDecode",
+            Filter::Jpx => "JPXDecode",
+        }
+    }
+}
+
+/// A filter data can be encoded with here, as a stream is written with
+/// [`Stream::filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Flate,
+    AsciiHex,
+    Ascii85,
+    RunLength,
+}
+
+impl Encoding {
+    /// The filter readers decode the data with.
+    pub fn filter(self) -> Filter {
+        match self {
+            Encoding::Flate => Filter::Flate,
+            Encoding::AsciiHex => Filter::AsciiHex,
+            Encoding::Ascii85 => Filter::Ascii85,
+            Encoding::RunLength => Filter::RunLength,
+        }
+    }
+
+    fn encode(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Encoding::Flate => deflate(data),
+            Encoding::AsciiHex => {
+                let mut encoded: Vec<u8> = data
+                    .iter()
+                    .flat_map(|byte| format!("{:02X}", byte).into_bytes())
+                    .collect();
+                encoded.push(b'>');
+                encoded
+            }
+            Encoding::Ascii85 => ascii85(data),
+            Encoding::RunLength => run_length(data),
+        }
+    }
+}
+
+impl From<Encoding> for Filter {
+    fn from(encoding: Encoding) -> Self {
+        encoding.filter()
+    }
+}
+
+/// `data` in ASCII base-85, four bytes in five characters from `!` on, or
+/// in `z` if all are zero, and the last bytes in one character more than
+/// there are of them.
+fn ascii85(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for chunk in data.chunks(4) {
+        let mut group = [0; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+        if chunk.len() == 4 && value == 0 {
+            encoded.push(b'z');
+            continue;
+        }
+        let mut digits = [0; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = b'!' + (value % 85) as u8;
+            value /= 85;
+        }
+        encoded.extend_from_slice(&digits[..chunk.len() + 1]);
+    }
+    encoded.extend_from_slice(b"~>");
+    encoded
+}
+
+/// `data` in runs: a length byte of `n` below 128 followed by `n + 1`
+/// bytes as they are, or of `257 - n` followed by a byte repeated `n`
+/// times, and the end marked by 128.
+fn run_length(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut literal: Vec<u8> = Vec::new();
+    let mut rest = data;
+    while let Some(&byte) = rest.first() {
+        let run = rest.iter().take(128).take_while(|&&b| b == byte).count();
+        if (run > 1 || literal.len() == 128) && !literal.is_empty() {
+            encoded.push(literal.len() as u8 - 1);
+            encoded.append(&mut literal);
+        }
+        if run > 1 {
+            encoded.extend_from_slice(&[(257 - run) as u8, byte]);
+        } else {
+            literal.push(byte);
+        }
+        rest = &rest[run..];
+    }
+    if !literal.is_empty() {
+        encoded.push(literal.len() as u8 - 1);
+        encoded.append(&mut literal);
+    }
+    encoded.push(128);
+    encoded
+}
+
+/// The dictionary and the data of a stream, written with its `/Length`
+/// once encoded with its filters, which `/Filter` and any `/DecodeParms`
+/// name.
+///
+/// The data is either given as it is, to be encoded with the filters on
+/// writing, or encoded already, as images are:
+///
+/// ```
+/// use pdf_creator::{Dictionary, Encoding, Object, Stream};
+///
+/// let stream = Stream::new(Dictionary::new(), "BT ET")
+///     .filter(Encoding::RunLength)
+///     .filter(Encoding::AsciiHex);
+/// assert_eq!(
+///     Object::from(stream).to_string(),
+///     "<< /Length 15 /Filter [/ASCIIHexDecode /RunLengthDecode] >>"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stream {
+    dict: Dictionary,
+    data: Vec<u8>,
+    /// The filters the data is encoded with already, in order, each with
+    /// its parameters.
+    filters: Vec<(Filter, Option<Dictionary>)>,
+    /// Those it is encoded with further on writing, in order.
+    encodings: Vec<Encoding>,
+}
+
+impl Stream {
+    pub fn new(dict: Dictionary, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            dict,
+            data: data.into(),
+            filters: Vec::new(),
+            encodings: Vec::new(),
+        }
+    }
+
+    /// Encodes the data with `encoding` when it is written, after the
+    /// filters given before. Data encoded with another filter is given
+    /// with [`encoded`](Self::encoded).
+    pub fn filter(mut self, encoding: Encoding) -> Self {
+        self.encodings.push(encoding);
+        self
+    }
+
+    /// Tells that the data given is encoded with `filter` already, after
+    /// those given so before, and decoded with the parameters `parms`, such
+    /// as a JPEG image with [`Filter::Dct`], or that of a PNG image with
+    /// [`Filter::Flate`] and its `/Predictor`. The filters of
+    /// [`filter`](Self::filter) encode it further.
+    pub fn encoded(mut self, filter: Filter, parms: Option<Dictionary>) -> Self {
+        self.filters.push((filter, parms));
+        self
+    }
+
+    /// The entries of the dictionary other than `/Length`, `/Filter` and
+    /// `/DecodeParms`.
+    pub fn dict(&self) -> &Dictionary {
+        &self.dict
+    }
+
+    /// The data as it is given, before it is encoded with the filters of
+    /// [`filter`](Self::filter).
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Whether the stream is given a filter, in its dictionary or by the
+    /// methods of its own.
+    pub(crate) fn is_filtered(&self) -> bool {
+        !self.filters.is_empty() || !self.encodings.is_empty() || self.dict.get("Filter").is_some()
+    }
+
+    /// The dictionary of the stream but its `/Length`, and its data, both
+    /// as written once it is encoded.
+    pub(crate) fn encode(&self) -> (Dictionary, Vec<u8>) {
+        let mut data = self.data.clone();
+        for encoding in &self.encodings {
+            data = encoding.encode(&data);
+        }
+        let mut dict = self.dict.clone();
+        // Readers decode with the filter data was last encoded with first.
+        let encodings = self.encodings.iter().map(|&e| (e.filter(), None));
+        let filters: Vec<(Filter, Option<Dictionary>)> = encodings
+            .rev()
+            .chain(self.filters.iter().rev().cloned())
+            .collect();
+        let mut names: Vec<Object> = filters
+            .iter()
+            .map(|(f, _)| Object::name(f.name()))
+            .collect();
+        let mut parms: Vec<Object> = filters
+            .into_iter()
+            .map(|(_, parms)| parms.map_or(Object::Null, Object::from))
+            .collect();
+        match names.len() {
+            0 => {}
+            1 => dict.set("Filter", names.remove(0)),
+            _ => dict.set("Filter", names),
+        }
+        if parms.iter().any(|parms| *parms != Object::Null) {
+            match parms.len() {
+                1 => dict.set("DecodeParms", parms.remove(0)),
+                _ => dict.set("DecodeParms", parms),
+            }
+        }
+        (dict, data)
+    }
+}
+
+/// Hands out object numbers in the order objects are registered, starting at
+/// 1 (object 0 is the head of the free list in the xref table).
+#[derive(Debug)]
+pub(crate) struct ObjectAllocator {
+    next: u32,
+}
+
+impl Default for ObjectAllocator {
+    fn default() -> Self {
+        Self { next: 1 }
+    }
+}
+
+impl ObjectAllocator {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hands out object numbers from `next` on, those below being taken by
+    /// the document an update is appended to.
+    pub(crate) fn starting_at(next: u32) -> Self {
+        Self { next }
+    }
+
+    pub(crate) fn allocate(&mut self) -> ObjectRef {
+        let object = ObjectRef::new(self.next);
+        self.next += 1;
+        object
+    }
+
+    /// Value of the trailer `/Size` entry: one more than the highest object
+    /// number handed out so far.
+    pub(crate) fn size(&self) -> u32 {
+        self.next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_in_order() {
+        let mut alloc = ObjectAllocator::new();
+        assert_eq!(alloc.size(), 1);
+        let first = alloc.allocate();
+        let second = alloc.allocate();
+        assert_eq!(first.to_string(), "1 0 R");
+        assert_eq!(second.to_string(), "2 0 R");
+        assert_eq!(alloc.size(), 3);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Page"));
+        dict.set("Odd Name", 0.5);
+        dict.set("Kids", vec![ObjectRef::new(3).into(), Object::Null]);
+        dict.set("Title", Object::string("a (b)"));
+        dict.set("Binary", Object::string(vec![0, 255]));
+        dict.set("Empty", Dictionary::new());
+        dict.set("Type", Object::name("Pages"));
+        assert_eq!(
+            dict.to_string(),
+            "<< /Type /Pages /Odd#20Name 0.5 /Kids [3 0 R null] /Title (a \\(b\\)) \
+             /Binary <00FF> /Empty << >> >>"
+        );
+        assert_eq!(dict.len(), 6);
+        assert_eq!(dict.remove("Binary"), Some(Object::string(vec![0, 255])));
+        assert_eq!(dict.get("Binary"), None);
+
+        assert_eq!(Object::hex("ID").to_string(), "<4944>");
+        assert_eq!(Object::text("ID"), Object::string("ID"));
+        assert_eq!(Object::text("é"), Object::hex([0xFE, 0xFF, 0, 0xE9]));
+        assert_eq!(Object::from(-1.0 / 3.0).to_string(), "-0.33333");
+        assert_eq!(Object::from(true).to_string(), "true");
+        assert_eq!(Object::Array(Vec::new()).to_string(), "[]");
+        let rect = Rect::new(10.0, 20.0, 30.0, -10.0);
+        assert_eq!(Object::from(rect).to_string(), "[10 10 40 20]");
+
+        let stream = Stream::new(dict.clone(), b"data".to_vec());
+        assert_eq!(
+            Object::from(stream.clone()).to_string(),
+            format!("<< /Length 4{} >>", dict.entries())
+        );
+        assert!(!Object::from(dict.clone()).contains_stream());
+        dict.set("Stream", stream);
+        assert!(Object::from(vec![dict.into()]).contains_stream());
+    }
+
+    #[test]
+    fn test_stream_filters() {
+        assert_eq!(Encoding::Ascii85.encode(b"Man \0\0\0\0."), b"9jqo^z/c~>");
+        assert_eq!(Encoding::AsciiHex.encode(&[0, 0xAB]), b"00AB>");
+        assert_eq!(
+            Encoding::RunLength.encode(b"abccc"),
+            [1, b'a', b'b', 254, b'c', 128]
+        );
+        let long = Encoding::RunLength.encode(&[7; 130]);
+        assert_eq!(long, [129, 7, 255, 7, 128]);
+
+        // Data encoded already comes before that encoded on writing.
+        let mut parms = Dictionary::new();
+        parms.set("Predictor", 15);
+        parms.set("Globals", ObjectRef::new(9));
+        let stream = Stream::new(Dictionary::new(), b"zlib".to_vec())
+            .filter(Encoding::AsciiHex)
+            .encoded(Filter::Flate, Some(parms.clone()));
+        let (dict, data) = stream.encode();
+        assert_eq!(data, b"7A6C6962>");
+        assert_eq!(
+            dict.to_string(),
+            format!(
+                "<< /Filter [/ASCIIHexDecode /FlateDecode] /DecodeParms [null {}] >>",
+                parms
+            )
+        );
+        assert_eq!(
+            Object::from(stream.clone()).references(),
+            [ObjectRef::new(9)]
+        );
+        assert!(stream.is_filtered());
+        assert_eq!(stream.data(), b"zlib");
+
+        let jpeg = Stream::new(Dictionary::new(), b"jpeg".to_vec()).encoded(Filter::Dct, None);
+        assert_eq!(jpeg.encode().0.to_string(), "<< /Filter /DCTDecode >>");
+        assert!(!Stream::default().is_filtered());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::destination::Destination;
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::writer::PdfWriter;
+use anyhow::Result;
+use std::io::Write;
+
+/// The bookmarks of a document, shown by viewers in a panel next to the
+/// pages: a tree of titled entries, each taking the reader to a
+/// destination. Built with [`PdfDocument::outline`](crate::PdfDocument::outline).
+#[derive(Debug, Clone, Default)]
+pub struct Outline {
+    items: Vec<OutlineItem>,
+}
+
+/// A bookmark, possibly with bookmarks of its own nested under it.
+#[derive(Debug, Clone)]
+pub struct OutlineItem {
+    title: String,
+    destination: Destination,
+    open: bool,
+    children: Vec<OutlineItem>,
+}
+
+impl Outline {
+    /// Adds a top-level bookmark after the others and returns it, for
+    /// nesting more under it.
+    pub fn add(&mut self, title: &str, destination: Destination) -> &mut OutlineItem {
+        push(&mut self.items, title, destination)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Writes the outline dictionary as `id`, the bookmarks numbered from
+    /// `alloc`, and `pages` being the page objects destinations refer to.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        alloc: &mut ObjectAllocator,
+        id: ObjectRef,
+        pages: &[ObjectRef],
+    ) -> Result<()> {
+        let (first, last) = write_items(&self.items, id, writer, alloc, pages)?;
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Outlines"));
+        dict.set("First", first);
+        dict.set("Last", last);
+        dict.set("Count", shown(&self.items));
+        writer.write_object(id, dict)
+    }
+}
+
+impl OutlineItem {
+    /// Nests a bookmark under this one, after those already there, and
+    /// returns it.
+    pub fn child(&mut self, title: &str, destination: Destination) -> &mut OutlineItem {
+        push(&mut self.children, title, destination)
+    }
+
+    /// Has the bookmarks nested under this one shown when the document is
+    /// opened, rather than collapsed, the default.
+    pub fn set_open(&mut self, open: bool) -> &mut Self {
+        self.open = open;
+        self
+    }
+}
+
+fn push<'a>(
+    items: &'a mut Vec<OutlineItem>,
+    title: &str,
+    destination: Destination,
+) -> &'a mut OutlineItem {
+    items.push(OutlineItem {
+        title: title.to_string(),
+        destination,
+        open: false,
+        children: Vec::new(),
+    });
+    items.last_mut().unwrap()
+}
+
+/// How many of `items` and their descendants are shown, those under
+/// collapsed items left out.
+fn shown(items: &[OutlineItem]) -> usize {
+    items
+        .iter()
+        .map(|item| 1 + if item.open { shown(&item.children) } else { 0 })
+        .sum()
+}
+
+/// Writes the linked list of the bookmarks `items`, nested under `parent`,
+/// and returns the first and the last.
+fn write_items<W: Write>(
+    items: &[OutlineItem],
+    parent: ObjectRef,
+    writer: &mut PdfWriter<W>,
+    alloc: &mut ObjectAllocator,
+    pages: &[ObjectRef],
+) -> Result<(ObjectRef, ObjectRef)> {
+    let ids: Vec<ObjectRef> = items.iter().map(|_| alloc.allocate()).collect();
+    for (i, item) in items.iter().enumerate() {
+        let mut dict = Dictionary::new();
+        dict.set("Title", Object::text(&item.title));
+        dict.set("Parent", parent);
+        if i > 0 {
+            dict.set("Prev", ids[i - 1]);
+        }
+        if let Some(&next) = ids.get(i + 1) {
+            dict.set("Next", next);
+        }
+        if !item.children.is_empty() {
+            let (first, last) = write_items(&item.children, ids[i], writer, alloc, pages)?;
+            // A negative count tells how many would be shown if opened.
+            let count = shown(&item.children) as i64;
+            let count = if item.open { count } else { -count };
+            dict.set("First", first);
+            dict.set("Last", last);
+            dict.set("Count", count);
+        }
+        dict.set("Dest", item.destination.to_pdf(pages)?);
+        writer.write_object(ids[i], dict)?;
+    }
+    Ok((ids[0], ids[ids.len() - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shown_items() {
+        let mut outline = Outline::default();
+        let chapter = outline.add("Chapter 1", Destination::page(0));
+        chapter
+            .child("1.1", Destination::page(0))
+            .child("1.1.1", Destination::page(1));
+        chapter.child("1.2", Destination::page(1));
+        outline.add("Chapter 2", Destination::page(2));
+        assert_eq!(shown(&outline.items), 2);
+
+        outline.items[0].set_open(true);
+        assert_eq!(shown(&outline.items), 4);
+        outline.items[0].children[0].set_open(true);
+        assert_eq!(shown(&outline.items), 5);
+    }
+}
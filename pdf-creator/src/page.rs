@@ -0,0 +1,1520 @@
+use crate::annotation::{Annotation, LinkTarget};
+use crate::bidi::{base_level, reorder_line};
+use crate::color::Color;
+use crate::destination::Destination;
+use crate::font::{Font, StandardFont};
+use crate::geometry::{Matrix, Point, Rect};
+use crate::graphics::{BlendMode, CornerRadii, ExtGState, FillRule, Paint, Path, PathBuilder};
+use crate::image::Image;
+use crate::layer::Layer;
+use crate::object::{Dictionary, Object, ObjectRef};
+use crate::resources::Resources;
+use crate::shading::Gradient;
+use crate::structure::{PageStructure, Tag};
+use crate::text::{Align, RichText, TextRendering, TextStyle, wrap_spans};
+use crate::xobject::{FormXObject, XObject};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Paper sizes, in PostScript points (1/72 inch), portrait orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A3,
+    Letter,
+    Legal,
+    Tabloid,
+    Custom {
+        width: f32,
+        height: f32,
+    },
+}
+
+impl PageSize {
+    /// Width and height in points.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match *self {
+            PageSize::A4 => (595.0, 842.0),
+            PageSize::A3 => (842.0, 1191.0),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Legal => (612.0, 1008.0),
+            PageSize::Tabloid => (792.0, 1224.0),
+            PageSize::Custom { width, height } => (width, height),
+        }
+    }
+
+    fn media_box(&self) -> [f32; 4] {
+        let (width, height) = self.dimensions();
+        [0.0, 0.0, width, height]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// Clockwise rotation applied by viewers when displaying or printing a page,
+/// written as the `/Rotate` page attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl Rotation {
+    pub fn degrees(&self) -> u16 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Deg90 => 90,
+            Rotation::Deg180 => 180,
+            Rotation::Deg270 => 270,
+        }
+    }
+}
+
+/// A page of the document. Drawing operations are provided by the
+/// [`ContentStream`] the page dereferences to.
+#[derive(Debug)]
+pub struct Page {
+    media_box: [f32; 4],
+    /// The page as it is cut to once printed, and the area around it that
+    /// printing extends to, for print production.
+    trim_box: Option<Rect>,
+    bleed_box: Option<Rect>,
+    rotation: Rotation,
+    lines: u32,
+    pub(crate) contents: ContentStream,
+    pub(crate) annotations: Vec<Annotation>,
+}
+
+impl Page {
+    pub fn new(size: PageSize) -> Self {
+        Self {
+            media_box: size.media_box(),
+            trim_box: None,
+            bleed_box: None,
+            rotation: Rotation::None,
+            lines: 0,
+            contents: ContentStream::default(),
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn with_orientation(size: PageSize, orientation: Orientation) -> Self {
+        let mut page = Self::new(size);
+        page.set_orientation(orientation);
+        page
+    }
+
+    /// Swaps the MediaBox width and height if the page does not already have
+    /// the requested orientation. Square pages count as portrait.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        if self.orientation() != orientation {
+            let [x0, y0, x1, y1] = self.media_box;
+            self.media_box = [y0, x0, y1, x1];
+        }
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        let [x0, y0, x1, y1] = self.media_box;
+        if x1 - x0 > y1 - y0 {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        }
+    }
+
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Adds a line of 24pt Helvetica below the line added previously,
+    /// starting near the top left corner of an A4 page.
+    pub fn add_line(&mut self, text: &str) {
+        let y = 700.0 - (LINE_SPACING * self.lines) as f32;
+        self.lines += 1;
+        self.contents
+            .draw_text(100.0, y, 24.0, StandardFont::Helvetica, text);
+    }
+
+    pub fn media_box(&self) -> [f32; 4] {
+        self.media_box
+    }
+
+    /// Sets the size the printed page is cut to, within the media box, or
+    /// leaves it to be the media box with `None`, the default. PDF/X
+    /// documents must tell it.
+    pub fn set_trim_box(&mut self, trim_box: Option<Rect>) {
+        self.trim_box = trim_box;
+    }
+
+    pub fn trim_box(&self) -> Option<Rect> {
+        self.trim_box
+    }
+
+    /// Sets how far past the trim box printing goes, for what is printed
+    /// to the edges of the cut page to still reach them, or leaves it to be
+    /// the media box with `None`, the default.
+    pub fn set_bleed_box(&mut self, bleed_box: Option<Rect>) {
+        self.bleed_box = bleed_box;
+    }
+
+    pub fn bleed_box(&self) -> Option<Rect> {
+        self.bleed_box
+    }
+
+    /// Makes `rect` a link opening `uri`, such as a web address or a
+    /// `mailto:` address, when clicked. Nothing is drawn: the link goes over
+    /// whatever the page shows there.
+    pub fn link(&mut self, rect: Rect, uri: &str) {
+        let target = LinkTarget::Uri(uri.to_string());
+        self.annotations.push(Annotation::link(rect, target));
+    }
+
+    /// Makes `rect` a link to `destination` in the document, such as an
+    /// entry of a table of contents or a cross-reference.
+    pub fn link_to(&mut self, rect: Rect, destination: Destination) {
+        let target = LinkTarget::Destination(destination);
+        self.annotations.push(Annotation::link(rect, target));
+    }
+
+    /// Places `annotation` over the page, above those added before.
+    pub fn add_annotation(&mut self, annotation: Annotation) {
+        self.annotations.push(annotation);
+    }
+
+    /// The media box, `[0 0 595.28 841.89]` for instance.
+    fn media_box_pdf(&self) -> Object {
+        Object::numbers(&self.media_box)
+    }
+
+    /// The page dictionary, `annots` being the annotation objects of the
+    /// page in order, `index` the key of its tagged content in the parent
+    /// tree and `resources` the dictionary of its resources. The attributes
+    /// the page has as `inherited` has them are left to be inherited.
+    pub(crate) fn to_dict(
+        &self,
+        parent: ObjectRef,
+        contents: ObjectRef,
+        annots: &[ObjectRef],
+        index: usize,
+        resources: Dictionary,
+        inherited: &InheritedAttributes,
+    ) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Page"));
+        dict.set("Parent", parent);
+        let media_box = self.media_box_pdf();
+        if inherited.media_box.as_ref() != Some(&media_box) {
+            dict.set("MediaBox", media_box);
+        }
+        // The boxes, the rotation and the tagged content.
+        if let Some(bleed_box) = self.bleed_box {
+            dict.set("BleedBox", bleed_box);
+        }
+        if let Some(trim_box) = self.trim_box {
+            dict.set("TrimBox", trim_box);
+        }
+        if self.rotation != Rotation::None {
+            dict.set("Rotate", u32::from(self.rotation.degrees()));
+        }
+        if !self.contents.structure.is_empty() {
+            dict.set("StructParents", index);
+        }
+        dict.set("Contents", contents);
+        if !annots.is_empty() {
+            dict.set(
+                "Annots",
+                annots.iter().map(|&annot| annot.into()).collect::<Vec<_>>(),
+            );
+        }
+        if inherited.resources.as_ref() != Some(&resources) {
+            dict.set("Resources", resources);
+        }
+        dict
+    }
+}
+
+/// The media box and the resources written on the root of the page tree,
+/// for the pages that have the same to inherit rather than repeat them.
+#[derive(Debug, Default)]
+pub(crate) struct InheritedAttributes {
+    media_box: Option<Object>,
+    resources: Option<Dictionary>,
+}
+
+impl InheritedAttributes {
+    /// The media box and the resources most of `pages` have, the first
+    /// found of those as common as any other, `resources` being the
+    /// dictionaries of the resources of the pages.
+    pub(crate) fn of(pages: &[Page], resources: &[Dictionary]) -> Self {
+        Self {
+            media_box: most_common(pages.iter().map(Page::media_box_pdf)),
+            resources: most_common(resources).cloned(),
+        }
+    }
+
+    /// Sets the attributes on `root`, the dictionary of the root of the
+    /// page tree.
+    pub(crate) fn set_on(&self, root: &mut Dictionary) {
+        if let Some(media_box) = &self.media_box {
+            root.set("MediaBox", media_box.clone());
+        }
+        if let Some(resources) = &self.resources {
+            root.set("Resources", resources.clone());
+        }
+    }
+}
+
+/// The value found most often in `values`, the first of those found as
+/// often as any other, values being the same when they are written the
+/// same.
+fn most_common<T: fmt::Display>(values: impl IntoIterator<Item = T>) -> Option<T> {
+    let values: Vec<T> = values.into_iter().collect();
+    // Where each value is first found, and how often.
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (i, value) in values.iter().enumerate() {
+        counts.entry(value.to_string()).or_insert((i, 0)).1 += 1;
+    }
+    let (first, _) = counts
+        .into_values()
+        .min_by_key(|&(first, count)| (Reverse(count), first))?;
+    values.into_iter().nth(first)
+}
+
+/// An A4 page showing a single line of text.
+impl From<&str> for Page {
+    fn from(content: &str) -> Self {
+        let mut page = Page::new(PageSize::A4);
+        page.add_line(content);
+        page
+    }
+}
+
+impl Deref for Page {
+    type Target = ContentStream;
+
+    fn deref(&self) -> &ContentStream {
+        &self.contents
+    }
+}
+
+impl DerefMut for Page {
+    fn deref_mut(&mut self) -> &mut ContentStream {
+        &mut self.contents
+    }
+}
+
+/// Vertical distance between consecutive lines added by [`Page::add_line`],
+/// in points.
+const LINE_SPACING: u32 = 28;
+
+/// A line of a text object.
+struct ShownLine<'t> {
+    /// Offset from the start of the text object.
+    dx: f32,
+    /// Distance down from the baseline of the previous line.
+    advance: f32,
+    runs: Vec<Run<'t>>,
+}
+
+/// Text shown in one style, followed by an adjustment of the position of
+/// what comes next, in thousandths of the font size as for `TJ`.
+struct Run<'t> {
+    style: &'t TextStyle,
+    text: Cow<'t, str>,
+    adjust: f32,
+}
+
+/// Whether text in the two styles can be shown by the same operator.
+fn same_state(a: &TextStyle, b: &TextStyle) -> bool {
+    a.font == b.font
+        && a.size == b.size
+        && a.color == b.color
+        && a.rendering == b.rendering
+        && a.stroke_color == b.stroke_color
+        && a.char_spacing == b.char_spacing
+        && a.word_spacing == b.word_spacing
+}
+
+/// The word spacing to set with `Tw`, which only applies to the single-byte
+/// code 32: text in embedded fonts, shown with two-byte glyph ids, has its
+/// word spacing applied as `TJ` adjustments after spaces instead.
+fn tw_operand(style: &TextStyle) -> f32 {
+    match style.font {
+        Font::Standard(_) => style.word_spacing,
+        Font::TrueType(_) => 0.0,
+    }
+}
+
+/// The sequence of drawing operators making up what a page shows.
+#[derive(Debug, Default)]
+pub struct ContentStream {
+    ops: Vec<u8>,
+    pub(crate) resources: Resources,
+    pub(crate) structure: PageStructure,
+}
+
+impl ContentStream {
+    /// Shows `text` with its baseline starting at (`x`, `y`), in its own
+    /// `BT`/`ET` text object so runs never affect one another. Each `\n`
+    /// starts a new line, 1.2 times the font size further down.
+    pub fn draw_text(&mut self, x: f32, y: f32, size: f32, font: impl Into<Font>, text: &str) {
+        self.draw_text_styled(x, y, text, &TextStyle::new(font, size));
+    }
+
+    /// Like [`draw_text`](Self::draw_text), with the line height of `style`.
+    /// Its alignment places each line relative to `x`: starting there,
+    /// centered on it or ending there. Right-to-left scripts are reordered
+    /// for display, see [`draw_text_wrapped`](Self::draw_text_wrapped).
+    pub fn draw_text_styled(&mut self, x: f32, y: f32, text: &str, style: &TextStyle) {
+        self.draw_text_transformed(Matrix::translate(x, y), text, style);
+    }
+
+    /// Shows `text` rotated counterclockwise by `degrees` about the start of
+    /// its baseline at (`x`, `y`), such as a diagonal watermark or a column
+    /// header running upwards. Lines and alignment follow the rotation.
+    pub fn draw_text_rotated(
+        &mut self,
+        x: f32,
+        y: f32,
+        degrees: f32,
+        text: &str,
+        style: &TextStyle,
+    ) {
+        let matrix = Matrix::rotate(degrees).then(&Matrix::translate(x, y));
+        self.draw_text_transformed(matrix, text, style);
+    }
+
+    /// Like [`draw_text_styled`](Self::draw_text_styled) in the coordinate
+    /// system `matrix` maps onto the page, set as the text matrix (`Tm`):
+    /// the text starts at its origin, running along its x axis.
+    pub fn draw_text_transformed(&mut self, matrix: Matrix, text: &str, style: &TextStyle) {
+        let base = base_level(text);
+        let shaped: Vec<Cow<str>> = text
+            .split('\n')
+            .map(|line| style.font.shape(line))
+            .collect();
+        let lines: Vec<ShownLine> = shaped
+            .iter()
+            .map(|line| {
+                let width = style.measure(line);
+                let dx = match style.align {
+                    Align::Left | Align::Justify => 0.0,
+                    Align::Center => -width / 2.0,
+                    Align::Right => -width,
+                };
+                let runs = reorder_line(&[line], base)
+                    .into_iter()
+                    .filter(|(_, text)| !text.is_empty())
+                    .map(|(_, text)| Run {
+                        style,
+                        text,
+                        adjust: 0.0,
+                    })
+                    .collect();
+                ShownLine {
+                    dx,
+                    advance: style.line_height(),
+                    runs,
+                }
+            })
+            .collect();
+        self.show_lines(matrix, &lines);
+    }
+
+    /// Shows `text` written top to bottom, as Japanese and Chinese are
+    /// traditionally set, in a column centered on `x` starting at `y`. Each
+    /// `\n` starts a new column to the left, the line height of `style`
+    /// away. Its alignment places each column relative to `y`: starting
+    /// there, centered on it or ending there.
+    ///
+    /// Embedded fonts are written in vertical mode (`Identity-V`) with their
+    /// vertical metrics and glyph forms. Standard fonts have no vertical
+    /// mode; their characters are stacked upright, one em apart.
+    pub fn draw_text_vertical(&mut self, x: f32, y: f32, text: &str, style: &TextStyle) {
+        let style = TextStyle {
+            font: style.font.vertical(),
+            ..style.clone()
+        };
+        for (index, column) in text.split('\n').enumerate() {
+            let length = style.font.measure_vertical(column, style.size);
+            let top = match style.align {
+                Align::Left | Align::Justify => y,
+                Align::Center => y + length / 2.0,
+                Align::Right => y + length,
+            };
+            let x = x - index as f32 * style.line_height();
+            let lines: Vec<ShownLine> = match style.font {
+                _ if column.is_empty() => continue,
+                Font::TrueType(_) => vec![ShownLine {
+                    dx: 0.0,
+                    advance: 0.0,
+                    runs: vec![Run {
+                        style: &style,
+                        text: Cow::Borrowed(column),
+                        adjust: 0.0,
+                    }],
+                }],
+                Font::Standard(_) => column
+                    .char_indices()
+                    .map(|(start, ch)| {
+                        let text = &column[start..start + ch.len_utf8()];
+                        ShownLine {
+                            dx: -style.measure(text) / 2.0,
+                            advance: style.size,
+                            runs: vec![Run {
+                                style: &style,
+                                text: Cow::Borrowed(text),
+                                adjust: 0.0,
+                            }],
+                        }
+                    })
+                    .collect(),
+            };
+            // Vertical fonts place glyphs below the pen, standard fonts on
+            // the baseline of their cell.
+            let y = match style.font {
+                Font::TrueType(_) => top,
+                Font::Standard(_) => top - style.font.ascent(style.size),
+            };
+            self.show_lines(Matrix::translate(x, y), &lines);
+        }
+    }
+
+    /// Shows `text` as a paragraph filling `rect` from the top, broken into
+    /// lines at spaces so that none is wider than the rectangle and placed
+    /// according to `style.align`. Lines that would fall below the rectangle
+    /// are left out.
+    ///
+    /// Hebrew, Arabic and other right-to-left text is laid out following the
+    /// Unicode bidirectional algorithm, the paragraph direction being that of
+    /// its first letter, and Arabic letters take their joining forms when
+    /// the font has them (embedded fonts only: the standard fonts cannot
+    /// show these scripts).
+    pub fn draw_text_wrapped(&mut self, rect: Rect, text: &str, style: &TextStyle) {
+        self.draw_spans_wrapped(rect, &[(text, style)], style.align);
+    }
+
+    /// Like [`draw_text_wrapped`](Self::draw_text_wrapped) for a paragraph
+    /// mixing styles, switching fonts, sizes and colors mid-line as needed.
+    pub fn draw_rich_text_wrapped(&mut self, rect: Rect, text: &RichText) {
+        let spans: Vec<(&str, &TextStyle)> = text
+            .spans
+            .iter()
+            .map(|span| (span.text.as_str(), &span.style))
+            .collect();
+        self.draw_spans_wrapped(rect, &spans, text.align);
+    }
+
+    fn draw_spans_wrapped(&mut self, rect: Rect, spans: &[(&str, &TextStyle)], align: Align) {
+        let shaped: Vec<Cow<str>> = spans
+            .iter()
+            .map(|(text, style)| style.font.shape(text))
+            .collect();
+        let spans: Vec<(&str, &TextStyle)> = shaped
+            .iter()
+            .zip(spans)
+            .map(|(text, (_, style))| (text.as_ref(), *style))
+            .collect();
+        let base = base_level(&spans.iter().map(|(text, _)| *text).collect::<String>());
+        let mut baseline = rect.top();
+        let mut first_baseline = None;
+        let mut lines = Vec::new();
+        for line in wrap_spans(&spans, rect.width) {
+            let advance = if lines.is_empty() {
+                line.size
+            } else {
+                line.height
+            };
+            baseline -= advance;
+            if baseline < rect.y {
+                break;
+            }
+            first_baseline.get_or_insert(baseline);
+            let slack = rect.width - line.width();
+            let mut adjusts = vec![0.0; line.fragments.len()];
+            let dx = match align {
+                Align::Justify if !line.last && !line.gaps.is_empty() => {
+                    // Spread the slack over the gaps between words.
+                    let extra = slack / line.gaps.len() as f32;
+                    for gap in &line.gaps {
+                        let size = line.fragments[*gap].style.size;
+                        adjusts[*gap] = (-extra * 1000.0 / size * 100.0).round() / 100.0;
+                    }
+                    0.0
+                }
+                Align::Justify if base.is_rtl() => slack,
+                Align::Left | Align::Justify => 0.0,
+                Align::Center => slack / 2.0,
+                Align::Right => slack,
+            };
+            let pieces: Vec<&str> = line
+                .fragments
+                .iter()
+                .map(|fragment| fragment.text)
+                .collect();
+            let runs = reorder_line(&pieces, base)
+                .into_iter()
+                .map(|(index, text)| Run {
+                    style: line.fragments[index].style,
+                    text,
+                    // A gap cut in two by the reordering widens only once.
+                    adjust: std::mem::take(&mut adjusts[index]),
+                })
+                .collect();
+            lines.push(ShownLine { dx, advance, runs });
+        }
+        if let Some(y) = first_baseline {
+            self.show_lines(Matrix::translate(rect.x, y), &lines);
+        }
+    }
+
+    /// Emits a text object showing `lines` one below the other in the
+    /// coordinate system of `origin`, the first with its baseline on the x
+    /// axis, each line starting its offset to the right of the y axis. A
+    /// line at the offset of the one before and the leading set with `TL`
+    /// below it is moved to with `T*`, any other with `Td`. Runs in the
+    /// same style share a `Tj` or `TJ`, kerned pairs of glyphs being pulled
+    /// together with `TJ` adjustments. Colors and rendering modes are set
+    /// inside `q`/`Q`, so they do not carry over to later drawing.
+    fn show_lines(&mut self, origin: Matrix, lines: &[ShownLine]) {
+        let mut runs = lines.iter().flat_map(|line| &line.runs);
+        let scoped = runs.clone().any(|run| {
+            let style = run.style;
+            style.color.is_some()
+                || style.stroke_color.is_some()
+                || style.rendering != TextRendering::Fill
+                || style.char_spacing != 0.0
+                || style.word_spacing != 0.0
+        });
+        let mut op = String::new();
+        if scoped {
+            op.push_str("q\n");
+        }
+        op.push_str("BT\n");
+        let mut current = None;
+        if let Some(run) = runs.next() {
+            self.select_style(&mut current, run.style, &mut op);
+        }
+        let leading = lines.get(1).map(|line| line.advance);
+        if let Some(leading) = leading {
+            op.push_str(&format!("{} TL\n", leading));
+        }
+
+        let mut previous: Option<f32> = None;
+        for line in lines {
+            match previous {
+                None => {
+                    let start = Matrix::translate(line.dx, 0.0).then(&origin);
+                    if start.is_translation() {
+                        op.push_str(&format!("{} {} Td\n", start.e, start.f));
+                    } else {
+                        op.push_str(&format!("{} Tm\n", start));
+                    }
+                }
+                Some(previous) if previous == line.dx && leading == Some(line.advance) => {
+                    op.push_str("T*\n")
+                }
+                Some(previous) => {
+                    op.push_str(&format!("{} {} Td\n", line.dx - previous, -line.advance))
+                }
+            }
+            previous = Some(line.dx);
+
+            for group in line.runs.chunk_by(|a, b| same_state(a.style, b.style)) {
+                let style = group[0].style;
+                self.select_style(&mut current, style, &mut op);
+                // Join runs not separated by an adjustment, then kern them.
+                let mut joined: Vec<(String, f32)> = Vec::new();
+                for run in group {
+                    match joined.last_mut() {
+                        Some((text, adjust)) if *adjust == 0.0 => {
+                            text.push_str(&run.text);
+                            *adjust = run.adjust;
+                        }
+                        _ => joined.push((run.text.to_string(), run.adjust)),
+                    }
+                }
+                let mut kerned: Vec<(&str, f32)> = Vec::new();
+                for (text, adjust) in &joined {
+                    kerned.extend(style.font.kern_runs(text));
+                    kerned.last_mut().unwrap().1 += adjust;
+                }
+                let spacing = style.word_spacing - tw_operand(style);
+                if spacing != 0.0 {
+                    // Word spacing `Tw` cannot apply, pushed after each space.
+                    let extra = (-spacing * 1000.0 / style.size * 100.0).round() / 100.0;
+                    let mut spaced = Vec::new();
+                    for (text, adjust) in kerned {
+                        let mut rest = text;
+                        while let Some(index) = rest.find(' ') {
+                            let (piece, tail) = rest.split_at(index + 1);
+                            let piece_adjust = if tail.is_empty() {
+                                adjust + extra
+                            } else {
+                                extra
+                            };
+                            spaced.push((piece, piece_adjust));
+                            rest = tail;
+                        }
+                        if !rest.is_empty() || text.is_empty() {
+                            spaced.push((rest, adjust));
+                        }
+                    }
+                    kerned = spaced;
+                }
+                let (_, used) = self.resources.font(&style.font);
+                match kerned.as_slice() {
+                    [(text, adjust)] if *adjust == 0.0 => {
+                        op.push_str(&style.font.encode(text, used));
+                        op.push_str(" Tj\n");
+                    }
+                    _ => {
+                        let mut parts = Vec::new();
+                        for (text, adjust) in &kerned {
+                            parts.push(style.font.encode(text, used));
+                            if *adjust != 0.0 {
+                                parts.push(adjust.to_string());
+                            }
+                        }
+                        op.push_str(&format!("[{}] TJ\n", parts.join(" ")));
+                    }
+                }
+            }
+        }
+        op.push_str("ET\n");
+        if scoped {
+            op.push_str("Q\n");
+        }
+        self.ops.extend_from_slice(op.as_bytes());
+    }
+
+    /// Emits the `Tf`, color, `Tr`, `Tc` and `Tw` operators needed to go
+    /// from the `current` text style to `style`.
+    fn select_style<'t>(
+        &mut self,
+        current: &mut Option<&'t TextStyle>,
+        style: &'t TextStyle,
+        op: &mut String,
+    ) {
+        if current.is_none_or(|current| current.font != style.font || current.size != style.size) {
+            let (name, _) = self.resources.font(&style.font);
+            op.push_str(&format!("/{} {} Tf\n", name, style.size));
+        }
+        let color = current.and_then(|current| current.color.as_ref());
+        if color != style.color.as_ref() {
+            let fill = style.color.as_ref().unwrap_or(&Color::BLACK);
+            op.push_str(&format!("{}\n", fill.fill_op(&mut self.resources)));
+        }
+        let stroke_color = current.and_then(|current| current.stroke_color.as_ref());
+        if stroke_color != style.stroke_color.as_ref() {
+            let stroke = style.stroke_color.as_ref().unwrap_or(&Color::BLACK);
+            op.push_str(&format!("{}\n", stroke.stroke_op(&mut self.resources)));
+        }
+        let rendering = current.map_or(TextRendering::Fill, |current| current.rendering);
+        if rendering != style.rendering {
+            op.push_str(&format!("{} Tr\n", style.rendering.mode()));
+        }
+        let (char_spacing, word_spacing) = current.map_or((0.0, 0.0), |current| {
+            (current.char_spacing, tw_operand(current))
+        });
+        if char_spacing != style.char_spacing {
+            op.push_str(&format!("{} Tc\n", style.char_spacing));
+        }
+        if word_spacing != tw_operand(style) {
+            op.push_str(&format!("{} Tw\n", tw_operand(style)));
+        }
+        *current = Some(style);
+    }
+
+    /// Draws a straight line from `from` to `to` with the outline of `paint`.
+    pub fn line(&mut self, from: impl Into<Point>, to: impl Into<Point>, paint: &Paint) {
+        let (from, to) = (from.into(), to.into());
+        let paint = Paint {
+            fill: None,
+            ..paint.clone()
+        };
+        let path = PathBuilder::new().move_to(from).line_to(to).build();
+        self.draw_path(&path, &paint);
+    }
+
+    /// Draws the rectangle with its lower-left corner at (`x`, `y`).
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, paint: &Paint) {
+        self.draw_path(&Path::rect(Rect::new(x, y, width, height)), paint);
+    }
+
+    /// Draws `rect` with rounded corners, such as a report card or a button.
+    /// See [`Path::rounded_rect`].
+    pub fn rounded_rect(&mut self, rect: Rect, radii: impl Into<CornerRadii>, paint: &Paint) {
+        self.draw_path(&Path::rounded_rect(rect, radii), paint);
+    }
+
+    /// Draws the circle of `radius` around `center`.
+    pub fn circle(&mut self, center: impl Into<Point>, radius: f32, paint: &Paint) {
+        self.draw_path(&Path::circle(center, radius), paint);
+    }
+
+    /// Draws the axis-aligned ellipse around `center` with radii `rx` and
+    /// `ry`.
+    pub fn ellipse(&mut self, center: impl Into<Point>, rx: f32, ry: f32, paint: &Paint) {
+        self.draw_path(&Path::ellipse(center, rx, ry), paint);
+    }
+
+    /// Draws the shape of `path`, outlining and filling it as `paint` says.
+    /// Subpaths left open are closed for filling but not when stroked. Any
+    /// color or width set is kept within `q`/`Q`.
+    pub fn draw_path(&mut self, path: &Path, paint: &Paint) {
+        if path.is_empty() {
+            return;
+        }
+        let state = paint.state_ops(&mut self.resources);
+        let mut op = String::new();
+        if !state.is_empty() {
+            op.push_str("q\n");
+            op.push_str(&state);
+        }
+        op.push_str(&path.to_ops());
+        op.push_str(paint.paint_op(path.fill_rule()));
+        op.push('\n');
+        if !state.is_empty() {
+            op.push_str("Q\n");
+        }
+        self.ops.extend_from_slice(op.as_bytes());
+    }
+
+    /// Fills the inside of `path` with `gradient`, painted with the `sh`
+    /// operator while the path clips, within `q`/`Q`.
+    pub fn fill_gradient(&mut self, path: &Path, gradient: &Gradient) {
+        self.clip(path, |g| {
+            let name = g.resources.shading(gradient);
+            g.ops
+                .extend_from_slice(format!("/{} sh\n", name).as_bytes());
+        });
+    }
+
+    /// Runs `draw` with what it paints cut to the inside of `path`, as its
+    /// fill rule decides, within `q`/`Q`: a photo in a circle, text masked
+    /// to a shape. Clips drawn inside combine, showing only where all
+    /// overlap.
+    pub fn clip(&mut self, path: &Path, draw: impl FnOnce(&mut ContentStream)) {
+        // Nothing would show through an empty clipping path.
+        if path.is_empty() {
+            return;
+        }
+        let clip = match path.fill_rule() {
+            FillRule::NonZero => "W",
+            FillRule::EvenOdd => "W*",
+        };
+        self.save_state(|g| {
+            let op = format!("{}{} n\n", path.to_ops(), clip);
+            g.ops.extend_from_slice(op.as_bytes());
+            draw(g);
+        });
+    }
+
+    /// Runs `draw` with everything it paints, outlines included, made
+    /// translucent: an `opacity` of 0 is invisible, 1 opaque. Such as a
+    /// watermark over the page content, or a highlight under it.
+    pub fn with_opacity(&mut self, opacity: f32, draw: impl FnOnce(&mut ContentStream)) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        let state = ExtGState {
+            stroke_alpha: Some(opacity),
+            fill_alpha: Some(opacity),
+            ..ExtGState::default()
+        };
+        self.with_ext_g_state(&state, draw);
+    }
+
+    /// Runs `draw` with what it paints blended into what is already there
+    /// as `mode` says, such as multiplying a tint over a photo.
+    pub fn with_blend_mode(&mut self, mode: BlendMode, draw: impl FnOnce(&mut ContentStream)) {
+        let state = ExtGState {
+            blend_mode: Some(mode),
+            ..ExtGState::default()
+        };
+        self.with_ext_g_state(&state, draw);
+    }
+
+    /// Runs `draw` after setting `state` with `gs`, within `q`/`Q`.
+    fn with_ext_g_state(&mut self, state: &ExtGState, draw: impl FnOnce(&mut ContentStream)) {
+        let name = self.resources.ext_g_state(state);
+        self.save_state(|g| {
+            g.ops
+                .extend_from_slice(format!("/{} gs\n", name).as_bytes());
+            draw(g);
+        });
+    }
+
+    /// Draws `image` upright, stretched to fill `rect`. Keep the aspect ratio
+    /// of the image in `rect` to avoid distorting it.
+    pub fn draw_image(&mut self, image: &Image, rect: Rect) {
+        let name = self.resources.image(image, rect.width, rect.height);
+        self.transformed(image.placement(rect), |g| {
+            g.ops
+                .extend_from_slice(format!("/{} Do\n", name).as_bytes());
+        });
+    }
+
+    /// Draws `image` stretched to fill `rect` like
+    /// [`ContentStream::draw_image`], its data written inline in the content
+    /// stream rather than as an object of its own. Meant for small images of
+    /// a few kilobytes at most, each drawn once, such as sparklines or chart
+    /// markers. Images with transparency and JPEG 2000 images, neither of
+    /// which can be inline, are drawn as objects of their own all the same.
+    pub fn draw_inline_image(&mut self, image: &Image, rect: Rect) {
+        let Some(ops) = image.inline_ops() else {
+            return self.draw_image(image, rect);
+        };
+        self.resources.device_space(image.device_space());
+        self.transformed(image.placement(rect), |g| g.ops.extend(ops));
+    }
+
+    /// Places `form` with its lower left corner at (`x`, `y`).
+    pub fn draw_form(&mut self, form: &FormXObject, x: f32, y: f32) {
+        self.draw_form_transformed(form, Matrix::translate(x, y));
+    }
+
+    /// Places `form` mapped through `matrix`, to scale, rotate or skew it.
+    pub fn draw_form_transformed(&mut self, form: &FormXObject, matrix: Matrix) {
+        let name = self.resources.x_object(&XObject::Form(form.clone()));
+        self.transformed(matrix, |g| {
+            g.ops
+                .extend_from_slice(format!("/{} Do\n", name).as_bytes());
+        });
+    }
+
+    /// Runs `draw` with what it paints put in `layer`, marked between `BDC`
+    /// and `EMC`, so that readers can hide it along with the rest of the
+    /// layer.
+    pub fn in_layer(&mut self, layer: &Layer, draw: impl FnOnce(&mut ContentStream)) {
+        let name = self.resources.layer(layer);
+        // Tagged content is marked within the layer, not around it.
+        self.structure.end_sequence(&mut self.ops);
+        self.ops
+            .extend_from_slice(format!("/OC /{} BDC\n", name).as_bytes());
+        self.structure.begin_sequence(&mut self.ops);
+        draw(self);
+        self.structure.end_sequence(&mut self.ops);
+        self.ops.extend_from_slice(b"EMC\n");
+        self.structure.begin_sequence(&mut self.ops);
+    }
+
+    /// Runs `draw` with what it paints tagged as an element of the kind of
+    /// `tag` in the structure tree, such as a paragraph or a table cell,
+    /// given as a [`StructureType`](crate::StructureType) or as a [`Tag`]
+    /// with alternative text for figures, within the
+    /// element being drawn into, if any: nested calls make elements within
+    /// elements. The elements of each page are written in a `Document`
+    /// element, in the order of the pages, and the document is then marked
+    /// as tagged, for assistive technology to read it by its structure.
+    ///
+    /// ```
+    /// # use pdf_creator::{Page, PageSize, StandardFont, StructureType};
+    /// let mut page = Page::new(PageSize::A4);
+    /// page.tagged(StructureType::H1, |g| {
+    ///     g.draw_text(72.0, 760.0, 24.0, StandardFont::HelveticaBold, "Minutes")
+    /// });
+    /// page.tagged(StructureType::L, |g| {
+    ///     for (i, item) in ["Budget", "Hiring"].into_iter().enumerate() {
+    ///         let y = 720.0 - 16.0 * i as f32;
+    ///         g.tagged(StructureType::LI, |g| {
+    ///             g.tagged(StructureType::Lbl, |g| {
+    ///                 g.draw_text(72.0, y, 12.0, StandardFont::Helvetica, "•")
+    ///             });
+    ///             g.tagged(StructureType::LBody, |g| {
+    ///                 g.draw_text(84.0, y, 12.0, StandardFont::Helvetica, item)
+    ///             });
+    ///         });
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// Tagging within an [`artifact`](Self::artifact), or in a form or a
+    /// tiling pattern, whose content is tagged where it is drawn instead,
+    /// makes the document fail to be written.
+    pub fn tagged(&mut self, tag: impl Into<Tag>, draw: impl FnOnce(&mut ContentStream)) {
+        self.structure.open(tag.into(), &mut self.ops);
+        draw(self);
+        self.structure.close(&mut self.ops);
+    }
+
+    /// Runs `draw` with what it paints marked as an artifact, which is not
+    /// part of the content but of the layout, such as running headers,
+    /// page numbers and decorations, for assistive technology to skip it.
+    pub fn artifact(&mut self, draw: impl FnOnce(&mut ContentStream)) {
+        self.structure.begin_artifact(&mut self.ops);
+        draw(self);
+        self.structure.end_artifact(&mut self.ops);
+    }
+
+    /// Runs `draw` with its coordinates mapped through `matrix` (`cm`), on
+    /// top of any transformation already in effect, within `q`/`Q`.
+    /// Gradient and pattern colors stay where they are on the page.
+    pub fn transformed(&mut self, matrix: Matrix, draw: impl FnOnce(&mut ContentStream)) {
+        self.save_state(|g| {
+            g.ops
+                .extend_from_slice(format!("{} cm\n", matrix).as_bytes());
+            draw(g);
+        });
+    }
+
+    /// Runs `draw` with its origin moved to (`dx`, `dy`).
+    pub fn translated(&mut self, dx: f32, dy: f32, draw: impl FnOnce(&mut ContentStream)) {
+        self.transformed(Matrix::translate(dx, dy), draw);
+    }
+
+    /// Runs `draw` rotated counterclockwise by `degrees` about `center`.
+    pub fn rotated_about(
+        &mut self,
+        center: impl Into<Point>,
+        degrees: f32,
+        draw: impl FnOnce(&mut ContentStream),
+    ) {
+        self.transformed(Matrix::rotate_about(center, degrees), draw);
+    }
+
+    /// Runs `draw` scaled by `sx` horizontally and `sy` vertically, about
+    /// the origin.
+    pub fn scaled(&mut self, sx: f32, sy: f32, draw: impl FnOnce(&mut ContentStream)) {
+        self.transformed(Matrix::scale(sx, sy), draw);
+    }
+
+    /// Runs `draw` between `q` and `Q`, so that whatever it changes in the
+    /// graphics state (clipping, transformations, opacity, colors) ends with
+    /// it, and returns what `draw` returns. Scopes nest, each `q` matched
+    /// by its `Q`.
+    pub fn save_state<R>(&mut self, draw: impl FnOnce(&mut ContentStream) -> R) -> R {
+        self.ops.extend_from_slice(b"q\n");
+        let result = draw(self);
+        self.ops.extend_from_slice(b"Q\n");
+        result
+    }
+
+    pub(crate) fn ops(&self) -> &[u8] {
+        &self.ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::ImageColorSpace;
+    use crate::resources::ResourceRefs;
+    use crate::structure::StructureType;
+
+    #[test]
+    fn test_page_sizes() {
+        assert_eq!(
+            Page::new(PageSize::A4).media_box(),
+            [0.0, 0.0, 595.0, 842.0]
+        );
+        assert_eq!(
+            Page::new(PageSize::Letter).media_box(),
+            [0.0, 0.0, 612.0, 792.0]
+        );
+        let custom = PageSize::Custom {
+            width: 288.0,
+            height: 432.0,
+        };
+        assert_eq!(Page::new(custom).media_box(), [0.0, 0.0, 288.0, 432.0]);
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(72.0, 36.5, 10.0, StandardFont::Helvetica, "Footer");
+        page.draw_text(300.0, 400.0, 18.0, StandardFont::Helvetica, "Middle");
+        page.draw_text(300.0, 300.0, 18.0, StandardFont::CourierBold, "Code");
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "BT\n/F1 10 Tf\n72 36.5 Td\n(Footer) Tj\nET\n\
+             BT\n/F1 18 Tf\n300 400 Td\n(Middle) Tj\nET\n\
+             BT\n/F2 18 Tf\n300 300 Td\n(Code) Tj\nET\n"
+        );
+    }
+
+    #[test]
+    fn test_draw_text_wrapped() {
+        let mut page = Page::new(PageSize::A4);
+        let style = TextStyle::new(StandardFont::Courier, 10.0);
+        let rect = Rect::new(50.0, 700.0, 60.0, 30.0);
+        page.draw_text_wrapped(rect, "the quick brown fox jumps over", &style);
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "BT\n/F1 10 Tf\n12 TL\n50 720 Td\n(the quick) Tj\nT*\n(brown fox) Tj\nET\n"
+        );
+    }
+
+    #[test]
+    fn test_alignment() {
+        let rect = Rect::new(50.0, 700.0, 60.0, 30.0);
+        let text = "ab cd efghij";
+        let mut page = Page::new(PageSize::A4);
+        for align in [Align::Center, Align::Right, Align::Justify] {
+            let style = TextStyle {
+                align,
+                ..TextStyle::new(StandardFont::Courier, 10.0)
+            };
+            page.draw_text_wrapped(rect, text, &style);
+        }
+        let ops = String::from_utf8_lossy(page.ops());
+        assert!(ops.contains("65 720 Td\n(ab cd) Tj\n-3 -12 Td\n(efghij) Tj\n"));
+        assert!(ops.contains("80 720 Td\n(ab cd) Tj\n-6 -12 Td\n(efghij) Tj\n"));
+        // Justified, the 30pt of slack go into the single gap.
+        assert!(ops.contains("50 720 Td\n[(ab ) -3000 (cd)] TJ\nT*\n(efghij) Tj\n"));
+    }
+
+    #[test]
+    fn test_multiline_text() {
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(10.0, 100.0, 10.0, StandardFont::Courier, "one\n\nthree");
+        let style = TextStyle {
+            leading: Some(20.0),
+            align: Align::Right,
+            ..TextStyle::new(StandardFont::Courier, 10.0)
+        };
+        page.draw_text_styled(100.0, 50.0, "ab\nabcd", &style);
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "BT\n/F1 10 Tf\n12 TL\n10 100 Td\n(one) Tj\nT*\nT*\n(three) Tj\nET\n\
+             BT\n/F1 10 Tf\n20 TL\n88 50 Td\n(ab) Tj\n-12 -20 Td\n(abcd) Tj\nET\n"
+        );
+    }
+
+    #[test]
+    fn test_rich_text() {
+        let regular = TextStyle::new(StandardFont::Courier, 10.0);
+        let bold = TextStyle {
+            color: Some(Color::Gray(0.5)),
+            ..TextStyle::new(StandardFont::CourierBold, 20.0)
+        };
+        let text = RichText {
+            align: Align::Justify,
+            ..RichText::new()
+        }
+        .span("a ", &regular)
+        .span("big", &bold)
+        .span(" b c", &regular);
+        let mut page = Page::new(PageSize::A4);
+        page.draw_rich_text_wrapped(Rect::new(0.0, 0.0, 66.0, 100.0), &text);
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\nBT\n/F1 10 Tf\n12 TL\n0 80 Td\n[(a ) -300] TJ\n\
+             /F2 20 Tf\n0.5 g\n(big) Tj\n/F1 10 Tf\n0 g\n[( ) -300 (b)] TJ\n\
+             T*\n(c) Tj\nET\nQ\n"
+        );
+    }
+
+    #[test]
+    fn test_text_color() {
+        let mut page = Page::new(PageSize::A4);
+        let style = TextStyle {
+            color: Some(Color::Rgb(1.0, 0.0, 0.0)),
+            ..TextStyle::default()
+        };
+        page.draw_text_styled(10.0, 20.0, "red", &style);
+        page.draw_text(10.0, 40.0, 12.0, StandardFont::Helvetica, "black");
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\nBT\n/F1 12 Tf\n1 0 0 rg\n10 20 Td\n(red) Tj\nET\nQ\n\
+             BT\n/F1 12 Tf\n10 40 Td\n(black) Tj\nET\n"
+        );
+    }
+
+    #[test]
+    fn test_rendering_modes() {
+        let mut page = Page::new(PageSize::A4);
+        let outline = TextStyle {
+            rendering: TextRendering::Stroke,
+            stroke_color: Some(Color::Rgb(0.0, 0.0, 1.0)),
+            ..TextStyle::default()
+        };
+        let hidden = TextStyle {
+            rendering: TextRendering::Invisible,
+            ..TextStyle::default()
+        };
+        let text = RichText::new()
+            .span("outlined ", &outline)
+            .span("hidden", &hidden);
+        page.draw_rich_text_wrapped(Rect::new(0.0, 0.0, 500.0, 100.0), &text);
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\nBT\n/F1 12 Tf\n0 0 1 RG\n1 Tr\n0 88 Td\n(outlined ) Tj\n\
+             0 G\n3 Tr\n(hidden) Tj\nET\nQ\n"
+        );
+    }
+
+    #[test]
+    fn test_spacing() {
+        let mut page = Page::new(PageSize::A4);
+        let style = TextStyle {
+            char_spacing: 1.0,
+            word_spacing: 5.0,
+            align: Align::Right,
+            ..TextStyle::new(StandardFont::Courier, 10.0)
+        };
+        // 3 characters of 6pt plus 1pt each, and 5pt for the space.
+        assert_eq!(style.measure("a b"), 26.0);
+        page.draw_text_styled(100.0, 0.0, "a b", &style);
+        let font = crate::truetype::test_font();
+        let embedded = TextStyle {
+            word_spacing: 5.0,
+            ..TextStyle::new(&font, 10.0)
+        };
+        page.draw_text_styled(0.0, 0.0, "a b c", &embedded);
+        let ops = String::from_utf8_lossy(page.ops()).into_owned();
+        assert!(ops.starts_with("q\nBT\n/F1 10 Tf\n1 Tc\n5 Tw\n74 0 Td\n(a b) Tj\nET\nQ\n"));
+        // Embedded fonts get the word spacing as adjustments after spaces.
+        assert!(ops.ends_with("0 0 Td\n[<00440003> -500 <00450003> -500 <0046>] TJ\nET\nQ\n"));
+    }
+
+    #[test]
+    fn test_draw_text_escapes() {
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(0.0, 0.0, 12.0, StandardFont::Courier, "f(x) \\ :)");
+        page.draw_text(0.0, 0.0, 12.0, StandardFont::Courier, "Café");
+        let ops = String::from_utf8_lossy(page.ops());
+        assert!(ops.contains("(f\\(x\\) \\\\ :\\)) Tj"));
+        assert!(ops.contains("<436166E9> Tj"));
+    }
+
+    #[test]
+    fn test_vertical_text() {
+        let mut page = Page::new(PageSize::A4);
+        let style = TextStyle {
+            align: Align::Right,
+            ..TextStyle::new(StandardFont::Courier, 10.0)
+        };
+        // Courier characters are 6pt wide and reach 6.29pt above the baseline.
+        page.draw_text_vertical(100.0, 50.0, "ab\nc", &style);
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "BT\n/F1 10 Tf\n10 TL\n97 63.71 Td\n(a) Tj\nT*\n(b) Tj\nET\n\
+             BT\n/F1 10 Tf\n85 53.71 Td\n(c) Tj\nET\n"
+        );
+    }
+
+    #[test]
+    fn test_rotated_text() {
+        let mut page = Page::new(PageSize::A4);
+        let style = TextStyle {
+            align: Align::Center,
+            ..TextStyle::new(StandardFont::Courier, 10.0)
+        };
+        page.draw_text_rotated(100.0, 200.0, 90.0, "abcd\nef", &style);
+        // Centering moves the first line 12pt back along the rotated axis.
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "BT\n/F1 10 Tf\n12 TL\n0 1 -1 0 100 188 Tm\n(abcd) Tj\n6 -12 Td\n(ef) Tj\nET\n"
+        );
+    }
+
+    #[test]
+    fn test_lines_and_rects() {
+        let mut page = Page::new(PageSize::A4);
+        page.line((10.0, 20.0), Point::new(110.0, 20.0), &Paint::default());
+        let card = Paint::fill_and_stroke(Color::Gray(0.9), Color::Rgb(0.0, 0.0, 1.0));
+        page.rect(50.0, 60.0, 200.0, 100.5, &card.with_line_width(2.0));
+        page.rect(0.0, 0.0, 5.0, 5.0, &Paint::fill(Color::Gray(0.0)));
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "10 20 m\n110 20 l\nS\n\
+             q\n0 0 1 RG\n0.9 g\n2 w\n50 60 200 100.5 re\nB\nQ\n\
+             0 0 5 5 re\nf\n"
+        );
+    }
+
+    #[test]
+    fn test_fill_rule() {
+        let mut page = Page::new(PageSize::A4);
+        let donut = PathBuilder::new()
+            .add_path(&Path::rect(Rect::new(0.0, 0.0, 30.0, 30.0)))
+            .add_path(&Path::rect(Rect::new(10.0, 10.0, 10.0, 10.0)))
+            .fill_rule(FillRule::EvenOdd)
+            .build();
+        page.draw_path(&donut, &Paint::fill(Color::Gray(0.0)));
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "0 0 30 30 re\n10 10 10 10 re\nf*\n"
+        );
+    }
+
+    #[test]
+    fn test_save_state() {
+        let mut page = Page::new(PageSize::A4);
+        let len = page.save_state(|g| {
+            g.save_state(|g| g.line((0.0, 0.0), (1.0, 1.0), &Paint::default()));
+            g.ops.len()
+        });
+        assert_eq!(len, 20);
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\nq\n0 0 m\n1 1 l\nS\nQ\nQ\n"
+        );
+    }
+
+    #[test]
+    fn test_transformations() {
+        let mut page = Page::new(PageSize::A4);
+        page.translated(100.0, 50.0, |g| {
+            g.rotated_about((10.0, 10.0), 90.0, |g| {
+                g.rect(0.0, 0.0, 20.0, 10.0, &Paint::fill(Color::BLACK));
+            });
+            g.scaled(2.0, 0.5, |_| {});
+        });
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\n1 0 0 1 100 50 cm\nq\n0 1 -1 0 20 0 cm\n0 0 20 10 re\nf\nQ\n\
+             q\n2 0 0 0.5 0 0 cm\nQ\nQ\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_image() {
+        let mut page = Page::new(PageSize::A4);
+        let marker = Image::from_raw(2, 2, ImageColorSpace::Rgb, 8, &[255; 12]).unwrap();
+        page.draw_inline_image(&marker, Rect::new(10.0, 20.0, 4.0, 4.0));
+        let ops = page.ops();
+        let header = b"q\n4 0 0 4 10 20 cm\nBI /W 2 /H 2 /CS /RGB /BPC 8 /F /Fl\nID\n";
+        assert!(ops.starts_with(header));
+        assert!(ops.ends_with(b"\nEI\nQ\n"));
+        assert!(page.resources.images().next().is_none());
+
+        let scan = Image::from_raw(8, 1, ImageColorSpace::Gray, 1, &[0x0F]).unwrap();
+        page.draw_inline_image(&scan, Rect::new(0.0, 0.0, 8.0, 1.0));
+        let ops = String::from_utf8_lossy(page.ops());
+        assert!(ops.contains(
+            "BI /W 8 /H 1 /CS /G /BPC 1 /F /CCF /DP << /K -1 /Columns 8 /Rows 1 >>\nID\n"
+        ));
+    }
+
+    #[test]
+    fn test_transparent_inline_image() {
+        let pixels = [0, 0, 0, 0];
+        let png = crate::image::tests::png_image(1, 1, png::ColorType::Rgba, &pixels);
+        let image = Image::from_png(&png).unwrap();
+        let mut page = Page::new(PageSize::A4);
+        page.draw_inline_image(&image, Rect::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\n1 0 0 1 0 0 cm\n/X1 Do\nQ\n"
+        );
+        assert_eq!(page.resources.images().count(), 1);
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut page = Page::new(PageSize::A4);
+        let ring = PathBuilder::new()
+            .add_path(&Path::circle((50.0, 50.0), 50.0))
+            .add_path(&Path::circle((50.0, 50.0), 25.0))
+            .fill_rule(FillRule::EvenOdd)
+            .build();
+        page.clip(&Path::rect(Rect::new(0.0, 0.0, 100.0, 50.0)), |g| {
+            g.draw_text(0.0, 10.0, 12.0, StandardFont::Courier, "cut");
+        });
+        page.clip(&ring, |g| {
+            g.rect(0.0, 0.0, 100.0, 100.0, &Paint::fill(Color::BLACK))
+        });
+        page.clip(&Path::default(), |g| {
+            g.rect(0.0, 0.0, 5.0, 5.0, &Paint::default())
+        });
+        let ops = String::from_utf8_lossy(page.ops()).into_owned();
+        assert!(ops.starts_with("q\n0 0 100 50 re\nW n\nBT\n"));
+        assert!(ops.contains("ET\nQ\nq\n100 50 m\n"));
+        assert!(ops.ends_with("h\nW* n\n0 0 100 100 re\nf\nQ\n"));
+    }
+
+    #[test]
+    fn test_opacity() {
+        let mut page = Page::new(PageSize::A4);
+        page.with_opacity(0.3, |g| {
+            g.rect(0.0, 0.0, 10.0, 10.0, &Paint::fill(Color::BLACK));
+            g.with_opacity(1.5, |g| g.line((0.0, 0.0), (5.0, 5.0), &Paint::default()));
+        });
+        page.with_opacity(0.3, |_| {});
+        page.with_blend_mode(BlendMode::Multiply, |_| {});
+        assert_eq!(
+            String::from_utf8_lossy(page.ops()),
+            "q\n/GS1 gs\n0 0 10 10 re\nf\nq\n/GS2 gs\n0 0 m\n5 5 l\nS\nQ\nQ\nq\n/GS1 gs\nQ\n\
+             q\n/GS3 gs\nQ\n"
+        );
+        assert_eq!(
+            page.resources.to_dict(&ResourceRefs::default()).to_string(),
+            "<< /ExtGState << /GS1 << /CA 0.3 /ca 0.3 >> /GS2 << /CA 1 /ca 1 >> \
+             /GS3 << /BM /Multiply >> >> >>"
+        );
+    }
+
+    #[test]
+    fn test_gradient_fills() {
+        let mut page = Page::new(PageSize::A4);
+        let gradient = Gradient::linear((0.0, 0.0), (0.0, 50.0), Color::BLACK, Color::WHITE);
+        page.fill_gradient(&Path::rect(Rect::new(0.0, 0.0, 50.0, 50.0)), &gradient);
+        page.circle((100.0, 25.0), 25.0, &Paint::fill(gradient.clone().into()));
+        let ops = String::from_utf8_lossy(page.ops()).into_owned();
+        assert!(ops.starts_with("q\n0 0 50 50 re\nW n\n/Sh1 sh\nQ\nq\n/Pattern cs /P1 scn\n"));
+
+        let resources = page.resources.to_dict(&ResourceRefs::default()).to_string();
+        let shading = gradient.to_pdf(&ResourceRefs::default());
+        assert!(resources.contains(&format!(" /Shading << /Sh1 {} >>", shading)));
+        assert!(resources.contains(&format!(
+            " /Pattern << /P1 << /PatternType 2 /Shading {} >> >>",
+            shading
+        )));
+    }
+
+    #[test]
+    fn test_landscape_and_rotate() {
+        let mut page = Page::with_orientation(PageSize::A4, Orientation::Landscape);
+        assert_eq!(page.media_box(), [0.0, 0.0, 842.0, 595.0]);
+        assert_eq!(page.orientation(), Orientation::Landscape);
+        page.set_orientation(Orientation::Landscape);
+        assert_eq!(page.media_box(), [0.0, 0.0, 842.0, 595.0]);
+
+        let refs = ResourceRefs::default();
+        let dict = page
+            .to_dict(
+                ObjectRef::new(2),
+                ObjectRef::new(5),
+                &[],
+                0,
+                page.contents.resources.to_dict(&refs),
+                &InheritedAttributes::default(),
+            )
+            .to_string();
+        assert!(!dict.contains("/Rotate"));
+        page.set_rotation(Rotation::Deg270);
+        let dict = page
+            .to_dict(
+                ObjectRef::new(2),
+                ObjectRef::new(5),
+                &[],
+                0,
+                page.contents.resources.to_dict(&refs),
+                &InheritedAttributes::default(),
+            )
+            .to_string();
+        assert!(dict.contains("/MediaBox [0 0 842 595] /Rotate 270 /Contents"));
+    }
+
+    #[test]
+    fn test_tagged_in_layer() {
+        let layer = Layer::new("Notes");
+        let mut page = Page::new(PageSize::A4);
+        page.tagged(StructureType::P, |g| {
+            g.in_layer(&layer, |g| {
+                g.rect(0.0, 0.0, 1.0, 1.0, &Paint::fill(Color::BLACK))
+            });
+        });
+        assert_eq!(
+            String::from_utf8_lossy(page.contents.ops()),
+            "/OC /OC1 BDC\n/P << /MCID 0 >> BDC\n0 0 1 1 re\nf\nEMC\nEMC\n"
+        );
+        let mut refs = ResourceRefs::default();
+        refs.layers.insert(layer, ObjectRef::new(4));
+        let dict = page
+            .to_dict(
+                ObjectRef::new(2),
+                ObjectRef::new(5),
+                &[],
+                3,
+                page.contents.resources.to_dict(&refs),
+                &InheritedAttributes::default(),
+            )
+            .to_string();
+        assert!(dict.contains(" /StructParents 3 "));
+    }
+
+    #[test]
+    fn test_print_boxes() {
+        let mut page = Page::new(PageSize::A4);
+        page.set_bleed_box(Some(Rect::new(6.0, 9.0, 583.0, 824.0)));
+        page.set_trim_box(Some(Rect::new(14.5, 17.5, 566.0, 807.0)));
+        let refs = ResourceRefs::default();
+        let dict = page
+            .to_dict(
+                ObjectRef::new(2),
+                ObjectRef::new(5),
+                &[],
+                0,
+                page.contents.resources.to_dict(&refs),
+                &InheritedAttributes::default(),
+            )
+            .to_string();
+        assert!(dict.contains(
+            "/MediaBox [0 0 595 842] /BleedBox [6 9 589 833] \
+             /TrimBox [14.5 17.5 580.5 824.5] /Contents"
+        ));
+        assert!(Rect::new(6.0, 9.0, 583.0, 824.0).contains(page.trim_box().unwrap()));
+        assert!(!page.trim_box().unwrap().contains(page.bleed_box().unwrap()));
+    }
+
+    #[test]
+    fn test_inherited_attributes() {
+        let mut pages = vec![
+            Page::from("One"),
+            Page::from("Two"),
+            Page::new(PageSize::Letter),
+        ];
+        pages[2].add_line("Three");
+        pages.push(Page::new(PageSize::Letter));
+        let mut refs = ResourceRefs::default();
+        for used in pages[0].contents.resources.fonts() {
+            refs.fonts.insert(used.font.clone(), ObjectRef::new(3));
+        }
+        let resources: Vec<Dictionary> = pages
+            .iter()
+            .map(|page| page.contents.resources.to_dict(&refs))
+            .collect();
+        let inherited = InheritedAttributes::of(&pages, &resources);
+        let mut root = Dictionary::new();
+        inherited.set_on(&mut root);
+        assert_eq!(
+            root.to_string(),
+            format!("<< /MediaBox [0 0 595 842] /Resources {} >>", resources[0])
+        );
+
+        let dict = |page: &Page| {
+            page.to_dict(
+                ObjectRef::new(2),
+                ObjectRef::new(5),
+                &[],
+                0,
+                page.contents.resources.to_dict(&refs),
+                &inherited,
+            )
+            .to_string()
+        };
+        assert_eq!(
+            dict(&pages[1]),
+            "<< /Type /Page /Parent 2 0 R /Contents 5 0 R >>"
+        );
+        assert_eq!(
+            dict(&pages[2]),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 5 0 R >>"
+        );
+        assert_eq!(
+            dict(&pages[3]),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 5 0 R /Resources << >> >>"
+        );
+        let mut root = Dictionary::new();
+        InheritedAttributes::of(&[], &[]).set_on(&mut root);
+        assert!(root.is_empty());
+    }
+}
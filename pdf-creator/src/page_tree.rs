@@ -0,0 +1,190 @@
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+
+/// Kids a node of the page tree has at most, past which pages are spread
+/// over nodes of their own, for readers not to go through thousands of
+/// kids to find a page.
+const MAX_KIDS: usize = 32;
+
+/// The page tree of a number of pages: a root and, if there are more pages
+/// than a node takes, nodes under it, as many levels deep everywhere, each
+/// with as many pages under it as can be. Nodes have no attributes of
+/// their own, for those of the root to be inherited by every page.
+#[derive(Debug)]
+pub(crate) struct PageTree {
+    /// The nodes, the root first, each before those under it.
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+    id: ObjectRef,
+    parent: Option<ObjectRef>,
+    kids: Kids,
+    /// The pages under the node, those of its kids included.
+    count: usize,
+}
+
+#[derive(Debug)]
+enum Kids {
+    /// The nodes of the indices.
+    Nodes(Vec<usize>),
+    /// The pages from the first on.
+    Pages { first: usize },
+}
+
+impl PageTree {
+    /// The page tree of `pages` pages, its nodes numbered in order.
+    pub(crate) fn new(pages: usize, alloc: &mut ObjectAllocator) -> Self {
+        let mut depth = 1;
+        let mut capacity = MAX_KIDS;
+        while capacity < pages {
+            depth += 1;
+            capacity *= MAX_KIDS;
+        }
+        let mut tree = Self { nodes: Vec::new() };
+        tree.add(None, 0, pages, depth, capacity, alloc);
+        tree
+    }
+
+    /// Adds the node of `count` pages from `first` on, `depth` levels deep
+    /// and so of `capacity` pages at most, and those under it.
+    fn add(
+        &mut self,
+        parent: Option<ObjectRef>,
+        first: usize,
+        count: usize,
+        depth: usize,
+        capacity: usize,
+        alloc: &mut ObjectAllocator,
+    ) -> usize {
+        let id = alloc.allocate();
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            parent,
+            kids: Kids::Pages { first },
+            count,
+        });
+        if depth == 1 {
+            return index;
+        }
+        // The pages are shared out evenly, the first kids taking one more.
+        let capacity = capacity / MAX_KIDS;
+        let kids = count.div_ceil(capacity);
+        let mut start = first;
+        let nodes = (0..kids)
+            .map(|kid| {
+                let count = count / kids + usize::from(kid < count % kids);
+                let node = self.add(Some(id), start, count, depth - 1, capacity, alloc);
+                start += count;
+                node
+            })
+            .collect();
+        self.nodes[index].kids = Kids::Nodes(nodes);
+        index
+    }
+
+    pub(crate) fn root(&self) -> ObjectRef {
+        self.nodes[0].id
+    }
+
+    /// The nodes the pages are kids of, in order.
+    pub(crate) fn parents(&self) -> Vec<ObjectRef> {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node.kids, Kids::Pages { .. }))
+            .flat_map(|node| std::iter::repeat_n(node.id, node.count))
+            .collect()
+    }
+
+    /// The nodes and their dictionaries, of the page objects `pages`.
+    pub(crate) fn to_dicts(&self, pages: &[ObjectRef]) -> Vec<(ObjectRef, Dictionary)> {
+        assert_eq!(self.nodes[0].count, pages.len());
+        self.nodes
+            .iter()
+            .map(|node| {
+                let kids: Vec<Object> = match &node.kids {
+                    Kids::Nodes(nodes) => {
+                        nodes.iter().map(|&kid| self.nodes[kid].id.into()).collect()
+                    }
+                    Kids::Pages { first } => pages[*first..*first + node.count]
+                        .iter()
+                        .map(|&page| page.into())
+                        .collect(),
+                };
+                let mut dict = Dictionary::new();
+                dict.set("Type", Object::name("Pages"));
+                if let Some(parent) = node.parent {
+                    dict.set("Parent", parent);
+                }
+                dict.set("Kids", kids);
+                dict.set("Count", node.count);
+                (node.id, dict)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(count: u32) -> Vec<ObjectRef> {
+        (0..count).map(|i| ObjectRef::new(100 + i)).collect()
+    }
+
+    #[test]
+    fn test_single_node() {
+        let mut alloc = ObjectAllocator::new();
+        let tree = PageTree::new(3, &mut alloc);
+        assert_eq!(tree.root(), ObjectRef::new(1));
+        assert_eq!(alloc.size(), 2);
+        assert_eq!(tree.parents(), [ObjectRef::new(1); 3]);
+        let dicts = tree.to_dicts(&pages(3));
+        assert_eq!(
+            dicts[0].1.to_string(),
+            "<< /Type /Pages /Kids [100 0 R 101 0 R 102 0 R] /Count 3 >>"
+        );
+        assert_eq!(PageTree::new(MAX_KIDS, &mut alloc).nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_balanced() {
+        let mut alloc = ObjectAllocator::new();
+        let tree = PageTree::new(MAX_KIDS + 1, &mut alloc);
+        let dicts = tree.to_dicts(&pages(MAX_KIDS as u32 + 1));
+        assert_eq!(dicts.len(), 3);
+        assert_eq!(
+            dicts[0].1.to_string(),
+            "<< /Type /Pages /Kids [2 0 R 3 0 R] /Count 33 >>"
+        );
+        assert!(
+            dicts[1]
+                .1
+                .to_string()
+                .starts_with("<< /Type /Pages /Parent 1 0 R /Kids [100 0 R")
+        );
+        assert!(dicts[1].1.to_string().ends_with("116 0 R] /Count 17 >>"));
+        assert!(dicts[2].1.to_string().ends_with("/Count 16 >>"));
+        let parents = tree.parents();
+        assert_eq!(
+            (parents[16], parents[17]),
+            (ObjectRef::new(2), ObjectRef::new(3))
+        );
+
+        // Every page as deep as any other, and no node over full.
+        let count = MAX_KIDS * MAX_KIDS + 1;
+        let tree = PageTree::new(count, &mut ObjectAllocator::new());
+        assert_eq!(tree.parents().len(), count);
+        let leaves = tree
+            .nodes
+            .iter()
+            .filter(|node| matches!(node.kids, Kids::Pages { .. }));
+        assert!(leaves.clone().all(|node| node.count <= MAX_KIDS));
+        assert_eq!(leaves.count(), 17 + 16);
+        match &tree.nodes[0].kids {
+            Kids::Nodes(kids) => assert_eq!(kids.len(), 2),
+            Kids::Pages { .. } => panic!("the root has pages as kids"),
+        }
+    }
+}
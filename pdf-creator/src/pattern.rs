@@ -0,0 +1,173 @@
+use crate::color::Color;
+use crate::geometry::Rect;
+use crate::object::{Dictionary, Object, ObjectRef};
+use crate::page::ContentStream;
+use crate::resources::{ResourceRefs, Resources};
+use crate::shading::Gradient;
+use crate::writer::PdfWriter;
+use anyhow::{Result, ensure};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Source of the ids telling tiling patterns apart.
+static NEXT_PATTERN_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A small cell, such as a hatch, a checkerboard square or a logo, drawn
+/// once and repeated side by side to fill shapes, text or backgrounds. It
+/// is used as a color, through `Color::from`. Tiles are laid out from the
+/// origin of the page, whatever the shape they fill.
+///
+/// Cloning the handle shares the cell, which is written once however many
+/// pages use it.
+///
+/// ```
+/// use pdf_creator::{Color, Paint, TilingPattern};
+///
+/// // Diagonal hatching, one line every 6 points.
+/// let hatch = TilingPattern::new(6.0, 6.0, |cell| {
+///     cell.line((0.0, 0.0), (6.0, 6.0), &Paint::stroke(Color::Gray(0.5)));
+/// });
+/// let paint = Paint::fill(hatch.into());
+/// ```
+#[derive(Clone)]
+pub struct TilingPattern {
+    inner: Arc<TilingData>,
+}
+
+struct TilingData {
+    id: u32,
+    width: f32,
+    height: f32,
+    cell: ContentStream,
+}
+
+impl TilingPattern {
+    /// A cell of `width` by `height` points, its lower left corner at the
+    /// origin of what `draw` draws. Anything drawn outside the cell is cut
+    /// off. Tagging the cell makes the document fail to be written:
+    /// patterns are tagged with the content they are painted in instead.
+    pub fn new(width: f32, height: f32, draw: impl FnOnce(&mut ContentStream)) -> Self {
+        let mut cell = ContentStream::default();
+        draw(&mut cell);
+        Self {
+            inner: Arc::new(TilingData {
+                id: NEXT_PATTERN_ID.fetch_add(1, Ordering::Relaxed),
+                width,
+                height,
+                cell,
+            }),
+        }
+    }
+
+    /// The resources the cell draws with.
+    pub(crate) fn resources(&self) -> &Resources {
+        &self.inner.cell.resources
+    }
+
+    /// Writes the `/PatternType 1` stream, painting its own colors and
+    /// repeating every cell size.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        id: ObjectRef,
+        refs: &ResourceRefs,
+    ) -> Result<()> {
+        ensure!(
+            self.inner.cell.structure.is_empty(),
+            "tiling patterns cannot be tagged, only the content they are painted in"
+        );
+        let (width, height) = (self.inner.width, self.inner.height);
+        let mut dict = Dictionary::new();
+        dict.set("PatternType", 1);
+        dict.set("PaintType", 1);
+        dict.set("TilingType", 1);
+        dict.set("BBox", Rect::new(0.0, 0.0, width, height));
+        dict.set("XStep", width);
+        dict.set("YStep", height);
+        dict.set("Resources", self.resources().to_dict(refs));
+        writer.write_compressed_stream(id, dict, self.inner.cell.ops())
+    }
+}
+
+impl PartialEq for TilingPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for TilingPattern {}
+
+impl Hash for TilingPattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl fmt::Debug for TilingPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TilingPattern")
+            .field("width", &self.inner.width)
+            .field("height", &self.inner.height)
+            .finish()
+    }
+}
+
+impl From<TilingPattern> for Color {
+    fn from(pattern: TilingPattern) -> Self {
+        Color::Tiling(pattern)
+    }
+}
+
+/// What fills with a color of the `/Pattern` color space.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Pattern {
+    Shading(Gradient),
+    Tiling(TilingPattern),
+}
+
+impl Pattern {
+    /// A shading pattern is written in the resource dictionary itself; a
+    /// tiling pattern, being a stream, is referred to.
+    pub(crate) fn to_pdf(&self, refs: &ResourceRefs) -> Object {
+        match self {
+            Pattern::Shading(gradient) => {
+                let mut dict = Dictionary::new();
+                dict.set("PatternType", 2);
+                dict.set("Shading", gradient.to_pdf(refs));
+                dict.into()
+            }
+            Pattern::Tiling(tiling) => refs.tilings[tiling].into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Paint;
+
+    #[test]
+    fn test_tiling_pattern_stream() {
+        let checker = TilingPattern::new(10.0, 10.0, |cell| {
+            cell.rect(0.0, 0.0, 5.0, 5.0, &Paint::fill(Color::BLACK));
+            cell.rect(5.0, 5.0, 5.0, 5.0, &Paint::fill(Color::BLACK));
+        });
+        assert_eq!(checker, checker.clone());
+        assert_ne!(checker, TilingPattern::new(10.0, 10.0, |_| {}));
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        checker
+            .write(&mut writer, ObjectRef::new(1), &ResourceRefs::default())
+            .unwrap();
+        let pdf = String::from_utf8(buf.into_inner()).unwrap();
+        assert!(pdf.ends_with(
+            "1 0 obj\n<< /Length 26 /PatternType 1 /PaintType 1 /TilingType 1 \
+             /BBox [0 0 10 10] /XStep 10 /YStep 10 /Resources << >> >>\nstream\n\
+             0 0 5 5 re\nf\n5 5 5 5 re\nf\n\nendstream\nendobj\n"
+        ));
+    }
+}
@@ -0,0 +1,596 @@
+use crate::color::ColorSpace;
+use crate::embedded::EmbeddedFile;
+use crate::font::{Font, FontContent};
+use crate::graphics::ExtGState;
+use crate::icc::IccProfile;
+#[cfg(feature = "image")]
+use crate::image::Downsampling;
+use crate::image::{Image, ImageContent};
+use crate::layer::Layer;
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::pattern::{Pattern, TilingPattern};
+use crate::shading::Gradient;
+use crate::writer::PdfWriter;
+use crate::xobject::{FormXObject, XObject};
+use anyhow::Result;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// Resources a content stream refers to by name, registered on first use.
+#[derive(Debug, Default)]
+pub(crate) struct Resources {
+    fonts: Vec<FontUse>,
+    color_spaces: Vec<ColorSpace>,
+    shadings: Vec<Gradient>,
+    patterns: Vec<Pattern>,
+    ext_g_states: Vec<ExtGState>,
+    x_objects: Vec<XObject>,
+    /// The largest width and height each image is drawn at, in points.
+    image_sizes: HashMap<Image, (f32, f32)>,
+    layers: Vec<Layer>,
+    /// The device color spaces colors are set in, such as `DeviceRGB`.
+    device_spaces: Vec<&'static str>,
+}
+
+/// A font of a resource dictionary and the glyphs shown with it (with the
+/// character each stands for), which decide what ends up in an embedded font
+/// subset.
+#[derive(Debug)]
+pub(crate) struct FontUse {
+    pub(crate) font: Font,
+    pub(crate) glyphs: BTreeMap<u16, char>,
+}
+
+impl Resources {
+    /// Returns the name `font` is known by in this resource dictionary,
+    /// registering it if needed, along with its set of used glyphs.
+    pub(crate) fn font(&mut self, font: &Font) -> (String, &mut BTreeMap<u16, char>) {
+        let index = match self.fonts.iter().position(|f| f.font == *font) {
+            Some(index) => index,
+            None => {
+                self.fonts.push(FontUse {
+                    font: font.clone(),
+                    glyphs: BTreeMap::new(),
+                });
+                self.fonts.len() - 1
+            }
+        };
+        (format!("F{}", index + 1), &mut self.fonts[index].glyphs)
+    }
+
+    /// Returns the name `space` is known by, registering it if needed.
+    pub(crate) fn color_space(&mut self, space: &ColorSpace) -> String {
+        format!("CS{}", register(&mut self.color_spaces, space))
+    }
+
+    /// Returns the name `gradient` is known by for the `sh` operator.
+    pub(crate) fn shading(&mut self, gradient: &Gradient) -> String {
+        format!("Sh{}", register(&mut self.shadings, gradient))
+    }
+
+    /// Returns the name of `pattern`, painted as a color.
+    pub(crate) fn pattern(&mut self, pattern: &Pattern) -> String {
+        format!("P{}", register(&mut self.patterns, pattern))
+    }
+
+    /// Returns the name `state` is set by with the `gs` operator.
+    pub(crate) fn ext_g_state(&mut self, state: &ExtGState) -> String {
+        format!("GS{}", register(&mut self.ext_g_states, state))
+    }
+
+    /// Returns the name `x_object` is painted by with the `Do` operator.
+    pub(crate) fn x_object(&mut self, x_object: &XObject) -> String {
+        format!("X{}", register(&mut self.x_objects, x_object))
+    }
+
+    /// Returns the name `image` is painted by, drawn `width` by `height`
+    /// points large.
+    pub(crate) fn image(&mut self, image: &Image, width: f32, height: f32) -> String {
+        let size = self.image_sizes.entry(image.clone()).or_default();
+        *size = (size.0.max(width.abs()), size.1.max(height.abs()));
+        self.x_object(&XObject::Image(image.clone()))
+    }
+
+    /// The largest size `image` is drawn at, if at all.
+    #[cfg(feature = "image")]
+    pub(crate) fn image_size(&self, image: &Image) -> Option<(f32, f32)> {
+        self.image_sizes.get(image).copied()
+    }
+
+    /// Returns the name `layer` is marked by in `BDC` operators.
+    pub(crate) fn layer(&mut self, layer: &Layer) -> String {
+        format!("OC{}", register(&mut self.layers, layer))
+    }
+
+    /// Records that a color is set in the device color space `name`.
+    pub(crate) fn device_space(&mut self, name: &'static str) {
+        register(&mut self.device_spaces, &name);
+    }
+
+    pub(crate) fn ext_g_states(&self) -> &[ExtGState] {
+        &self.ext_g_states
+    }
+
+    pub(crate) fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// The forms placed, each drawn with resources of its own.
+    pub(crate) fn forms(&self) -> impl Iterator<Item = &FormXObject> {
+        self.x_objects.iter().filter_map(|x_object| match x_object {
+            XObject::Form(form) => Some(form),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn images(&self) -> impl Iterator<Item = &Image> {
+        self.x_objects.iter().filter_map(|x_object| match x_object {
+            XObject::Image(image) => Some(image),
+            _ => None,
+        })
+    }
+
+    /// The tiling patterns used, each drawn with resources of its own.
+    pub(crate) fn tilings(&self) -> impl Iterator<Item = &TilingPattern> {
+        self.patterns.iter().filter_map(|pattern| match pattern {
+            Pattern::Tiling(tiling) => Some(tiling),
+            _ => None,
+        })
+    }
+
+    /// The ICC profiles colors are painted in, possibly repeated.
+    pub(crate) fn profiles(&self) -> impl Iterator<Item = &IccProfile> {
+        let spaces = self.color_spaces.iter().filter_map(|space| match space {
+            ColorSpace::IccBased(profile) => Some(profile),
+            _ => None,
+        });
+        spaces.chain(self.gradients().filter_map(Gradient::profile))
+    }
+
+    /// The device color spaces painted in, by name, such as `DeviceRGB`,
+    /// possibly repeated: those colors are set in, and those of images and
+    /// gradients.
+    pub(crate) fn device_spaces(&self) -> impl Iterator<Item = &'static str> {
+        let images = self.images().map(Image::device_space);
+        let gradients = self.gradients().filter_map(Gradient::device_space);
+        self.device_spaces
+            .iter()
+            .copied()
+            .chain(images)
+            .chain(gradients)
+    }
+
+    /// The gradients painted, with `sh` or as shading patterns.
+    fn gradients(&self) -> impl Iterator<Item = &Gradient> {
+        let shadings = self.patterns.iter().filter_map(|pattern| match pattern {
+            Pattern::Shading(gradient) => Some(gradient),
+            _ => None,
+        });
+        self.shadings.iter().chain(shadings)
+    }
+
+    pub(crate) fn fonts(&self) -> &[FontUse] {
+        &self.fonts
+    }
+
+    pub(crate) fn to_dict(&self, refs: &ResourceRefs) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let mut category = |key: &str, prefix: &str, values: Vec<Object>| {
+            if values.is_empty() {
+                return;
+            }
+            let mut names = Dictionary::new();
+            for (i, value) in values.into_iter().enumerate() {
+                names.set(format!("{}{}", prefix, i + 1), value);
+            }
+            dict.set(key, names);
+        };
+        let fonts = self.fonts.iter().map(|used| refs.fonts[&used.font].into());
+        category("Font", "F", fonts.collect());
+        let spaces = self.color_spaces.iter().map(|space| space.to_pdf(refs));
+        category("ColorSpace", "CS", spaces.collect());
+        let states = self.ext_g_states.iter().map(|state| state.to_pdf().into());
+        category("ExtGState", "GS", states.collect());
+        let shadings = self
+            .shadings
+            .iter()
+            .map(|gradient| gradient.to_pdf(refs).into());
+        category("Shading", "Sh", shadings.collect());
+        let patterns = self.patterns.iter().map(|pattern| pattern.to_pdf(refs));
+        category("Pattern", "P", patterns.collect());
+        let x_objects = self
+            .x_objects
+            .iter()
+            .map(|x_object| x_object.to_pdf(refs).into());
+        category("XObject", "X", x_objects.collect());
+        let layers = self.layers.iter().map(|layer| refs.layers[layer].into());
+        category("Properties", "OC", layers.collect());
+        dict
+    }
+}
+
+/// The 1-based number of `item` in `list`, appending it if it is new.
+fn register<T: PartialEq + Clone>(list: &mut Vec<T>, item: &T) -> usize {
+    match list.iter().position(|known| known == item) {
+        Some(index) => index + 1,
+        None => {
+            list.push(item.clone());
+            list.len()
+        }
+    }
+}
+
+/// The resource objects of a whole document, each written once however
+/// many content streams use it: fonts, with the glyphs shown in each
+/// anywhere, ICC profiles, tiling patterns and forms, which draw with
+/// resources of their own, images, layers and attached files. Fonts, ICC
+/// profiles and images loaded apart from the same data are written once
+/// too, found by their content.
+#[derive(Debug)]
+pub(crate) struct SharedResources<'a> {
+    /// The resources of every content stream, those of pages first.
+    resources: Vec<&'a Resources>,
+    tilings: Vec<&'a TilingPattern>,
+    forms: Vec<&'a FormXObject>,
+    fonts: Vec<(Font, BTreeMap<u16, char>)>,
+    profiles: Vec<&'a IccProfile>,
+    /// The images of the same content, the first written for all.
+    images: Vec<Vec<&'a Image>>,
+    layers: Vec<&'a Layer>,
+    files: Vec<&'a EmbeddedFile>,
+}
+
+impl<'a> SharedResources<'a> {
+    /// Gathers what the content streams of `pages` use, along with what
+    /// the forms `forms` placed elsewhere, such as annotation appearances,
+    /// and the patterns and forms they draw with use in turn.
+    pub(crate) fn gather(
+        pages: impl IntoIterator<Item = &'a Resources>,
+        forms: impl IntoIterator<Item = &'a FormXObject>,
+    ) -> Self {
+        let mut shared = Self {
+            resources: pages.into_iter().collect(),
+            tilings: Vec::new(),
+            forms: Vec::new(),
+            fonts: Vec::new(),
+            profiles: Vec::new(),
+            images: Vec::new(),
+            layers: Vec::new(),
+            files: Vec::new(),
+        };
+        for form in forms {
+            if !shared.forms.contains(&form) {
+                shared.forms.push(form);
+                shared.resources.push(form.resources());
+            }
+        }
+        let mut next = 0;
+        while next < shared.resources.len() {
+            for tiling in shared.resources[next].tilings() {
+                if !shared.tilings.contains(&tiling) {
+                    shared.tilings.push(tiling);
+                    shared.resources.push(tiling.resources());
+                }
+            }
+            for form in shared.resources[next].forms() {
+                if !shared.forms.contains(&form) {
+                    shared.forms.push(form);
+                    shared.resources.push(form.resources());
+                }
+            }
+            next += 1;
+        }
+        shared
+    }
+
+    pub(crate) fn resources(&self) -> &[&'a Resources] {
+        &self.resources
+    }
+
+    pub(crate) fn layers(&self) -> &[&'a Layer] {
+        &self.layers
+    }
+
+    /// Numbers the objects in `refs`: fonts, `fonts` included, ICC
+    /// profiles, `profile` included, tiling patterns, forms, images each
+    /// followed by its soft mask, layers, then attached files, `files`
+    /// included. Those of the same content as one numbered before are
+    /// given its number.
+    pub(crate) fn allocate(
+        &mut self,
+        alloc: &mut ObjectAllocator,
+        refs: &mut ResourceRefs,
+        fonts: impl IntoIterator<Item = Font>,
+        profile: Option<&'a IccProfile>,
+        files: impl IntoIterator<Item = &'a EmbeddedFile>,
+    ) {
+        let mut fonts_by_content: HashMap<FontContent<'a>, usize> = HashMap::new();
+        for used in self
+            .resources
+            .iter()
+            .flat_map(|resources| resources.fonts())
+        {
+            let index = *fonts_by_content
+                .entry(used.font.content())
+                .or_insert_with(|| {
+                    refs.fonts.insert(used.font.clone(), alloc.allocate());
+                    self.fonts.push((used.font.clone(), BTreeMap::new()));
+                    self.fonts.len() - 1
+                });
+            let (font, glyphs) = &mut self.fonts[index];
+            glyphs.extend(&used.glyphs);
+            let id = refs.fonts[font];
+            refs.fonts.insert(used.font.clone(), id);
+        }
+        for font in fonts {
+            if !refs.fonts.contains_key(&font) {
+                refs.fonts.insert(font.clone(), alloc.allocate());
+                self.fonts.push((font, BTreeMap::new()));
+            }
+        }
+        let used_profiles = self
+            .resources
+            .iter()
+            .flat_map(|resources| resources.profiles());
+        let mut profiles_by_content: HashMap<&'a [u8], ObjectRef> = HashMap::new();
+        for profile in used_profiles.chain(profile) {
+            let id = *profiles_by_content
+                .entry(profile.data())
+                .or_insert_with(|| {
+                    self.profiles.push(profile);
+                    alloc.allocate()
+                });
+            refs.profiles.insert(profile.clone(), id);
+        }
+        for tiling in &self.tilings {
+            refs.tilings
+                .insert(TilingPattern::clone(tiling), alloc.allocate());
+        }
+        for form in &self.forms {
+            refs.forms
+                .insert(FormXObject::clone(form), alloc.allocate());
+        }
+        let mut images_by_content: HashMap<ImageContent<'a>, usize> = HashMap::new();
+        for image in self
+            .resources
+            .iter()
+            .flat_map(|resources| resources.images())
+        {
+            if refs.images.contains_key(image) {
+                continue;
+            }
+            match images_by_content.entry(image.content()) {
+                Entry::Occupied(entry) => {
+                    let group = &mut self.images[*entry.get()];
+                    refs.images.insert(image.clone(), refs.images[group[0]]);
+                    // Images of the same content have masks of the same.
+                    if let (Some(mask), Some(first)) = (image.soft_mask(), group[0].soft_mask()) {
+                        refs.images.insert(mask.clone(), refs.images[first]);
+                    }
+                    group.push(image);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(self.images.len());
+                    refs.images.insert(image.clone(), alloc.allocate());
+                    self.images.push(vec![image]);
+                    if let Some(mask) = image.soft_mask() {
+                        refs.images.insert(mask.clone(), alloc.allocate());
+                    }
+                }
+            }
+        }
+        for layer in self
+            .resources
+            .iter()
+            .flat_map(|resources| resources.layers())
+        {
+            if !refs.layers.contains_key(layer) {
+                refs.layers.insert(layer.clone(), alloc.allocate());
+                self.layers.push(layer);
+            }
+        }
+        for file in files {
+            if !refs.files.contains_key(file) {
+                refs.files.insert(file.clone(), alloc.allocate());
+                self.files.push(file);
+            }
+        }
+    }
+
+    /// Writes the objects numbered in `refs`, fonts with a `/CIDSet` if
+    /// `cid_set`, and images resampled as `downsampling` says, if at all.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        alloc: &mut ObjectAllocator,
+        refs: &ResourceRefs,
+        cid_set: bool,
+        #[cfg(feature = "image")] downsampling: Option<&Downsampling>,
+    ) -> Result<()> {
+        for (font, glyphs) in &self.fonts {
+            font.write(writer, alloc, refs.fonts[font], glyphs, cid_set)?;
+        }
+        for profile in &self.profiles {
+            profile.write(writer, refs.profiles[*profile])?;
+        }
+        for tiling in &self.tilings {
+            tiling.write(writer, refs.tilings[*tiling], refs)?;
+        }
+        for form in &self.forms {
+            form.write(writer, refs.forms[*form], refs)?;
+        }
+        for group in &self.images {
+            let image = group[0];
+            let id = refs.images[image];
+            let mask = image.soft_mask().map(|mask| refs.images[mask]);
+            #[cfg(feature = "image")]
+            let image = &match downsampling {
+                Some(downsampling) => {
+                    let size = self
+                        .resources
+                        .iter()
+                        .flat_map(|resources| {
+                            group.iter().filter_map(|image| resources.image_size(image))
+                        })
+                        .fold((0.0, 0.0), |(w, h): (f32, f32), (width, height)| {
+                            (w.max(width), h.max(height))
+                        });
+                    image
+                        .downsampled(downsampling, size)?
+                        .unwrap_or_else(|| Image::clone(image))
+                }
+                None => Image::clone(image),
+            };
+            image.write(writer, id, mask)?;
+            // That of the image written, which may have been resampled.
+            if let (Some(soft_mask), Some(mask)) = (image.soft_mask(), mask) {
+                soft_mask.write(writer, mask, None)?;
+            }
+        }
+        for layer in &self.layers {
+            writer.write(refs.layers[*layer], &layer.to_pdf().into())?;
+        }
+        for file in &self.files {
+            file.write(writer, refs.files[*file])?;
+        }
+        Ok(())
+    }
+}
+
+/// Object numbers of the resource objects shared by the whole document.
+#[derive(Debug, Default)]
+pub(crate) struct ResourceRefs {
+    pub(crate) fonts: HashMap<Font, ObjectRef>,
+    pub(crate) profiles: HashMap<IccProfile, ObjectRef>,
+    pub(crate) tilings: HashMap<TilingPattern, ObjectRef>,
+    pub(crate) forms: HashMap<FormXObject, ObjectRef>,
+    pub(crate) images: HashMap<Image, ObjectRef>,
+    pub(crate) layers: HashMap<Layer, ObjectRef>,
+    pub(crate) files: HashMap<EmbeddedFile, ObjectRef>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{Color, SpotColor};
+    use crate::font::StandardFont;
+
+    #[test]
+    fn test_fonts_registered_once() {
+        let mut resources = Resources::default();
+        assert_eq!(resources.font(&StandardFont::TimesBold.into()).0, "F1");
+        assert_eq!(resources.font(&StandardFont::Courier.into()).0, "F2");
+        assert_eq!(resources.font(&StandardFont::TimesBold.into()).0, "F1");
+
+        let mut refs = ResourceRefs::default();
+        refs.fonts
+            .insert(StandardFont::TimesBold.into(), ObjectRef::new(7));
+        refs.fonts
+            .insert(StandardFont::Courier.into(), ObjectRef::new(3));
+        assert_eq!(
+            resources.to_dict(&refs).to_string(),
+            "<< /Font << /F1 7 0 R /F2 3 0 R >> >>"
+        );
+    }
+
+    #[test]
+    fn test_color_spaces() {
+        let mut resources = Resources::default();
+        let spot = ColorSpace::Separation(SpotColor::rgb("Gold", 0.8, 0.6, 0.2));
+        assert_eq!(resources.color_space(&spot), "CS1");
+        assert_eq!(resources.color_space(&spot), "CS1");
+        assert_eq!(
+            resources.to_dict(&ResourceRefs::default()).to_string(),
+            format!(
+                "<< /ColorSpace << /CS1 {} >> >>",
+                spot.to_pdf(&ResourceRefs::default())
+            )
+        );
+    }
+
+    #[test]
+    fn test_device_spaces() {
+        let mut resources = Resources::default();
+        assert_eq!(Color::rgb8(255, 0, 0).fill_op(&mut resources), "1 0 0 rg");
+        Color::Gray(0.5).stroke_op(&mut resources);
+        Color::Gray(0.0).fill_op(&mut resources);
+        SpotColor::cmyk("Gold", 0.0, 0.2, 0.8, 0.1)
+            .tint(1.0)
+            .fill_op(&mut resources);
+        let gradient = Gradient::linear(
+            (0.0, 0.0),
+            (10.0, 0.0),
+            Color::Cmyk(0.0, 0.0, 0.0, 1.0),
+            Color::Gray(1.0),
+        );
+        resources.shading(&gradient);
+        let spaces: Vec<&str> = resources.device_spaces().collect();
+        assert_eq!(spaces, ["DeviceRGB", "DeviceGray", "DeviceCMYK"]);
+    }
+
+    #[test]
+    fn test_same_content_written_once() {
+        let jpeg = crate::image::tests::jpeg_header(8, 8, 3);
+        let (first, second) = (
+            Image::from_jpeg(jpeg.clone()).unwrap(),
+            Image::from_jpeg(jpeg).unwrap(),
+        );
+        let other = Image::from_jpeg(crate::image::tests::jpeg_header(8, 9, 3)).unwrap();
+        let font = crate::truetype::test_font();
+        let same_font = crate::truetype::test_font();
+        let header = crate::icc::tests::profile_header(b"RGB ");
+        let profile = IccProfile::from_bytes(header.clone()).unwrap();
+        let same_profile = IccProfile::from_bytes(header).unwrap();
+
+        let mut pages = [Resources::default(), Resources::default()];
+        for (resources, (image, font)) in pages
+            .iter_mut()
+            .zip([(&first, &font), (&second, &same_font)])
+        {
+            resources.image(image, 10.0, 10.0);
+            resources.font(&font.clone().into());
+        }
+        pages[1].image(&other, 10.0, 10.0);
+        pages[0].color_space(&ColorSpace::IccBased(profile.clone()));
+        let mut shared = SharedResources::gather(&pages, []);
+        let mut refs = ResourceRefs::default();
+        let mut alloc = ObjectAllocator::new();
+        shared.allocate(&mut alloc, &mut refs, [], Some(&same_profile), []);
+
+        assert_eq!(refs.images[&first], refs.images[&second]);
+        assert_ne!(refs.images[&first], refs.images[&other]);
+        assert_eq!(shared.images.len(), 2);
+        let fonts = (Font::from(font), Font::from(same_font));
+        assert_eq!(refs.fonts[&fonts.0], refs.fonts[&fonts.1]);
+        assert_eq!(shared.fonts.len(), 1);
+        assert_eq!(refs.profiles[&profile], refs.profiles[&same_profile]);
+        assert_eq!(shared.profiles.len(), 1);
+        assert_eq!(alloc.size(), 5);
+    }
+
+    #[test]
+    fn test_font_options_written_apart() {
+        let font = crate::truetype::test_font();
+        let same_font = crate::truetype::test_font();
+        let handles = [
+            Font::from(font.clone()),
+            Font::from(font.vertical()),
+            Font::from(same_font.vertical()),
+        ];
+        let mut pages = [Resources::default(), Resources::default()];
+        pages[0].font(&handles[0]);
+        pages[0].font(&handles[1]);
+        pages[1].font(&handles[2]);
+        let mut shared = SharedResources::gather(&pages, []);
+        let mut refs = ResourceRefs::default();
+        let mut alloc = ObjectAllocator::new();
+        shared.allocate(&mut alloc, &mut refs, [], None, []);
+
+        // The same bytes written horizontally and vertically are two
+        // fonts, those of the same options one.
+        assert_ne!(refs.fonts[&handles[0]], refs.fonts[&handles[1]]);
+        assert_eq!(refs.fonts[&handles[1]], refs.fonts[&handles[2]]);
+        assert_eq!(shared.fonts.len(), 2);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::color::Color;
+use crate::geometry::Point;
+use crate::icc::IccProfile;
+use crate::object::{Dictionary, Object};
+use crate::resources::ResourceRefs;
+
+/// A smooth blend between colors along a line (axial) or between circles
+/// (radial). Gradients fill shapes either with
+/// [`ContentStream::fill_gradient`](crate::ContentStream::fill_gradient) or
+/// as a color, through `Color::from`, anywhere a fill or stroke color goes.
+///
+/// Beyond its ends, the gradient keeps the color of the nearest stop.
+/// `fill_gradient` places it in the coordinates of the content it is drawn
+/// in, transformations included; as a color it stays put on the page.
+///
+/// ```
+/// use pdf_creator::{Color, Gradient};
+///
+/// let orange = Color::rgb8(255, 165, 0);
+/// let navy = Color::rgb8(0, 0, 128);
+/// let sunset = Gradient::linear((0.0, 0.0), (0.0, 200.0), orange, navy)
+///     .with_stop(0.4, Color::rgb8(220, 20, 60));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    shape: Shape,
+    /// Offsets from 0 to 1 and their colors, in increasing order of offset.
+    stops: Vec<(f32, Color)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Axial { from: Point, to: Point },
+    Radial { center: Point, radius: f32 },
+}
+
+impl Gradient {
+    /// Goes from `start` at `from` to `end` at `to`, with lines of equal
+    /// color square to the axis between them.
+    pub fn linear(from: impl Into<Point>, to: impl Into<Point>, start: Color, end: Color) -> Self {
+        let shape = Shape::Axial {
+            from: from.into(),
+            to: to.into(),
+        };
+        Self::new(shape, start, end)
+    }
+
+    /// Goes from `inner` at `center` to `outer` on the circle of `radius`
+    /// around it.
+    pub fn radial(center: impl Into<Point>, radius: f32, inner: Color, outer: Color) -> Self {
+        let shape = Shape::Radial {
+            center: center.into(),
+            radius,
+        };
+        Self::new(shape, inner, outer)
+    }
+
+    fn new(shape: Shape, start: Color, end: Color) -> Self {
+        let gradient = Self {
+            shape,
+            stops: vec![(0.0, start), (1.0, end)],
+        };
+        gradient.check_stops();
+        gradient
+    }
+
+    /// Adds `color` at `offset` between the start (0) and the end (1). Stops
+    /// at the same offset make a sharp edge.
+    ///
+    /// # Panics
+    ///
+    /// If the colors of the stops cannot be painted in the same color space.
+    /// Gray mixes with RGB and CMYK; spot colors only with tints of the same
+    /// ink and ICC colors with colors of the same profile.
+    pub fn with_stop(mut self, offset: f32, color: Color) -> Self {
+        let offset = offset.clamp(0.0, 1.0);
+        let index = self.stops.partition_point(|(at, _)| *at <= offset);
+        // Stay before the end stop even when added at 1.
+        let index = index.min(self.stops.len() - 1);
+        self.stops.insert(index, (offset, color));
+        self.check_stops();
+        self
+    }
+
+    fn check_stops(&self) {
+        let space = self.space_color();
+        assert!(
+            self.stops
+                .iter()
+                .all(|(_, color)| color.components_like(space).is_some()),
+            "gradient stops must share a color space"
+        );
+    }
+
+    /// The stop whose color space all stops are painted in: the first whose
+    /// color is not gray, if any.
+    fn space_color(&self) -> &Color {
+        self.stops
+            .iter()
+            .map(|(_, color)| color)
+            .find(|color| !matches!(color, Color::Gray(_)))
+            .unwrap_or(&self.stops[0].1)
+    }
+
+    /// The profile the stops are painted in, written along with the pages.
+    pub(crate) fn profile(&self) -> Option<&IccProfile> {
+        match self.space_color() {
+            Color::Icc(profile, _) => Some(profile),
+            _ => None,
+        }
+    }
+
+    /// The name of the device color space the stops are painted in, if it
+    /// is one.
+    pub(crate) fn device_space(&self) -> Option<&'static str> {
+        match self.space_color() {
+            Color::Gray(_) => Some("DeviceGray"),
+            Color::Rgb(..) => Some("DeviceRGB"),
+            Color::Cmyk(..) => Some("DeviceCMYK"),
+            _ => None,
+        }
+    }
+
+    /// The `/ShadingType 2` or `3` dictionary, extended past both ends.
+    pub(crate) fn to_pdf(&self, refs: &ResourceRefs) -> Dictionary {
+        let (shading_type, coords) = match self.shape {
+            Shape::Axial { from, to } => (2, [from.x, from.y, to.x, to.y].to_vec()),
+            Shape::Radial { center, radius } => (
+                3,
+                [center.x, center.y, 0.0, center.x, center.y, radius].to_vec(),
+            ),
+        };
+        let mut dict = Dictionary::new();
+        dict.set("ShadingType", shading_type);
+        dict.set("ColorSpace", self.space_color().space(refs));
+        dict.set("Coords", Object::numbers(&coords));
+        dict.set("Function", self.function());
+        dict.set("Extend", vec![true.into(), true.into()]);
+        dict
+    }
+
+    /// The color function over `t` from 0 to 1: a single interpolation for
+    /// two stops, else one per pair of neighbouring stops, stitched together.
+    fn function(&self) -> Dictionary {
+        let space = self.space_color();
+        let components = |color: &Color| color.components_like(space).unwrap();
+        let mut interpolations: Vec<Dictionary> = self
+            .stops
+            .windows(2)
+            .map(|pair| interpolation(&components(&pair[0].1), &components(&pair[1].1)))
+            .collect();
+        if interpolations.len() == 1 {
+            return interpolations.remove(0);
+        }
+        let bounds: Vec<f32> = self.stops[1..self.stops.len() - 1]
+            .iter()
+            .map(|(offset, _)| *offset)
+            .collect();
+        let encode = Object::numbers(&[0.0, 1.0].repeat(interpolations.len()));
+        let mut dict = Dictionary::new();
+        dict.set("FunctionType", 3);
+        dict.set("Domain", Object::numbers(&[0.0, 1.0]));
+        let functions = interpolations.into_iter().map(Object::from).collect();
+        dict.set("Functions", Object::Array(functions));
+        dict.set("Bounds", Object::numbers(&bounds));
+        dict.set("Encode", encode);
+        dict
+    }
+}
+
+/// The `/FunctionType 2` function going linearly from the components `c0`
+/// to `c1`.
+pub(crate) fn interpolation(c0: &[f32], c1: &[f32]) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("FunctionType", 2);
+    dict.set("Domain", Object::numbers(&[0.0, 1.0]));
+    dict.set("C0", Object::numbers(c0));
+    dict.set("C1", Object::numbers(c1));
+    dict.set("N", 1);
+    dict
+}
+
+impl From<Gradient> for Color {
+    fn from(gradient: Gradient) -> Self {
+        Color::Gradient(Box::new(gradient))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_gradient() {
+        let gradient = Gradient::linear(
+            (0.0, 0.0),
+            (100.0, 0.0),
+            Color::Rgb(1.0, 0.0, 0.0),
+            Color::WHITE,
+        );
+        assert_eq!(
+            gradient.to_pdf(&ResourceRefs::default()).to_string(),
+            "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 100 0] \
+             /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [1 1 1] /N 1 >> \
+             /Extend [true true] >>"
+        );
+    }
+
+    #[test]
+    fn test_radial_gradient_stops() {
+        let gradient = Gradient::radial((50.0, 50.0), 20.0, Color::BLACK, Color::WHITE)
+            .with_stop(1.0, Color::Gray(0.8))
+            .with_stop(0.25, Color::Gray(0.5));
+        let offsets: Vec<f32> = gradient.stops.iter().map(|(offset, _)| *offset).collect();
+        assert_eq!(offsets, [0.0, 0.25, 1.0, 1.0]);
+        assert_eq!(
+            gradient.function().to_string(),
+            "<< /FunctionType 3 /Domain [0 1] /Functions [\
+             << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [0.5] /N 1 >> \
+             << /FunctionType 2 /Domain [0 1] /C0 [0.5] /C1 [0.8] /N 1 >> \
+             << /FunctionType 2 /Domain [0 1] /C0 [0.8] /C1 [1] /N 1 >>] \
+             /Bounds [0.25 1] /Encode [0 1 0 1 0 1] >>"
+        );
+        assert!(
+            gradient
+                .to_pdf(&ResourceRefs::default())
+                .to_string()
+                .starts_with(
+                    "<< /ShadingType 3 /ColorSpace /DeviceGray /Coords [50 50 0 50 50 20]"
+                )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "gradient stops must share a color space")]
+    fn test_mixed_color_spaces() {
+        Gradient::linear(
+            (0.0, 0.0),
+            (1.0, 0.0),
+            Color::Rgb(1.0, 0.0, 0.0),
+            Color::Cmyk(0.0, 0.0, 0.0, 1.0),
+        );
+    }
+}
@@ -0,0 +1,864 @@
+use crate::font::{Font, StandardFont};
+use crate::geometry::Rect;
+use crate::image::Image;
+use crate::metadata::PdfDate;
+use crate::object::{Dictionary, Object};
+use crate::string::hex_string;
+use crate::timestamp;
+use crate::xobject::FormXObject;
+use anyhow::{Context, Result, ensure};
+use cms::cert::CertificateChoices;
+use cms::cert::IssuerAndSerialNumber;
+use cms::cert::x509::attr::Attribute;
+use cms::cert::x509::ext::pkix::name::GeneralName;
+use cms::cert::x509::serial_number::SerialNumber;
+use cms::cert::x509::spki::AlgorithmIdentifierOwned;
+use cms::cert::x509::{Certificate, name::Name};
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::signed_data::{
+    CertificateSet, EncapsulatedContentInfo, SignedAttributes, SignedData, SignerIdentifier,
+    SignerInfo, SignerInfos,
+};
+use const_oid::ObjectIdentifier;
+use const_oid::db::rfc4519::CN;
+use const_oid::db::rfc5911::{
+    ID_AA_SIGNING_CERTIFICATE_V_2, ID_CONTENT_TYPE, ID_DATA, ID_MESSAGE_DIGEST, ID_SIGNED_DATA,
+};
+use const_oid::db::rfc5912::{ECDSA_WITH_SHA_256, ID_SHA_256, RSA_ENCRYPTION};
+use der::asn1::{BmpString, OctetString, SetOfVec};
+use der::{Any, Decode, Encode, EncodeValue, Sequence, Tag, Tagged};
+use rand_core::OsRng;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// The attribute of a timestamp token of the signature value.
+const SIGNATURE_TIMESTAMP: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.14");
+
+/// Room kept in a signature, beyond the certificates and the signature
+/// value, for the structure around them.
+const CMS_OVERHEAD: usize = 1024;
+
+/// Room kept for a timestamp token, which holds the certificates of the
+/// time stamping authority.
+const TIMESTAMP_ROOM: usize = 16384;
+
+/// What `/ByteRange` is written as until the file is complete, wide
+/// enough for the offsets of files up to 10 GB.
+pub(crate) const BYTE_RANGE_PLACEHOLDER: &str = "[0 0000000000 0000000000 0000000000]";
+
+/// Digitally signs documents as they are written, with a key and the
+/// certificate that vouches for it: readers check that the document is as
+/// it was signed and show who signed it. The signature is a detached CMS
+/// (PKCS#7) signature, of a SHA-256 digest of the whole file, or one to
+/// the PAdES baseline profiles with [`pades`](Self::pades).
+///
+/// Applied with [`PdfDocument::sign`](crate::PdfDocument::sign).
+#[derive(Clone)]
+pub struct Signer {
+    key: SigningKey,
+    /// The signer's certificate first, then those of the authorities that
+    /// issued it.
+    certificates: Vec<Certificate>,
+    name: Option<String>,
+    reason: Option<String>,
+    location: Option<String>,
+    contact_info: Option<String>,
+    time: Option<PdfDate>,
+    pades: bool,
+    /// Turns a timestamp request into the response of the authority.
+    timestamper: Option<Arc<Callback>>,
+    appearance: Option<SignatureAppearance>,
+}
+
+/// Answers the bytes it is given: signs them, for [`Signer::external`], or
+/// sends them to a time stamping authority, for [`Signer::timestamp`].
+type Callback = dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync;
+
+#[derive(Clone)]
+enum SigningKey {
+    Rsa(Box<RsaPrivateKey>),
+    External {
+        algorithm: SignatureAlgorithm,
+        sign: Arc<Callback>,
+    },
+}
+
+/// How a signer set up with [`Signer::external`] signs, always over a
+/// SHA-256 digest of what it is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// RSA with PKCS#1 v1.5 padding.
+    RsaSha256,
+    /// ECDSA, its signature a DER `Ecdsa-Sig-Value`.
+    EcdsaSha256,
+}
+
+/// What a visible signature shows in its field: who signed and when, the
+/// reason and location if given, and a logo to the left, such as that of a
+/// company or a scan of a handwritten signature.
+///
+/// Applied with [`Signer::appearance`].
+#[derive(Debug, Clone)]
+pub struct SignatureAppearance {
+    font: Font,
+    logo: Option<Image>,
+}
+
+/// Where the blank `/ByteRange` and `/Contents` of the signature are in a
+/// written file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SignaturePlaceholder {
+    pub(crate) byte_range: usize,
+    /// The hex string, angle brackets included.
+    pub(crate) contents: Range<usize>,
+}
+
+/// The CAdES `SigningCertificateV2` attribute of RFC 5035, which names
+/// the certificate of the signer by its SHA-256 digest, the default
+/// algorithm, and by its issuer and serial number.
+#[derive(Sequence)]
+struct SigningCertificateV2 {
+    certs: Vec<EssCertIdV2>,
+}
+
+#[derive(Sequence)]
+struct EssCertIdV2 {
+    cert_hash: OctetString,
+    issuer_serial: IssuerSerial,
+}
+
+#[derive(Sequence)]
+struct IssuerSerial {
+    issuer: Vec<GeneralName>,
+    serial_number: SerialNumber,
+}
+
+impl Signer {
+    /// A signer with the RSA private key `key`, DER-encoded as PKCS#8 or
+    /// PKCS#1, and `certificates` (DER X.509), the first for `key`, the
+    /// others those of the authorities that issued it, which readers need
+    /// to trust the signature unless they know them already.
+    ///
+    /// Fails if the key cannot be read or is not that of the first
+    /// certificate.
+    pub fn rsa(key: &[u8], certificates: Vec<Vec<u8>>) -> Result<Self> {
+        let key = RsaPrivateKey::from_pkcs8_der(key)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_der(key))
+            .context("the private key is not a DER RSA key")?;
+        ensure!(
+            key.size() >= 128,
+            "RSA keys of fewer than 1024 bits are not supported"
+        );
+        let public_key = key.to_public_key();
+        let signer = Self::with_key(SigningKey::Rsa(Box::new(key)), certificates)?;
+        let public_key_info = signer.certificates[0]
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()?;
+        ensure!(
+            RsaPublicKey::from_public_key_der(&public_key_info).ok() == Some(public_key),
+            "the private key is not that of the certificate"
+        );
+        Ok(signer)
+    }
+
+    /// A signer whose key is kept elsewhere, such as in a hardware security
+    /// module: `sign` is given the bytes to sign (the DER of the signed
+    /// attributes) and returns their signature by `algorithm`, the key
+    /// being that of the first of `certificates`, as for
+    /// [`rsa`](Self::rsa).
+    pub fn external(
+        certificates: Vec<Vec<u8>>,
+        algorithm: SignatureAlgorithm,
+        sign: impl Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let key = SigningKey::External {
+            algorithm,
+            sign: Arc::new(sign),
+        };
+        Self::with_key(key, certificates)
+    }
+
+    fn with_key(key: SigningKey, certificates: Vec<Vec<u8>>) -> Result<Self> {
+        ensure!(
+            !certificates.is_empty(),
+            "signing needs the certificate of the signer"
+        );
+        let certificates = certificates
+            .iter()
+            .map(|der| Certificate::from_der(der).context("the certificate is not DER"))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            key,
+            certificates,
+            name: None,
+            reason: None,
+            location: None,
+            contact_info: None,
+            time: None,
+            pades: false,
+            timestamper: None,
+            appearance: None,
+        })
+    }
+
+    /// The name of the signer, as readers show it, when the certificate
+    /// does not tell it well enough.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Why the document is signed, such as "Approved".
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    /// Where the document is signed, such as a city.
+    pub fn location(mut self, location: &str) -> Self {
+        self.location = Some(location.to_string());
+        self
+    }
+
+    /// How to reach the signer, such as a phone number.
+    pub fn contact_info(mut self, contact_info: &str) -> Self {
+        self.contact_info = Some(contact_info.to_string());
+        self
+    }
+
+    /// When the document is taken to be signed, rather than when it is
+    /// written. Readers trust it only as far as they trust the signer.
+    pub fn time(mut self, time: PdfDate) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Signs to the PAdES baseline profiles of ETSI EN 319 142, which
+    /// eIDAS workflows take: B-B, or B-T with a [`timestamp`]. The
+    /// signature is then a CAdES signature, which names the certificate of
+    /// the signer by its digest, lest it be swapped for another of the same
+    /// key.
+    ///
+    /// [`timestamp`]: Self::timestamp
+    pub fn pades(mut self) -> Self {
+        self.pades = true;
+        self
+    }
+
+    /// Has the signature timestamped by a time stamping authority, as RFC
+    /// 3161 specifies, which proves it was made by the time the authority
+    /// tells: `request` is given the DER of a timestamp request to send to
+    /// the authority, usually posted over HTTP as
+    /// `application/timestamp-query`, and returns the DER of its response.
+    /// Signing fails if the authority refuses, or answers with a token for
+    /// something else.
+    pub fn timestamp(
+        mut self,
+        request: impl Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        self.timestamper = Some(Arc::new(request));
+        self
+    }
+
+    /// Has the signature show in its field as `appearance` tells, in place
+    /// of the look the field has, the field being of some size. What it
+    /// shows is drawn as the document is written.
+    pub fn appearance(mut self, appearance: SignatureAppearance) -> Self {
+        self.appearance = Some(appearance);
+        self
+    }
+
+    /// When the document is taken to be signed: the time given, or now.
+    pub(crate) fn signing_time(&self) -> PdfDate {
+        self.time.unwrap_or_else(PdfDate::now)
+    }
+
+    /// The look of the signature in a field of the size of `rect`, unless
+    /// the signature is invisible.
+    pub(crate) fn look(&self, rect: Rect) -> Result<Option<FormXObject>> {
+        let Some(appearance) = &self.appearance else {
+            return Ok(None);
+        };
+        let (width, height) = (rect.width.abs(), rect.height.abs());
+        if width == 0.0 || height == 0.0 {
+            return Ok(None);
+        }
+        let name = match &self.name {
+            Some(name) => Some(name.clone()),
+            None => common_name(&self.certificates[0].tbs_certificate.subject)?,
+        };
+        let mut lines = vec![
+            match name {
+                Some(name) => format!("Digitally signed by {}", name),
+                None => "Digitally signed".to_string(),
+            },
+            format!("Date: {}", self.signing_time().to_text()),
+        ];
+        if let Some(reason) = &self.reason {
+            lines.push(format!("Reason: {}", reason));
+        }
+        if let Some(location) = &self.location {
+            lines.push(format!("Location: {}", location));
+        }
+        Ok(Some(appearance.draw(width, height, &lines)))
+    }
+
+    /// How many bytes the signature can take at most.
+    pub(crate) fn reserved_size(&self) -> usize {
+        let signature = match &self.key {
+            SigningKey::Rsa(key) => key.size(),
+            // Room for RSA keys of up to 8192 bits.
+            SigningKey::External { .. } => 1024,
+        };
+        let length = |certificate: &Certificate| {
+            certificate
+                .encoded_len()
+                .ok()
+                .and_then(|length| usize::try_from(length).ok())
+                .unwrap_or_default()
+        };
+        // The issuer of the signer's certificate is repeated in the
+        // signature.
+        let certificates: usize = self.certificates.iter().map(length).sum();
+        let timestamp = match self.timestamper {
+            Some(_) => TIMESTAMP_ROOM,
+            None => 0,
+        };
+        certificates + length(&self.certificates[0]) + signature + timestamp + CMS_OVERHEAD
+    }
+
+    /// The signature dictionary, but for `/ByteRange` and `/Contents`.
+    pub(crate) fn to_dict(&self) -> Dictionary {
+        let sub_filter = match self.pades {
+            true => "ETSI.CAdES.detached",
+            false => "adbe.pkcs7.detached",
+        };
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Sig"));
+        dict.set("Filter", Object::name("Adobe.PPKLite"));
+        dict.set("SubFilter", Object::name(sub_filter));
+        dict.set("M", self.signing_time().to_pdf());
+        let details = [
+            ("Name", &self.name),
+            ("Reason", &self.reason),
+            ("Location", &self.location),
+            ("ContactInfo", &self.contact_info),
+        ];
+        for (key, value) in details {
+            if let Some(value) = value {
+                dict.set(key, Object::text(value));
+            }
+        }
+        dict
+    }
+
+    /// Signs the written file `pdf`: fills in the byte range, everything
+    /// but the contents of the signature, and the signature of it.
+    pub(crate) fn sign_file(
+        &self,
+        pdf: &mut [u8],
+        placeholder: &SignaturePlaceholder,
+    ) -> Result<()> {
+        let Range { start, end } = placeholder.contents;
+        let byte_range = format!("[0 {} {} {}]", start, end, pdf.len() - end);
+        ensure!(
+            byte_range.len() <= BYTE_RANGE_PLACEHOLDER.len(),
+            "the document is too large to be signed"
+        );
+        let range = placeholder.byte_range..placeholder.byte_range + BYTE_RANGE_PLACEHOLDER.len();
+        pdf[range].copy_from_slice(
+            format!(
+                "{:width$}",
+                byte_range,
+                width = BYTE_RANGE_PLACEHOLDER.len()
+            )
+            .as_bytes(),
+        );
+
+        let digest = Sha256::new()
+            .chain_update(&pdf[..start])
+            .chain_update(&pdf[end..])
+            .finalize();
+        let signature = hex_string(&self.cms(&digest)?);
+        ensure!(
+            signature.len() <= end - start,
+            "the signature is larger than the room kept for it"
+        );
+        // The contents stay padded with zeros.
+        let hex = signature.trim_start_matches('<').trim_end_matches('>');
+        pdf[start + 1..start + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+        Ok(())
+    }
+
+    /// The CMS `SignedData` of `digest`, that of the signed file, without
+    /// the file itself.
+    fn cms(&self, digest: &[u8]) -> Result<Vec<u8>> {
+        let certificate = &self.certificates[0];
+        let fields = &certificate.tbs_certificate;
+        let mut attributes = vec![
+            attribute(ID_CONTENT_TYPE, &ID_DATA)?,
+            attribute(ID_MESSAGE_DIGEST, &OctetString::new(digest)?)?,
+        ];
+        if self.pades {
+            let signing_certificate = SigningCertificateV2 {
+                certs: vec![EssCertIdV2 {
+                    cert_hash: OctetString::new(Sha256::digest(certificate.to_der()?).as_slice())?,
+                    issuer_serial: IssuerSerial {
+                        issuer: vec![GeneralName::DirectoryName(fields.issuer.clone())],
+                        serial_number: fields.serial_number.clone(),
+                    },
+                }],
+            };
+            attributes.push(attribute(
+                ID_AA_SIGNING_CERTIFICATE_V_2,
+                &signing_certificate,
+            )?);
+        }
+        // The attributes are signed as a SET OF, and written tagged [0].
+        let signed_attributes = SignedAttributes::try_from(attributes)?;
+        let signature = self.sign(&signed_attributes.to_der()?)?;
+
+        // A timestamp token of the signature itself.
+        let unsigned_attributes = match &self.timestamper {
+            Some(timestamper) => {
+                let digest = Sha256::digest(&signature);
+                let mut nonce = [0; 8];
+                getrandom::fill(&mut nonce)?;
+                let response = timestamper(&timestamp::request(&digest, &nonce)?)?;
+                let token = timestamp::token(&response, &digest, &nonce)?;
+                Some(SetOfVec::try_from(vec![attribute(
+                    SIGNATURE_TIMESTAMP,
+                    &token,
+                )?])?)
+            }
+            None => None,
+        };
+
+        let algorithm = match &self.key {
+            SigningKey::Rsa(_)
+            | SigningKey::External {
+                algorithm: SignatureAlgorithm::RsaSha256,
+                ..
+            } => AlgorithmIdentifierOwned {
+                oid: RSA_ENCRYPTION,
+                parameters: Some(Any::null()),
+            },
+            SigningKey::External {
+                algorithm: SignatureAlgorithm::EcdsaSha256,
+                ..
+            } => AlgorithmIdentifierOwned {
+                oid: ECDSA_WITH_SHA_256,
+                parameters: None,
+            },
+        };
+        let signer_info = SignerInfo {
+            version: CmsVersion::V1,
+            sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: fields.issuer.clone(),
+                serial_number: fields.serial_number.clone(),
+            }),
+            digest_alg: sha256_algorithm(),
+            signed_attrs: Some(signed_attributes),
+            signature_algorithm: algorithm,
+            signature: OctetString::new(signature)?,
+            unsigned_attrs: unsigned_attributes,
+        };
+        let certificates = self
+            .certificates
+            .iter()
+            .cloned()
+            .map(CertificateChoices::Certificate)
+            .collect::<Vec<_>>();
+        let signed_data = SignedData {
+            version: CmsVersion::V1,
+            digest_algorithms: SetOfVec::try_from(vec![sha256_algorithm()])?,
+            encap_content_info: EncapsulatedContentInfo {
+                econtent_type: ID_DATA,
+                econtent: None,
+            },
+            certificates: Some(CertificateSet(SetOfVec::try_from(certificates)?)),
+            crls: None,
+            signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info])?),
+        };
+        let content_info = ContentInfo {
+            content_type: ID_SIGNED_DATA,
+            content: Any::encode_from(&signed_data)?,
+        };
+        Ok(content_info.to_der()?)
+    }
+
+    /// The signature of `data` by the key.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match &self.key {
+            // Blinded, for the time signing takes not to tell the key.
+            SigningKey::Rsa(key) => Ok(key.sign_with_rng(
+                &mut OsRng,
+                Pkcs1v15Sign::new::<Sha256>(),
+                &Sha256::digest(data),
+            )?),
+            SigningKey::External { sign, .. } => {
+                let signature = sign(data)?;
+                ensure!(!signature.is_empty(), "the external signer signed nothing");
+                Ok(signature)
+            }
+        }
+    }
+}
+
+/// Keeps the key out of logs.
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match &self.key {
+            SigningKey::Rsa(key) => format!("Rsa({} bits)", key.size() * 8),
+            SigningKey::External { algorithm, .. } => format!("External({:?})", algorithm),
+        };
+        f.debug_struct("Signer")
+            .field("key", &key)
+            .field("certificates", &self.certificates.len())
+            .field("name", &self.name)
+            .field("reason", &self.reason)
+            .field("location", &self.location)
+            .field("contact_info", &self.contact_info)
+            .field("time", &self.time)
+            .field("pades", &self.pades)
+            .field("timestamped", &self.timestamper.is_some())
+            .field("appearance", &self.appearance)
+            .finish()
+    }
+}
+
+/// How far the content of visible signatures keeps from the edges of
+/// their field, and the largest size of their text.
+const PADDING: f32 = 2.0;
+const TEXT_SIZE: f32 = 10.0;
+
+impl Default for SignatureAppearance {
+    fn default() -> Self {
+        Self {
+            font: Font::Standard(StandardFont::Helvetica),
+            logo: None,
+        }
+    }
+}
+
+impl SignatureAppearance {
+    /// Text in Helvetica, without a logo.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the text in `font`, such as an embedded font with the letters
+    /// of names the standard fonts lack.
+    pub fn font(mut self, font: impl Into<Font>) -> Self {
+        self.font = font.into();
+        self
+    }
+
+    /// Shows `logo` to the left of the text, on up to 40% of the field, as
+    /// large as fits without distorting it.
+    pub fn logo(mut self, logo: Image) -> Self {
+        self.logo = Some(logo);
+        self
+    }
+
+    /// The look of a field of `width` by `height` showing `lines`, as large
+    /// as they fit up to 10 points, centered on the height.
+    fn draw(&self, width: f32, height: f32, lines: &[String]) -> FormXObject {
+        let left = match self.logo {
+            Some(_) => width * 0.4,
+            None => PADDING,
+        };
+        let font = &self.font;
+        let widest = lines
+            .iter()
+            .map(|line| font.measure(line, 1.0))
+            .fold(0.0, f32::max);
+        let leading = 1.2;
+        let size = ((height - 2.0 * PADDING) / (lines.len() as f32 * leading))
+            .min((width - left - PADDING) / widest)
+            .clamp(0.1, TEXT_SIZE);
+        FormXObject::new(width, height, |g| {
+            if let Some(logo) = &self.logo {
+                let (room_width, room_height) = (left - 2.0 * PADDING, height - 2.0 * PADDING);
+                let (pixels_wide, pixels_high) = (logo.width() as f32, logo.height() as f32);
+                let scale = (room_width / pixels_wide).min(room_height / pixels_high);
+                let (logo_width, logo_height) = (pixels_wide * scale, pixels_high * scale);
+                let placed = Rect::new(
+                    PADDING + (room_width - logo_width) / 2.0,
+                    (height - logo_height) / 2.0,
+                    logo_width,
+                    logo_height,
+                );
+                g.draw_image(logo, placed);
+            }
+            let top = (height + lines.len() as f32 * size * leading) / 2.0;
+            for (i, line) in lines.iter().enumerate() {
+                let y = top - i as f32 * size * leading - font.ascent(size);
+                g.draw_text(left, y, size, font.clone(), line);
+            }
+        })
+    }
+}
+
+/// The `AlgorithmIdentifier` of SHA-256, its parameters absent.
+pub(crate) fn sha256_algorithm() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ID_SHA_256,
+        parameters: None,
+    }
+}
+
+/// The CMS attribute of `kind`, of the single value `value`.
+fn attribute(kind: ObjectIdentifier, value: &(impl EncodeValue + Tagged)) -> Result<Attribute> {
+    Ok(Attribute {
+        oid: kind,
+        values: SetOfVec::try_from(vec![Any::encode_from(value)?])?,
+    })
+}
+
+/// The common name in `name`, that of the subject of a certificate, the
+/// name of the signer, if it has one.
+fn common_name(name: &Name) -> Result<Option<String>> {
+    // A name is a sequence of sets of attributes, each a type and value.
+    let Some(attribute) = name
+        .0
+        .iter()
+        .flat_map(|names| names.0.iter())
+        .find(|attribute| attribute.oid == CN)
+    else {
+        return Ok(None);
+    };
+    let value = &attribute.value;
+    let name = match value.tag() {
+        // A BMPString, in UTF-16.
+        Tag::BmpString => value.decode_as::<BmpString>()?.to_string(),
+        _ => String::from_utf8_lossy(value.value()).into_owned(),
+    };
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::object::ObjectRef;
+    use crate::resources::ResourceRefs;
+    use crate::timestamp::tests::{answer, response};
+    use crate::writer::PdfWriter;
+    use cmpv2::status::PkiStatus;
+
+    /// The DER of the key the test certificate is for.
+    pub(crate) fn test_key() -> Vec<u8> {
+        std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/certs/signer.key.der")).unwrap()
+    }
+
+    /// The DER of the test certificate.
+    pub(crate) fn test_certificate() -> Vec<u8> {
+        std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/certs/signer.crt.der")).unwrap()
+    }
+
+    pub(crate) fn test_signer() -> Signer {
+        Signer::rsa(&test_key(), vec![test_certificate()]).unwrap()
+    }
+
+    #[test]
+    fn test_signer_checks() {
+        let certificate = test_certificate();
+        assert!(Signer::rsa(&test_key(), Vec::new()).is_err());
+        assert!(Signer::rsa(&test_key(), vec![certificate[..100].to_vec()]).is_err());
+        assert!(Signer::rsa(b"not a key", vec![certificate.clone()]).is_err());
+
+        let signer = test_signer()
+            .reason("Approved")
+            .time(PdfDate::new(2024, 5, 1).unwrap());
+        assert_eq!(
+            signer.to_dict().to_string(),
+            "<< /Type /Sig /Filter /Adobe.PPKLite /SubFilter /adbe.pkcs7.detached \
+             /M (D:20240501000000Z) /Reason (Approved) >>"
+        );
+        assert!(format!("{:?}", signer).contains("key: \"Rsa(2048 bits)\""));
+        let subject = &signer.certificates[0].tbs_certificate.subject;
+        assert_eq!(
+            common_name(subject).unwrap().as_deref(),
+            Some("pdf-creator test signer")
+        );
+    }
+
+    /// The signer info of the DER signature `cms`.
+    fn signer_info(cms: &[u8]) -> SignerInfo {
+        let content_info = ContentInfo::from_der(cms).unwrap();
+        assert_eq!(content_info.content_type, ID_SIGNED_DATA);
+        let signed_data: SignedData = content_info.content.decode_as().unwrap();
+        signed_data.signer_infos.0.get(0).unwrap().clone()
+    }
+
+    /// The single value of the attribute of `kind` among `attributes`.
+    fn value(attributes: &SetOfVec<Attribute>, kind: ObjectIdentifier) -> Any {
+        let attribute = attributes.iter().find(|a| a.oid == kind).unwrap();
+        attribute.values.get(0).unwrap().clone()
+    }
+
+    #[test]
+    fn test_rsa_signature() {
+        let digest = Sha256::digest(b"the file");
+        let signer_info = signer_info(&test_signer().cms(&digest).unwrap());
+        assert_eq!(signer_info.signature_algorithm.oid, RSA_ENCRYPTION);
+        let attributes = signer_info.signed_attrs.unwrap();
+        let message_digest: OctetString =
+            value(&attributes, ID_MESSAGE_DIGEST).decode_as().unwrap();
+        assert_eq!(message_digest.as_bytes(), digest.as_slice());
+
+        // The signature is of the signed attributes, by the key of the
+        // certificate.
+        let certificate = Certificate::from_der(&test_certificate()).unwrap();
+        let public_key_info = certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()
+            .unwrap();
+        let public_key = RsaPublicKey::from_public_key_der(&public_key_info).unwrap();
+        let signed = Sha256::digest(attributes.to_der().unwrap());
+        public_key
+            .verify(
+                Pkcs1v15Sign::new::<Sha256>(),
+                &signed,
+                signer_info.signature.as_bytes(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_appearance() {
+        let signer = test_signer()
+            .location("Pune")
+            .time(PdfDate::new(2024, 5, 1).unwrap().at(9, 30, 0).unwrap());
+        let rect = Rect::new(100.0, 100.0, 200.0, 50.0);
+        assert!(signer.look(rect).unwrap().is_none());
+
+        let content = |look: FormXObject| {
+            let mut refs = ResourceRefs::default();
+            refs.fonts
+                .insert(StandardFont::Helvetica.into(), ObjectRef::new(3));
+            let mut buf = std::io::Cursor::new(Vec::new());
+            let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+            writer.set_compression(false);
+            look.write(&mut writer, ObjectRef::new(5), &refs).unwrap();
+            String::from_utf8(buf.into_inner()).unwrap()
+        };
+        let signer = signer.appearance(SignatureAppearance::new());
+        let look = signer.look(rect).unwrap().unwrap();
+        assert_eq!((look.width(), look.height()), (200.0, 50.0));
+        let ops = content(look);
+        assert!(ops.contains("(Digitally signed by pdf-creator test signer) Tj"));
+        assert!(ops.contains("(Date: 2024-05-01 09:30:00 UTC) Tj"));
+        assert!(ops.contains("(Location: Pune) Tj"));
+        // A field of no size keeps the signature invisible.
+        assert!(signer.look(Rect::default()).unwrap().is_none());
+
+        let named = signer.name("A. Signer");
+        let ops = content(named.look(rect).unwrap().unwrap());
+        assert!(ops.contains("(Digitally signed by A. Signer) Tj"));
+        // Text starts at the padding, at most 10 points.
+        assert!(ops.contains("/F1 10 Tf\n2 "));
+    }
+
+    #[test]
+    fn test_pades() {
+        let signer = Signer::external(
+            vec![test_certificate()],
+            SignatureAlgorithm::RsaSha256,
+            |_| Ok(vec![0xAB; 256]),
+        )
+        .unwrap()
+        .pades()
+        .timestamp(|request| Ok(response(request, None)));
+        assert!(
+            signer
+                .to_dict()
+                .to_string()
+                .contains("/SubFilter /ETSI.CAdES.detached /M")
+        );
+        let cms = signer.cms(&[1; 32]).unwrap();
+        let signer_info = signer_info(&cms);
+        let signing_certificate = value(
+            signer_info.signed_attrs.as_ref().unwrap(),
+            ID_AA_SIGNING_CERTIFICATE_V_2,
+        );
+        let digest = Sha256::digest(test_certificate());
+        assert!(
+            signing_certificate
+                .value()
+                .windows(32)
+                .any(|w| w == digest.as_slice())
+        );
+
+        // The signer info ends with a timestamp token of the signature.
+        let unsigned = signer_info.unsigned_attrs.unwrap();
+        let token: ContentInfo = value(&unsigned, SIGNATURE_TIMESTAMP).decode_as().unwrap();
+        let imprint = Sha256::digest([0xAB; 256]);
+        let token = token.to_der().unwrap();
+        assert!(token.windows(32).any(|w| w == imprint.as_slice()));
+
+        let refused = signer.timestamp(|_| Ok(answer(PkiStatus::Rejection, None)));
+        assert!(refused.cms(&[1; 32]).is_err());
+    }
+
+    #[test]
+    fn test_sign_file() {
+        let signed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = signed.clone();
+        let signer = Signer::external(
+            vec![test_certificate()],
+            SignatureAlgorithm::EcdsaSha256,
+            move |data| {
+                *seen.lock().unwrap() = data.to_vec();
+                Ok(vec![0xAB; 70])
+            },
+        )
+        .unwrap();
+        let mut pdf = format!(
+            "%PDF-1.7\n<< /ByteRange {} /Contents <{}> >>\n%%EOF\n",
+            BYTE_RANGE_PLACEHOLDER,
+            "0".repeat(2 * signer.reserved_size())
+        )
+        .into_bytes();
+        let text = String::from_utf8(pdf.clone()).unwrap();
+        let (start, end) = (
+            text.find("/Contents ").unwrap() + 10,
+            text.find("> >>").unwrap() + 1,
+        );
+        let placeholder = SignaturePlaceholder {
+            byte_range: text.find(BYTE_RANGE_PLACEHOLDER).unwrap(),
+            contents: start..end,
+        };
+        signer.sign_file(&mut pdf, &placeholder).unwrap();
+        let text = String::from_utf8(pdf.clone()).unwrap();
+        let byte_range = format!("[0 {} {} {}]", start, end, pdf.len() - end);
+        assert!(text.contains(&format!("/ByteRange {:36} /Contents <3082", byte_range)));
+        assert!(text[start..end].ends_with("00>"));
+
+        // The signed attributes hold the digest of the file around the
+        // contents.
+        let digest = Sha256::new()
+            .chain_update(&pdf[..start])
+            .chain_update(&pdf[end..])
+            .finalize();
+        let signed = SignedAttributes::from_der(&signed.lock().unwrap()).unwrap();
+        let message_digest: OctetString = value(&signed, ID_MESSAGE_DIGEST).decode_as().unwrap();
+        assert_eq!(message_digest.as_bytes(), digest.as_slice());
+
+        let mut small = pdf.clone();
+        let tight = SignaturePlaceholder {
+            contents: start..start + 100,
+            ..placeholder
+        };
+        assert!(signer.sign_file(&mut small, &tight).is_err());
+    }
+}
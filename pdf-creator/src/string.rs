@@ -0,0 +1,105 @@
+/// Serializes `bytes` as a PDF string object. Printable ASCII is written as a
+/// literal string; anything else (WinAnsi codes above 127, control bytes,
+/// multi-byte glyph codes) as a hex string, which survives any transport.
+pub(crate) fn pdf_string(bytes: &[u8]) -> String {
+    if bytes.iter().all(|byte| (b' '..=b'~').contains(byte)) {
+        literal_string(bytes)
+    } else {
+        hex_string(bytes)
+    }
+}
+
+/// Writes `bytes` between parentheses, escaping the delimiters and the
+/// backslash. Only meant for printable ASCII; see [`pdf_string`].
+pub(crate) fn literal_string(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(bytes.len() + 2);
+    string.push('(');
+    for &byte in bytes {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            string.push('\\');
+        }
+        string.push(byte as char);
+    }
+    string.push(')');
+    string
+}
+
+/// Writes `bytes` as a `<...>` hex string.
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(bytes.len() * 2 + 2);
+    string.push('<');
+    for byte in bytes {
+        string.push_str(&format!("{:02X}", byte));
+    }
+    string.push('>');
+    string
+}
+
+/// The bytes of `text` as a PDF text string, for strings readers show such
+/// as titles and names: printable ASCII as is, anything else in UTF-16BE
+/// with a byte order mark.
+pub(crate) fn text_bytes(text: &str) -> Vec<u8> {
+    if text.bytes().all(|byte| (b' '..=b'~').contains(&byte)) {
+        return text.as_bytes().to_vec();
+    }
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    bytes
+}
+
+/// Writes `name` as a PDF name object, `/` included. Bytes outside the
+/// printable ASCII range, along with delimiters and `#`, are written as
+/// `#xx` hex escapes, but for null bytes, which names cannot hold even so
+/// and are left out.
+pub(crate) fn pdf_name(name: impl AsRef<[u8]>) -> String {
+    let name = name.as_ref();
+    let mut escaped = String::with_capacity(name.len() + 1);
+    escaped.push('/');
+    for &byte in name {
+        if byte == 0 {
+            continue;
+        }
+        if (b'!'..=b'~').contains(&byte) && !b"()<>[]{}/%#".contains(&byte) {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("#{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_escapes() {
+        assert_eq!(pdf_string(b"f(x) = a\\b"), "(f\\(x\\) = a\\\\b)");
+        assert_eq!(pdf_string(b"(unbalanced"), "(\\(unbalanced)");
+        assert_eq!(pdf_string(b""), "()");
+    }
+
+    #[test]
+    fn test_name_escapes() {
+        assert_eq!(pdf_name("DeviceRGB"), "/DeviceRGB");
+        assert_eq!(pdf_name("PANTONE 185 C"), "/PANTONE#20185#20C");
+        assert_eq!(pdf_name("a#b/c"), "/a#23b#2Fc");
+        assert_eq!(pdf_name("Grün"), "/Gr#C3#BCn");
+        assert_eq!(pdf_name("Tab\tNul\0"), "/Tab#09Nul");
+    }
+
+    #[test]
+    fn test_text_strings() {
+        let text_string = |text| pdf_string(&text_bytes(text));
+        assert_eq!(text_string("Layer (1)"), "(Layer \\(1\\))");
+        assert_eq!(text_string("Maße"), "<FEFF004D006100DF0065>");
+        assert_eq!(text_string("😀"), "<FEFFD83DDE00>");
+    }
+
+    #[test]
+    fn test_hex_fallback() {
+        assert_eq!(pdf_string(b"Z\xfcrich"), "<5AFC72696368>");
+        assert_eq!(pdf_string(b"line\nbreak"), "<6C696E650A627265616B>");
+        assert_eq!(hex_string(&[0x00, 0x3d]), "<003D>");
+    }
+}
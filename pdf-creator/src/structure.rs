@@ -0,0 +1,416 @@
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::writer::PdfWriter;
+use anyhow::{Result, ensure};
+use std::io::Write;
+
+/// What a part of the content is, told by the element of the structure
+/// tree holding it, which screen readers and other assistive technology
+/// go by, as do readers reflowing text for small screens. Content is
+/// tagged with one by
+/// [`ContentStream::tagged`](crate::ContentStream::tagged).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureType {
+    /// A section of the document, holding headings and paragraphs.
+    Sect,
+    /// A group of elements of no kind of their own.
+    Div,
+    /// A paragraph.
+    P,
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+    /// A quotation of one or more paragraphs.
+    BlockQuote,
+    /// The caption of a table or a figure.
+    Caption,
+    /// A list, holding list items.
+    L,
+    /// A list item, holding its label and its body.
+    LI,
+    /// The bullet or number of a list item.
+    Lbl,
+    /// The text of a list item.
+    LBody,
+    /// A table, holding rows, possibly grouped by its head, body and foot.
+    Table,
+    THead,
+    TBody,
+    TFoot,
+    /// A row of a table, holding its cells.
+    TR,
+    /// A header cell.
+    TH,
+    /// A data cell.
+    TD,
+    /// A picture or a chart.
+    Figure,
+    /// A mathematical formula.
+    Formula,
+    /// A run of text within a paragraph of no kind of its own.
+    Span,
+    /// A quotation within a paragraph.
+    Quote,
+    /// Computer code within a paragraph.
+    Code,
+}
+
+impl StructureType {
+    fn name(self) -> &'static str {
+        match self {
+            StructureType::Sect => "Sect",
+            StructureType::Div => "Div",
+            StructureType::P => "P",
+            StructureType::H1 => "H1",
+            StructureType::H2 => "H2",
+            StructureType::H3 => "H3",
+            StructureType::H4 => "H4",
+            StructureType::H5 => "H5",
+            StructureType::H6 => "H6",
+            StructureType::BlockQuote => "BlockQuote",
+            StructureType::Caption => "Caption",
+            StructureType::L => "L",
+            StructureType::LI => "LI",
+            StructureType::Lbl => "Lbl",
+            StructureType::LBody => "LBody",
+            StructureType::Table => "Table",
+            StructureType::THead => "THead",
+            StructureType::TBody => "TBody",
+            StructureType::TFoot => "TFoot",
+            StructureType::TR => "TR",
+            StructureType::TH => "TH",
+            StructureType::TD => "TD",
+            StructureType::Figure => "Figure",
+            StructureType::Formula => "Formula",
+            StructureType::Span => "Span",
+            StructureType::Quote => "Quote",
+            StructureType::Code => "Code",
+        }
+    }
+}
+
+/// The kind of a structure element along with what is told of it besides,
+/// for [`ContentStream::tagged`](crate::ContentStream::tagged), which also
+/// takes a [`StructureType`] alone.
+///
+/// ```
+/// # use pdf_creator::{Image, Page, PageSize, Rect, StructureType, Tag};
+/// # fn chart(page: &mut Page, image: &Image) {
+/// let figure = Tag::new(StructureType::Figure).alt("Sales doubled from 2023 to 2024");
+/// page.tagged(figure, |g| g.draw_image(image, Rect::new(72.0, 400.0, 300.0, 200.0)));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    kind: StructureType,
+    alt: Option<String>,
+}
+
+impl Tag {
+    pub fn new(kind: StructureType) -> Self {
+        Self { kind, alt: None }
+    }
+
+    /// Sets the alternative text of the element (`/Alt`), read by screen
+    /// readers in place of its content: a description of a figure or a
+    /// formula, which have no text of their own to read.
+    pub fn alt(mut self, text: impl Into<String>) -> Self {
+        self.alt = Some(text.into());
+        self
+    }
+}
+
+impl From<StructureType> for Tag {
+    fn from(kind: StructureType) -> Self {
+        Self::new(kind)
+    }
+}
+
+/// An element of the structure tree and what it holds, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Element {
+    tag: Tag,
+    kids: Vec<Kid>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kid {
+    /// The marked-content sequence of the page with this identifier.
+    Content(u32),
+    Element(Element),
+}
+
+/// The structure elements of the content of a page, built as it is
+/// tagged, along with the marked-content sequences they hold. Those of
+/// enclosing elements are ended where others begin, as they may not nest.
+#[derive(Debug, Default)]
+pub(crate) struct PageStructure {
+    /// The outermost elements, in order.
+    elements: Vec<Element>,
+    /// The elements being drawn into, innermost last.
+    open: Vec<Element>,
+    /// Where the marked-content sequence of the innermost open element
+    /// starts in the content, where its content starts, and its
+    /// identifier.
+    sequence: Option<(usize, usize, u32)>,
+    next_mcid: u32,
+    /// Whether the content drawn is an artifact, which holds no elements.
+    in_artifact: bool,
+    /// Whether an element was opened within an artifact, which makes the
+    /// page fail to be written.
+    tagged_artifact: bool,
+}
+
+impl PageStructure {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Opens an element tagged `tag` in the innermost open one, what is
+    /// drawn next in `ops` going into it. Within an artifact, the page
+    /// fails to be written.
+    pub(crate) fn open(&mut self, tag: Tag, ops: &mut Vec<u8>) {
+        self.tagged_artifact |= self.in_artifact;
+        self.end_sequence(ops);
+        self.open.push(Element {
+            tag,
+            kids: Vec::new(),
+        });
+        self.begin_sequence(ops);
+    }
+
+    /// Closes the innermost open element, what is drawn next in `ops`
+    /// going into the one enclosing it, if any.
+    pub(crate) fn close(&mut self, ops: &mut Vec<u8>) {
+        self.end_sequence(ops);
+        let element = self.open.pop().expect("an element should be open");
+        match self.open.last_mut() {
+            Some(parent) => parent.kids.push(Kid::Element(element)),
+            None => self.elements.push(element),
+        }
+        self.begin_sequence(ops);
+    }
+
+    /// Marks what is drawn next in `ops` as an artifact, such as a running
+    /// header or a decoration, which is not part of the structure.
+    pub(crate) fn begin_artifact(&mut self, ops: &mut Vec<u8>) {
+        self.end_sequence(ops);
+        self.in_artifact = true;
+        ops.extend_from_slice(b"/Artifact BMC\n");
+    }
+
+    pub(crate) fn end_artifact(&mut self, ops: &mut Vec<u8>) {
+        ops.extend_from_slice(b"EMC\n");
+        self.in_artifact = false;
+        self.begin_sequence(ops);
+    }
+
+    /// Begins a marked-content sequence of the innermost open element, if
+    /// any and not in an artifact, for what is drawn next in `ops`.
+    pub(crate) fn begin_sequence(&mut self, ops: &mut Vec<u8>) {
+        let Some(element) = self.open.last() else {
+            return;
+        };
+        if self.in_artifact {
+            return;
+        }
+        let start = ops.len();
+        let mcid = self.next_mcid;
+        self.next_mcid += 1;
+        ops.extend_from_slice(
+            format!("/{} << /MCID {} >> BDC\n", element.tag.kind.name(), mcid).as_bytes(),
+        );
+        self.sequence = Some((start, ops.len(), mcid));
+    }
+
+    /// Ends the open marked-content sequence, or takes it back if nothing
+    /// was drawn in it.
+    pub(crate) fn end_sequence(&mut self, ops: &mut Vec<u8>) {
+        let Some((start, content, mcid)) = self.sequence.take() else {
+            return;
+        };
+        if ops.len() == content {
+            ops.truncate(start);
+            self.next_mcid -= 1;
+            return;
+        }
+        ops.extend_from_slice(b"EMC\n");
+        let element = self.open.last_mut().expect("an element should be open");
+        element.kids.push(Kid::Content(mcid));
+    }
+}
+
+/// Writes the structure tree `root`, holding a document element with the
+/// elements of the pages `pages`, which are known by their index in the
+/// parent tree, then the parent tree, telling for each page the element
+/// each marked-content sequence is in.
+pub(crate) fn write_tree<W: Write>(
+    writer: &mut PdfWriter<W>,
+    alloc: &mut ObjectAllocator,
+    root: ObjectRef,
+    pages: &[(ObjectRef, &PageStructure)],
+) -> Result<()> {
+    let document = alloc.allocate();
+    let parent_tree = alloc.allocate();
+    let mut tops = Vec::new();
+    let mut nums = Vec::new();
+    for (index, (page, structure)) in pages.iter().enumerate() {
+        ensure!(
+            !structure.tagged_artifact,
+            "artifacts cannot hold tagged content"
+        );
+        let mut parents = vec![None; structure.next_mcid as usize];
+        for element in &structure.elements {
+            let id = alloc.allocate();
+            tops.push(id.into());
+            write_element(writer, alloc, element, id, document, *page, &mut parents)?;
+        }
+        if !parents.is_empty() {
+            let parents = parents
+                .iter()
+                .map(|parent| parent.expect("each sequence is in an element").into())
+                .collect();
+            nums.push(index.into());
+            nums.push(Object::Array(parents));
+        }
+    }
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::name("StructTreeRoot"));
+    dict.set("K", document);
+    dict.set("ParentTree", parent_tree);
+    dict.set("ParentTreeNextKey", pages.len());
+    writer.write_object(root, dict)?;
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::name("StructElem"));
+    dict.set("S", Object::name("Document"));
+    dict.set("P", root);
+    dict.set("K", Object::Array(tops));
+    writer.write_object(document, dict)?;
+    let mut dict = Dictionary::new();
+    dict.set("Nums", Object::Array(nums));
+    writer.write_object(parent_tree, dict)
+}
+
+/// Writes `element` as `id`, in `parent`, followed by the elements it
+/// holds, recording it in `parents` as that of its marked-content
+/// sequences.
+fn write_element<W: Write>(
+    writer: &mut PdfWriter<W>,
+    alloc: &mut ObjectAllocator,
+    element: &Element,
+    id: ObjectRef,
+    parent: ObjectRef,
+    page: ObjectRef,
+    parents: &mut [Option<ObjectRef>],
+) -> Result<()> {
+    let mut kids = Vec::new();
+    let mut children = Vec::new();
+    for kid in &element.kids {
+        match kid {
+            Kid::Content(mcid) => {
+                parents[*mcid as usize] = Some(id);
+                kids.push((*mcid).into());
+            }
+            Kid::Element(child) => {
+                let child_id = alloc.allocate();
+                kids.push(child_id.into());
+                children.push((child, child_id));
+            }
+        }
+    }
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::name("StructElem"));
+    dict.set("S", Object::name(element.tag.kind.name()));
+    dict.set("P", parent);
+    dict.set("Pg", page);
+    if let Some(text) = &element.tag.alt {
+        dict.set("Alt", Object::text(text));
+    }
+    dict.set("K", Object::Array(kids));
+    writer.write_object(id, dict)?;
+    for (child, child_id) in children {
+        write_element(writer, alloc, child, child_id, id, page, parents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marked_content() {
+        let mut structure = PageStructure::default();
+        let mut ops = Vec::new();
+        structure.open(StructureType::L.into(), &mut ops);
+        structure.open(StructureType::LI.into(), &mut ops);
+        ops.extend_from_slice(b"(one) Tj\n");
+        structure.close(&mut ops);
+        ops.extend_from_slice(b"(loose) Tj\n");
+        structure.close(&mut ops);
+        structure.begin_artifact(&mut ops);
+        ops.extend_from_slice(b"(page 1) Tj\n");
+        structure.end_artifact(&mut ops);
+        assert_eq!(
+            String::from_utf8(ops).unwrap(),
+            "/LI << /MCID 0 >> BDC\n(one) Tj\nEMC\n/L << /MCID 1 >> BDC\n(loose) Tj\nEMC\n\
+             /Artifact BMC\n(page 1) Tj\nEMC\n"
+        );
+        let item = Element {
+            tag: StructureType::LI.into(),
+            kids: vec![Kid::Content(0)],
+        };
+        assert_eq!(
+            structure.elements,
+            [Element {
+                tag: StructureType::L.into(),
+                kids: vec![Kid::Element(item), Kid::Content(1)],
+            }]
+        );
+        assert_eq!(structure.next_mcid, 2);
+    }
+
+    #[test]
+    fn test_alt_text() {
+        let mut structure = PageStructure::default();
+        let mut ops = Vec::new();
+        let figure = Tag::new(StructureType::Figure).alt("Sales, 2024: up 12 %");
+        structure.open(figure, &mut ops);
+        ops.extend_from_slice(b"/X1 Do\n");
+        structure.close(&mut ops);
+
+        let mut alloc = ObjectAllocator::new();
+        let root = alloc.allocate();
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        let page = ObjectRef::new(9);
+        write_tree(&mut writer, &mut alloc, root, &[(page, &structure)]).unwrap();
+        let pdf = String::from_utf8(buf.into_inner()).unwrap();
+        assert!(pdf.contains(
+            "4 0 obj\n<< /Type /StructElem /S /Figure /P 2 0 R /Pg 9 0 R \
+             /Alt (Sales, 2024: up 12 %) /K [0] >>"
+        ));
+        assert!(pdf.contains("3 0 obj\n<< /Nums [0 [4 0 R]] >>"));
+    }
+
+    #[test]
+    fn test_tagged_artifact() {
+        let mut structure = PageStructure::default();
+        let mut ops = Vec::new();
+        structure.begin_artifact(&mut ops);
+        structure.open(StructureType::P.into(), &mut ops);
+        ops.extend_from_slice(b"(page 1) Tj\n");
+        structure.close(&mut ops);
+        structure.end_artifact(&mut ops);
+
+        let mut alloc = ObjectAllocator::new();
+        let root = alloc.allocate();
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        let page = ObjectRef::new(9);
+        let result = write_tree(&mut writer, &mut alloc, root, &[(page, &structure)]);
+        assert!(result.is_err());
+    }
+}
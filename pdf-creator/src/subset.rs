@@ -0,0 +1,447 @@
+use anyhow::{Context, Result, bail, ensure};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Tables kept in a subsetted font program. Everything else (layout tables,
+/// hinting device tables, signatures...) is only useful to text shaping
+/// engines, which never see an embedded PDF font.
+const KEPT_TABLES: [&[u8; 4]; 11] = [
+    b"OS/2", b"cmap", b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"maxp", b"name",
+    b"prep",
+];
+
+/// Composite glyph flags, see the `glyf` table specification.
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// Builds a copy of the TrueType font `data` that only carries the outlines
+/// of `glyphs` (plus `.notdef` and the components of composite glyphs), and
+/// tells which glyphs it keeps.
+///
+/// Glyph ids are preserved: outlines of unused glyphs are simply left empty,
+/// so text already encoded against the full font stays valid. `cmap` is the
+/// character to glyph mapping written to the subset's `cmap` table.
+pub(crate) fn subset_truetype(
+    data: &[u8],
+    glyphs: &BTreeSet<u16>,
+    cmap: &BTreeMap<char, u16>,
+) -> Result<(Vec<u8>, BTreeSet<u16>)> {
+    let tables = parse_tables(data)?;
+    let table = |tag: &[u8; 4]| {
+        tables
+            .get(tag)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("font has no {} table", String::from_utf8_lossy(tag)))
+    };
+
+    let head = table(b"head")?;
+    let maxp = table(b"maxp")?;
+    let loca = table(b"loca")?;
+    let glyf = table(b"glyf")?;
+    ensure!(head.len() >= 54 && maxp.len() >= 6, "truncated font header");
+    let num_glyphs = read_u16(maxp, 4)? as usize;
+    let long_loca = read_u16(head, 50)? != 0;
+
+    let glyph_range = |gid: usize| -> Result<(usize, usize)> {
+        let (start, end) = if long_loca {
+            (
+                read_u32(loca, gid * 4)? as usize,
+                read_u32(loca, gid * 4 + 4)? as usize,
+            )
+        } else {
+            (
+                read_u16(loca, gid * 2)? as usize * 2,
+                read_u16(loca, gid * 2 + 2)? as usize * 2,
+            )
+        };
+        ensure!(start <= end && end <= glyf.len(), "invalid loca entry");
+        Ok((start, end))
+    };
+
+    // Collect composite glyph components until nothing new turns up.
+    let mut keep: BTreeSet<u16> = glyphs
+        .iter()
+        .copied()
+        .filter(|gid| (*gid as usize) < num_glyphs)
+        .collect();
+    keep.insert(0);
+    let mut pending: Vec<u16> = keep.iter().copied().collect();
+    while let Some(gid) = pending.pop() {
+        let (start, end) = glyph_range(gid as usize)?;
+        for component in composite_components(&glyf[start..end])? {
+            if (component as usize) < num_glyphs && keep.insert(component) {
+                pending.push(component);
+            }
+        }
+    }
+
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::with_capacity((num_glyphs + 1) * 4);
+    for gid in 0..num_glyphs {
+        new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+        if keep.contains(&(gid as u16)) {
+            let (start, end) = glyph_range(gid)?;
+            new_glyf.extend_from_slice(&glyf[start..end]);
+            while new_glyf.len() % 4 != 0 {
+                new_glyf.push(0);
+            }
+        }
+    }
+    new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+    let mut new_head = head.to_vec();
+    // checkSumAdjustment is filled in once the whole font is assembled, and
+    // the rebuilt loca table always uses 32-bit offsets.
+    new_head[8..12].copy_from_slice(&[0; 4]);
+    new_head[50..52].copy_from_slice(&1u16.to_be_bytes());
+
+    let mut out_tables: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+    for tag in KEPT_TABLES {
+        let data = match tag {
+            b"head" => new_head.clone(),
+            b"loca" => continue,
+            b"glyf" => new_glyf.clone(),
+            b"cmap" => build_cmap(cmap)?,
+            _ => match tables.get(tag) {
+                Some(data) => data.to_vec(),
+                None => continue,
+            },
+        };
+        out_tables.push((*tag, data));
+    }
+    out_tables.push((*b"loca", new_loca));
+    // Version 3 post table: same metrics, no glyph names.
+    if let Some(post) = tables.get(b"post").filter(|post| post.len() >= 32) {
+        let mut new_post = post[..32].to_vec();
+        new_post[0..4].copy_from_slice(&0x0003_0000u32.to_be_bytes());
+        out_tables.push((*b"post", new_post));
+    }
+    out_tables.sort_by_key(|(tag, _)| *tag);
+
+    let mut font = assemble(&out_tables);
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+    let head_offset = head_offset(&font)?;
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    Ok((font, keep))
+}
+
+/// Six letter tag prefixed to the names of a subsetted font (`ABCDEF+Name`),
+/// derived from the glyph set so the same subset always gets the same tag.
+pub(crate) fn subset_tag(glyphs: &BTreeSet<u16>) -> String {
+    // FNV-1a, which unlike the std hashers is stable across Rust releases.
+    let mut hash = glyphs.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, gid| {
+        gid.to_be_bytes().iter().fold(hash, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    });
+    (0..6)
+        .map(|_| {
+            let letter = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+            letter
+        })
+        .collect()
+}
+
+fn parse_tables(data: &[u8]) -> Result<BTreeMap<[u8; 4], &[u8]>> {
+    let version = read_u32(data, 0)?;
+    if version != 0x0001_0000 && version != u32::from_be_bytes(*b"true") {
+        bail!("only TrueType outlines can be subsetted");
+    }
+    let num_tables = read_u16(data, 4)? as usize;
+    let mut tables = BTreeMap::new();
+    for i in 0..num_tables {
+        let record = 12 + i * 16;
+        let tag: [u8; 4] = data
+            .get(record..record + 4)
+            .ok_or_else(|| anyhow::anyhow!("truncated table directory"))?
+            .try_into()?;
+        let offset = read_u32(data, record + 8)? as usize;
+        let len = read_u32(data, record + 12)? as usize;
+        let table = data
+            .get(offset..offset + len)
+            .ok_or_else(|| anyhow::anyhow!("table out of bounds"))?;
+        tables.insert(tag, table);
+    }
+    Ok(tables)
+}
+
+fn composite_components(glyph: &[u8]) -> Result<Vec<u16>> {
+    let mut components = Vec::new();
+    if glyph.len() < 10 || (read_u16(glyph, 0)? as i16) >= 0 {
+        return Ok(components);
+    }
+    let mut pos = 10;
+    loop {
+        let flags = read_u16(glyph, pos)?;
+        components.push(read_u16(glyph, pos + 2)?);
+        pos += 4;
+        pos += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            pos += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            pos += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            pos += 8;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(components);
+        }
+    }
+}
+
+/// A `cmap` table with a single Windows Unicode BMP (3, 1) format 4
+/// subtable, or, if there are too many runs of characters for one, a
+/// Windows Unicode full repertoire (3, 10) format 12 subtable.
+fn build_cmap(cmap: &BTreeMap<char, u16>) -> Result<Vec<u8>> {
+    // Format 4 tells nothing of the characters past the BMP.
+    let bmp = runs(cmap.iter().filter(|(ch, _)| (**ch as u32) < 0xFFFF));
+    let (encoding, sub) = match format_4(&bmp) {
+        Some(sub) => (1u16, sub),
+        None => (10, format_12(&runs(cmap.iter()))?),
+    };
+    let mut table = Vec::new();
+    for v in [0u16, 1, 3, encoding] {
+        table.extend_from_slice(&v.to_be_bytes());
+    }
+    table.extend_from_slice(&12u32.to_be_bytes());
+    table.extend_from_slice(&sub);
+    Ok(table)
+}
+
+/// The runs of consecutive characters mapped to consecutive glyphs in
+/// `cmap`, in order: the first and last character and the first glyph.
+fn runs<'a>(cmap: impl Iterator<Item = (&'a char, &'a u16)>) -> Vec<(u32, u32, u16)> {
+    let mut runs: Vec<(u32, u32, u16)> = Vec::new();
+    for (&ch, &gid) in cmap {
+        let ch = ch as u32;
+        match runs.last_mut() {
+            Some((first, last, glyph))
+                if ch == *last + 1 && u32::from(gid) == u32::from(*glyph) + (ch - *first) =>
+            {
+                *last = ch
+            }
+            _ => runs.push((ch, ch, gid)),
+        }
+    }
+    runs
+}
+
+/// A format 4 subtable of the BMP characters `runs`, a segment each, if
+/// their number and the length of the subtable fit its 16-bit fields.
+fn format_4(runs: &[(u32, u32, u16)]) -> Option<Vec<u8>> {
+    // The segment ending the table, which maps 0xFFFF to .notdef.
+    let mut segments: Vec<(u16, u16, u16)> = runs
+        .iter()
+        .map(|&(first, last, gid)| {
+            let first = first as u16;
+            (first, last as u16, gid.wrapping_sub(first))
+        })
+        .collect();
+    segments.push((0xFFFF, 0xFFFF, 1));
+
+    let seg_count = segments.len();
+    let length = u16::try_from(16 + seg_count * 8).ok()?;
+    let entry_selector = seg_count.ilog2();
+    let search_range = 2u16 << entry_selector;
+    let seg_count_x2 = seg_count as u16 * 2;
+    let mut sub = Vec::new();
+    for v in [
+        4,
+        length,
+        0,
+        seg_count_x2,
+        search_range,
+        entry_selector as u16,
+        seg_count_x2 - search_range,
+    ] {
+        sub.extend_from_slice(&v.to_be_bytes());
+    }
+    for (_, end, _) in &segments {
+        sub.extend_from_slice(&end.to_be_bytes());
+    }
+    sub.extend_from_slice(&0u16.to_be_bytes());
+    for (start, _, _) in &segments {
+        sub.extend_from_slice(&start.to_be_bytes());
+    }
+    for (_, _, delta) in &segments {
+        sub.extend_from_slice(&delta.to_be_bytes());
+    }
+    for _ in &segments {
+        sub.extend_from_slice(&0u16.to_be_bytes());
+    }
+    Some(sub)
+}
+
+/// A format 12 subtable of the characters `runs`, a group each.
+fn format_12(runs: &[(u32, u32, u16)]) -> Result<Vec<u8>> {
+    let groups = u32::try_from(runs.len())?;
+    let length = groups
+        .checked_mul(12)
+        .and_then(|groups| groups.checked_add(16))
+        .context("too many characters for a cmap subtable")?;
+    let mut sub = Vec::new();
+    sub.extend_from_slice(&12u16.to_be_bytes());
+    sub.extend_from_slice(&0u16.to_be_bytes());
+    for v in [length, 0, groups] {
+        sub.extend_from_slice(&v.to_be_bytes());
+    }
+    for &(first, last, gid) in runs {
+        for v in [first, last, u32::from(gid)] {
+            sub.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+    Ok(sub)
+}
+
+fn assemble(tables: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let num_tables = tables.len() as u16;
+    let mut search_range = 1u16;
+    let mut entry_selector = 0u16;
+    while search_range * 2 <= num_tables {
+        search_range *= 2;
+        entry_selector += 1;
+    }
+    search_range *= 16;
+    let range_shift = num_tables * 16 - search_range;
+
+    let mut font = Vec::new();
+    font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    for v in [num_tables, search_range, entry_selector, range_shift] {
+        font.extend_from_slice(&v.to_be_bytes());
+    }
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in tables {
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().div_ceil(4) * 4;
+    }
+    for (_, data) in tables {
+        font.extend_from_slice(data);
+        while font.len() % 4 != 0 {
+            font.push(0);
+        }
+    }
+    font
+}
+
+fn head_offset(font: &[u8]) -> Result<usize> {
+    let num_tables = read_u16(font, 4)? as usize;
+    (0..num_tables)
+        .map(|i| 12 + i * 16)
+        .find(|record| &font[*record..*record + 4] == b"head")
+        .map(|record| read_u32(font, record + 8).map(|offset| offset as usize))
+        .unwrap_or_else(|| bail!("font has no head table"))
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    match data.get(pos..pos + 2) {
+        Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+        None => bail!("unexpected end of font data"),
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32> {
+    match data.get(pos..pos + 4) {
+        Some(bytes) => Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => bail!("unexpected end of font data"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ttf_parser::{Face, GlyphId};
+
+    #[test]
+    fn test_subset_keeps_used_glyphs_only() {
+        let data =
+            std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/fonts/DejaVuSans.ttf")).unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let cmap: BTreeMap<char, u16> = "Hi é"
+            .chars()
+            .map(|ch| (ch, face.glyph_index(ch).unwrap().0))
+            .collect();
+        let glyphs: BTreeSet<u16> = cmap.values().copied().collect();
+
+        let (subset, kept) = subset_truetype(&data, &glyphs, &cmap).unwrap();
+        assert!(subset.len() < data.len() / 10);
+        assert!(kept.contains(&0) && kept.is_superset(&glyphs));
+        assert_eq!(checksum(&subset), 0xB1B0_AFBA);
+
+        let sub_face = Face::parse(&subset, 0).unwrap();
+        assert_eq!(sub_face.number_of_glyphs(), face.number_of_glyphs());
+        assert_eq!(sub_face.glyph_index('é'), face.glyph_index('é'));
+        assert_eq!(sub_face.glyph_index('x'), None);
+        let bbox = |face: &Face, gid| face.glyph_bounding_box(GlyphId(gid));
+        let h = cmap[&'H'];
+        assert_eq!(bbox(&sub_face, h), bbox(&face, h));
+        let x = face.glyph_index('x').unwrap().0;
+        assert!(bbox(&face, x).is_some());
+        assert!(bbox(&sub_face, x).is_none());
+    }
+
+    #[test]
+    fn test_large_cmap() {
+        let data =
+            std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/fonts/DejaVuSans.ttf")).unwrap();
+        let glyphs: BTreeSet<u16> = [36].into_iter().collect();
+        let ideograph = |i: u32| char::from_u32(0x4E00 + i).unwrap();
+
+        // Runs of characters and glyphs are one segment each.
+        let cmap: BTreeMap<char, u16> = (0..20000).map(|i| (ideograph(i), 1 + i as u16)).collect();
+        let (subset, _) = subset_truetype(&data, &glyphs, &cmap).unwrap();
+        let face = Face::parse(&subset, 0).unwrap();
+        let subtable = face.tables().cmap.unwrap().subtables.get(0).unwrap();
+        assert!(matches!(
+            subtable.format,
+            ttf_parser::cmap::Format::SegmentMappingToDeltaValues(_)
+        ));
+        assert_eq!(face.glyph_index(ideograph(19999)), Some(GlyphId(20000)));
+
+        // Too many segments for format 4.
+        let cmap: BTreeMap<char, u16> = (0..10000)
+            .map(|i| (ideograph(i), 1 + (i * 7 % 5000) as u16))
+            .collect();
+        let (subset, _) = subset_truetype(&data, &glyphs, &cmap).unwrap();
+        let face = Face::parse(&subset, 0).unwrap();
+        let subtable = face.tables().cmap.unwrap().subtables.get(0).unwrap();
+        assert!(matches!(
+            subtable.format,
+            ttf_parser::cmap::Format::SegmentedCoverage(_)
+        ));
+        for i in [0, 1, 4999, 9999] {
+            assert_eq!(
+                face.glyph_index(ideograph(i)),
+                Some(GlyphId(cmap[&ideograph(i)]))
+            );
+        }
+        assert_eq!(face.glyph_index('A'), None);
+    }
+
+    #[test]
+    fn test_subset_tag() {
+        let glyphs: BTreeSet<u16> = [3, 40, 41].into_iter().collect();
+        let tag = subset_tag(&glyphs);
+        assert_eq!(tag.len(), 6);
+        assert!(tag.chars().all(|ch| ch.is_ascii_uppercase()));
+        assert_eq!(tag, subset_tag(&glyphs));
+    }
+}
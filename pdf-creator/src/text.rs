@@ -0,0 +1,418 @@
+use crate::color::Color;
+use crate::font::{Font, StandardFont};
+
+/// Horizontal placement of the lines of a paragraph within its rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the space between words so lines fill the whole width,
+    /// except for the last line of a paragraph, which stays left aligned
+    /// (right aligned in right-to-left paragraphs).
+    Justify,
+}
+
+/// How the glyphs of text are painted, the `Tr` text rendering mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextRendering {
+    #[default]
+    Fill,
+    /// Only the outlines of the glyphs, as for outlined headlines.
+    Stroke,
+    FillStroke,
+    /// Nothing is painted, but the text can still be selected, searched and
+    /// extracted, as laid over a scanned page by OCR.
+    Invisible,
+}
+
+impl TextRendering {
+    /// The operand of `Tr`.
+    pub(crate) fn mode(self) -> u8 {
+        match self {
+            TextRendering::Fill => 0,
+            TextRendering::Stroke => 1,
+            TextRendering::FillStroke => 2,
+            TextRendering::Invisible => 3,
+        }
+    }
+}
+
+/// How a run of text is drawn: which font, at what size and color, and how
+/// its lines are spaced and aligned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub font: Font,
+    /// Font size in points.
+    pub size: f32,
+    /// Distance between the baselines of consecutive lines, in points; 1.2
+    /// times the font size if not set.
+    pub leading: Option<f32>,
+    pub align: Align,
+    /// Fill color of the glyphs; black if not set.
+    pub color: Option<Color>,
+    pub rendering: TextRendering,
+    /// Color of the glyph outlines when `rendering` strokes them; black if
+    /// not set.
+    pub stroke_color: Option<Color>,
+    /// Space added after every character, in points (`Tc`), for tracking
+    /// headings tighter or looser.
+    pub char_spacing: f32,
+    /// Space added after every space character, in points (`Tw`).
+    pub word_spacing: f32,
+}
+
+impl TextStyle {
+    /// A black, filled, left-aligned style with the default leading.
+    pub fn new(font: impl Into<Font>, size: f32) -> Self {
+        Self {
+            font: font.into(),
+            size,
+            leading: None,
+            align: Align::Left,
+            color: None,
+            rendering: TextRendering::Fill,
+            stroke_color: None,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+        }
+    }
+
+    /// Distance between the baselines of consecutive lines, in points.
+    pub fn line_height(&self) -> f32 {
+        self.leading.unwrap_or(self.size * 1.2)
+    }
+
+    /// Horizontal advance of `text` in this style, in points, character and
+    /// word spacing included.
+    pub fn measure(&self, text: &str) -> f32 {
+        let mut width = self.font.measure(text, self.size);
+        if self.char_spacing != 0.0 {
+            width += self.char_spacing * text.chars().count() as f32;
+        }
+        if self.word_spacing != 0.0 {
+            width += self.word_spacing * text.matches(' ').count() as f32;
+        }
+        width
+    }
+}
+
+/// 12pt Helvetica.
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(StandardFont::Helvetica, 12.0)
+    }
+}
+
+/// A piece of a [`RichText`] paragraph drawn in a single style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+/// A paragraph mixing styles, such as a bold word or a larger, colored
+/// phrase within regular text. Spans follow each other without any space
+/// added between them. The paragraph is wrapped and aligned as a whole, so
+/// the alignment of the span styles is not used; each line is as tall as
+/// the tallest span on it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RichText {
+    pub spans: Vec<Span>,
+    pub align: Align,
+}
+
+impl RichText {
+    /// An empty, left-aligned paragraph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `text` drawn with `style`.
+    pub fn span(mut self, text: impl Into<String>, style: &TextStyle) -> Self {
+        self.spans.push(Span::new(text, style.clone()));
+        self
+    }
+}
+
+/// A slice of the text of a span placed on a line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Fragment<'t> {
+    pub(crate) style: &'t TextStyle,
+    pub(crate) text: &'t str,
+}
+
+impl Fragment<'_> {
+    fn width(&self) -> f32 {
+        self.style.measure(self.text)
+    }
+}
+
+fn width(fragments: &[Fragment]) -> f32 {
+    fragments.iter().map(Fragment::width).sum()
+}
+
+/// A line of wrapped text.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Line<'t> {
+    /// The text of the line, without surrounding spaces.
+    pub(crate) fragments: Vec<Fragment<'t>>,
+    /// Indices of the fragments ending a space between words, the ones
+    /// justification widens.
+    pub(crate) gaps: Vec<usize>,
+    /// Whether the line ends its paragraph.
+    pub(crate) last: bool,
+    /// Largest font size on the line.
+    pub(crate) size: f32,
+    /// Largest line height on the line.
+    pub(crate) height: f32,
+}
+
+impl Line<'_> {
+    pub(crate) fn width(&self) -> f32 {
+        width(&self.fragments)
+    }
+}
+
+/// What wrapping sees of a paragraph: words (which may mix styles), the
+/// spaces between them and forced line breaks.
+enum Token<'t> {
+    Word(Vec<Fragment<'t>>),
+    Space(Vec<Fragment<'t>>),
+    Break(&'t TextStyle),
+}
+
+fn tokens<'t>(spans: &[(&'t str, &'t TextStyle)]) -> Vec<Token<'t>> {
+    let mut tokens = Vec::new();
+    for (text, style) in spans {
+        let mut rest = *text;
+        while let Some(ch) = rest.chars().next() {
+            if ch == '\n' {
+                tokens.push(Token::Break(style));
+                rest = &rest[1..];
+                continue;
+            }
+            let space = ch == ' ';
+            let len = rest
+                .find(|c: char| c == '\n' || (c == ' ') != space)
+                .unwrap_or(rest.len());
+            let fragment = Fragment {
+                style,
+                text: &rest[..len],
+            };
+            match (tokens.last_mut(), space) {
+                (Some(Token::Space(fragments)), true) | (Some(Token::Word(fragments)), false) => {
+                    fragments.push(fragment)
+                }
+                (_, true) => tokens.push(Token::Space(vec![fragment])),
+                (_, false) => tokens.push(Token::Word(vec![fragment])),
+            }
+            rest = &rest[len..];
+        }
+    }
+    tokens
+}
+
+/// Breaks a paragraph made of `spans` into lines no wider than `width`, at
+/// spaces where possible. Newlines always start a new line; a word wider
+/// than `width` on its own is split between characters.
+pub(crate) fn wrap_spans<'t>(spans: &[(&'t str, &'t TextStyle)], width: f32) -> Vec<Line<'t>> {
+    let Some((_, style)) = spans.first() else {
+        return Vec::new();
+    };
+    let mut wrapper = Wrapper {
+        max: width,
+        lines: Vec::new(),
+        fragments: Vec::new(),
+        gaps: Vec::new(),
+        width: 0.0,
+        space: Vec::new(),
+        style,
+    };
+    for token in tokens(spans) {
+        match token {
+            Token::Word(word) => wrapper.word(word),
+            Token::Space(space) => {
+                if !wrapper.fragments.is_empty() {
+                    wrapper.space = space;
+                }
+            }
+            Token::Break(style) => {
+                wrapper.style = style;
+                wrapper.finish(true);
+            }
+        }
+    }
+    wrapper.finish(true);
+    wrapper.lines
+}
+
+/// Line filling state of [`wrap_spans`].
+struct Wrapper<'t> {
+    max: f32,
+    lines: Vec<Line<'t>>,
+    fragments: Vec<Fragment<'t>>,
+    gaps: Vec<usize>,
+    width: f32,
+    /// The space after the last word, kept if another word fits behind it.
+    space: Vec<Fragment<'t>>,
+    /// Style of the latest text, which sizes lines without any.
+    style: &'t TextStyle,
+}
+
+impl<'t> Wrapper<'t> {
+    fn word(&mut self, word: Vec<Fragment<'t>>) {
+        self.style = word.last().unwrap().style;
+        let word_width = width(&word);
+        if !self.fragments.is_empty() {
+            let space_width = width(&self.space);
+            if self.width + space_width + word_width <= self.max {
+                self.fragments.append(&mut self.space);
+                self.gaps.push(self.fragments.len() - 1);
+                self.fragments.extend(word);
+                self.width += space_width + word_width;
+                return;
+            }
+            self.finish(false);
+        }
+        // The word starts a line; split it if it cannot fit on one.
+        let mut pieces = if word_width > self.max {
+            split_word(word, self.max)
+        } else {
+            vec![word]
+        };
+        let last = pieces.pop().unwrap();
+        for piece in pieces {
+            self.fragments = piece;
+            self.finish(false);
+        }
+        self.width = width(&last);
+        self.fragments = last;
+    }
+
+    fn finish(&mut self, last: bool) {
+        let styles = || self.fragments.iter().map(|fragment| fragment.style);
+        let size = styles().map(|style| style.size).reduce(f32::max);
+        let height = styles().map(TextStyle::line_height).reduce(f32::max);
+        self.lines.push(Line {
+            fragments: std::mem::take(&mut self.fragments),
+            gaps: std::mem::take(&mut self.gaps),
+            last,
+            size: size.unwrap_or(self.style.size),
+            height: height.unwrap_or(self.style.line_height()),
+        });
+        self.width = 0.0;
+        self.space.clear();
+    }
+}
+
+/// Splits a word between characters into pieces no wider than `max`, each
+/// holding at least one character.
+fn split_word<'t>(word: Vec<Fragment<'t>>, max: f32) -> Vec<Vec<Fragment<'t>>> {
+    let mut pieces = Vec::new();
+    let mut piece = Vec::new();
+    let mut piece_width = 0.0;
+    for fragment in word {
+        let mut start = 0;
+        for (index, ch) in fragment.text.char_indices() {
+            let char_width = Fragment {
+                text: &fragment.text[index..index + ch.len_utf8()],
+                ..fragment
+            }
+            .width();
+            if piece_width + char_width > max && (index > start || !piece.is_empty()) {
+                if index > start {
+                    piece.push(Fragment {
+                        text: &fragment.text[start..index],
+                        ..fragment
+                    });
+                }
+                pieces.push(std::mem::take(&mut piece));
+                piece_width = 0.0;
+                start = index;
+            }
+            piece_width += char_width;
+        }
+        piece.push(Fragment {
+            text: &fragment.text[start..],
+            ..fragment
+        });
+    }
+    pieces.push(piece);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap<'t>(text: &'t str, width: f32, style: &'t TextStyle) -> Vec<String> {
+        wrap_spans(&[(text, style)], width)
+            .iter()
+            .map(|line| {
+                line.fragments
+                    .iter()
+                    .map(|fragment| fragment.text)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wrap_at_spaces() {
+        let style = TextStyle::new(StandardFont::Courier, 10.0);
+        // Courier advances 6pt per character at 10pt.
+        let lines = wrap("the quick  brown fox jumps", 60.0, &style);
+        assert_eq!(lines, ["the quick", "brown fox", "jumps"]);
+        let lines = wrap_spans(&[("one two\n\nthree", &style)], 40.0);
+        let last: Vec<bool> = lines.iter().map(|line| line.last).collect();
+        assert_eq!(last, [false, true, true, true]);
+    }
+
+    #[test]
+    fn test_wrap_long_words() {
+        let style = TextStyle::new(StandardFont::Courier, 10.0);
+        let lines = wrap("a supercalifragilistic day", 30.0, &style);
+        assert_eq!(lines, ["a", "super", "calif", "ragil", "istic", "day"]);
+        assert_eq!(wrap("wide", 1.0, &style), ["w", "i", "d", "e"]);
+    }
+
+    #[test]
+    fn test_wrap_spans() {
+        let regular = TextStyle::new(StandardFont::Courier, 10.0);
+        let large = TextStyle::new(StandardFont::CourierBold, 20.0);
+        // "big" is 36pt wide, the other characters 6pt.
+        let spans = [
+            ("a ", &regular),
+            ("big", &large),
+            ("gest word ok", &regular),
+        ];
+        let lines = wrap_spans(&spans, 80.0);
+        let texts: Vec<Vec<&str>> = lines
+            .iter()
+            .map(|line| {
+                line.fragments
+                    .iter()
+                    .map(|fragment| fragment.text)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [vec!["a", " ", "big", "gest"], vec!["word", " ", "ok"]]
+        );
+        assert_eq!(lines[0].gaps, [1]);
+        assert_eq!((lines[0].size, lines[0].height), (20.0, 24.0));
+        assert_eq!((lines[1].size, lines[1].height), (10.0, 12.0));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::signing::sha256_algorithm;
+use anyhow::{Context, Result, ensure};
+use cmpv2::status::PkiStatus;
+use cms::content_info::ContentInfo;
+use cms::signed_data::SignedData;
+use const_oid::ObjectIdentifier;
+use const_oid::db::rfc5912::ID_SHA_256;
+use der::asn1::{Int, OctetString, Uint};
+use der::{Decode, Encode};
+use x509_tsp::{MessageImprint, TimeStampReq, TimeStampResp, TspVersion, TstInfo};
+
+/// The object identifier of the content of timestamp tokens, `TSTInfo`.
+const TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// The DER of an RFC 3161 `TimeStampReq` for the SHA-256 digest `digest`,
+/// asking for the certificate of the authority to be included and the
+/// random `nonce` repeated.
+pub(crate) fn request(digest: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+    let request = TimeStampReq {
+        version: TspVersion::V1,
+        message_imprint: message_imprint(digest)?,
+        req_policy: None,
+        nonce: Some(Int::from(Uint::new(nonce)?)),
+        cert_req: true,
+        extensions: None,
+    };
+    Ok(request.to_der()?)
+}
+
+/// The timestamp token of the DER `TimeStampResp` `response` to a
+/// [`request`] for `digest` with `nonce`, checked to be of that digest.
+pub(crate) fn token(response: &[u8], digest: &[u8], nonce: &[u8]) -> Result<ContentInfo> {
+    let response =
+        TimeStampResp::from_der(response).context("the timestamp response is not DER")?;
+    ensure!(
+        matches!(
+            response.status.status,
+            PkiStatus::Accepted | PkiStatus::GrantedWithMods
+        ),
+        "the time stamping authority refused the request"
+    );
+    let token = response
+        .time_stamp_token
+        .context("the timestamp response holds no token")?;
+
+    // The token is a CMS signature of a TSTInfo, which tells what was
+    // timestamped.
+    let signed_data: SignedData = token.content.decode_as()?;
+    let content = signed_data.encap_content_info;
+    ensure!(
+        content.econtent_type == TST_INFO,
+        "the timestamp token is not of a TSTInfo"
+    );
+    let info = content
+        .econtent
+        .context("the timestamp token holds no TSTInfo")?
+        .decode_as::<OctetString>()?;
+    let info = TstInfo::from_der(info.as_bytes())?;
+    let imprint = info.message_imprint;
+    ensure!(
+        imprint.hash_algorithm.oid == ID_SHA_256 && imprint.hashed_message.as_bytes() == digest,
+        "the timestamp token is not of the signature"
+    );
+    ensure!(
+        info.nonce == Some(Int::from(Uint::new(nonce)?)),
+        "the timestamp token does not repeat the nonce"
+    );
+    Ok(token)
+}
+
+/// The `MessageImprint` of the SHA-256 digest `digest`.
+fn message_imprint(digest: &[u8]) -> Result<MessageImprint> {
+    Ok(MessageImprint {
+        hash_algorithm: sha256_algorithm(),
+        hashed_message: OctetString::new(digest)?,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use cmpv2::status::PkiStatusInfo;
+    use cms::content_info::CmsVersion;
+    use cms::signed_data::{EncapsulatedContentInfo, SignerInfos};
+    use const_oid::db::rfc5911::ID_SIGNED_DATA;
+    use der::Any;
+    use der::asn1::{GeneralizedTime, SetOfVec};
+    use std::time::Duration;
+
+    /// What a time stamping authority answers to the DER `request`, its
+    /// token's signature left out, or with `digest` in place of that of the
+    /// request.
+    pub(crate) fn response(request: &[u8], digest: Option<&[u8]>) -> Vec<u8> {
+        let request = TimeStampReq::from_der(request).unwrap();
+        let message_imprint = match digest {
+            Some(digest) => message_imprint(digest).unwrap(),
+            None => request.message_imprint,
+        };
+        let info = TstInfo {
+            version: TspVersion::V1,
+            policy: ObjectIdentifier::new_unwrap("1.2.3.4"),
+            message_imprint,
+            serial_number: Int::new(&[42]).unwrap(),
+            gen_time: GeneralizedTime::from_unix_duration(Duration::from_secs(1714564800)).unwrap(),
+            accuracy: None,
+            ordering: false,
+            nonce: request.nonce,
+            tsa: None,
+            extensions: None,
+        };
+        let octets = OctetString::new(info.to_der().unwrap()).unwrap();
+        let signed_data = SignedData {
+            version: CmsVersion::V3,
+            digest_algorithms: SetOfVec::try_from(vec![sha256_algorithm()]).unwrap(),
+            encap_content_info: EncapsulatedContentInfo {
+                econtent_type: TST_INFO,
+                econtent: Some(Any::encode_from(&octets).unwrap()),
+            },
+            certificates: None,
+            crls: None,
+            signer_infos: SignerInfos(SetOfVec::new()),
+        };
+        let token = ContentInfo {
+            content_type: ID_SIGNED_DATA,
+            content: Any::encode_from(&signed_data).unwrap(),
+        };
+        answer(PkiStatus::Accepted, Some(token))
+    }
+
+    /// The DER of a `TimeStampResp` of `status` and `token`.
+    pub(crate) fn answer(status: PkiStatus, token: Option<ContentInfo>) -> Vec<u8> {
+        let response = TimeStampResp {
+            status: PkiStatusInfo {
+                status,
+                status_string: None,
+                fail_info: None,
+            },
+            time_stamp_token: token,
+        };
+        response.to_der().unwrap()
+    }
+
+    #[test]
+    fn test_token() {
+        let digest = [7; 32];
+        let request = request(&digest, &[0x80, 1]).unwrap();
+        assert_eq!(request[..5], [0x30, 0x3C, 0x02, 0x01, 0x01]);
+        assert!(request.ends_with(&[0x02, 0x03, 0x00, 0x80, 0x01, 0x01, 0x01, 0xFF]));
+
+        let answer = response(&request, None);
+        let token = token(&answer, &digest, &[0x80, 1]).unwrap();
+        assert!(answer.ends_with(&token.to_der().unwrap()));
+        assert!(super::token(&answer, &digest, &[0x80, 2]).is_err());
+        let other = response(&request, Some(&[8; 32]));
+        assert!(super::token(&other, &digest, &[0x80, 1]).is_err());
+        let refused = tests::answer(PkiStatus::Rejection, None);
+        assert!(super::token(&refused, &digest, &[0x80, 1]).is_err());
+        assert!(super::token(b"not DER", &digest, &[0x80, 1]).is_err());
+    }
+}
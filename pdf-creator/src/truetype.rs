@@ -0,0 +1,645 @@
+use crate::bidi::shape_arabic;
+use crate::font::FontContent;
+use crate::object::{Dictionary, Object, ObjectAllocator, ObjectRef};
+use crate::subset::{subset_tag, subset_truetype};
+use crate::writer::PdfWriter;
+use anyhow::{Context, Result, anyhow, ensure};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+use ttf_parser::{Face, GlyphId, Tag, name_id};
+
+/// Hands out a distinct id to every loaded font, so cheap clones of a handle
+/// can be recognised as the same font.
+static NEXT_FONT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Most `bfchar` entries a single block of a CMap may hold.
+const CMAP_BLOCK: usize = 100;
+
+/// A TrueType font program embedded in the document (`/FontFile2`).
+/// Cloning the handle shares the loaded font.
+///
+/// The font is written as a composite (`Type0`) font keyed by glyph id, with
+/// a `ToUnicode` CMap, so any character the font covers can be shown and
+/// extracted again. Only the glyphs actually shown somewhere in the document
+/// are embedded, as a subset font named with a tag prefix such as
+/// `ABCDEF+DejaVuSans`.
+#[derive(Clone)]
+pub struct TrueTypeFont {
+    inner: Arc<TrueTypeData>,
+    /// Whether text runs top to bottom (`Identity-V`) rather than left to
+    /// right. Each writing mode is a font dictionary of its own.
+    vertical: bool,
+}
+
+struct TrueTypeData {
+    id: u32,
+    data: Vec<u8>,
+    postscript_name: String,
+    flags: u32,
+    bbox: [i32; 4],
+    italic_angle: f32,
+    ascent: i32,
+    descent: i32,
+    cap_height: i32,
+    line_gap: i32,
+    stem_v: i32,
+    units_per_em: u16,
+}
+
+impl TrueTypeFont {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .with_context(|| format!("failed to read font {}", path.display()))?;
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let face = Face::parse(&data, 0).map_err(|e| anyhow!("invalid TrueType font: {}", e))?;
+        ensure!(
+            face.tables().glyf.is_some(),
+            "only fonts with TrueType outlines can be embedded"
+        );
+        let scale = 1000.0 / face.units_per_em() as f32;
+        let to_pdf = |v: i16| (v as f32 * scale).round() as i32;
+
+        let postscript_name = face
+            .names()
+            .into_iter()
+            .filter(|name| name.name_id == name_id::POST_SCRIPT_NAME)
+            .find_map(|name| name.to_string())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| String::from("EmbeddedFont"));
+
+        // Symbolic, as glyphs are selected by id rather than by character.
+        let mut flags = 1 << 2;
+        if face.is_monospaced() {
+            flags |= 1;
+        }
+        if face.is_italic() {
+            flags |= 1 << 6;
+        }
+
+        let bbox = face.global_bounding_box();
+        let ascent = to_pdf(face.ascender());
+        let font = TrueTypeData {
+            id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+            postscript_name,
+            flags,
+            bbox: [
+                to_pdf(bbox.x_min),
+                to_pdf(bbox.y_min),
+                to_pdf(bbox.x_max),
+                to_pdf(bbox.y_max),
+            ],
+            italic_angle: face.italic_angle(),
+            ascent,
+            descent: to_pdf(face.descender()),
+            cap_height: face.capital_height().map_or(ascent, to_pdf),
+            line_gap: to_pdf(face.line_gap()),
+            stem_v: if face.is_bold() { 120 } else { 80 },
+            units_per_em: face.units_per_em(),
+            data,
+        };
+        Ok(Self {
+            inner: Arc::new(font),
+            vertical: false,
+        })
+    }
+
+    pub fn postscript_name(&self) -> &str {
+        &self.inner.postscript_name
+    }
+
+    /// Identifies the loaded font and the writing mode of the handle.
+    pub(crate) fn id(&self) -> (u32, bool) {
+        (self.inner.id, self.vertical)
+    }
+
+    /// The font program and the options of the handle, which fonts
+    /// loaded apart from the same bytes share a font dictionary by.
+    pub(crate) fn content(&self) -> FontContent<'_> {
+        FontContent::TrueType {
+            data: &self.inner.data,
+            vertical: self.vertical,
+        }
+    }
+
+    /// A handle to the same font for vertical writing: glyphs are stacked
+    /// top to bottom, centred on the pen position, and replaced by the
+    /// vertical forms the font has for them (rotated brackets, shifted
+    /// small kana and punctuation).
+    pub(crate) fn vertical(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            vertical: true,
+        }
+    }
+
+    fn face(&self) -> Face<'_> {
+        Face::parse(&self.inner.data, 0).expect("font was validated when loaded")
+    }
+
+    /// Converts `text` to big-endian two-byte glyph ids (Identity-H or
+    /// Identity-V), recording the glyph shown for each character in `used`.
+    /// Characters the font lacks show `.notdef`.
+    pub(crate) fn encode(&self, text: &str, used: &mut BTreeMap<u16, char>) -> Vec<u8> {
+        let face = self.face();
+        let mut codes = Vec::with_capacity(text.len() * 2);
+        for ch in text.chars() {
+            let glyph = self.glyph(&face, ch).0;
+            if glyph != 0 {
+                used.entry(glyph).or_insert(ch);
+            }
+            codes.extend_from_slice(&glyph.to_be_bytes());
+        }
+        codes
+    }
+
+    /// The glyph showing `ch`, in its vertical form for vertical handles.
+    fn glyph(&self, face: &Face, ch: char) -> GlyphId {
+        let glyph = face.glyph_index(ch).unwrap_or(GlyphId(0));
+        if self.vertical {
+            vertical_form(face, glyph)
+        } else {
+            glyph
+        }
+    }
+
+    /// Ascent, descent and line height (including the line gap) in
+    /// thousandths of the font size, from the `hhea` table.
+    pub(crate) fn vertical_metrics(&self) -> (i32, i32, i32) {
+        let font = &self.inner;
+        (
+            font.ascent,
+            font.descent,
+            font.ascent - font.descent + font.line_gap,
+        )
+    }
+
+    /// `text` with its Arabic letters replaced by the joining forms the font
+    /// has glyphs for.
+    pub(crate) fn shape<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let face = self.face();
+        shape_arabic(text, |ch| face.glyph_index(ch).is_some())
+    }
+
+    /// Length of `text` written top to bottom, in thousandths of the font
+    /// size. Glyphs without vertical metrics advance by one em.
+    pub(crate) fn text_height(&self, text: &str) -> f32 {
+        let face = self.face();
+        text.chars()
+            .map(|ch| self.vertical_advance(&face, self.glyph(&face, ch)))
+            .sum()
+    }
+
+    /// Vertical advance of `glyph` in thousandths of the font size.
+    fn vertical_advance(&self, face: &Face, glyph: GlyphId) -> f32 {
+        let scale = 1000.0 / self.inner.units_per_em as f32;
+        face.glyph_ver_advance(glyph)
+            .map_or(1000.0, |advance| advance as f32 * scale)
+    }
+
+    /// Width of `text` in thousandths of the font size, kerning included.
+    /// Characters the font lacks advance by the width of `.notdef`.
+    pub(crate) fn text_width(&self, text: &str) -> f32 {
+        let face = self.face();
+        let scale = 1000.0 / self.inner.units_per_em as f32;
+        let advances: f32 = text
+            .chars()
+            .map(|ch| {
+                let glyph = face.glyph_index(ch).unwrap_or(GlyphId(0));
+                face.glyph_hor_advance(glyph).unwrap_or(0) as f32 * scale
+            })
+            .sum();
+        let kerning: f32 = self.kern_runs(text).iter().map(|(_, adjust)| adjust).sum();
+        advances - kerning
+    }
+
+    /// Splits `text` between the pairs of glyphs the font kerns, each piece
+    /// followed by the adjustment of the next one in thousandths of the font
+    /// size, as for `TJ`: positive values pull the glyphs together. Vertical
+    /// text is not kerned.
+    pub(crate) fn kern_runs<'t>(&self, text: &'t str) -> Vec<(&'t str, f32)> {
+        if self.vertical {
+            return vec![(text, 0.0)];
+        }
+        let face = self.face();
+        let scale = 1000.0 / self.inner.units_per_em as f32;
+        let mut runs = Vec::new();
+        let mut start = 0;
+        let mut previous = None;
+        for (index, ch) in text.char_indices() {
+            let glyph = face.glyph_index(ch).unwrap_or(GlyphId(0));
+            if let Some(previous) = previous {
+                let kern = pair_kerning(&face, previous, glyph);
+                if kern != 0 {
+                    let adjust = (-(kern as f32) * scale * 100.0).round() / 100.0;
+                    runs.push((&text[start..index], adjust));
+                    start = index;
+                }
+            }
+            previous = Some(glyph);
+        }
+        runs.push((&text[start..], 0.0));
+        runs
+    }
+
+    /// Writes the Type0 font dictionary as `id`, followed by its descendant
+    /// CIDFont, font descriptor, the font program subsetted to the glyphs in
+    /// `used`, the ToUnicode CMap and, with `cid_set`, the set of glyphs the
+    /// subset has.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        alloc: &mut ObjectAllocator,
+        id: ObjectRef,
+        used: &BTreeMap<u16, char>,
+        cid_set: bool,
+    ) -> Result<()> {
+        let font = &self.inner;
+        let cid_font_id = alloc.allocate();
+        let descriptor_id = alloc.allocate();
+        let file_id = alloc.allocate();
+        let to_unicode_id = alloc.allocate();
+        let cid_set_id = cid_set.then(|| alloc.allocate());
+
+        let glyphs: BTreeSet<u16> = used.keys().copied().collect();
+        let cmap: BTreeMap<char, u16> = used.iter().map(|(glyph, ch)| (*ch, *glyph)).collect();
+        let (program, kept) = subset_truetype(&font.data, &glyphs, &cmap)?;
+        let name = Object::name(format!("{}+{}", subset_tag(&glyphs), font.postscript_name));
+
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Font"));
+        dict.set("Subtype", Object::name("Type0"));
+        dict.set("BaseFont", name.clone());
+        let encoding = if self.vertical {
+            "Identity-V"
+        } else {
+            "Identity-H"
+        };
+        dict.set("Encoding", Object::name(encoding));
+        dict.set("DescendantFonts", vec![cid_font_id.into()]);
+        dict.set("ToUnicode", to_unicode_id);
+        writer.write_object(id, dict)?;
+
+        let mut system = Dictionary::new();
+        system.set("Registry", Object::string("Adobe"));
+        system.set("Ordering", Object::string("Identity"));
+        system.set("Supplement", 0);
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("Font"));
+        dict.set("Subtype", Object::name("CIDFontType2"));
+        dict.set("BaseFont", name.clone());
+        dict.set("CIDSystemInfo", system);
+        dict.set("FontDescriptor", descriptor_id);
+        dict.set("W", self.widths(&glyphs));
+        if self.vertical {
+            dict.set("DW2", vec![font.ascent.into(), (-1000).into()]);
+            let array = self.vertical_metrics_array(&glyphs);
+            if !array.is_empty() {
+                dict.set("W2", array);
+            }
+        }
+        dict.set("CIDToGIDMap", Object::name("Identity"));
+        writer.write_object(cid_font_id, dict)?;
+
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("FontDescriptor"));
+        dict.set("FontName", name);
+        dict.set("Flags", font.flags);
+        dict.set("FontBBox", font.bbox.map(Object::from).to_vec());
+        dict.set("ItalicAngle", font.italic_angle);
+        dict.set("Ascent", font.ascent);
+        dict.set("Descent", font.descent);
+        dict.set("CapHeight", font.cap_height);
+        dict.set("StemV", font.stem_v);
+        dict.set("FontFile2", file_id);
+        if let Some(cid_set_id) = cid_set_id {
+            dict.set("CIDSet", cid_set_id);
+        }
+        writer.write_object(descriptor_id, dict)?;
+
+        let mut dict = Dictionary::new();
+        dict.set("Length1", program.len());
+        writer.write_compressed_stream(file_id, dict, &program)?;
+        let cmap = to_unicode_cmap(used);
+        writer.write_compressed_stream(to_unicode_id, Dictionary::new(), cmap.as_bytes())?;
+        if let Some(cid_set_id) = cid_set_id {
+            // A bit for each glyph id, the highest bit of the first byte
+            // for glyph 0.
+            let last = kept.last().copied().unwrap_or_default();
+            let mut bits = vec![0u8; usize::from(last) / 8 + 1];
+            for glyph in kept {
+                bits[usize::from(glyph) / 8] |= 0x80 >> (glyph % 8);
+            }
+            writer.write_compressed_stream(cid_set_id, Dictionary::new(), &bits)?;
+        }
+        Ok(())
+    }
+
+    /// The `/W` array of a CIDFont, one `first [w1 w2 ...]` run per range of
+    /// consecutive glyph ids, in thousandths of the font size.
+    fn widths(&self, glyphs: &BTreeSet<u16>) -> Vec<Object> {
+        let face = self.face();
+        let scale = 1000.0 / self.inner.units_per_em as f32;
+        let mut runs: Vec<(u16, Vec<Object>)> = Vec::new();
+        for glyph in glyphs {
+            let width = face
+                .glyph_hor_advance(GlyphId(*glyph))
+                .map_or(0, |advance| (advance as f32 * scale).round() as i32);
+            match runs.last_mut() {
+                Some((first, widths)) if *first as usize + widths.len() == *glyph as usize => {
+                    widths.push(width.into())
+                }
+                _ => runs.push((*glyph, vec![width.into()])),
+            }
+        }
+        runs.into_iter()
+            .flat_map(|(first, widths)| [u32::from(first).into(), widths.into()])
+            .collect()
+    }
+
+    /// The `/W2` array of a vertical CIDFont, one `first [w1y vx vy ...]`
+    /// run per range of consecutive glyph ids with vertical metrics in the
+    /// font: the advance (negative, downwards) and the position of the
+    /// vertical origin, horizontally centred and above the glyph by its top
+    /// side bearing. Other glyphs use the `/DW2` default.
+    fn vertical_metrics_array(&self, glyphs: &BTreeSet<u16>) -> Vec<Object> {
+        let face = self.face();
+        let scale = 1000.0 / self.inner.units_per_em as f32;
+        let to_pdf = |v: f32| Object::from((v * scale).round() as i32);
+        let mut runs: Vec<(u16, Vec<Object>)> = Vec::new();
+        for glyph in glyphs {
+            let id = GlyphId(*glyph);
+            let Some(advance) = face.glyph_ver_advance(id) else {
+                continue;
+            };
+            let width = face.glyph_hor_advance(id).unwrap_or(0) as f32;
+            let top = face.glyph_bounding_box(id).map_or(0, |bbox| bbox.y_max)
+                + face.glyph_ver_side_bearing(id).unwrap_or(0);
+            let metrics = [
+                to_pdf(-(advance as f32)),
+                to_pdf(width / 2.0),
+                to_pdf(top as f32),
+            ];
+            match runs.last_mut() {
+                Some((first, entries))
+                    if *first as usize + entries.len() / 3 == *glyph as usize =>
+                {
+                    entries.extend(metrics)
+                }
+                _ => runs.push((*glyph, metrics.to_vec())),
+            }
+        }
+        runs.into_iter()
+            .flat_map(|(first, entries)| [u32::from(first).into(), entries.into()])
+            .collect()
+    }
+}
+
+/// The vertical form of `glyph` from the single substitutions of the GSUB
+/// `vert` or `vrt2` feature, or the glyph itself if it has none.
+fn vertical_form(face: &Face, glyph: GlyphId) -> GlyphId {
+    let Some(gsub) = face.tables().gsub else {
+        return glyph;
+    };
+    let lookups = gsub
+        .features
+        .into_iter()
+        .filter(|feature| {
+            [b"vert", b"vrt2"]
+                .map(Tag::from_bytes)
+                .contains(&feature.tag)
+        })
+        .flat_map(|feature| feature.lookup_indices);
+    for lookup in lookups.filter_map(|index| gsub.lookups.get(index)) {
+        for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+            let SubstitutionSubtable::Single(single) = subtable else {
+                continue;
+            };
+            match single {
+                SingleSubstitution::Format1 { coverage, delta } if coverage.contains(glyph) => {
+                    return GlyphId((glyph.0 as i32 + delta as i32) as u16);
+                }
+                SingleSubstitution::Format2 {
+                    coverage,
+                    substitutes,
+                } => {
+                    if let Some(substitute) =
+                        coverage.get(glyph).and_then(|index| substitutes.get(index))
+                    {
+                        return substitute;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    glyph
+}
+
+/// Kerning between two glyphs in font units, from the pair adjustments of
+/// the GPOS `kern` feature if the font has them, else from its `kern` table.
+fn pair_kerning(face: &Face, left: GlyphId, right: GlyphId) -> i16 {
+    if let Some(gpos) = face.tables().gpos {
+        let lookups = gpos
+            .features
+            .into_iter()
+            .filter(|feature| feature.tag == Tag::from_bytes(b"kern"))
+            .flat_map(|feature| feature.lookup_indices);
+        for lookup in lookups.filter_map(|index| gpos.lookups.get(index)) {
+            for subtable in lookup.subtables.into_iter::<PositioningSubtable>() {
+                let PositioningSubtable::Pair(pair) = subtable else {
+                    continue;
+                };
+                let Some(coverage) = pair.coverage().get(left) else {
+                    continue;
+                };
+                let values = match pair {
+                    PairAdjustment::Format1 { sets, .. } => {
+                        sets.get(coverage).and_then(|set| set.get(right))
+                    }
+                    PairAdjustment::Format2 {
+                        classes, matrix, ..
+                    } => matrix.get((classes.0.get(left), classes.1.get(right))),
+                };
+                if let Some((first, _)) = values {
+                    return first.x_advance;
+                }
+            }
+        }
+    }
+    face.tables()
+        .kern
+        .into_iter()
+        .flat_map(|kern| kern.subtables)
+        .filter(|subtable| subtable.horizontal && !subtable.variable)
+        .find_map(|subtable| subtable.glyphs_kerning(left, right))
+        .unwrap_or(0)
+}
+
+/// CMap mapping two-byte glyph ids back to the characters they show, so
+/// viewers can extract and search the text.
+fn to_unicode_cmap(used: &BTreeMap<u16, char>) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let entries: Vec<_> = used.iter().collect();
+    for block in entries.chunks(CMAP_BLOCK) {
+        cmap.push_str(&format!("{} beginbfchar\n", block.len()));
+        for (glyph, ch) in block {
+            let mut units = [0u16; 2];
+            let utf16: String = ch
+                .encode_utf16(&mut units)
+                .iter()
+                .map(|unit| format!("{:04X}", unit))
+                .collect();
+            cmap.push_str(&format!("<{:04X}> <{}>\n", glyph, utf16));
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+impl fmt::Debug for TrueTypeFont {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrueTypeFont")
+            .field("postscript_name", &self.inner.postscript_name)
+            .field("len", &self.inner.data.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_font() -> TrueTypeFont {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fonts/DejaVuSans.ttf");
+    TrueTypeFont::from_file(path).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manual::PdfDocument;
+    use crate::page::{Page, PageSize};
+
+    #[test]
+    fn test_embed_truetype() {
+        let font = test_font();
+        assert_eq!(font.postscript_name(), "DejaVuSans");
+
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(72.0, 720.0, 14.0, &font, "Zürich");
+        page.draw_text(72.0, 700.0, 14.0, font.clone(), "Привет \u{10FFFF}");
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(text.matches("/Subtype /Type0").count(), 1);
+        assert!(text.contains("/Encoding /Identity-H /DescendantFonts [6 0 R] /ToUnicode 9 0 R"));
+        assert!(text.contains("/CIDToGIDMap /Identity"));
+        let tag = text.split("/FontName /").nth(1).unwrap();
+        assert_eq!(&tag[6..24], "+DejaVuSans /Flags");
+        assert!(tag[..6].chars().all(|ch| ch.is_ascii_uppercase()));
+        assert!(text.contains(&format!("/BaseFont /{}", &tag[..17])));
+        assert!(!text.contains(&format!("/Length1 {}", font.inner.data.len())));
+
+        // "Z" is glyph 61 and "ü" glyph 190; U+10FFFF is not in the font.
+        assert!(text.contains("<003D00BE"));
+        assert!(text.contains("<003D> <005A>\n"));
+        assert!(text.contains("<00BE> <00FC>\n"));
+        assert!(text.contains("0000> Tj"));
+    }
+
+    #[test]
+    fn test_escaped_name() {
+        let mut font = test_font();
+        Arc::get_mut(&mut font.inner).unwrap().postscript_name = String::from("Grün (Bold)/2");
+        let mut page = Page::new(PageSize::A4);
+        page.draw_text(72.0, 720.0, 14.0, &font, "A");
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.set_compression(false);
+        let pdf = doc.to_bytes().unwrap();
+
+        let text = String::from_utf8_lossy(&pdf);
+        let tag = text.split("/BaseFont /").nth(1).unwrap();
+        assert!(tag[6..].starts_with("+Gr#C3#BCn#20#28Bold#29#2F2 "));
+        assert!(text.contains(&format!("/FontName /{}", &tag[..34])));
+    }
+
+    #[test]
+    fn test_widths_runs() {
+        let font = test_font();
+        let face = font.face();
+        let glyphs: BTreeSet<u16> = ['A', 'B', 'Z']
+            .iter()
+            .map(|ch| face.glyph_index(*ch).unwrap().0)
+            .collect();
+        assert_eq!(
+            Object::from(font.widths(&glyphs)).to_string(),
+            "[36 [684 686] 61 [685]]"
+        );
+    }
+
+    #[test]
+    fn test_kerning() {
+        let font = test_font();
+        let runs = font.kern_runs("AVA x");
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].0, "A");
+        assert_eq!(runs[1].0, "V");
+        assert_eq!(runs[2], ("A x", 0.0));
+        assert!(runs[0].1 > 0.0);
+        let unkerned = font.text_width("A") * 2.0 + font.text_width("V x");
+        assert!(font.text_width("AVA x") < unkerned);
+        assert_eq!(font.kern_runs("x"), [("x", 0.0)]);
+    }
+
+    #[test]
+    fn test_vertical_font() {
+        let font = test_font();
+        let mut page = Page::new(PageSize::A4);
+        let style = crate::text::TextStyle::new(&font, 10.0);
+        page.draw_text_vertical(300.0, 700.0, "AB", &style);
+        page.draw_text(72.0, 72.0, 10.0, &font, "AB");
+        // DejaVu Sans has no vertical metrics: one em per glyph.
+        assert_eq!(font.vertical().text_height("AB"), 2000.0);
+        assert_eq!(font.vertical().kern_runs("AVA"), [("AVA", 0.0)]);
+        let mut doc = PdfDocument::default();
+        doc.add_page(page);
+        doc.set_compression(false);
+        let text = String::from_utf8_lossy(&doc.to_bytes().unwrap()).into_owned();
+        // The two writing modes are separate fonts sharing the file.
+        assert_eq!(text.matches("/Subtype /Type0").count(), 2);
+        assert!(text.contains("/Encoding /Identity-V"));
+        assert!(text.contains("/W [36 [684 686]] /DW2 [928 -1000] /CIDToGIDMap"));
+        assert!(text.contains("BT\n/F1 10 Tf\n300 700 Td\n<00240025> Tj\nET\n"));
+    }
+
+    #[test]
+    fn test_to_unicode_surrogates() {
+        let used: BTreeMap<u16, char> = [(5, 'A'), (7, '\u{1D11E}')].into_iter().collect();
+        let cmap = to_unicode_cmap(&used);
+        assert!(cmap.contains("2 beginbfchar\n<0005> <0041>\n<0007> <D834DD1E>\nendbfchar\n"));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(TrueTypeFont::from_bytes(b"not a font".to_vec()).is_err());
+    }
+}
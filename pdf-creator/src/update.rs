@@ -0,0 +1,576 @@
+use crate::annotation::Annotation;
+use crate::form::{APPEND_ONLY, SIGNATURES_EXIST, appearance, signature_outline, widget};
+use crate::geometry::Rect;
+use crate::manual::PdfVersion;
+use crate::object::{self, ObjectAllocator, ObjectRef};
+use crate::resources::{ResourceRefs, SharedResources};
+use crate::signing::{SignaturePlaceholder, Signer};
+use crate::writer::{PdfWriter, Trailer};
+use crate::xobject::FormXObject;
+use anyhow::{Context, Result, ensure};
+use lopdf::{Dictionary, Document, Object, StringFormat, decode_text_string};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+/// An existing document changed by appending to it: the objects added or
+/// changed, then a cross-reference section of them pointing back to the
+/// one before, as an incremental update. What was there is left byte for
+/// byte, so signatures already in the document still hold, and another
+/// one can be added.
+///
+/// ```no_run
+/// use pdf_creator::{Annotation, PdfUpdate, Rect};
+///
+/// let mut update = PdfUpdate::open("contract.pdf")?;
+/// update.add_annotation(0, Annotation::note(Rect::new(72.0, 720.0, 20.0, 20.0), "Approved"));
+/// update.create_to_path("contract.pdf")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct PdfUpdate {
+    original: Vec<u8>,
+    document: Document,
+    version: PdfVersion,
+    /// Where the last cross-reference section of the file starts.
+    prev: u64,
+    /// Whether that section is a stream, that of the update then being one
+    /// too.
+    xref_stream: bool,
+    /// The page objects, in order.
+    pages: Vec<ObjectRef>,
+    compress: bool,
+    annotations: Vec<(usize, Annotation)>,
+    signature: Option<(String, usize, Rect, Signer)>,
+}
+
+impl PdfUpdate {
+    /// Reads the document at `path` to update.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// Reads the document `pdf` to update. Encrypted documents cannot be
+    /// updated, nor those with objects of generations other than 0.
+    pub fn from_bytes(pdf: Vec<u8>) -> Result<Self> {
+        let document = Document::load_mem(&pdf).context("the file is not a readable PDF")?;
+        ensure!(
+            document.trailer.get(b"Encrypt").is_err(),
+            "encrypted documents cannot be updated"
+        );
+        ensure!(
+            document
+                .objects
+                .keys()
+                .all(|(_, generation)| *generation == 0),
+            "documents with objects of generations other than 0 cannot be updated"
+        );
+        // The catalog tells the version when an update raised it past that
+        // of the header. Versions before 1.4 are taken for 1.4, the
+        // earliest written.
+        let catalog_version = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"Version"))
+            .and_then(Object::as_name)
+            .ok();
+        let version = [Some(document.version.as_bytes()), catalog_version]
+            .into_iter()
+            .flatten()
+            .filter_map(|number| {
+                [
+                    PdfVersion::Pdf14,
+                    PdfVersion::Pdf15,
+                    PdfVersion::Pdf16,
+                    PdfVersion::Pdf17,
+                    PdfVersion::Pdf20,
+                ]
+                .into_iter()
+                .find(|version| version.number().as_bytes() == number)
+            })
+            .max()
+            .unwrap_or_default();
+        let pages = document
+            .get_pages()
+            .into_values()
+            .map(|(id, _)| ObjectRef::new(id))
+            .collect();
+        let prev = document.xref_start;
+        let section = pdf
+            .get(prev..)
+            .context("the cross-reference section is past the end of the file")?;
+        Ok(Self {
+            xref_stream: !section.trim_ascii_start().starts_with(b"xref"),
+            prev: prev as u64,
+            original: pdf,
+            document,
+            version,
+            pages,
+            compress: true,
+            annotations: Vec::new(),
+            signature: None,
+        })
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Has streams without a filter of their own, such as the appearances
+    /// of annotations, Flate-compressed, the default, or written as is.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    /// Adds `annotation` to the page at index `page`, counting from 0.
+    pub fn add_annotation(&mut self, page: usize, annotation: Annotation) {
+        self.annotations.push((page, annotation));
+    }
+
+    /// Has the document signed by `signer` as the update is written, in a
+    /// new signature field called `field` in `rect` on the page at index
+    /// `page`: one of no size makes the signature invisible, otherwise it
+    /// shows as [`Signer::appearance`] has it, or is outlined. The
+    /// signature is of the whole file, the update included, and earlier
+    /// ones are left as they are.
+    pub fn sign(&mut self, field: &str, page: usize, rect: Rect, signer: Signer) {
+        self.signature = Some((field.to_string(), page, rect, signer));
+    }
+
+    /// Writes the document with the update appended to a newly created (or
+    /// truncated) file at `path`, which may be the one it was read from.
+    pub fn create_to_path(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_to(File::create(path)?)
+    }
+
+    /// The document with the update appended, in memory.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        self.write_to(&mut buf)?;
+        Ok(buf.into_inner())
+    }
+
+    /// Writes the document with the update appended into any sink,
+    /// seekable or not, which may already contain data.
+    ///
+    /// The objects new to the document are numbered from its `/Size` on,
+    /// in the order they are written: the resources of the appearances of
+    /// the annotations, the annotations, then the signature field and the
+    /// signature dictionary if the document is signed. The pages given
+    /// annotations are written again, or their arrays of annotations if
+    /// those are objects of their own, as are the interactive form or the
+    /// catalog, and the array of fields, for a signature.
+    ///
+    /// A signed update is put together in memory first, the signature
+    /// being of the whole file.
+    pub fn write_to<W: Write>(&self, mut w: W) -> Result<()> {
+        let Some((field, page, rect, signer)) = &self.signature else {
+            return self.write_update(w, None).map(|_| ());
+        };
+        // The time is settled once, for what the signature shows to agree
+        // with it.
+        let signer = signer.clone().time(signer.signing_time());
+        let look = signer
+            .look(*rect)?
+            .unwrap_or_else(|| signature_outline(*rect));
+        let mut pdf = Cursor::new(Vec::new());
+        let placeholder =
+            self.write_update(&mut pdf, Some((field, *page, *rect, &signer, &look)))?;
+        let mut pdf = pdf.into_inner();
+        let placeholder = placeholder.expect("signed updates have a signature dictionary");
+        signer.sign_file(&mut pdf, &placeholder)?;
+        w.write_all(&pdf)?;
+        Ok(())
+    }
+
+    /// Writes the document with the update appended as
+    /// [`write_to`](Self::write_to) says, signed in the field, on the
+    /// page, in the rectangle, by the signer and showing the look of
+    /// `signature`, if any, and tells where the signature is to be filled
+    /// in.
+    fn write_update<W: Write>(
+        &self,
+        w: W,
+        signature: Option<(&str, usize, Rect, &Signer, &FormXObject)>,
+    ) -> Result<Option<SignaturePlaceholder>> {
+        let size = self.document.max_id + 1;
+        let mut writer = PdfWriter::append(w, &self.original, &self.version, size)?;
+        writer.set_compression(self.compress);
+        writer.set_xref_stream(self.xref_stream)?;
+        let mut alloc = ObjectAllocator::starting_at(size);
+
+        let mut refs = ResourceRefs::default();
+        let appearances = self
+            .annotations
+            .iter()
+            .filter_map(|(_, annotation)| annotation.appearance_form());
+        let look = signature.map(|(_, _, _, _, look)| look);
+        let mut shared = SharedResources::gather(std::iter::empty(), appearances.chain(look));
+        let attached = self
+            .annotations
+            .iter()
+            .filter_map(|(_, annotation)| annotation.attached_file());
+        shared.allocate(&mut alloc, &mut refs, std::iter::empty(), None, attached);
+        let annots: Vec<ObjectRef> = self.annotations.iter().map(|_| alloc.allocate()).collect();
+        let signed = signature.map(|_| (alloc.allocate(), alloc.allocate()));
+        shared.write(
+            &mut writer,
+            &mut alloc,
+            &refs,
+            false,
+            #[cfg(feature = "image")]
+            None,
+        )?;
+
+        // The annotations new to each page.
+        let mut added: BTreeMap<usize, Vec<ObjectRef>> = BTreeMap::new();
+        for ((page, annotation), id) in self.annotations.iter().zip(annots) {
+            ensure!(
+                *page < self.pages.len(),
+                "annotation on page {} of a document of {} pages",
+                page + 1,
+                self.pages.len()
+            );
+            writer.write_object(id, annotation.to_pdf(&self.pages, &refs)?)?;
+            added.entry(*page).or_default().push(id);
+        }
+        let placeholder = match signature.zip(signed) {
+            Some(((name, page, rect, signer, look), (field, signature))) => {
+                let mut dict = widget(name, page, rect, &self.pages)?;
+                dict.set("FT", object::Object::name("Sig"));
+                dict.set("T", object::Object::text(name));
+                dict.set("AP", appearance(refs.forms[look]));
+                dict.set("V", signature);
+                writer.write_object(field, dict)?;
+                added.entry(page).or_default().push(field);
+                self.write_form(&mut writer, name, field)?;
+                Some(writer.write_signature(signature, signer.to_dict(), signer.reserved_size())?)
+            }
+            None => None,
+        };
+        for (page, added) in added {
+            self.write_annots(&mut writer, self.pages[page], &added)?;
+        }
+
+        let mut id = [0; 16];
+        getrandom::fill(&mut id)?;
+        let trailer = Trailer {
+            root: self.root()?,
+            info: self
+                .document
+                .trailer
+                .get(b"Info")
+                .and_then(Object::as_reference)
+                .ok()
+                .map(|(id, _)| ObjectRef::new(id)),
+            id: Some(id),
+            original_id: self
+                .document
+                .trailer
+                .get(b"ID")
+                .and_then(Object::as_array)
+                .ok()
+                .and_then(|ids| ids.first())
+                .and_then(|id| id.as_str().ok())
+                .map(<[u8]>::to_vec),
+            prev: Some(self.prev),
+        };
+        writer.finish(alloc.size(), trailer)?;
+        Ok(placeholder)
+    }
+
+    fn root(&self) -> Result<ObjectRef> {
+        let (id, _) = self
+            .document
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .context("the trailer of the document has no catalog")?;
+        Ok(ObjectRef::new(id))
+    }
+
+    /// Writes the page `page` again with `added` added to its annotations,
+    /// or only its array of annotations if that is an object of its own.
+    fn write_annots<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        page: ObjectRef,
+        added: &[ObjectRef],
+    ) -> Result<()> {
+        let dict = self.document.get_dictionary((page.id(), 0))?;
+        let added = added
+            .iter()
+            .map(|annotation| Object::Reference((annotation.id(), 0)));
+        match dict.get(b"Annots") {
+            Ok(Object::Reference(id)) => {
+                let mut annots = self.document.get_object(*id)?.as_array()?.clone();
+                annots.extend(added);
+                writer.write(ObjectRef::new(id.0), &converted(&Object::Array(annots)))
+            }
+            annots => {
+                let mut annots = match annots {
+                    Ok(Object::Array(annots)) => annots.clone(),
+                    _ => Vec::new(),
+                };
+                annots.extend(added);
+                let mut dict = dict.clone();
+                dict.set("Annots", annots);
+                writer.write(page, &converted(&Object::Dictionary(dict)))
+            }
+        }
+    }
+
+    /// Writes the interactive form again with the signature field `field`,
+    /// called `name`, added to its fields, and telling the document is
+    /// signed: the form dictionary if it is an object of its own, the
+    /// catalog otherwise, along with the array of fields if that is.
+    fn write_form<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        name: &str,
+        field: ObjectRef,
+    ) -> Result<()> {
+        let root = self.root()?;
+        let catalog = self.document.get_dictionary((root.id(), 0))?;
+        let (form_id, mut form) = match catalog.get(b"AcroForm") {
+            Ok(Object::Reference(id)) => (Some(*id), self.document.get_dictionary(*id)?.clone()),
+            Ok(Object::Dictionary(form)) => (None, form.clone()),
+            _ => (None, Dictionary::new()),
+        };
+        let field = Object::Reference((field.id(), 0));
+        match form.get(b"Fields") {
+            Ok(Object::Reference(id)) => {
+                let mut fields = self.document.get_object(*id)?.as_array()?.clone();
+                self.check_name(&fields, name)?;
+                fields.push(field);
+                writer.write(ObjectRef::new(id.0), &converted(&Object::Array(fields)))?;
+            }
+            fields => {
+                let mut fields = match fields {
+                    Ok(Object::Array(fields)) => fields.clone(),
+                    _ => Vec::new(),
+                };
+                self.check_name(&fields, name)?;
+                fields.push(field);
+                form.set("Fields", fields);
+            }
+        }
+        form.set("SigFlags", i64::from(SIGNATURES_EXIST | APPEND_ONLY));
+        match form_id {
+            Some(id) => writer.write(ObjectRef::new(id.0), &converted(&Object::Dictionary(form))),
+            None => {
+                let mut catalog = catalog.clone();
+                catalog.set("AcroForm", form);
+                writer.write(root, &converted(&Object::Dictionary(catalog)))
+            }
+        }
+    }
+
+    /// Fails if one of the fields `fields` is called `name`.
+    fn check_name(&self, fields: &[Object], name: &str) -> Result<()> {
+        for field in fields {
+            let Ok((_, Object::Dictionary(field))) = self.document.dereference(field) else {
+                continue;
+            };
+            let taken = field
+                .get(b"T")
+                .and_then(decode_text_string)
+                .is_ok_and(|taken| taken == name);
+            ensure!(!taken, "the form already has a field {:?}", name);
+        }
+        Ok(())
+    }
+}
+
+/// `object`, read from the document, in the object model it is written
+/// from, every object being of generation 0.
+fn converted(object: &Object) -> object::Object {
+    match object {
+        Object::Null => object::Object::Null,
+        Object::Boolean(value) => object::Object::Bool(*value),
+        Object::Integer(value) => object::Object::Integer(*value),
+        Object::Real(value) => object::Object::Real(*value),
+        Object::Name(name) => object::Object::Name(name.clone()),
+        Object::String(bytes, StringFormat::Literal) => object::Object::String(bytes.clone()),
+        Object::String(bytes, StringFormat::Hexadecimal) => {
+            object::Object::HexString(bytes.clone())
+        }
+        Object::Array(items) => object::Object::Array(items.iter().map(converted).collect()),
+        Object::Dictionary(dict) => {
+            let mut converted_dict = object::Dictionary::new();
+            for (key, value) in dict.iter() {
+                converted_dict.set(key.clone(), converted(value));
+            }
+            converted_dict.into()
+        }
+        Object::Stream(_) => unreachable!("streams are objects of their own"),
+        Object::Reference((id, _)) => ObjectRef::new(*id).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manual::PdfDocument;
+    use crate::page::{Page, PageSize};
+    use crate::signing::tests::test_signer;
+
+    fn document(pages: usize) -> PdfDocument {
+        let mut doc = PdfDocument::new("First");
+        for _ in 1..pages {
+            doc.add_page(Page::new(PageSize::A4));
+        }
+        doc.set_compression(false);
+        doc
+    }
+
+    #[test]
+    fn test_converted() {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"Page".to_vec()));
+        dict.set("Odd Name", 0.5);
+        dict.set("Kids", vec![Object::Reference((3, 0)), Object::Null]);
+        dict.set("Title", Object::string_literal("a (b)"));
+        dict.set(
+            "ID",
+            Object::String(b"id".to_vec(), StringFormat::Hexadecimal),
+        );
+        assert_eq!(
+            converted(&Object::Dictionary(dict)).to_string(),
+            "<< /Type /Page /Odd#20Name 0.5 /Kids [3 0 R null] /Title (a \\(b\\)) /ID <6964> >>"
+        );
+    }
+
+    #[test]
+    fn test_annotation_update() {
+        let original = document(2).to_bytes().unwrap();
+        let mut update = PdfUpdate::from_bytes(original.clone()).unwrap();
+        assert_eq!(update.page_count(), 2);
+        let rect = Rect::new(72.0, 720.0, 20.0, 20.0);
+        update.add_annotation(1, Annotation::note(rect, "Seen"));
+        update.add_annotation(1, Annotation::note(rect, "Twice"));
+        let pdf = update.to_bytes().unwrap();
+
+        // The file is appended to, its last section pointing back.
+        assert!(pdf.starts_with(&original));
+        let appended = String::from_utf8_lossy(&pdf[original.len()..]).into_owned();
+        let startxref = String::from_utf8_lossy(&original)
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .trim_end_matches("%%EOF")
+            .trim()
+            .to_string();
+        assert!(appended.contains(&format!(" /Prev {} ", startxref)));
+        assert!(appended.contains("xref\n0 1\n0000000000 65535 f \n"));
+
+        let loaded = lopdf::Document::load_mem(&pdf).unwrap();
+        let page = loaded.get_pages()[&2];
+        let annots = loaded.get_page_annotations(page).unwrap();
+        assert_eq!(annots.len(), 2);
+        assert!(
+            loaded
+                .get_page_annotations(loaded.get_pages()[&1])
+                .unwrap()
+                .is_empty()
+        );
+
+        // An annotation on no page of the document is refused.
+        let mut update = PdfUpdate::from_bytes(original).unwrap();
+        update.add_annotation(2, Annotation::note(rect, "Nowhere"));
+        let error = update.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "annotation on page 3 of a document of 2 pages"
+        );
+    }
+
+    #[test]
+    fn test_xref_stream_update() {
+        let mut doc = document(1);
+        doc.set_version(PdfVersion::Pdf15);
+        doc.set_object_streams(true);
+        let original = doc.to_bytes().unwrap();
+        let mut update = PdfUpdate::from_bytes(original.clone()).unwrap();
+        let rect = Rect::new(72.0, 720.0, 20.0, 20.0);
+        update.add_annotation(0, Annotation::note(rect, "Seen"));
+        let pdf = update.to_bytes().unwrap();
+        let appended = String::from_utf8_lossy(&pdf[original.len()..]).into_owned();
+        assert!(appended.contains("/Type /XRef"));
+        assert!(appended.contains("/Index [0 1 "));
+        let loaded = lopdf::Document::load_mem(&pdf).unwrap();
+        let page = loaded.get_pages()[&1];
+        assert_eq!(loaded.get_page_annotations(page).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_malformed_offsets() {
+        let original = document(1).to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&original).into_owned();
+        let start = text.rfind("startxref\n").unwrap() + 10;
+        let end = start + text[start..].find('\n').unwrap();
+        let xref: usize = text[start..end].parse().unwrap();
+        let pointing_at = |xref: usize| format!("{}{}\n%%EOF\n", &text[..start], xref);
+        assert!(PdfUpdate::from_bytes(pointing_at(xref).into_bytes()).is_ok());
+        let past = pointing_at(original.len() + 100);
+        assert!(PdfUpdate::from_bytes(past.into_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_catalog_version() {
+        let original = document(1).to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&original).into_owned();
+        // Of the same length, for the offsets to hold.
+        let raised = text.replacen("/Type /Catalog", "/Version /1.7 ", 1);
+        let update = PdfUpdate::from_bytes(raised.into_bytes()).unwrap();
+        assert_eq!(update.version, PdfVersion::Pdf17);
+        let lowered = text.replacen("/Type /Catalog", "/Version /1.3 ", 1);
+        let update = PdfUpdate::from_bytes(lowered.into_bytes()).unwrap();
+        assert_eq!(update.version, PdfVersion::Pdf14);
+    }
+
+    #[test]
+    fn test_signed_update() {
+        let mut doc = document(1);
+        doc.form()
+            .text_field("name", Rect::new(72.0, 600.0, 200.0, 20.0), 0);
+        let original = doc.to_bytes().unwrap();
+        let mut update = PdfUpdate::from_bytes(original.clone()).unwrap();
+        let rect = Rect::new(72.0, 500.0, 200.0, 50.0);
+        update.sign("approval", 0, rect, test_signer());
+        let pdf = update.to_bytes().unwrap();
+        assert!(pdf.starts_with(&original));
+
+        // The signature covers the whole file, the original included.
+        let text = String::from_utf8_lossy(&pdf).into_owned();
+        let byte_range = text.split("/ByteRange [").nth(1).unwrap();
+        let byte_range: Vec<usize> = byte_range
+            .split(']')
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .map(|n| n.parse().unwrap())
+            .collect();
+        assert_eq!(byte_range[0], 0);
+        assert_eq!(byte_range[2] + byte_range[3], pdf.len());
+
+        let loaded = lopdf::Document::load_mem(&pdf).unwrap();
+        let form = loaded
+            .catalog()
+            .unwrap()
+            .get(b"AcroForm")
+            .and_then(|form| loaded.dereference(form))
+            .unwrap()
+            .1
+            .as_dict()
+            .unwrap();
+        assert_eq!(form.get(b"SigFlags").unwrap().as_i64().unwrap(), 3);
+        assert_eq!(form.get(b"Fields").unwrap().as_array().unwrap().len(), 2);
+
+        // Fields are not named twice.
+        let mut update = PdfUpdate::from_bytes(pdf).unwrap();
+        update.sign("name", 0, rect, test_signer());
+        let error = update.to_bytes().unwrap_err();
+        assert_eq!(error.to_string(), "the form already has a field \"name\"");
+    }
+}
@@ -0,0 +1,835 @@
+use crate::encryption::SecurityHandler;
+use crate::linearization::{Held, Lengths, Plan, hint_stream, renumber};
+use crate::manual::PdfVersion;
+use crate::object::{Dictionary, Encoding, Object, ObjectRef};
+use crate::signing::{BYTE_RANGE_PLACEHOLDER, SignaturePlaceholder};
+use anyhow::{Result, bail, ensure};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// How many objects go into one object stream at most, a reader having to
+/// decode a whole stream to get at any object in it.
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// Counts the bytes written through it, which tell where each object
+/// starts with no need for the output to be seekable: a file, a socket,
+/// the standard output or an encoder such as gzip alike.
+struct CountingWriter<W> {
+    w: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.w.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Tracks where each indirect object starts so the xref table can be built
+/// once everything has been written.
+pub(crate) struct PdfWriter<W> {
+    w: CountingWriter<W>,
+    version: PdfVersion,
+    offsets: Vec<(ObjectRef, Location)>,
+    /// Whether streams without a filter of their own are Flate-compressed.
+    compress: bool,
+    /// Whether objects other than streams are packed into object streams.
+    object_streams: bool,
+    /// Whether the cross-reference section is a stream rather than a table,
+    /// as it always is with object streams.
+    xref_stream: bool,
+    /// Objects waiting to be packed into an object stream.
+    packed: Vec<(ObjectRef, Object)>,
+    /// What strings and streams are encrypted with, if they are.
+    security: Option<SecurityHandler>,
+    /// The pages of the document, in order, if it is linearized, its
+    /// objects then held back to be laid out when finishing.
+    linearized: Option<Vec<ObjectRef>>,
+    held: Vec<Held>,
+    /// The number objects new to the file start at, those below being of
+    /// the document an update is appended to, and written again only if
+    /// changed.
+    first: u32,
+}
+
+/// The entries of the trailer, the objects reading a document starts from.
+#[derive(Debug, Clone)]
+pub(crate) struct Trailer {
+    pub(crate) root: ObjectRef,
+    pub(crate) info: Option<ObjectRef>,
+    /// The identifier of the file, which encrypted files have anyway.
+    pub(crate) id: Option<[u8; 16]>,
+    /// The identifier the file was first written with, which an update
+    /// keeps, if it had one.
+    pub(crate) original_id: Option<Vec<u8>>,
+    /// Where the cross-reference section of the document an update is
+    /// appended to starts.
+    pub(crate) prev: Option<u64>,
+}
+
+impl Trailer {
+    /// The entries of the trailer but `/Size`.
+    fn to_dict(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Root", self.root);
+        if let Some(info) = self.info {
+            dict.set("Info", info);
+        }
+        if let Some(id) = self.id {
+            let original = self.original_id.clone().unwrap_or(id.to_vec());
+            dict.set("ID", vec![Object::HexString(original), Object::hex(id)]);
+        }
+        if let Some(prev) = self.prev {
+            dict.set("Prev", prev as i64);
+        }
+        dict
+    }
+}
+
+/// Where an indirect object is found, as told by its cross-reference entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Location {
+    /// Byte offset from the `%PDF` header.
+    Offset(u64),
+    /// Number of the object stream and index of the object within it.
+    Packed(u32, usize),
+}
+
+impl<W: Write> PdfWriter<W> {
+    pub(crate) fn new(w: W, version: &PdfVersion) -> Result<Self> {
+        let mut w = CountingWriter { w, count: 0 };
+        w.write_all(version.to_str().as_bytes())?;
+        Ok(Self {
+            w,
+            version: *version,
+            offsets: Vec::new(),
+            compress: false,
+            object_streams: false,
+            xref_stream: false,
+            packed: Vec::new(),
+            security: None,
+            linearized: None,
+            held: Vec::new(),
+            first: 1,
+        })
+    }
+
+    /// Writes `original`, a whole file, for an update to be appended to
+    /// it, with objects new to it numbered from `size`, its `/Size`, on.
+    pub(crate) fn append(w: W, original: &[u8], version: &PdfVersion, size: u32) -> Result<Self> {
+        let mut w = CountingWriter { w, count: 0 };
+        w.write_all(original)?;
+        if !original.ends_with(b"\n") {
+            w.write_all(b"\n")?;
+        }
+        Ok(Self {
+            w,
+            version: *version,
+            offsets: Vec::new(),
+            compress: false,
+            object_streams: false,
+            xref_stream: false,
+            packed: Vec::new(),
+            security: None,
+            linearized: None,
+            held: Vec::new(),
+            first: size,
+        })
+    }
+
+    pub(crate) fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    /// Has objects other than streams packed into object streams (PDF 1.5),
+    /// written when finishing, along with a cross-reference stream.
+    pub(crate) fn set_object_streams(&mut self, object_streams: bool) -> Result<()> {
+        if object_streams {
+            self.require(PdfVersion::Pdf15, "object streams")?;
+        }
+        self.object_streams = object_streams;
+        Ok(())
+    }
+
+    /// Has the cross-reference section written as a stream (PDF 1.5) even
+    /// without object streams.
+    pub(crate) fn set_xref_stream(&mut self, xref_stream: bool) -> Result<()> {
+        if xref_stream {
+            self.require(PdfVersion::Pdf15, "cross-reference streams")?;
+        }
+        self.xref_stream = xref_stream;
+        Ok(())
+    }
+
+    /// Has the strings and streams of every object written from now on
+    /// encrypted by `security`, and its encryption dictionary written when
+    /// finishing.
+    pub(crate) fn set_encryption(&mut self, security: SecurityHandler) -> Result<()> {
+        let (version, feature) = security.required_version();
+        self.require(version, feature)?;
+        self.security = Some(security);
+        Ok(())
+    }
+
+    /// Has the document linearized, the objects of the first of the pages
+    /// `pages` written first, followed by those of each other page, along
+    /// with hint tables telling where they are. Every object is held back
+    /// until finishing, to be laid out and renumbered, and encrypted then.
+    pub(crate) fn set_linearized(&mut self, pages: Vec<ObjectRef>) -> Result<()> {
+        ensure!(
+            !self.object_streams && !self.xref_stream,
+            "linearized documents are written with cross-reference tables, \
+             without object streams"
+        );
+        self.linearized = Some(pages);
+        Ok(())
+    }
+
+    /// Fails unless the document is written for `version` or later, which
+    /// `feature` takes.
+    pub(crate) fn require(&self, version: PdfVersion, feature: &str) -> Result<()> {
+        ensure!(
+            self.version >= version,
+            "{} need PDF {} or later, the document is PDF {}",
+            feature,
+            version.number(),
+            self.version.number()
+        );
+        Ok(())
+    }
+
+    /// Where the next byte goes, from where the file starts.
+    fn position(&self) -> u64 {
+        self.w.count
+    }
+
+    fn begin_object(&mut self, object: ObjectRef) -> Result<()> {
+        let offset = self.position();
+        self.offsets.push((object, Location::Offset(offset)));
+        self.w.write_all(object_header(object).as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes `value` as the object `object`, as a stream if it is one,
+    /// Flate-compressed if it has no filter of its own and compression is
+    /// on.
+    pub(crate) fn write(&mut self, object: ObjectRef, value: &Object) -> Result<()> {
+        match value {
+            Object::Stream(stream) => {
+                let (dict, data) = match self.compress && !stream.is_filtered() {
+                    true => stream.clone().filter(Encoding::Flate).encode(),
+                    false => stream.encode(),
+                };
+                self.write_stream(object, dict, &data)
+            }
+            value => self.write_object(object, value.clone()),
+        }
+    }
+
+    /// Writes `value`, any object but a stream, as the object `object`.
+    pub(crate) fn write_object(
+        &mut self,
+        object: ObjectRef,
+        value: impl Into<Object>,
+    ) -> Result<()> {
+        let value = value.into();
+        ensure!(
+            !value.contains_stream(),
+            "streams are objects of their own, referred to by others"
+        );
+        if self.linearized.is_some() {
+            self.hold(object, value, None, true);
+            return Ok(());
+        }
+        // Objects in object streams are encrypted along with the stream.
+        if self.object_streams {
+            self.packed.push((object, value));
+            return Ok(());
+        }
+        let value = self.encrypted(object, value)?;
+        self.write_plain_object(object, &value)
+    }
+
+    /// Writes an object as is, never encrypted.
+    fn write_plain_object(&mut self, object: ObjectRef, value: &Object) -> Result<()> {
+        let offset = self.position();
+        self.offsets.push((object, Location::Offset(offset)));
+        self.w.write_all(&object_bytes(object, value))?;
+        Ok(())
+    }
+
+    fn hold(&mut self, object: ObjectRef, body: Object, data: Option<Vec<u8>>, encrypted: bool) {
+        self.held.push(Held {
+            object,
+            body,
+            data,
+            encrypted,
+        });
+    }
+
+    /// `value` of the object `object` with its strings encrypted, if the
+    /// document is.
+    fn encrypted(&self, object: ObjectRef, mut value: Object) -> Result<Object> {
+        if let Some(security) = &self.security {
+            value.visit_mut(&mut security.encrypt_strings(object))?;
+        }
+        Ok(value)
+    }
+
+    /// The dictionary `dict` of the object `object` with its strings
+    /// encrypted, if the document is.
+    fn encrypted_dict(&self, object: ObjectRef, mut dict: Dictionary) -> Result<Dictionary> {
+        if let Some(security) = &self.security {
+            dict.visit_mut(&mut security.encrypt_strings(object))?;
+        }
+        Ok(dict)
+    }
+
+    /// Writes a signature dictionary of `dict` with its `/ByteRange` and
+    /// its `/Contents`, room for `size` bytes, left blank, to be filled in
+    /// once the file is complete. It is never packed into an object stream,
+    /// and its contents are not encrypted.
+    pub(crate) fn write_signature(
+        &mut self,
+        object: ObjectRef,
+        dict: Dictionary,
+        size: usize,
+    ) -> Result<SignaturePlaceholder> {
+        if self.linearized.is_some() {
+            bail!("signed documents cannot be linearized");
+        }
+        let dict = self.encrypted_dict(object, dict)?;
+        self.begin_object(object)?;
+        self.w
+            .write_all(format!("<<{} /ByteRange ", dict.entries()).as_bytes())?;
+        let byte_range = self.position() as usize;
+        self.w.write_all(BYTE_RANGE_PLACEHOLDER.as_bytes())?;
+        self.w.write_all(b" /Contents ")?;
+        let contents = self.position() as usize;
+        self.w
+            .write_all(format!("<{}>", "0".repeat(2 * size)).as_bytes())?;
+        self.w.write_all(b" >>\nendobj\n")?;
+        Ok(SignaturePlaceholder {
+            byte_range,
+            contents: contents..contents + 2 * size + 2,
+        })
+    }
+
+    /// Writes a stream object of the dictionary `dict`, its `/Length`
+    /// written first, and of the data `data`, encoded already with the
+    /// filters `dict` names.
+    pub(crate) fn write_stream(
+        &mut self,
+        object: ObjectRef,
+        dict: Dictionary,
+        data: &[u8],
+    ) -> Result<()> {
+        ensure!(
+            !dict.values().any(Object::contains_stream),
+            "streams are objects of their own, referred to by others"
+        );
+        if self.linearized.is_some() {
+            self.hold(object, dict.into(), Some(data.to_vec()), true);
+            return Ok(());
+        }
+        match &self.security {
+            Some(security) => {
+                let data = security.encrypt(object, data)?;
+                let dict = self.encrypted_dict(object, dict)?;
+                self.write_plain_stream(object, &dict, &data)
+            }
+            None => self.write_plain_stream(object, &dict, data),
+        }
+    }
+
+    /// Writes a stream object as is, never encrypted.
+    fn write_plain_stream(
+        &mut self,
+        object: ObjectRef,
+        dict: &Dictionary,
+        data: &[u8],
+    ) -> Result<()> {
+        let offset = self.position();
+        self.offsets.push((object, Location::Offset(offset)));
+        self.w.write_all(&stream_bytes(object, dict, data))?;
+        Ok(())
+    }
+
+    /// Writes a stream of data not encoded otherwise, such as a content
+    /// stream or a font program, Flate-compressed if compression is on.
+    pub(crate) fn write_compressed_stream(
+        &mut self,
+        object: ObjectRef,
+        mut dict: Dictionary,
+        data: &[u8],
+    ) -> Result<()> {
+        if !self.compress {
+            return self.write_stream(object, dict, data);
+        }
+        dict.set_first("Filter", Object::name("FlateDecode"));
+        self.write_stream(object, dict, &deflate(data))
+    }
+
+    /// Writes the encryption dictionary, if any, and the objects waiting
+    /// to be packed, numbered from `size` on, and the cross-reference
+    /// section, of the objects written only if appending an update.
+    pub(crate) fn finish(mut self, mut size: u32, mut trailer: Trailer) -> Result<()> {
+        // Encrypted files are identified by what their keys derive from.
+        if self.security.is_some() {
+            trailer.id = None;
+        }
+        let mut document = vec![trailer.root];
+        let mut trailer = trailer.to_dict();
+        if let Some(security) = self.security.take() {
+            let encrypt = ObjectRef::new(size);
+            size += 1;
+            match self.linearized {
+                Some(_) => self.hold(encrypt, security.to_dict().into(), None, false),
+                None => self.write_plain_object(encrypt, &Object::from(security.to_dict()))?,
+            }
+            let id = Object::hex(security.file_id());
+            trailer.set("Encrypt", encrypt);
+            trailer.set("ID", vec![id.clone(), id]);
+            self.security = Some(security);
+            document.push(encrypt);
+        }
+        if let Some(pages) = self.linearized.take() {
+            return self.write_linearized(size, &trailer, &pages, &document);
+        }
+        let packed = std::mem::take(&mut self.packed);
+        for objects in packed.chunks(OBJECTS_PER_STREAM) {
+            self.write_object_stream(ObjectRef::new(size), objects)?;
+            size += 1;
+        }
+
+        self.offsets.sort();
+        let first = self.first;
+        ensure!(
+            self.offsets
+                .iter()
+                .map(|(object, _)| object.id())
+                .filter(|id| *id >= first)
+                .eq(first..size),
+            "every allocated object must be written exactly once"
+        );
+        ensure!(
+            self.offsets
+                .windows(2)
+                .all(|pair| pair[0].0.id() < pair[1].0.id()),
+            "an object of the document must be changed only once"
+        );
+        if self.xref_stream || !packed.is_empty() {
+            self.write_xref_stream(ObjectRef::new(size), &trailer)
+        } else {
+            self.write_xref_table(size, &trailer)
+        }
+    }
+
+    /// Lays out the objects held (ISO 32000-1, annex F) for the pages
+    /// `pages`: the linearization dictionary, the cross-reference table of
+    /// the first page along with the trailer, the objects opening the
+    /// document takes, `document`, the hint stream, the objects of the
+    /// first page, then those of each other page, the shared ones and the
+    /// rest, then the main cross-reference table, of those after the first
+    /// page. The objects up to the first page are numbered after the
+    /// others, for each table to be of consecutive numbers. Entries whose
+    /// values depend on where everything ends up are padded to a fixed
+    /// width, for those to be known beforehand.
+    fn write_linearized(
+        &mut self,
+        size: u32,
+        trailer: &Dictionary,
+        pages: &[ObjectRef],
+        document: &[ObjectRef],
+    ) -> Result<()> {
+        let mut ids: Vec<u32> = self.held.iter().map(|held| held.object.id()).collect();
+        ids.sort_unstable();
+        ensure!(
+            ids.into_iter().eq(1..size),
+            "every allocated object must be written exactly once"
+        );
+        let plan = Plan::new(&self.held, pages, document)?;
+        let main: Vec<usize> = plan
+            .pages
+            .iter()
+            .flatten()
+            .chain(&plan.shared)
+            .chain(&plan.rest)
+            .copied()
+            .collect();
+        let first_id = main.len() as u32 + 1;
+        let linearization = ObjectRef::new(first_id);
+        let hint = ObjectRef::new(first_id + 1 + plan.document.len() as u32);
+        let size = hint.id() + 1 + plan.first.len() as u32;
+        let mut numbers = HashMap::new();
+        let numbered = main
+            .iter()
+            .zip(1..)
+            .chain(plan.document.iter().zip(first_id + 1..))
+            .chain(plan.first.iter().zip(hint.id() + 1..));
+        for (&object, number) in numbered {
+            numbers.insert(self.held[object].object.id(), number);
+        }
+        let bytes = self
+            .held
+            .iter()
+            .map(|held| {
+                let object = ObjectRef::new(numbers[&held.object.id()]);
+                let mut body = held.body.clone();
+                body.visit_mut(&mut renumber(&numbers))?;
+                self.sealed(object, body, held.data.as_deref(), held.encrypted)
+            })
+            .collect::<Result<Vec<Vec<u8>>>>()?;
+        let lengths: Vec<usize> = bytes.iter().map(Vec::len).collect();
+        let length = |objects: &[usize]| -> u64 {
+            objects.iter().map(|&object| lengths[object] as u64).sum()
+        };
+
+        let dictionary = |l: u64, h: (u64, u64), e: u64, t: u64| {
+            let mut dict = Dictionary::new();
+            dict.set("Linearized", 1);
+            dict.set("L", l as i64);
+            dict.set("H", vec![(h.0 as i64).into(), (h.1 as i64).into()]);
+            dict.set("O", numbers[&pages[0].id()]);
+            dict.set("E", e as i64);
+            dict.set("N", pages.len());
+            dict.set("T", t as i64);
+            Object::from(dict)
+        };
+        let widest = dictionary(
+            LARGEST_OFFSET,
+            (LARGEST_OFFSET, LARGEST_OFFSET),
+            LARGEST_OFFSET,
+            LARGEST_OFFSET,
+        )
+        .to_string()
+        .len();
+        let mut trailer = trailer.clone();
+        trailer.visit_mut(&mut renumber(&numbers))?;
+        let first_trailer = |prev: u64| {
+            format!(
+                "trailer\n<< /Size {}{} /Prev {} >>",
+                size,
+                trailer.entries(),
+                prev
+            )
+        };
+        let widest_trailer = first_trailer(LARGEST_OFFSET).len();
+        let first_xref_length = format!("xref\n{} {}\n", first_id, size - first_id).len()
+            + XREF_ENTRY_LENGTH * (size - first_id) as usize
+            + widest_trailer
+            + "\nstartxref\n0\n%%EOF\n".len();
+
+        let start = self.position();
+        let first_xref =
+            start + padded_object_bytes(linearization, &" ".repeat(widest)).len() as u64;
+        let document_start = first_xref + first_xref_length as u64;
+        let hint_start = document_start + length(&plan.document);
+        // Offsets in the hint tables leave the hint stream out.
+        let other_pages: u64 = plan.pages.iter().map(|page| length(page)).sum();
+        let first_shared = plan.shared.first().map(|&object| {
+            let offset = hint_start + length(&plan.first) + other_pages;
+            (offset, numbers[&self.held[object].object.id()])
+        });
+        let lengths_of = Lengths {
+            lengths: &lengths,
+            first_page: hint_start,
+            first_shared,
+        };
+        let (hints, shared_table) = hint_stream(&plan, &lengths_of);
+        let mut entries = Dictionary::new();
+        entries.set("S", shared_table);
+        let hints = match self.compress {
+            true => {
+                entries.set_first("Filter", Object::name("FlateDecode"));
+                deflate(&hints)
+            }
+            false => hints,
+        };
+        let hints = self.sealed(hint, entries.into(), Some(&hints), true)?;
+
+        // Where each object held starts.
+        let mut offsets = vec![0; self.held.len()];
+        let mut offset = document_start;
+        for &object in &plan.document {
+            offsets[object] = offset;
+            offset += lengths[object] as u64;
+        }
+        offset += hints.len() as u64;
+        for &object in plan.first.iter().chain(&main) {
+            offsets[object] = offset;
+            offset += lengths[object] as u64;
+        }
+        let end_of_first_page = hint_start + hints.len() as u64 + length(&plan.first);
+        let main_xref = offset;
+        let mut main_table = format!("xref\n0 {}", first_id);
+        // The offset of the line break before the entry of object 0.
+        let zero_entry = main_xref + main_table.len() as u64;
+        main_table.push_str("\n0000000000 65535 f \n");
+        for &object in &main {
+            main_table.push_str(&xref_entry(offsets[object]));
+        }
+        main_table.push_str(&format!(
+            "trailer\n<< /Size {} >>\nstartxref\n{}\n%%EOF",
+            first_id, first_xref
+        ));
+        let file_length = main_xref + main_table.len() as u64;
+
+        let linearization_dict = dictionary(
+            file_length,
+            (hint_start, hints.len() as u64),
+            end_of_first_page,
+            zero_entry,
+        )
+        .to_string();
+        let mut first_table = format!("xref\n{} {}\n", first_id, size - first_id);
+        first_table.push_str(&xref_entry(start));
+        for &object in &plan.document {
+            first_table.push_str(&xref_entry(offsets[object]));
+        }
+        first_table.push_str(&xref_entry(hint_start));
+        for &object in &plan.first {
+            first_table.push_str(&xref_entry(offsets[object]));
+        }
+        first_table.push_str(&format!(
+            "{:<1$}\nstartxref\n0\n%%EOF\n",
+            first_trailer(main_xref),
+            widest_trailer
+        ));
+
+        self.w.write_all(&padded_object_bytes(
+            linearization,
+            &format!("{:<1$}", linearization_dict, widest),
+        ))?;
+        self.w.write_all(first_table.as_bytes())?;
+        for &object in &plan.document {
+            self.w.write_all(&bytes[object])?;
+        }
+        self.w.write_all(&hints)?;
+        for &object in plan.first.iter().chain(&main) {
+            self.w.write_all(&bytes[object])?;
+        }
+        self.w.write_all(main_table.as_bytes())?;
+        self.w.flush()?;
+        Ok(())
+    }
+
+    /// The bytes of the object `object` of `body`, or for a stream of the
+    /// dictionary `body` and `data`, encrypted if `encrypted` and the
+    /// document is.
+    fn sealed(
+        &self,
+        object: ObjectRef,
+        mut body: Object,
+        data: Option<&[u8]>,
+        encrypted: bool,
+    ) -> Result<Vec<u8>> {
+        let security = self.security.as_ref().filter(|_| encrypted);
+        if let Some(security) = security {
+            body.visit_mut(&mut security.encrypt_strings(object))?;
+        }
+        Ok(match (data, body) {
+            (Some(data), Object::Dictionary(dict)) => match security {
+                Some(security) => stream_bytes(object, &dict, &security.encrypt(object, data)?),
+                None => stream_bytes(object, &dict, data),
+            },
+            (Some(_), _) => unreachable!("streams are held by their dictionary"),
+            (None, body) => object_bytes(object, &body),
+        })
+    }
+
+    /// Writes `objects` into a single `/ObjStm` stream: the numbers and
+    /// offsets of the objects, then the objects themselves.
+    fn write_object_stream(
+        &mut self,
+        stream: ObjectRef,
+        objects: &[(ObjectRef, Object)],
+    ) -> Result<()> {
+        let mut header = String::new();
+        let mut bodies = String::new();
+        for (index, (object, value)) in objects.iter().enumerate() {
+            header.push_str(&format!("{} {} ", object.id(), bodies.len()));
+            bodies.push_str(&value.to_string());
+            bodies.push('\n');
+            self.offsets
+                .push((*object, Location::Packed(stream.id(), index)));
+        }
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("ObjStm"));
+        dict.set("N", objects.len());
+        dict.set("First", header.len());
+        header.push_str(&bodies);
+        self.write_compressed_stream(stream, dict, header.as_bytes())
+    }
+
+    /// Writes the cross-reference table, in subsections of consecutive
+    /// numbers, object 0 heading the first.
+    fn write_xref_table(&mut self, size: u32, trailer: &Dictionary) -> Result<()> {
+        let xref_pos = self.position();
+        self.w.write_all(b"xref\n")?;
+        let ids: Vec<u32> = self.offsets.iter().map(|(object, _)| object.id()).collect();
+        let mut entries = self.offsets.iter();
+        for (start, count) in subsections(&ids) {
+            self.w
+                .write_all(format!("{} {}\n", start, count).as_bytes())?;
+            let mut count = count;
+            if start == 0 {
+                self.w.write_all(b"0000000000 65535 f \n")?;
+                count -= 1;
+            }
+            for (object, location) in entries.by_ref().take(count) {
+                let Location::Offset(off) = location else {
+                    unreachable!("objects in object streams need a cross-reference stream");
+                };
+                self.w
+                    .write_all(format!("{:010} {:05} n \n", off, object.generation()).as_bytes())?;
+            }
+        }
+
+        self.w.write_all(
+            format!(
+                "trailer\n<< /Size {}{} >>\nstartxref\n{}\n%%EOF",
+                size,
+                trailer.entries(),
+                xref_pos
+            )
+            .as_bytes(),
+        )?;
+        self.w.flush()?;
+        Ok(())
+    }
+
+    /// Writes the cross-reference section as the stream `xref` (PDF 1.5),
+    /// which also serves as the trailer, `trailer` being its entries. Each
+    /// entry is a type byte, then an offset or the number of an object
+    /// stream, then a generation or an index within that stream, all
+    /// big-endian. It is never encrypted.
+    fn write_xref_stream(&mut self, xref: ObjectRef, trailer: &Dictionary) -> Result<()> {
+        let xref_pos = self.position();
+        self.offsets.push((xref, Location::Offset(xref_pos)));
+        let largest = self
+            .offsets
+            .iter()
+            .map(|(_, location)| match *location {
+                Location::Offset(off) => off,
+                Location::Packed(stream, _) => stream.into(),
+            })
+            .max()
+            .unwrap_or_default();
+        let width = (u64::BITS - largest.leading_zeros()).div_ceil(8).max(1) as usize;
+
+        // Object 0 heads the list of free objects.
+        let mut data = vec![0; width + 1];
+        data.extend_from_slice(&[0xff, 0xff]);
+        for (object, location) in &self.offsets {
+            let (kind, field, index) = match *location {
+                Location::Offset(off) => (1, off, object.generation()),
+                Location::Packed(stream, index) => (2, stream.into(), index as u16),
+            };
+            data.push(kind);
+            data.extend_from_slice(&field.to_be_bytes()[8 - width..]);
+            data.extend_from_slice(&index.to_be_bytes());
+        }
+
+        let mut dict = Dictionary::new();
+        if self.compress {
+            dict.set("Filter", Object::name("FlateDecode"));
+            data = deflate(&data);
+        }
+        dict.set("Type", Object::name("XRef"));
+        dict.set("Size", xref.id() + 1);
+        dict.set("W", vec![1.into(), width.into(), 2.into()]);
+        let ids: Vec<u32> = self.offsets.iter().map(|(object, _)| object.id()).collect();
+        match subsections(&ids).as_slice() {
+            [(0, _)] => {}
+            subsections => {
+                let index: Vec<Object> = subsections
+                    .iter()
+                    .flat_map(|&(start, count)| [start.into(), count.into()])
+                    .collect();
+                dict.set("Index", index);
+            }
+        }
+        for (key, value) in trailer.iter() {
+            dict.set(key, value.clone());
+        }
+        self.write_plain_stream(xref, &dict, &data)?;
+        self.w
+            .write_all(format!("startxref\n{}\n%%EOF", xref_pos).as_bytes())?;
+        self.w.flush()?;
+        Ok(())
+    }
+}
+
+/// The length of each entry of a cross-reference table.
+const XREF_ENTRY_LENGTH: usize = 20;
+
+/// The largest offset a cross-reference table has room for, and which the
+/// entries of linearized documents telling offsets and lengths are padded
+/// for.
+const LARGEST_OFFSET: u64 = 9_999_999_999;
+
+/// The first number and the count of each run of consecutive numbers of
+/// `ids`, sorted, taken along with object 0, the head of the list of free
+/// objects.
+fn subsections(ids: &[u32]) -> Vec<(u32, usize)> {
+    let mut subsections: Vec<(u32, usize)> = vec![(0, 1)];
+    for &id in ids {
+        match subsections.last_mut() {
+            Some((start, count)) if *start + *count as u32 == id => *count += 1,
+            _ => subsections.push((id, 1)),
+        }
+    }
+    subsections
+}
+
+/// The entry of a cross-reference table of an object at `offset`.
+fn xref_entry(offset: u64) -> String {
+    format!("{:010} 00000 n \n", offset)
+}
+
+fn object_header(object: ObjectRef) -> String {
+    format!("{} {} obj\n", object.id(), object.generation())
+}
+
+/// The bytes of the object `object` of `value`.
+fn object_bytes(object: ObjectRef, value: &Object) -> Vec<u8> {
+    padded_object_bytes(object, &value.to_string())
+}
+
+/// The bytes of the object `object` of `body`, an object written already,
+/// padded as the linearization dictionary is.
+fn padded_object_bytes(object: ObjectRef, body: &str) -> Vec<u8> {
+    format!("{}{}\nendobj\n", object_header(object), body).into_bytes()
+}
+
+/// The bytes of the stream `object` of `data`, the entries of `dict` being
+/// written after its `/Length`.
+fn stream_bytes(object: ObjectRef, dict: &Dictionary, data: &[u8]) -> Vec<u8> {
+    let mut bytes = format!(
+        "{}<< /Length {}{} >>\nstream\n",
+        object_header(object),
+        data.len(),
+        dict.entries()
+    )
+    .into_bytes();
+    bytes.extend_from_slice(data);
+    bytes.extend_from_slice(b"\nendstream\nendobj\n");
+    bytes
+}
+
+/// Compresses `data` for the `FlateDecode` filter.
+pub(crate) fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing to memory cannot fail.
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
@@ -0,0 +1,150 @@
+use crate::geometry::Rect;
+use crate::image::Image;
+use crate::object::{Dictionary, Object, ObjectRef};
+use crate::page::ContentStream;
+use crate::resources::{ResourceRefs, Resources};
+use crate::writer::PdfWriter;
+use anyhow::{Result, ensure};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Source of the ids telling forms apart.
+static NEXT_FORM_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A drawing defined once, such as a letterhead, a stamp or a table header,
+/// and placed any number of times with
+/// [`ContentStream::draw_form`](crate::ContentStream::draw_form): a Form
+/// XObject. Its content is written a single time for the whole document,
+/// each placement only refers to it.
+///
+/// Cloning the handle shares the drawing.
+#[derive(Clone)]
+pub struct FormXObject {
+    inner: Arc<FormData>,
+}
+
+struct FormData {
+    id: u32,
+    width: f32,
+    height: f32,
+    content: ContentStream,
+}
+
+impl FormXObject {
+    /// A drawing of `width` by `height` points, its lower left corner at the
+    /// origin of what `draw` draws. Anything drawn outside is cut off.
+    /// Tagging the drawing makes the document fail to be written: forms
+    /// are tagged where they are drawn instead.
+    pub fn new(width: f32, height: f32, draw: impl FnOnce(&mut ContentStream)) -> Self {
+        let mut content = ContentStream::default();
+        draw(&mut content);
+        Self {
+            inner: Arc::new(FormData {
+                id: NEXT_FORM_ID.fetch_add(1, Ordering::Relaxed),
+                width,
+                height,
+                content,
+            }),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.inner.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.inner.height
+    }
+
+    /// The resources the drawing uses.
+    pub(crate) fn resources(&self) -> &Resources {
+        &self.inner.content.resources
+    }
+
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut PdfWriter<W>,
+        id: ObjectRef,
+        refs: &ResourceRefs,
+    ) -> Result<()> {
+        ensure!(
+            self.inner.content.structure.is_empty(),
+            "forms cannot be tagged, only the content they are drawn in"
+        );
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::name("XObject"));
+        dict.set("Subtype", Object::name("Form"));
+        let bbox = Rect::new(0.0, 0.0, self.inner.width, self.inner.height);
+        dict.set("BBox", bbox);
+        dict.set("Resources", self.resources().to_dict(refs));
+        writer.write_compressed_stream(id, dict, self.inner.content.ops())
+    }
+}
+
+impl PartialEq for FormXObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for FormXObject {}
+
+impl Hash for FormXObject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl fmt::Debug for FormXObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormXObject")
+            .field("width", &self.inner.width)
+            .field("height", &self.inner.height)
+            .finish()
+    }
+}
+
+/// What the `Do` operator paints, named in the `/XObject` resources.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum XObject {
+    Form(FormXObject),
+    Image(Image),
+}
+
+impl XObject {
+    pub(crate) fn to_pdf(&self, refs: &ResourceRefs) -> ObjectRef {
+        match self {
+            XObject::Form(form) => refs.forms[form],
+            XObject::Image(image) => refs.images[image],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::graphics::Paint;
+
+    #[test]
+    fn test_form_stream() {
+        let stamp = FormXObject::new(40.0, 20.5, |g| {
+            g.rect(0.0, 0.0, 40.0, 20.5, &Paint::fill(Color::BLACK));
+        });
+        assert_eq!(stamp, stamp.clone());
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut writer = PdfWriter::new(&mut buf, &Default::default()).unwrap();
+        stamp
+            .write(&mut writer, ObjectRef::new(1), &ResourceRefs::default())
+            .unwrap();
+        let pdf = String::from_utf8(buf.into_inner()).unwrap();
+        assert!(pdf.ends_with(
+            "1 0 obj\n<< /Length 17 /Type /XObject /Subtype /Form /BBox [0 0 40 20.5] \
+             /Resources << >> >>\nstream\n0 0 40 20.5 re\nf\n\nendstream\nendobj\n"
+        ));
+    }
+}